@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy_hsm::prelude::*;
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SIZES: [usize; 2] = [1_000, 10_000];
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .register_guard("always", |_: In<GuardContext>| true)
+        .register_guard("never", |_: In<GuardContext>| false);
+    app
+}
+
+fn spawn_toggling(world: &mut World, count: usize) {
+    for _ in 0..count {
+        world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="always", guard_exit="always")]: Toggle
+            )
+        ));
+    }
+    world.flush();
+}
+
+fn spawn_deep(world: &mut World, count: usize) {
+    for _ in 0..count {
+        world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="always")]: Outer(
+                    #[state(guard_enter="always")]: Middle(
+                        #[state(guard_enter="always", guard_exit="always")]: Leaf
+                    )
+                )
+            )
+        ));
+    }
+    world.flush();
+}
+
+fn spawn_idle(world: &mut World, count: usize) {
+    for _ in 0..count {
+        world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="never")]: Idle
+            )
+        ));
+    }
+    world.flush();
+}
+
+fn spawn_acting(world: &mut World, count: usize) -> Vec<Entity> {
+    let state_machines = (0..count)
+        .map(|_| {
+            world
+                .spawn(hsm!(
+                    StateLifecycle::default(),
+                    #[state]: Root(
+                        #[state(guard_enter="always", on_update="Update:work")]: Working
+                    )
+                ))
+                .id()
+        })
+        .collect();
+    world.flush();
+    state_machines
+}
+
+fn spawn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                app,
+                |mut app| spawn_deep(app.world_mut(), size),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn update(c: &mut Criterion) {
+    let kinds: [(&str, fn(&mut World, usize)); 3] = [
+        ("toggling", spawn_toggling),
+        ("deep", spawn_deep),
+        ("idle", spawn_idle),
+    ];
+    for (name, spawn) in kinds {
+        let mut group = c.benchmark_group(format!("update/{}", name));
+        for size in SIZES {
+            let mut app = app();
+            spawn(app.world_mut(), size);
+            app.update();
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+                b.iter(|| app.update());
+            });
+        }
+        group.finish();
+    }
+}
+
+/// 每帧销毁一批拥有更新动作的状态机并生成同样数量的新状态机
+///
+/// Every frame, despawn a batch of machines with an update action and spawn as many new ones
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("churn");
+    for size in SIZES {
+        let mut app = app();
+        app.add_action_system(Update, "work", |contexts: In<Vec<ActionContext>>| {
+            Some(contexts.0)
+        });
+        let mut state_machines = spawn_acting(app.world_mut(), size);
+        app.update();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let world = app.world_mut();
+                for state_machine in state_machines.drain(..size / 10) {
+                    let states = world
+                        .get::<StateTree>(state_machine)
+                        .map(|state_tree| state_tree.iter().collect::<Vec<_>>())
+                        .unwrap_or_default();
+                    world.despawn(state_machine);
+                    for state in states {
+                        world.despawn(state);
+                    }
+                }
+                state_machines.extend(spawn_acting(world, size / 10));
+                app.update();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, spawn, update, churn);
+criterion_main!(benches);
@@ -31,8 +31,15 @@ struct Fsm {
 impl Parse for Fsm {
     fn parse(input: ParseStream) -> Result<Self> {
         let machine_config = if input.peek(kw::init) {
-            input.parse::<kw::init>()?;
-            Some(input.parse::<StateMachineConfig>()?)
+            let init = input.parse::<kw::init>()?;
+            let config = input.parse::<StateMachineConfig>()?;
+            if !config.start.is_empty() {
+                return Err(syn::Error::new(
+                    init.span,
+                    "start is only supported by hsm!",
+                ));
+            }
+            Some(config)
         } else {
             None
         };
@@ -14,6 +14,8 @@ pub(super) enum GuardCondition {
     And(Vec<GuardCondition>),
     Or(Vec<GuardCondition>),
     Not(Box<GuardCondition>),
+    Sticky(Box<GuardCondition>, Option<f64>),
+    Call(syn::Ident, Vec<String>),
     Id(GuardId),
 }
 
@@ -39,6 +41,27 @@ impl quote::ToTokens for GuardCondition {
                     GuardCondition::Not(Box::new(#condition))
                 });
             }
+            GuardCondition::Sticky(condition, timeout) => {
+                let timeout = match timeout {
+                    Some(seconds) => quote::quote! {
+                        ::core::option::Option::Some(::core::time::Duration::from_secs_f64(#seconds))
+                    },
+                    None => quote::quote! { ::core::option::Option::None },
+                };
+                tokens.extend(quote::quote! {
+                    GuardCondition::sticky(#condition, #timeout)
+                });
+            }
+            GuardCondition::Call(name, args) => {
+                let name = name.to_string();
+                let len = args.len();
+                tokens.extend(quote::quote! {
+                    {
+                        let args: [&str; #len] = [#(#args),*];
+                        GuardCondition::call(#name, args)
+                    }
+                });
+            }
             GuardCondition::Id(id) => {
                 tokens.extend(quote::quote! {
                     GuardCondition::from(#id)
@@ -81,6 +104,56 @@ impl Parse for GuardCondition {
                 ));
             }
             GuardCondition::Not(Box::new(conditions.into_iter().next().unwrap()))
+        } else if lookahead.peek(kw::sticky) {
+            input.parse::<kw::sticky>()?;
+            let content;
+            syn::parenthesized!(content in input);
+            let condition = content.parse::<GuardCondition>()?;
+            let timeout = match content.parse::<Option<Token![,]>>()? {
+                Some(_) => {
+                    let literal = parse_literal(&content)?;
+                    match literal.parse::<f64>() {
+                        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Some(seconds),
+                        _ => {
+                            return Err(syn::Error::new(
+                                content.span(),
+                                "sticky timeout must be a non-negative number of seconds",
+                            ));
+                        }
+                    }
+                }
+                None => None,
+            };
+            if !content.is_empty() {
+                return Err(
+                    content.error("sticky condition takes a condition and an optional timeout")
+                );
+            }
+            GuardCondition::Sticky(Box::new(condition), timeout)
+        } else if lookahead.peek(syn::Ident) && input.peek2(syn::token::Paren) {
+            let name = input.parse::<syn::Ident>()?;
+            let content;
+            syn::parenthesized!(content in input);
+            let is_field = name == "field";
+            let mut args = Vec::new();
+            while !content.is_empty() {
+                // `field(Switch)` 中的组件名可以不加引号
+                if is_field && args.is_empty() && content.peek(syn::Ident) {
+                    args.push(content.parse::<syn::Ident>()?.to_string());
+                } else {
+                    args.push(parse_literal(&content)?);
+                }
+                if content.is_empty() {
+                    break;
+                }
+                content.parse::<Token![,]>()?;
+            }
+            // `field(...) == "value"` 将比较值作为最后一个参数
+            if is_field && input.peek(Token![==]) {
+                input.parse::<Token![==]>()?;
+                args.push(parse_literal(input)?);
+            }
+            GuardCondition::Call(name, args)
         } else if lookahead.peek(syn::LitStr) {
             GuardCondition::Id(input.parse()?)
         } else if lookahead.peek(Token![#]) && input.peek2(syn::Ident) {
@@ -104,6 +177,26 @@ impl GuardCondition {
     }
 }
 
+/// 解析带参数守卫的字面量参数：字符串或（可带负号的）数字
+fn parse_literal(input: syn::parse::ParseStream) -> syn::Result<String> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let literal = match input.parse::<syn::Lit>()? {
+        syn::Lit::Str(lit) if !negative => return Ok(lit.value()),
+        syn::Lit::Int(lit) => lit.base10_digits().to_string(),
+        syn::Lit::Float(lit) => lit.base10_digits().to_string(),
+        lit => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "guard arguments must be string or number literals",
+            ));
+        }
+    };
+    Ok(match negative {
+        true => format!("-{}", literal),
+        false => literal,
+    })
+}
+
 #[derive(Clone, Debug)]
 pub enum GuardId {
     Str(syn::LitStr),
@@ -32,6 +32,7 @@ impl quote::ToTokens for HsmImpl {
             machine_config,
         } = self;
         let hsm_state_machine = machine_config.hsm_config();
+        let start_selector = machine_config.hsm_start_selector();
 
         tokens.extend(quote::quote! {
             bevy_hsm::markers::SpawnStateMachine::new(move |mut entity_commands: EntityCommands|{
@@ -39,7 +40,7 @@ impl quote::ToTokens for HsmImpl {
                 let mut commands = entity_commands.commands();
                 #state_tree
                 let structure_id = entity_commands.id();
-                entity_commands.insert((#hsm_state_machine,state_tree,#components));
+                entity_commands.insert((#hsm_state_machine,state_tree,#start_selector #components));
                 #config_fn
             })
         });
@@ -1,6 +1,7 @@
 syn::custom_keyword!(and);
 syn::custom_keyword!(not);
 syn::custom_keyword!(or);
+syn::custom_keyword!(sticky);
 
 syn::custom_keyword!(states);
 syn::custom_keyword!(components);
@@ -29,5 +30,6 @@ syn::custom_punctuation!(Both, <=>);
 syn::custom_keyword!(history_capacity);
 syn::custom_keyword!(init_state);
 syn::custom_keyword!(curr_state);
+syn::custom_keyword!(start);
 
 syn::custom_keyword!(init);
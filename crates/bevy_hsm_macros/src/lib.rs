@@ -20,7 +20,8 @@ use proc_macro::TokenStream;
 /// Combines multiple guard conditions into a single complex condition for state transitions.
 ///
 /// This macro simplifies the creation of complex guard logic by allowing you to create nested
-/// logical conditions using `and`, `or`, and `not` operators. It is used within the `#[state]`
+/// logical conditions using `and`, `or`, `not` and `sticky` operators, together with parameterized
+/// guards such as `chance(0.3)` or `field(Door) == "Open"`. It is used within the `#[state]`
 /// attribute to define `guard_enter` or `guard_exit` conditions.
 ///
 /// # EBNF Syntax
@@ -30,10 +31,17 @@ use proc_macro::TokenStream;
 ///
 /// guard_expression ::= ( 'and' | 'or' ), '(', guard_expression, ',', guard_expression, { ',', guard_expression }, ')'
 ///                    | 'not', '(', guard_expression, ')'
+///                    | 'sticky', '(', guard_expression, [ ',', number_literal ], ')'
+///                    | 'field', '(', ( identifier | literal ), { ',', literal }, ')', [ '==', literal ]
+///                    | identifier, '(', [ literal, { ',', literal } ], ')' (* parameterized guard *)
 ///                    | guard_id;
 /// guard_id ::= lit_str | ( '#', identifier );
+/// literal ::= lit_str | [ '-' ], number_literal;
 /// ```
 ///
+/// A `lit_str` guard id goes through `GuardCondition::from`, which parses expressions containing parentheses at
+/// runtime, so `"once()"` and `once()` build the same condition.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -50,9 +58,10 @@ use proc_macro::TokenStream;
 ///     ]);
 ///     
 ///     let enter_condition = combination_condition!(and("is_a", not("is_b")));
+///     let exit_condition = combination_condition!(or(sticky("is_a", 0.2), chance(0.3)));
 ///
 ///     commands.spawn(hsm!(
-///         #[state(guard_enter = #enter_condition)]: Initial
+///         #[state(guard_enter = #enter_condition, guard_exit = #exit_condition)]: Initial
 ///     ));
 /// }
 /// ```
@@ -72,7 +81,9 @@ pub fn combination_condition(item: TokenStream) -> TokenStream {
 /// hsm ::= [ machine_config, ',', ], state_node, { ',', component }, [ ',', config_fn ];
 /// machine_config ::= 'init', '(', [ machine_config_param, { ',', machine_config_param } ], ')';
 /// machine_config_param ::= 'history_capacity', '=', integer_literal
-///                        | ( 'init_state' | 'curr_state' ), '=', state_ref;
+///                        | ( 'init_state' | 'curr_state' ), '=', state_ref
+///                        | 'start', '=', '[', [ start_candidate, { ',', start_candidate } ], ']';
+/// start_candidate ::= guard_expression, '=>', state_ref; (* hsm! only *)
 /// state_node ::= state_attribute, [ ':', state_name ], [ '(', { state_content }, ')' ];
 /// state_content ::= ( state_node | component ), { ',', ( state_node | component ) };
 /// state_attribute ::= '#[state', [ '(', state_attribute_param, { ',', state_attribute_param }, ')' ], ']'
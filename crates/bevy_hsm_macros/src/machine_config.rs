@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 
-use crate::kw::{self};
+use crate::{
+    guard_condition::GuardCondition,
+    kw::{self},
+};
 use proc_macro2::Span;
 use quote::quote;
 use syn::{
-    Ident, LitInt, Result, Token, parenthesized,
+    Ident, LitInt, Result, Token, bracketed, parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
 };
@@ -15,6 +18,8 @@ pub(crate) struct StateMachineConfigImpl {
     pub history_capacity: Option<LitInt>,
     pub curr_state: usize,
     pub init_state: usize,
+    #[cfg(feature = "hsm")]
+    pub start: Vec<(GuardCondition, usize)>,
 }
 
 impl StateMachineConfigImpl {
@@ -48,6 +53,19 @@ impl StateMachineConfigImpl {
         }
     }
 
+    /// 启动选择器组件，没有候选状态时为空
+    #[cfg(feature = "hsm")]
+    pub fn hsm_start_selector(&self) -> proc_macro2::TokenStream {
+        if self.start.is_empty() {
+            return quote! {};
+        }
+        let candidates = self
+            .start
+            .iter()
+            .map(|(condition, index)| quote! {.with(#condition, ids[#index])});
+        quote! {HsmStartSelector::default()#(#candidates)*,}
+    }
+
     #[cfg(feature = "fsm")]
     pub fn fsm_config(&self) -> proc_macro2::TokenStream {
         let Self {
@@ -77,6 +95,7 @@ pub(crate) struct StateMachineConfig {
     pub history_capacity: Option<LitInt>,
     pub curr_state: Option<StateRef>,
     pub init_state: Option<StateRef>,
+    pub start: Vec<(GuardCondition, StateRef)>,
 }
 
 impl Parse for StateMachineConfig {
@@ -88,6 +107,7 @@ impl Parse for StateMachineConfig {
         let mut history_capacity: Option<LitInt> = None;
         let mut init_state: Option<StateRef> = None;
         let mut curr_state: Option<StateRef> = None;
+        let mut start: Vec<(GuardCondition, StateRef)> = Vec::new();
 
         for attr in attrs {
             match attr {
@@ -119,6 +139,12 @@ impl Parse for StateMachineConfig {
                     }
                     curr_state = Some(state_ref);
                 }
+                ConfigAttr::Start(span, candidates) => {
+                    if !start.is_empty() {
+                        return Err(syn::Error::new(span, "start already exists"));
+                    }
+                    start = candidates;
+                }
             }
         }
 
@@ -127,6 +153,7 @@ impl Parse for StateMachineConfig {
             history_capacity,
             init_state,
             curr_state,
+            start,
         })
     }
 }
@@ -137,41 +164,10 @@ impl StateMachineConfig {
         name_to_index: &HashMap<Ident, usize>,
         state_len: usize,
     ) -> syn::Result<StateMachineConfigImpl> {
-        let init_state = match &self.init_state {
-            Some(StateRef::Named(name)) => {
-                if let Some(index) = name_to_index.get(name) {
-                    *index
-                } else {
-                    return Err(syn::Error::new_spanned(
-                        name,
-                        "Initial state with this name not found.",
-                    ));
-                }
-            }
-            Some(StateRef::Index(i)) => {
-                let index = i.base10_parse::<usize>()?;
-                if index >= state_len {
-                    return Err(syn::Error::new_spanned(
-                        i,
-                        "Initial state index out of bounds.",
-                    ));
-                }
-                index
-            }
-            None => 0,
-        };
-
-        let curr_state = match &self.curr_state {
-            Some(StateRef::Named(name)) => {
-                if let Some(index) = name_to_index.get(name) {
-                    *index
-                } else {
-                    return Err(syn::Error::new_spanned(
-                        name,
-                        "Initial state with this name not found.",
-                    ));
-                }
-            }
+        let resolve = |state_ref: Option<&StateRef>| match state_ref {
+            Some(StateRef::Named(name)) => name_to_index.get(name).copied().ok_or_else(|| {
+                syn::Error::new_spanned(name, "Initial state with this name not found.")
+            }),
             Some(StateRef::Index(i)) => {
                 let index = i.base10_parse::<usize>()?;
                 if index >= state_len {
@@ -180,16 +176,22 @@ impl StateMachineConfig {
                         "Initial state index out of bounds.",
                     ));
                 }
-                index
+                Ok(index)
             }
-            None => 0,
+            None => Ok(0),
         };
 
         Ok(StateMachineConfigImpl {
             #[cfg(feature = "history")]
             history_capacity: self.history_capacity.clone(),
-            curr_state,
-            init_state,
+            curr_state: resolve(self.curr_state.as_ref())?,
+            init_state: resolve(self.init_state.as_ref())?,
+            #[cfg(feature = "hsm")]
+            start: self
+                .start
+                .iter()
+                .map(|(condition, state_ref)| Ok((condition.clone(), resolve(Some(state_ref))?)))
+                .collect::<syn::Result<_>>()?,
         })
     }
 }
@@ -197,6 +199,7 @@ impl StateMachineConfig {
 enum ConfigAttr {
     InitState(StateRef),
     CurrState(StateRef),
+    Start(Span, Vec<(GuardCondition, StateRef)>),
     #[cfg(feature = "history")]
     HistoryCapacity(LitInt),
 }
@@ -218,12 +221,39 @@ impl Parse for ConfigAttr {
             input.parse::<kw::curr_state>()?;
             input.parse::<Token![=]>()?;
             Ok(ConfigAttr::CurrState(input.parse()?))
+        } else if lookahead.peek(kw::start) {
+            let span = input.parse::<kw::start>()?.span;
+            input.parse::<Token![=]>()?;
+            let content;
+            bracketed!(content in input);
+            let candidates = Punctuated::<StartCandidate, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .map(|candidate| (candidate.condition, candidate.state))
+                .collect();
+            Ok(ConfigAttr::Start(span, candidates))
         } else {
             Err(lookahead.error())
         }
     }
 }
 
+/// `guard_expression => state_ref`
+struct StartCandidate {
+    condition: GuardCondition,
+    state: StateRef,
+}
+
+impl Parse for StartCandidate {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let condition = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        Ok(Self {
+            condition,
+            state: input.parse()?,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum StateRef {
     Named(Ident),
@@ -21,11 +21,10 @@ struct LightTimer(Timer);
 impl LightTimer {
     fn light_timer(
         entity: In<GuardContext>,
-        time: Res<Time<Fixed>>,
-        mut query: Query<&mut LightTimer>,
+        mut query: Query<(&StateClock, &mut LightTimer)>,
     ) -> bool {
-        let mut timer = query.get_mut(entity.service_target).unwrap();
-        timer.0.tick(time.delta());
+        let (clock, mut timer) = query.get_mut(entity.service_target).unwrap();
+        timer.0.tick(clock.delta());
         timer.0.is_finished()
     }
 }
@@ -90,6 +89,7 @@ fn setup(mut commands: Commands) {
         Name::new("Blinking Light Paused"),
         StateLifecycle::default(),
         LightTimer(Timer::from_seconds(1.0, TimerMode::Repeating)),
+        StateClock::new(StateClockMode::FixedUpdate),
     ));
 }
 
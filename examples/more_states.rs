@@ -14,25 +14,11 @@ fn debug_on_transition(info: &str) -> impl Fn(In<TransitionContext>) {
     }
 }
 
-fn is_up(_entity: In<GuardContext>, input: Res<ButtonInput<KeyCode>>) -> bool {
-    input.just_pressed(KeyCode::ArrowUp)
-}
-
-fn is_down(_entity: In<GuardContext>, input: Res<ButtonInput<KeyCode>>) -> bool {
-    input.just_pressed(KeyCode::ArrowDown)
-}
-
 fn register_condition(
     mut commands: Commands,
-    mut guard_registry: ResMut<GuardRegistry>,
     mut action_registry: ResMut<ActionRegistry>,
     mut transition_registry: ResMut<TransitionRegistry>,
 ) {
-    let id = commands.register_system(is_up);
-    guard_registry.insert("is_up", id);
-    let id = commands.register_system(is_down);
-    guard_registry.insert("is_down", id);
-
     let id = commands.register_system(debug_on_state("进入状态"));
     action_registry.insert("debug_on_enter", id);
     let id = commands.register_system(debug_on_state("退出状态"));
@@ -59,8 +45,8 @@ fn setup(mut commands: Commands) {
         .spawn((
             Name::new("ON1"),
             HsmState::default(),
-            GuardEnter::new("is_up"),
-            GuardExit::new("is_down"),
+            GuardEnter(GuardCondition::call("just_pressed", ["ArrowUp"])),
+            GuardExit(GuardCondition::call("just_pressed", ["ArrowDown"])),
             BeforeEnterSystem::new("debug_before_enter"),
             AfterExitSystem::new("debug_after_exit"),
             AfterEnterSystem::new("debug_on_enter"),
@@ -72,8 +58,8 @@ fn setup(mut commands: Commands) {
         .spawn((
             Name::new("ON2"),
             HsmState::default(),
-            GuardEnter::new("is_up"),
-            GuardExit::new("is_down"),
+            GuardEnter(GuardCondition::call("just_pressed", ["ArrowUp"])),
+            GuardExit(GuardCondition::call("just_pressed", ["ArrowDown"])),
             BeforeEnterSystem::new("debug_before_enter"),
             AfterExitSystem::new("debug_after_exit"),
             AfterEnterSystem::new("debug_on_enter"),
@@ -85,8 +71,8 @@ fn setup(mut commands: Commands) {
         .spawn((
             Name::new("ON3"),
             HsmState::default(),
-            GuardEnter::new("is_up"),
-            GuardExit::new("is_down"),
+            GuardEnter(GuardCondition::call("just_pressed", ["ArrowUp"])),
+            GuardExit(GuardCondition::call("just_pressed", ["ArrowDown"])),
             BeforeEnterSystem::new("debug_before_enter"),
             AfterExitSystem::new("debug_after_exit"),
             AfterEnterSystem::new("debug_on_enter"),
@@ -121,15 +107,15 @@ fn setup(mut commands: Commands) {
 /// # 流程图\Flowchart
 ///    [`OFF`]
 ///
-///  is_up↓↑is_dowm
+///  ArrowUp↓↑ArrowDown
 ///
 ///    [`ON1`]
 ///
-///  is_up↓↑is_down
+///  ArrowUp↓↑ArrowDown
 ///
 ///    [`ON2`]
 ///
-///  is_up↓↑is_down
+///  ArrowUp↓↑ArrowDown
 ///
 ///    [`ON3`]
 ///    
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use bevy_hsm::{StateMachinePlugin, prelude::*, system_registry};
+use bevy_hsm::{StateMachinePlugin, prelude::*};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 struct ToggleEvent;
@@ -33,20 +33,7 @@ fn log_on_update(
     Some(contexts)
 }
 
-fn setup_fsm(
-    mut commands: Commands,
-    mut action_registry: ResMut<ActionRegistry>,
-    mut transition_registry: ResMut<TransitionRegistry>,
-) {
-    let system_id = commands.register_system(log_on_enter);
-    action_registry.insert("log_on_enter", system_id);
-    let system_id = commands.register_system(log_on_exit);
-    action_registry.insert("log_on_exit", system_id);
-    system_registry!(<commands,transition_registry>[
-        "log_before_enter"=>log_on_transition("before enter"),
-        "log_after_exit"=>log_on_transition("after exit"),
-    ]);
-
+fn setup_fsm(mut commands: Commands) {
     let state_a = commands
         .spawn((
             FsmState,
@@ -129,7 +116,11 @@ fn main() {
     app.add_plugins(DefaultPlugins)
         .add_plugins(StateMachinePlugin::default());
 
-    app.add_action_system(Update, "log_on_update", log_on_update);
+    app.add_action_system(Update, "log_on_update", log_on_update)
+        .register_action("log_on_enter", log_on_enter)
+        .register_action("log_on_exit", log_on_exit)
+        .register_transition("log_before_enter", log_on_transition("before enter"))
+        .register_transition("log_after_exit", log_on_transition("after exit"));
 
     app.add_systems(Startup, setup_fsm)
         .add_systems(Update, handle_input);
@@ -0,0 +1,128 @@
+//! 演示在单线程执行器下使用状态机：`wasm32` 上的调度本就是单线程的，原生平台上则强制各调度使用单线程执行器，
+//! 使两者的行为一致。库本身不需要额外的代码路径，动作缓存的闭包只会在独占的 `&mut World` 上下文中调用。
+//!
+//! Demonstrates using a state machine under single-threaded executors: schedules are single-threaded on `wasm32`
+//! already, and on native targets every schedule is forced onto the single-threaded executor so both behave the same.
+//! The library itself needs no separate code path, since the action buffer closures only run from an exclusive
+//! `&mut World` context.
+//!
+//! ```sh
+//! cargo run --example single_threaded
+//! cargo build --example single_threaded --target wasm32-unknown-unknown
+//! ```
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::ecs::schedule::{ExecutorKind, ScheduleLabel};
+use bevy::{app::ScheduleRunnerPlugin, prelude::*};
+use bevy_hsm::{prelude::*, system_registry};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct Toggle;
+
+#[derive(Resource, Deref, DerefMut)]
+struct ToggleTimer(Timer);
+
+fn log_on_enter(In(context): In<ActionContext>, query: Query<&Name>) {
+    if let Ok(name) = query.get(context.state()) {
+        println!("Entering state: {}", name);
+    }
+}
+
+fn log_on_update(
+    In(contexts): In<Vec<ActionContext>>,
+    query: Query<&Name>,
+) -> Option<Vec<ActionContext>> {
+    for name in query.iter_many(contexts.iter().map(|c| c.state())) {
+        println!("Updating state: {}", name);
+    }
+    Some(contexts)
+}
+
+fn setup(mut commands: Commands, mut action_registry: ResMut<ActionRegistry>) {
+    system_registry!(<commands, action_registry>[
+        "log_on_enter" => log_on_enter,
+    ]);
+
+    let on = commands
+        .spawn((
+            FsmState,
+            Name::new("On"),
+            AfterEnterSystem::new("log_on_enter"),
+            OnUpdateSystem::new("Update:log_on_update"),
+        ))
+        .id();
+    let off = commands
+        .spawn((
+            FsmState,
+            Name::new("Off"),
+            AfterEnterSystem::new("log_on_enter"),
+        ))
+        .id();
+
+    let mut graph = FsmGraph::new(on);
+    graph
+        .with_event(on, Toggle, off)
+        .with_event(off, Toggle, on);
+    let graph_id = commands.spawn(graph).id();
+
+    commands.spawn((
+        FsmStateMachine::with(
+            graph_id,
+            on,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        Name::new("Light"),
+    ));
+}
+
+fn toggle(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: ResMut<ToggleTimer>,
+    state_machine: Single<Entity, With<FsmStateMachine>>,
+) {
+    if timer.tick(time.delta()).just_finished() {
+        commands.trigger(FsmTrigger::with_event(
+            *state_machine,
+            EventData::new(Toggle),
+        ));
+    }
+}
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins(
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / 30.0,
+        ))),
+    )
+    .add_plugins(StateMachinePlugin::default())
+    .insert_resource(ToggleTimer(Timer::from_seconds(1.0, TimerMode::Repeating)))
+    .add_action_system(Update, "log_on_update", log_on_update)
+    .add_systems(Startup, setup)
+    .add_systems(Update, toggle);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    force_single_threaded(&mut app);
+
+    app.run();
+}
+
+/// 在原生平台上也强制使用单线程执行器，与浏览器中的行为保持一致
+///
+/// Force single-threaded executors on native targets too, matching the behaviour in the browser
+#[cfg(not(target_arch = "wasm32"))]
+fn force_single_threaded(app: &mut App) {
+    for label in [
+        Startup.intern(),
+        First.intern(),
+        Update.intern(),
+        Last.intern(),
+    ] {
+        app.edit_schedule(label, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+    }
+}
@@ -0,0 +1,98 @@
+//! 生成大量层级与条件各异的状态机，测量转换吞吐量与每帧开销。
+//!
+//! Spawns a large number of state machines with mixed hierarchies and conditions, measuring transition throughput and
+//! per-frame overhead.
+//!
+//! ```sh
+//! cargo run --release --example stress_hsm -- [machines] [frames]
+//! ```
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_hsm::prelude::*;
+
+#[derive(Resource, Default)]
+struct Entered(u64);
+
+fn count_enter(_: In<ActionContext>, mut entered: ResMut<Entered>) {
+    entered.0 += 1;
+}
+
+/// 按序号交替生成三种状态机：每帧来回转换的扁平状态机、逐层进入并在叶状态往返的深层状态机，以及条件从不成立的空闲状态机
+///
+/// Spawns three kinds of machines by index: flat machines toggling every frame, deep machines entering level by level and
+/// bouncing at the leaf, and idle machines whose conditions never hold
+fn spawn_machine(world: &mut World, index: usize) {
+    match index % 3 {
+        0 => world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="always", guard_exit="always", after_enter="count_enter")]: Toggle
+            )
+        )),
+        1 => world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="always", after_enter="count_enter")]: Outer(
+                    #[state(guard_enter="always", after_enter="count_enter")]: Middle(
+                        #[state(guard_enter="always", guard_exit="always", after_enter="count_enter")]: Leaf
+                    )
+                )
+            )
+        )),
+        _ => world.spawn(hsm!(
+            StateLifecycle::default(),
+            #[state]: Root(
+                #[state(guard_enter="never", after_enter="count_enter")]: Idle
+            )
+        )),
+    };
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let machines = args.next().and_then(|s| s.parse().ok()).unwrap_or(50_000);
+    let frames = args.next().and_then(|s| s.parse().ok()).unwrap_or(300);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<Entered>()
+        .register_guard("always", |_: In<GuardContext>| true)
+        .register_guard("never", |_: In<GuardContext>| false)
+        .register_action("count_enter", count_enter);
+
+    let start = Instant::now();
+    for index in 0..machines {
+        spawn_machine(app.world_mut(), index);
+    }
+    app.world_mut().flush();
+    println!(
+        "Spawned {} state machines in {:?}",
+        machines,
+        start.elapsed()
+    );
+
+    let mut slowest = Duration::ZERO;
+    let start = Instant::now();
+    for _ in 0..frames {
+        let frame = Instant::now();
+        app.update();
+        slowest = slowest.max(frame.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    let entered = app.world().resource::<Entered>().0;
+    println!(
+        "{} frames in {:?}: {:?}/frame on average, {:?} at worst",
+        frames,
+        elapsed,
+        elapsed / frames.max(1),
+        slowest
+    );
+    println!(
+        "{} states entered, {:.0} enters/s",
+        entered,
+        entered as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy_hsm::prelude::*;
+
+#[derive(Resource)]
+struct Menu {
+    state_machine: Entity,
+    main: Entity,
+    settings: Entity,
+    audio: Entity,
+}
+
+fn spawn_page(commands: &mut Commands, title: &str, hint: &str, color: Color) -> Entity {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(color),
+            children![
+                (Text::new(title), TextFont::from_font_size(48.0)),
+                Text::new(hint)
+            ],
+        ))
+        .id()
+}
+
+fn startup(mut commands: Commands) {
+    commands.spawn(Camera2d);
+
+    let main_page = spawn_page(
+        &mut commands,
+        "Main Menu",
+        "[S] Settings",
+        Color::srgb(0.1, 0.1, 0.2),
+    );
+    let settings_page = spawn_page(
+        &mut commands,
+        "Settings",
+        "[A] Audio  [Esc] Back",
+        Color::srgb(0.1, 0.2, 0.1),
+    );
+    let audio_page = spawn_page(
+        &mut commands,
+        "Audio",
+        "[Esc] Back",
+        Color::srgb(0.2, 0.1, 0.1),
+    );
+
+    // 子状态活动时其父状态仍处于活动中, 因此子页面会叠加在父页面之上
+    // A super-state stays active while its sub-state is active, so sub-pages are stacked on top of their parents
+    let main = commands
+        .spawn((
+            Name::new("Main"),
+            HsmState::default(),
+            HsmVisibilityBinding::display(main_page),
+        ))
+        .id();
+    let settings = commands
+        .spawn((
+            Name::new("Settings"),
+            HsmState::default(),
+            HsmVisibilityBinding::new(settings_page),
+        ))
+        .id();
+    let audio = commands
+        .spawn((
+            Name::new("Audio"),
+            HsmState::default(),
+            HsmVisibilityBinding::display(audio_page),
+        ))
+        .id();
+
+    let mut state_tree = StateTree::new(main);
+    state_tree
+        .with_child(main, settings)
+        .with_child(settings, audio);
+
+    let state_machine = commands.spawn_empty().id();
+    commands.entity(state_machine).insert((
+        Name::new("Menu"),
+        HsmStateMachine::with(
+            state_machine,
+            main,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+        state_tree,
+    ));
+
+    commands.insert_resource(Menu {
+        state_machine,
+        main,
+        settings,
+        audio,
+    });
+}
+
+fn key_event(mut commands: Commands, input: Res<ButtonInput<KeyCode>>, menu: Res<Menu>) {
+    if input.just_pressed(KeyCode::KeyS) {
+        commands.trigger(HsmTrigger::chain(menu.state_machine, menu.settings));
+    }
+    if input.just_pressed(KeyCode::KeyA) {
+        commands.trigger(HsmTrigger::chain(menu.state_machine, menu.audio));
+    }
+    if input.just_pressed(KeyCode::Escape) {
+        commands.trigger(HsmTrigger::to_super(menu.state_machine));
+    }
+    if input.just_pressed(KeyCode::Home) {
+        commands.trigger(HsmTrigger::chain(menu.state_machine, menu.main));
+    }
+}
+
+/// # UI 菜单示例\UI Menu Example
+///
+/// 使用 [`HsmVisibilityBinding`] 根据状态显示或隐藏菜单页面
+///
+/// Uses [`HsmVisibilityBinding`] to show or hide menu pages based on the active state
+///
+/// [`Main`] -S-> [`Settings`] -A-> [`Audio`], Esc 返回上一级\Esc goes back one level
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .add_systems(Startup, startup)
+        .add_systems(Update, key_event)
+        .run();
+}
@@ -1,48 +1,209 @@
-use std::{any::TypeId, fmt::Debug, hash::Hash, marker::PhantomData, mem::swap, sync::Arc};
+use std::{cell::Cell, fmt::Debug, hash::Hash, mem::swap, rc::Rc, sync::Arc};
 
 use bevy::{
     app::App,
     ecs::{
-        schedule::{IntoScheduleConfigs, ScheduleLabel},
+        schedule::{InternedScheduleLabel, IntoScheduleConfigs, ScheduleLabel},
+        system::SystemParam,
         world::unsafe_world_cell::UnsafeWorldCell,
     },
-    platform::collections::{Equivalent, HashMap, HashSet},
+    platform::collections::{Equivalent, HashMap},
     prelude::*,
 };
+use smallvec::SmallVec;
 
 use crate::{
-    action_dispatcher::system_state_trait::ExpandScheduleLabelFunction, context::*,
-    error::StateMachineError, labels::SystemLabel, state_actions::*,
+    action_dispatcher::system_state_trait::ExpandScheduleLabelFunction,
+    context::*,
+    error::StateMachineError,
+    labels::{ActionKey, SystemLabel},
+    state_actions::*,
 };
 
 /// # 一个对状态机系统的抽象\An abstraction of a state machine system
 /// * In : 输入上下文
 /// - In : Input context
-/// * Out : 输出上下文
-/// - Out : Output context
-///     * None: 下一帧将不再执行该状态
-///     - None: The next frame will no longer execute this state
-///     * Some: 继续执行该状态, 里面的数量为空时将视为None
-///     - Some: Continue executing this state. When the quantity inside is empty, it will be treated as None
-///         * 过滤条件 :
-///         * Filter Condition:
-///             * `OnUpdate`: 继续执行该状态
-///             - `OnUpdate`: Continue executing this state
-///             * `BeforeExit`  : 停止执行该状态
-///             - `BeforeExit`  : Stop executing this state
+/// * Out : 输出上下文，任意实现了 [`ActionSystemOutput`] 的类型
+/// - Out : Output context, any type implementing [`ActionSystemOutput`]
+///     * `Option<Vec<ActionContext>>`:
+///         * None: 下一帧将不再执行该状态
+///         - None: The next frame will no longer execute this state
+///         * Some: 继续执行该状态, 里面的数量为空时将视为None
+///         - Some: Continue executing this state. When the quantity inside is empty, it will be treated as None
+///             * 过滤条件 :
+///             * Filter Condition:
+///                 * `OnUpdate`: 继续执行该状态
+///                 - `OnUpdate`: Continue executing this state
+///                 * `BeforeExit`  : 停止执行该状态
+///                 - `BeforeExit`  : Stop executing this state
+///     * `Vec<(ActionContext, ActionVerdict)>`: 为每个上下文单独给出 [`ActionVerdict`]
+///     - `Vec<(ActionContext, ActionVerdict)>`: gives each context its own [`ActionVerdict`]
 pub trait IntoActionSystem<M> {
-    fn into_system(self) -> impl IntoSystem<In<Vec<ActionContext>>, Option<Vec<ActionContext>>, M>;
+    type Out: ActionSystemOutput;
+
+    fn into_system(self) -> impl System<In = In<Vec<ActionContext>>, Out = Self::Out>;
 }
 
-impl<F, M> IntoActionSystem<M> for F
+impl<F, M, O> IntoActionSystem<(O, M)> for F
 where
-    F: IntoSystem<In<Vec<ActionContext>>, Option<Vec<ActionContext>>, M>,
+    F: IntoSystem<In<Vec<ActionContext>>, O, M>,
+    O: ActionSystemOutput,
 {
-    fn into_system(self) -> impl IntoSystem<In<Vec<ActionContext>>, Option<Vec<ActionContext>>, M> {
-        self
+    type Out = O;
+
+    fn into_system(self) -> impl System<In = In<Vec<ActionContext>>, Out = Self::Out> {
+        IntoSystem::into_system(self)
     }
 }
 
+/// # 动作裁决\Action Verdict
+/// * 批量动作系统对单个上下文的处理结果，使一个系统可以让部分实体继续执行、部分停止、部分请求退出。
+/// - The outcome of a batched action system for a single context, letting one system continue some agents, stop others
+///   and request exit for a few.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct Stamina(f32);
+///
+/// fn run(
+///     contexts: In<Vec<ActionContext>>,
+///     query: Query<&Stamina>,
+/// ) -> Vec<(ActionContext, ActionVerdict)> {
+///     contexts
+///         .iter()
+///         .map(|&context| match query.get(context.service_target) {
+///             Ok(stamina) if stamina.0 > 0.0 => (context, ActionVerdict::Keep),
+///             Ok(_) => (context, ActionVerdict::Exit),
+///             Err(_) => (context, ActionVerdict::Drop),
+///         })
+///         .collect()
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionVerdict {
+    /// 下一帧继续执行该上下文
+    ///
+    /// Keep running the context next frame
+    #[default]
+    Keep,
+    /// 下一帧不再执行该上下文
+    ///
+    /// Stop running the context from the next frame
+    Drop,
+    /// 不再执行该上下文，并请求状态机退出该状态
+    ///
+    /// Stop running the context and ask the state machine to exit the state
+    Exit,
+}
+
+/// # 动作系统输出\Action System Output
+/// * 动作系统可以返回的类型，参见 [`IntoActionSystem`]。
+/// - Types an action system may return, see [`IntoActionSystem`].
+pub trait ActionSystemOutput: Send + 'static {
+    /// 拆分为继续执行的上下文与请求退出的上下文
+    ///
+    /// Split into the contexts to keep and the contexts requesting exit
+    fn into_verdicts(self) -> (Vec<ActionContext>, Vec<ActionContext>);
+}
+
+impl ActionSystemOutput for Option<Vec<ActionContext>> {
+    fn into_verdicts(self) -> (Vec<ActionContext>, Vec<ActionContext>) {
+        (self.unwrap_or_default(), Vec::new())
+    }
+}
+
+impl ActionSystemOutput for Vec<(ActionContext, ActionVerdict)> {
+    fn into_verdicts(self) -> (Vec<ActionContext>, Vec<ActionContext>) {
+        let mut keep = Vec::new();
+        let mut exit = Vec::new();
+        for (context, verdict) in self {
+            match verdict {
+                ActionVerdict::Keep => keep.push(context),
+                ActionVerdict::Drop => {}
+                ActionVerdict::Exit => exit.push(context),
+            }
+        }
+        (keep, exit)
+    }
+}
+
+/// # 退出请求\Exit Requests
+/// * 让更新动作直接请求状态机退出某个上下文中的状态，无需设置标志并等待退出条件；
+///   退出在命令应用时进行，状态已不再激活时忽略。效果与返回 [`ActionVerdict::Exit`] 相同。
+/// - Lets update actions ask a state machine to leave the state of a context directly, instead of setting a flag and
+///   waiting for the exit condition. The exit happens when commands are applied and is ignored once the state is no longer
+///   active. Same effect as returning [`ActionVerdict::Exit`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct Health(f32);
+///
+/// fn attack(
+///     contexts: In<Vec<ActionContext>>,
+///     query: Query<&Health>,
+///     mut exits: HsmExitRequests,
+/// ) -> Option<Vec<ActionContext>> {
+///     for context in contexts.iter() {
+///         if query.get(context.service_target).is_ok_and(|health| health.0 < 10.0) {
+///             exits.request(*context);
+///         }
+///     }
+///     Some(contexts.0)
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct HsmExitRequests<'w, 's> {
+    commands: Commands<'w, 's>,
+}
+
+impl HsmExitRequests<'_, '_> {
+    /// 请求退出上下文中的状态
+    ///
+    /// Request exiting the state of the context
+    pub fn request(&mut self, context: ActionContext) {
+        self.commands
+            .queue(move |world: &mut World| request_exit(world, context));
+    }
+}
+
+/// 请求状态机退出上下文中的状态，状态已不再激活时忽略
+///
+/// Ask the state machine to exit the state of the context, ignored once the state is no longer active
+#[cfg_attr(not(feature = "hsm"), allow(unused_variables))]
+pub(crate) fn request_exit(world: &mut World, context: ActionContext) {
+    #[cfg(feature = "hsm")]
+    if let Some(state_machine) =
+        world.get::<crate::hsm::state_machine::HsmStateMachine>(context.state_machine)
+    {
+        use crate::hsm::{event::HsmTrigger, state_tree::StateTree};
+
+        let curr_state = state_machine.curr_state_id();
+        let Some(state_tree) = world.get::<StateTree>(state_machine.state_tree()) else {
+            return;
+        };
+        if curr_state == context.state() {
+            world.trigger(HsmTrigger::to_super(context.state_machine));
+        } else if state_tree
+            .path_iter(curr_state)
+            .any(|state| state == context.state())
+            && let Some(super_state) = state_tree.get_super_state(context.state())
+        {
+            world.trigger(HsmTrigger::chain(context.state_machine, super_state));
+        }
+        return;
+    }
+    warn!(
+        "[ActionVerdict::Exit] {:?} is not supported by this state machine",
+        context
+    );
+}
+
 /// A trait for adding, removing, and replacing action systems in a Bevy `App` or `World`.
 /// This provides a unified interface for managing action systems.
 ///
@@ -53,7 +214,8 @@ pub trait SystemState {
     ///
     /// # Arguments
     ///
-    /// * `schedule`: 将要添加动作系统的 `Schedule`。
+    /// * `schedule`: 将要添加动作系统的 `Schedule`，可以是任意调度标签的值，包括携带数据的标签。
+    ///   状态上对应的键见 [`OnUpdateSystem::in_schedule`]。
     /// * `action_name`: 动作系统的唯一名称。
     /// * `system`: 要添加的动作系统，必须实现 `IntoActionSystem`。
     ///
@@ -61,7 +223,8 @@ pub trait SystemState {
     ///
     /// # Arguments
     ///
-    /// * `schedule`: The schedule to which the action system will be added.
+    /// * `schedule`: The schedule to which the action system will be added, any schedule label value including labels
+    ///   carrying data. See [`OnUpdateSystem::in_schedule`] for the matching key on states.
     /// * `action_name`: A unique name for the action system.
     /// * `system`: The action system to add, which must implement `IntoActionSystem`.
     ///
@@ -81,7 +244,7 @@ pub trait SystemState {
     ///
     fn add_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Default,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self;
@@ -177,7 +340,7 @@ pub trait SystemState {
     ///
     fn replace_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Clone,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self;
@@ -186,7 +349,7 @@ pub trait SystemState {
 impl SystemState for App {
     fn add_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Default,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self {
@@ -207,7 +370,7 @@ impl SystemState for App {
 
     fn replace_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Clone,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self {
@@ -220,7 +383,7 @@ impl SystemState for App {
 impl SystemState for World {
     fn add_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Default,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self {
@@ -237,7 +400,7 @@ impl SystemState for World {
             |world: &mut World, mut systems: Mut<'_, ActionSystemRegistry>| {
                 let index = schedule.push_system_index(action_name, &mut systems);
                 let mut schedules = world.resource_mut::<Schedules>();
-                let schedule = schedules.entry(schedule);
+                let schedule = schedules.entry(schedule.intern());
                 schedule.add_systems(system.in_set(index));
             },
         );
@@ -261,7 +424,7 @@ impl SystemState for World {
 
     fn replace_action_system<M>(
         &mut self,
-        schedule: impl ScheduleLabel + Clone,
+        schedule: impl ScheduleLabel,
         action_name: impl Into<SystemLabel>,
         system: impl IntoActionSystem<M>,
     ) -> &mut Self {
@@ -273,6 +436,16 @@ impl SystemState for World {
     }
 }
 
+/// 获取动作缓存的入口
+///
+/// `Send + Sync` 约束来自 [`Resource`]，而不是对多线程的假设：闭包只会在持有 `&mut World` 的独占上下文中调用，
+/// 因此在单线程执行器（例如 `wasm32`）下同样可用。
+///
+/// Entry point used to reach an action buffer
+///
+/// The `Send + Sync` bounds come from [`Resource`] rather than from any threading assumption: the closure is only
+/// ever called from an exclusive `&mut World` context, so it works the same under single-threaded executors (e.g. `wasm32`).
+#[doc(hidden)]
 pub type GetBufferId =
     Arc<dyn Fn(&mut World, Box<dyn FnOnce(&mut StateActionBuffer)>) + Send + Sync + 'static>;
 
@@ -282,86 +455,394 @@ pub type GetBufferId =
 /// # 作用\Effect
 /// * 用于获取对应时间点的缓存资源入口
 /// - Used to get the entry point of the cache resource at a certain time
-/// * Key: "`ScheduleLabel`:`action_name`"
+/// * Key: [`ActionKey`]，旧格式的键按调度名称解析为最先以该名称注册的调度；多个调度共用同一名称时，
+///   配置校验会报告 [`StateConfigIssue::AmbiguousSchedule`](crate::hsm::validation::StateConfigIssue::AmbiguousSchedule)
+/// - Key: [`ActionKey`]; legacy keys resolve by schedule name to the first schedule registered under that name. When
+///   several schedules share a name, configuration validation reports
+///   [`StateConfigIssue::AmbiguousSchedule`](crate::hsm::validation::StateConfigIssue::AmbiguousSchedule)
 /// * Value: 是如何通过[World]获取缓存[StateActionBuffer]的方法
 /// - Value: How to get the cache resource through [World]
+#[doc(hidden)]
 #[derive(Resource, Default, Clone)]
-pub struct ActionDispatch(HashMap<SystemLabel, GetBufferId>);
+pub struct ActionDispatch {
+    buffers: HashMap<ActionKey, GetBufferId>,
+    schedules: HashMap<String, SmallVec<[InternedScheduleLabel; 1]>>,
+}
 
 impl ActionDispatch {
-    pub(super) fn insert(&mut self, action_name: impl Into<SystemLabel>, system_id: GetBufferId) {
-        let action_name = action_name.into();
-        self.0.insert(action_name, system_id);
+    pub(super) fn insert(&mut self, key: ActionKey, system_id: GetBufferId) {
+        let schedules = self.schedules.entry(key.schedule_name()).or_default();
+        if !schedules.contains(&key.schedule()) {
+            schedules.push(key.schedule());
+        }
+        self.buffers.insert(key, system_id);
     }
 
-    pub(super) fn remove<Q>(&mut self, action_name: &Q) -> Option<GetBufferId>
-    where
-        Q: Hash + Equivalent<SystemLabel> + ?Sized,
-    {
-        self.0.remove(action_name)
+    /// 以给定名称注册过动作系统的所有调度，按注册顺序排列
+    ///
+    /// Every schedule registered with action systems under the given name, in registration order
+    pub fn schedules_named(&self, schedule_name: &str) -> &[InternedScheduleLabel] {
+        self.schedules
+            .get(schedule_name)
+            .map_or(&[], SmallVec::as_slice)
     }
 
-    pub(super) fn get<Q>(&self, action_name: &Q) -> Option<GetBufferId>
-    where
-        Q: Hash + Equivalent<SystemLabel> + ?Sized,
-    {
-        self.0.get(action_name).cloned()
+    pub(super) fn remove(&mut self, key: &ActionKey) -> Option<GetBufferId> {
+        self.buffers.remove(key)
+    }
+
+    /// 将旧格式的键解析为已注册调度的键，其余键原样返回
+    ///
+    /// Resolve a legacy key to the key of a registered schedule, returning other keys unchanged
+    pub fn resolve(&self, key: &ActionKey) -> ActionKey {
+        let Some(schedule_name) = key.legacy_schedule() else {
+            return key.clone();
+        };
+        match self.schedules_named(schedule_name).first() {
+            Some(schedule) => key.with_schedule(*schedule),
+            None => key.clone(),
+        }
+    }
+
+    pub(super) fn get(&self, key: &ActionKey) -> Option<GetBufferId> {
+        self.buffers
+            .get(key)
+            .or_else(|| self.buffers.get(&self.resolve(key)))
+            .cloned()
+    }
+
+    /// 注册了给定名称的动作系统的所有调度
+    ///
+    /// Every schedule an action system with the given name is registered in
+    pub fn schedules_of(&self, name: &SystemLabel) -> Vec<InternedScheduleLabel> {
+        self.buffers
+            .keys()
+            .filter(|key| key.name() == name)
+            .map(ActionKey::schedule)
+            .collect()
+    }
+
+    /// 所有已注册的动作键
+    ///
+    /// Every registered action key
+    pub fn keys(&self) -> impl Iterator<Item = &ActionKey> {
+        self.buffers.keys()
+    }
+
+    /// 获取一个动作缓存的只读快照，键的格式与 [`OnUpdateSystem`] 相同
+    ///
+    /// Get a read-only snapshot of an action buffer, keyed the same way as [`OnUpdateSystem`]
+    /// # 示例\Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_hsm::prelude::*;
+    /// fn why_not_running(world: &mut World) {
+    ///     if let Some(snapshot) = ActionDispatch::snapshot(world, ActionKey::new(Update, "patrol")) {
+    ///         info!("scheduled for next frame: {:?}", snapshot.scheduled);
+    ///     }
+    /// }
+    /// ```
+    pub fn snapshot(world: &mut World, key: impl Into<ActionKey>) -> Option<ActionBufferSnapshot> {
+        let get_buffer = world.get_resource::<ActionDispatch>()?.get(&key.into())?;
+        let snapshot = Rc::new(Cell::new(None));
+        let out = Rc::clone(&snapshot);
+        get_buffer(
+            world,
+            Box::new(move |buffer: &mut StateActionBuffer| out.set(Some(buffer.snapshot()))),
+        );
+        snapshot.take()
     }
-}
 
-#[inline]
-fn action_dispatch_key<T: ScheduleLabel>(action_name: &SystemLabel) -> String {
-    let label = ShortName::of::<T>();
-    if action_name.is_empty() {
-        label.to_string()
-    } else {
-        format!("{}:{}", label, action_name)
+    /// 获取所有动作缓存的只读快照，按键排序
+    ///
+    /// Get read-only snapshots of every action buffer, sorted by key
+    pub fn snapshots(world: &mut World) -> Vec<(ActionKey, ActionBufferSnapshot)> {
+        let mut keys = world
+            .get_resource::<ActionDispatch>()
+            .map(|dispatch| dispatch.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        keys.sort_by_cached_key(ToString::to_string);
+        keys.into_iter()
+            .filter_map(|key| Some((key.clone(), Self::snapshot(world, key)?)))
+            .collect()
     }
 }
 
-#[derive(ScheduleLabel, Hash, Debug, PartialEq, Eq, Clone)]
-pub(super) struct DefaultActionSchedule;
+/// # 动作缓存快照\Action Buffer Snapshot
+/// * [`StateActionBuffer`] 某一时刻的只读副本，用于排查“为什么我的更新动作没有运行”。
+/// - A read-only copy of a [`StateActionBuffer`] at one point in time, for debugging "why didn't my update action run".
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ActionBufferSnapshot {
+    /// 本帧正在处理的上下文
+    ///
+    /// Contexts being processed this frame
+    pub current: Vec<ActionContext>,
+    /// 下一帧将要处理的上下文
+    ///
+    /// Contexts scheduled for the next frame
+    pub scheduled: Vec<ActionContext>,
+    /// 过滤器数量
+    ///
+    /// Number of filters
+    pub filters: usize,
+    /// 拦截器数量
+    ///
+    /// Number of interceptors
+    pub interceptors: usize,
+}
 
-/// 状态机组系统缓存
+/// 状态机组系统缓存，按调度标签的值区分，携带数据的标签的不同取值拥有各自的缓存
 ///
-/// Status machine system cache manager
+/// Status machine system cache manager, keyed by schedule label value so that labels carrying data get a cache per value
 #[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
-pub(super) struct ScheduleActionBuffers<T: ScheduleLabel = DefaultActionSchedule> {
-    buffers: HashMap<SystemLabel, StateActionBuffer>,
-    _marker: PhantomData<T>,
+pub(super) struct ScheduleActionBuffers {
+    buffers: HashMap<InternedScheduleLabel, HashMap<SystemLabel, StateActionBuffer>>,
 }
 
-impl<T: ScheduleLabel> ScheduleActionBuffers<T> {
-    pub fn insert_buffer(&mut self, action_name: SystemLabel, buffer: StateActionBuffer) {
-        self.buffers.insert(action_name, buffer);
+impl ScheduleActionBuffers {
+    pub fn insert_buffer(
+        &mut self,
+        schedule: InternedScheduleLabel,
+        action_name: SystemLabel,
+        buffer: StateActionBuffer,
+    ) {
+        self.buffers
+            .entry(schedule)
+            .or_default()
+            .insert(action_name, buffer);
     }
 
-    pub fn get_buffer<Q>(&self, action_name: &Q) -> Option<&StateActionBuffer>
+    pub fn get_buffer<Q>(
+        &self,
+        schedule: InternedScheduleLabel,
+        action_name: &Q,
+    ) -> Option<&StateActionBuffer>
     where
         Q: Hash + Equivalent<SystemLabel> + ?Sized,
     {
-        self.buffers.get(action_name)
+        self.buffers.get(&schedule)?.get(action_name)
     }
 
-    pub fn get_buffer_mut<Q>(&mut self, action_name: &Q) -> Option<&mut StateActionBuffer>
+    pub fn get_buffer_mut<Q>(
+        &mut self,
+        schedule: InternedScheduleLabel,
+        action_name: &Q,
+    ) -> Option<&mut StateActionBuffer>
     where
         Q: Hash + Equivalent<SystemLabel> + ?Sized,
     {
-        self.buffers.get_mut(action_name)
+        self.buffers.get_mut(&schedule)?.get_mut(action_name)
     }
 
-    pub fn remove_buffer<Q>(&mut self, action_name: &Q) -> Option<StateActionBuffer>
+    pub fn remove_buffer<Q>(
+        &mut self,
+        schedule: InternedScheduleLabel,
+        action_name: &Q,
+    ) -> Option<StateActionBuffer>
     where
         Q: Hash + Equivalent<SystemLabel> + ?Sized,
     {
-        self.buffers.remove(action_name)
+        self.buffers.get_mut(&schedule)?.remove(action_name)
     }
 
-    pub fn contains<Q>(&self, action_name: &Q) -> bool
+    pub fn contains<Q>(&self, schedule: InternedScheduleLabel, action_name: &Q) -> bool
     where
         Q: Hash + Equivalent<SystemLabel> + ?Sized,
     {
-        self.buffers.contains_key(action_name)
+        self.get_buffer(schedule, action_name).is_some()
+    }
+
+    /// 状态机被移除时，清除它在所有缓存中的上下文，避免大量智能体反复生成与销毁时残留的拦截器不断累积
+    ///
+    /// When a state machine is removed, drop its contexts from every buffer so that leftover interceptors do not pile up
+    /// while thousands of agents are spawned and despawned
+    pub(crate) fn on_remove_state_machine<T: Component>(
+        remove: On<Remove, T>,
+        buffers: Option<ResMut<Self>>,
+    ) {
+        let Some(mut buffers) = buffers else {
+            return;
+        };
+        for buffer in buffers.buffers.values_mut().flat_map(HashMap::values_mut) {
+            buffer.remove_state_machine(remove.entity);
+        }
+    }
+
+    /// 状态的更新动作被替换时，把该状态在旧动作缓存中等待运行的上下文转移到新的动作缓存，
+    /// 使同一个状态机在不同调度之间切换时，旧调度中的动作不会在退出后继续运行
+    ///
+    /// When the update actions of a state are replaced, move the contexts of that state waiting in the old action
+    /// buffers to the new ones, so that a machine switching schedules does not keep running the old action after exit
+    pub(crate) fn rebind_state(world: &mut World, state: Entity, old_keys: &[ActionKey]) {
+        let new_keys = world
+            .get_entity(state)
+            .map(|entity| OnUpdateSystems::labels_of(&entity))
+            .unwrap_or_default();
+        let Some(dispatch) = world.get_resource::<ActionDispatch>() else {
+            return;
+        };
+        let old_keys = old_keys
+            .iter()
+            .map(|key| dispatch.resolve(key))
+            .collect::<SmallVec<[_; 2]>>();
+        let new_keys = new_keys
+            .iter()
+            .map(|key| dispatch.resolve(key))
+            .collect::<SmallVec<[_; 2]>>();
+        let Some(mut buffers) = world.get_resource_mut::<Self>() else {
+            return;
+        };
+
+        let mut pending = ActionContexts::default();
+        for key in old_keys.iter().filter(|key| !new_keys.contains(key)) {
+            if let Some(buffer) = buffers.get_buffer_mut(key.schedule(), key.name()) {
+                pending.extend(buffer.take_state(state));
+            }
+        }
+        if pending.is_empty() {
+            return;
+        }
+        for key in new_keys.iter().filter(|key| !old_keys.contains(key)) {
+            if let Some(buffer) = buffers.get_buffer_mut(key.schedule(), key.name()) {
+                for context in &pending {
+                    buffer.remove_filter(*context);
+                    buffer.remove_interceptor(*context);
+                    buffer.add(*context);
+                }
+            }
+        }
+    }
+}
+
+/// # 动作上下文集合\Action Context Set
+/// * 按状态机分组保存的 [`ActionContext`] 集合：以状态机实体为键，每个状态机通常只有一两个上下文，
+///   因此添加、移除与查询只需一次哈希查找加一次极短的扫描，移除某个状态机的全部上下文同样是 O(1)。
+/// - A set of [`ActionContext`]s grouped by state machine: keyed by the machine entity, and since a machine rarely has
+///   more than one or two contexts, adding, removing and looking up take one hash lookup plus a very short scan.
+///   Dropping every context of a machine is O(1) as well.
+/// * 清空时保留已分配的空间，大量智能体反复进出状态时不会反复分配。
+/// - Clearing keeps the allocated capacity, so thousands of agents churning through states do not keep reallocating.
+#[derive(Default, Clone)]
+pub struct ActionContexts {
+    contexts: HashMap<Entity, SmallVec<[ActionContext; 1]>>,
+    len: usize,
+}
+
+impl ActionContexts {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, context: &ActionContext) -> bool {
+        self.contexts
+            .get(&context.state_machine)
+            .is_some_and(|contexts| contexts.contains(context))
+    }
+
+    /// 添加一个上下文，已存在时返回 `false`
+    ///
+    /// Add a context, returning `false` when it was already present
+    pub fn insert(&mut self, context: ActionContext) -> bool {
+        let contexts = self.contexts.entry(context.state_machine).or_default();
+        if contexts.contains(&context) {
+            return false;
+        }
+        contexts.push(context);
+        self.len += 1;
+        true
+    }
+
+    /// 移除一个上下文，不存在时返回 `false`
+    ///
+    /// Remove a context, returning `false` when it was not present
+    pub fn remove(&mut self, context: &ActionContext) -> bool {
+        let Some(contexts) = self.contexts.get_mut(&context.state_machine) else {
+            return false;
+        };
+        let Some(index) = contexts.iter().position(|c| c == context) else {
+            return false;
+        };
+        contexts.swap_remove(index);
+        if contexts.is_empty() {
+            self.contexts.remove(&context.state_machine);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// 移除某个状态机的全部上下文，返回移除的数量
+    ///
+    /// Remove every context of a state machine, returning how many were removed
+    pub fn remove_state_machine(&mut self, state_machine: Entity) -> usize {
+        let removed = self
+            .contexts
+            .remove(&state_machine)
+            .map_or(0, |contexts| contexts.len());
+        self.len -= removed;
+        removed
+    }
+
+    /// 某个状态机的全部上下文
+    ///
+    /// Every context of a state machine
+    pub fn state_machine(&self, state_machine: Entity) -> &[ActionContext] {
+        self.contexts
+            .get(&state_machine)
+            .map_or(&[], SmallVec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActionContext> {
+        self.contexts.values().flatten()
+    }
+
+    pub fn clear(&mut self) {
+        self.contexts.clear();
+        self.len = 0;
+    }
+}
+
+impl PartialEq for ActionContexts {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|context| other.contains(context))
+    }
+}
+
+impl Eq for ActionContexts {}
+
+impl Debug for ActionContexts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<ActionContext> for ActionContexts {
+    fn extend<T: IntoIterator<Item = ActionContext>>(&mut self, iter: T) {
+        for context in iter {
+            self.insert(context);
+        }
+    }
+}
+
+impl FromIterator<ActionContext> for ActionContexts {
+    fn from_iter<T: IntoIterator<Item = ActionContext>>(iter: T) -> Self {
+        let mut contexts = Self::default();
+        contexts.extend(iter);
+        contexts
+    }
+}
+
+impl<'a> IntoIterator for &'a ActionContexts {
+    type Item = &'a ActionContext;
+    type IntoIter = std::iter::Flatten<
+        bevy::platform::collections::hash_map::Values<'a, Entity, SmallVec<[ActionContext; 1]>>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.contexts.values().flatten()
     }
 }
 
@@ -371,24 +852,27 @@ impl<T: ScheduleLabel> ScheduleActionBuffers<T> {
 /// # 作用\Effect
 /// * 收集当前帧触发的实体, 并且在下一帧进行系统处理
 /// - Collect entities triggered by the current frame and perform system processing in the next frame
+/// * 所有上下文按状态机分组保存（[`ActionContexts`]），过滤只需遍历过滤器本身，而不是整个当前帧状态组
+/// - Every context is grouped by state machine ([`ActionContexts`]); filtering walks the filters only rather than the
+///   whole current frame group
 #[derive(Default, Clone, PartialEq, Eq)]
 pub struct StateActionBuffer {
     /// 当前帧状态组
     ///
     /// Current frame status group
-    pub curr: HashSet<ActionContext>,
+    pub curr: ActionContexts,
     /// 下一帧状态组
     ///
     /// Next frame status group
-    pub next: HashSet<ActionContext>,
+    pub next: ActionContexts,
     /// 过滤器: 用于筛选掉下一帧的状态
     ///
     /// Filter: used to filter out the next frame's status
-    filter: HashSet<ActionContext>,
+    filter: ActionContexts,
     /// 拦截器: 用于筛选掉当前帧的状态
     ///
     /// Interceptor: Use to filter out the current frame's status
-    interceptor: HashSet<ActionContext>,
+    interceptor: ActionContexts,
 }
 
 impl StateActionBuffer {
@@ -400,6 +884,18 @@ impl StateActionBuffer {
         self.curr.iter().cloned().collect()
     }
 
+    /// 获取当前状态的只读快照
+    ///
+    /// Get a read-only snapshot of the current state
+    pub fn snapshot(&self) -> ActionBufferSnapshot {
+        ActionBufferSnapshot {
+            current: self.current_actions(),
+            scheduled: self.next.iter().cloned().collect(),
+            filters: self.filter.len(),
+            interceptors: self.interceptor.len(),
+        }
+    }
+
     /// 更新为当前状态组
     ///
     /// Update to the current state group
@@ -411,7 +907,9 @@ impl StateActionBuffer {
         swap(curr, next);
 
         if !filter.is_empty() {
-            curr.retain(|x| !filter.contains(x));
+            for context in filter.iter() {
+                curr.remove(context);
+            }
             filter.clear();
         }
 
@@ -422,7 +920,13 @@ impl StateActionBuffer {
     ///
     /// Update interceptor
     fn update_interceptor(&mut self) {
-        self.interceptor.extend(self.curr.difference(&self.next));
+        let Self {
+            curr,
+            next,
+            interceptor,
+            ..
+        } = self;
+        interceptor.extend(curr.iter().filter(|c| !next.contains(c)).copied());
     }
 
     /// 添加一个上下文
@@ -464,6 +968,44 @@ impl StateActionBuffer {
         self.interceptor.remove(&context);
     }
 
+    /// 移除某个状态机在缓存中的全部上下文，包括过滤器与拦截器
+    ///
+    /// Remove every context of a state machine from the buffer, filters and interceptors included
+    pub fn remove_state_machine(&mut self, state_machine: Entity) {
+        self.curr.remove_state_machine(state_machine);
+        self.next.remove_state_machine(state_machine);
+        self.filter.remove_state_machine(state_machine);
+        self.interceptor.remove_state_machine(state_machine);
+    }
+
+    /// 移除某个状态在缓存中的全部上下文，返回其中等待下一帧运行的上下文
+    ///
+    /// Remove every context of a state from the buffer, returning the ones waiting to run next frame
+    fn take_state(&mut self, state: Entity) -> Vec<ActionContext> {
+        let of_state = |contexts: &ActionContexts| {
+            contexts
+                .iter()
+                .filter(|context| context.state() == state)
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let pending = of_state(&self.next)
+            .into_iter()
+            .filter(|context| !self.filter.contains(context))
+            .collect();
+        for contexts in [
+            &mut self.curr,
+            &mut self.next,
+            &mut self.filter,
+            &mut self.interceptor,
+        ] {
+            for context in of_state(contexts) {
+                contexts.remove(&context);
+            }
+        }
+        pending
+    }
+
     /// 获取缓存作用域
     ///
     /// Get the buffer scope
@@ -474,23 +1016,29 @@ impl StateActionBuffer {
     /// - Can add or modify state contexts in the scope
     /// * 作用域结束后，会自动更新缓存
     /// - The scope will automatically update the cache after ending
+    /// * 状态拥有多个更新动作（[`OnUpdateSystems`]）时，按顺序对每个缓存调用一次
+    /// - When the state has several update actions ([`OnUpdateSystems`]), it is called once per buffer, in order
+    #[doc(hidden)]
     pub fn buffer_scope(
         world: UnsafeWorldCell,
         state_id: Entity,
-        f: impl FnOnce(&mut StateActionBuffer) + 'static,
+        f: impl FnOnce(&mut StateActionBuffer) + Clone + 'static,
     ) {
         // SAFETY: 该函数必须在系统中调用，并且保证在调用过程中不会有并发访问 `World` 的情况发生。
         let world = unsafe { world.world_mut() };
-        let Some(on_update_system) = world.get::<OnUpdateSystem>(state_id) else {
-            return;
-        };
-        let guard_registry = world.resource::<ActionDispatch>();
-        let Some(get_buffer_scope) = guard_registry.get(on_update_system) else {
-            warn!("{}", on_update_system.not_found_error(state_id));
+        let Ok(entity) = world.get_entity(state_id) else {
             return;
         };
+        let labels = OnUpdateSystems::labels_of(&entity);
+        for on_update_system in labels {
+            let guard_registry = world.resource::<ActionDispatch>();
+            let Some(get_buffer_scope) = guard_registry.get(&on_update_system) else {
+                on_update_system.not_found_error(state_id).report(world);
+                continue;
+            };
 
-        (get_buffer_scope)(world, Box::new(f));
+            (get_buffer_scope)(world, Box::new(f.clone()));
+        }
     }
 }
 
@@ -513,32 +1061,37 @@ impl Extend<ActionContext> for StateActionBuffer {
 
 /// 创建一个处理动作系统运行逻辑的闭包。
 /// 这个闭包会接收来自前一个系统的 `ActionContext`，并将其添加到对应的 `StateActionBuffer` 中。
-fn create_action_system_runner<T: ScheduleLabel>(
+fn create_action_system_runner<O: ActionSystemOutput>(
+    schedule: InternedScheduleLabel,
     action_name: SystemLabel,
-) -> impl Fn(In<Option<Vec<ActionContext>>>, ResMut<ScheduleActionBuffers<T>>) {
-    move |state_contexts: In<Option<Vec<ActionContext>>>,
-          mut action_system_buffers: ResMut<ScheduleActionBuffers<T>>| {
-        let Some(buffer) = action_system_buffers.get_buffer_mut(&action_name) else {
+) -> impl Fn(In<O>, ResMut<ScheduleActionBuffers>, HsmExitRequests) {
+    move |In(output): In<O>,
+          mut action_system_buffers: ResMut<ScheduleActionBuffers>,
+          mut exits: HsmExitRequests| {
+        let Some(buffer) = action_system_buffers.get_buffer_mut(schedule, &action_name) else {
             return;
         };
-        if let bevy::prelude::In(Some(state_contexts)) = state_contexts
-            && !state_contexts.is_empty()
-        {
-            buffer.extend(state_contexts);
+        let (keep, exit) = output.into_verdicts();
+        if !keep.is_empty() {
+            buffer.extend(keep);
         }
         buffer.update_interceptor();
+        for context in exit {
+            exits.request(context);
+        }
     }
 }
 
 /// 创建一个用于判断动作系统是否应该运行的条件闭包。
 /// 只有当对应的 `StateActionBuffer` 的 `next` 缓冲区不为空时，系统才会运行。
-fn create_run_condition_for_action_system<T: ScheduleLabel>(
+fn create_run_condition_for_action_system(
+    schedule: InternedScheduleLabel,
     action_name: SystemLabel,
-) -> impl Fn(Option<Res<ScheduleActionBuffers<T>>>) -> bool {
-    move |action_system_buffer: Option<Res<ScheduleActionBuffers<T>>>| {
+) -> impl Fn(Option<Res<ScheduleActionBuffers>>) -> bool {
+    move |action_system_buffer: Option<Res<ScheduleActionBuffers>>| {
         action_system_buffer.is_some_and(|buffers| {
             buffers
-                .get_buffer(&action_name)
+                .get_buffer(schedule, &action_name)
                 .is_some_and(|buffer| !buffer.next.is_empty())
         })
     }
@@ -546,11 +1099,12 @@ fn create_run_condition_for_action_system<T: ScheduleLabel>(
 
 /// 创建一个更新 `StateActionBuffer` 并返回当前动作的闭包。
 /// 这个闭包是动作系统管道的第一个阶段，它负责准备好当前帧需要处理的 `ActionContext`。
-fn create_buffer_updater_and_get_actions<T: ScheduleLabel>(
+fn create_buffer_updater_and_get_actions(
+    schedule: InternedScheduleLabel,
     action_name: SystemLabel,
-) -> impl Fn(ResMut<ScheduleActionBuffers<T>>) -> Vec<ActionContext> {
-    move |mut action_system_buffers: ResMut<ScheduleActionBuffers<T>>| -> Vec<ActionContext> {
-        if let Some(buffer) = action_system_buffers.get_buffer_mut(&action_name) {
+) -> impl Fn(ResMut<ScheduleActionBuffers>) -> Vec<ActionContext> {
+    move |mut action_system_buffers: ResMut<ScheduleActionBuffers>| -> Vec<ActionContext> {
+        if let Some(buffer) = action_system_buffers.get_buffer_mut(schedule, &action_name) {
             buffer.update();
             buffer.current_actions()
         } else {
@@ -575,7 +1129,10 @@ pub(super) mod system_state_trait {
         prelude::ActionSystemRegistry,
     };
 
-    /// 系统状态
+    /// 系统状态，所有操作都以调度标签的值（[`ScheduleLabel::intern`]）为键，因此携带数据的标签同样可以承载动作系统
+    ///
+    /// System state; every operation is keyed by the schedule label value ([`ScheduleLabel::intern`]), so labels
+    /// carrying data can host action systems as well
     pub trait ExpandScheduleLabelFunction: Send + Sync + 'static {
         fn configuration_action_system<M>(
             &self,
@@ -585,10 +1142,15 @@ pub(super) mod system_state_trait {
         where
             Self: ScheduleLabel + Sized,
         {
-            let action_system = create_buffer_updater_and_get_actions::<Self>(action_name.clone())
-                .pipe(system.into_system())
-                .pipe(create_action_system_runner::<Self>(action_name.clone()));
-            action_system.run_if(create_run_condition_for_action_system::<Self>(action_name))
+            let schedule = self.intern();
+            let action_system =
+                create_buffer_updater_and_get_actions(schedule, action_name.clone())
+                    .pipe(system.into_system())
+                    .pipe(create_action_system_runner(schedule, action_name.clone()));
+            action_system.run_if(create_run_condition_for_action_system(
+                schedule,
+                action_name,
+            ))
         }
 
         #[inline]
@@ -600,7 +1162,7 @@ pub(super) mod system_state_trait {
         where
             Self: ScheduleLabel + Sized,
         {
-            update_systems.push::<Self>(action_name)
+            update_systems.push(self.intern(), action_name)
         }
 
         #[inline]
@@ -612,16 +1174,14 @@ pub(super) mod system_state_trait {
         where
             Self: ScheduleLabel + Sized,
         {
-            update_systems.replace::<Self>(action_name)
+            update_systems.replace(self.intern(), action_name)
         }
 
         fn add_system_info(
             &self,
             world: &mut World,
             action_name: SystemLabel,
-        ) -> bevy::prelude::Result<()>
-        where
-            Self: Default;
+        ) -> bevy::prelude::Result<()>;
 
         fn remove_system_info(
             &self,
@@ -640,9 +1200,7 @@ pub(super) mod system_state_trait {
             world: &mut World,
             action_name: SystemLabel,
             system: impl IntoActionSystem<M>,
-        ) -> bevy::prelude::Result<()>
-        where
-            Self: Clone;
+        ) -> bevy::prelude::Result<()>;
     }
 }
 
@@ -652,32 +1210,30 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
         &self,
         world: &mut World,
         action_name: SystemLabel,
-    ) -> bevy::prelude::Result<()>
-    where
-        Self: Default,
-    {
-        let mut buffers = world.get_resource_or_init::<ScheduleActionBuffers<T>>();
-        if buffers.contains(&action_name) {
+    ) -> bevy::prelude::Result<()> {
+        let schedule = self.intern();
+        let mut buffers = world.get_resource_or_init::<ScheduleActionBuffers>();
+        if buffers.contains(schedule, &action_name) {
             return Err(StateMachineError::ActionBufferAlreadyExists(
                 action_name.clone(),
                 std::any::type_name::<T>(),
             )
             .into());
         }
-        buffers.insert_buffer(action_name.clone(), StateActionBuffer::default());
+        buffers.insert_buffer(schedule, action_name.clone(), StateActionBuffer::default());
 
-        let name = action_dispatch_key::<T>(&action_name);
+        let key = ActionKey::new(schedule, action_name.clone());
         let get_buffer_id = move |world: &mut World, f: Box<dyn FnOnce(&mut StateActionBuffer)>| {
-            let mut buffers = world.resource_mut::<ScheduleActionBuffers<T>>();
-            let Some(buffer) = buffers.get_buffer_mut(&action_name) else {
-                warn!("{}", StateMachineError::ActionNotFound(action_name.clone()));
+            let mut buffers = world.resource_mut::<ScheduleActionBuffers>();
+            let Some(buffer) = buffers.get_buffer_mut(schedule, &action_name) else {
+                StateMachineError::ActionNotFound(action_name.clone()).report(world);
                 return;
             };
             f(buffer);
         };
 
         let mut hsm_action_systems = world.get_resource_or_init::<ActionDispatch>();
-        hsm_action_systems.insert(name, Arc::new(get_buffer_id));
+        hsm_action_systems.insert(key, Arc::new(get_buffer_id));
         Ok(())
     }
 
@@ -686,8 +1242,9 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
         world: &mut World,
         action_name: &SystemLabel,
     ) -> bevy::prelude::Result<()> {
-        let mut buffers = world.resource_mut::<ScheduleActionBuffers<T>>();
-        if !buffers.contains(action_name) {
+        let schedule = self.intern();
+        let mut buffers = world.resource_mut::<ScheduleActionBuffers>();
+        if !buffers.contains(schedule, action_name) {
             return Err(StateMachineError::ActionBufferNotExists(
                 action_name.clone(),
                 std::any::type_name::<T>(),
@@ -695,11 +1252,10 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
             .into());
         }
 
-        buffers.remove_buffer(action_name);
+        buffers.remove_buffer(schedule, action_name);
 
-        let name = action_dispatch_key::<T>(action_name);
         let mut hsm_action_systems = world.get_resource_or_init::<ActionDispatch>();
-        hsm_action_systems.remove(name.as_str());
+        hsm_action_systems.remove(&ActionKey::new(schedule, action_name.clone()));
         Ok(())
     }
 
@@ -708,11 +1264,12 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
         world: &mut World,
         action_name: SystemLabel,
     ) -> bevy::prelude::Result<()> {
+        let schedule = self.intern();
         world.resource_scope(|world: &mut World, mut systems: Mut<'_, ActionSystemRegistry>| {
-            let Some(index) = systems.remove::<T>(&action_name) else {
+            let Some(index) = systems.remove(schedule, &action_name) else {
                 return Err(StateMachineError::ActionNotFound(action_name));
             };
-            world.schedule_scope(self,move|world:&mut World,schedule:&mut Schedule|{
+            world.schedule_scope(schedule,move|world:&mut World,schedule:&mut Schedule|{
                 schedule.remove_systems_in_set( index,world, bevy::ecs::schedule::ScheduleCleanupPolicy::RemoveSetAndSystemsAllowBreakages)?;
                 Ok(())
             },
@@ -725,10 +1282,7 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
         world: &mut World,
         action_name: SystemLabel,
         system: impl IntoActionSystem<M>,
-    ) -> bevy::prelude::Result<()>
-    where
-        Self: Clone,
-    {
+    ) -> bevy::prelude::Result<()> {
         let system = self.configuration_action_system(action_name.clone(), system);
         world.resource_scope(
                 |world: &mut World, mut systems: Mut<'_, ActionSystemRegistry>| {
@@ -736,7 +1290,7 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
                     let Some(index)= self.replace_system_index(&action_name,&mut systems) else {
                         return Err(StateMachineError::ActionNotFound(action_name));
                     };
-                    world.schedule_scope(self,|world: &mut World, schedule:&mut Schedule| {
+                    world.schedule_scope(self.intern(),|world: &mut World, schedule:&mut Schedule| {
                     schedule.add_systems(system.in_set(new_index));
 
                     schedule.remove_systems_in_set(index,world, bevy::ecs::schedule::ScheduleCleanupPolicy::RemoveSetAndSystemsAllowBreakages)?;
@@ -750,37 +1304,43 @@ impl<T: ScheduleLabel> system_state_trait::ExpandScheduleLabelFunction for T {
 
 #[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
 pub(crate) struct ActionSystemRegistry {
-    systems: HashMap<TypeId, HashMap<SystemLabel, ActionSystemSet>>,
+    systems: HashMap<InternedScheduleLabel, HashMap<SystemLabel, ActionSystemSet>>,
     counter: usize,
 }
 
 impl ActionSystemRegistry {
-    pub fn push<T: ScheduleLabel>(&mut self, action_name: SystemLabel) -> ActionSystemSet {
+    pub fn push(
+        &mut self,
+        schedule: InternedScheduleLabel,
+        action_name: SystemLabel,
+    ) -> ActionSystemSet {
         let index = ActionSystemSet(self.counter);
         self.systems
-            .entry(TypeId::of::<T>())
+            .entry(schedule)
             .or_default()
             .insert(action_name, index);
         self.counter += 1;
         index
     }
 
-    pub fn remove<T: ScheduleLabel>(
+    pub fn remove(
         &mut self,
+        schedule: InternedScheduleLabel,
         action_name: &SystemLabel,
     ) -> Option<ActionSystemSet> {
         self.systems
-            .get_mut(&TypeId::of::<T>())
+            .get_mut(&schedule)
             .and_then(|map| map.remove(action_name))
     }
 
-    pub fn replace<T: ScheduleLabel>(
+    pub fn replace(
         &mut self,
+        schedule: InternedScheduleLabel,
         action_name: &SystemLabel,
     ) -> Option<ActionSystemSet> {
         let new = ActionSystemSet(self.counter);
         self.counter += 1;
-        self.systems.get_mut(&TypeId::of::<T>()).and_then(|map| {
+        self.systems.get_mut(&schedule).and_then(|map| {
             map.get_mut(action_name)
                 .map(|old| std::mem::replace(old, new))
         })
@@ -0,0 +1,70 @@
+//! # 状态音效\State Sounds
+//!
+//! 声明式的进入/退出音效，简单的音频反馈无需为每个状态注册自定义的进入/退出动作。
+//!
+//! Declarative enter/exit sounds, so simple audio feedback doesn't require registering custom enter/exit actions for every state.
+
+use bevy::prelude::*;
+
+/// # 进入音效\Enter Sound
+/// * 挂载在状态实体上，进入该状态时播放（在 [`AfterEnterSystem`](crate::prelude::AfterEnterSystem) 之后）。
+/// - Lives on a state entity and is played when the state is entered (after [`AfterEnterSystem`](crate::prelude::AfterEnterSystem)).
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// commands.spawn((
+///     HsmState::default(),
+///     HsmEnterSound(asset_server.load("sounds/open.ogg")),
+///     HsmExitSound(asset_server.load("sounds/close.ogg")),
+/// ));
+/// # }
+/// ```
+#[derive(Component, Debug, Clone, PartialEq, Eq, Deref)]
+pub struct HsmEnterSound(pub Handle<AudioSource>);
+
+/// # 退出音效\Exit Sound
+/// * 挂载在状态实体上，退出该状态时播放（在 [`BeforeExitSystem`](crate::prelude::BeforeExitSystem) 之后）。
+/// - Lives on a state entity and is played when the state is exited (after [`BeforeExitSystem`](crate::prelude::BeforeExitSystem)).
+#[derive(Component, Debug, Clone, PartialEq, Eq, Deref)]
+pub struct HsmExitSound(pub Handle<AudioSource>);
+
+/// 播放状态上的音效，播放完毕后销毁音频实体
+///
+/// Play the sound on the state, despawning the audio entity once it has finished
+pub(crate) fn play_state_sound<T>(state: Entity) -> impl Command
+where
+    T: Component + std::ops::Deref<Target = Handle<AudioSource>>,
+{
+    move |world: &mut World| {
+        let Some(sound) = world.get::<T>(state).map(|sound| (**sound).clone()) else {
+            return;
+        };
+        world.spawn((AudioPlayer(sound), PlaybackSettings::DESPAWN));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_state_sound() {
+        let mut world = World::new();
+        let state = world.spawn(HsmEnterSound(Handle::default())).id();
+
+        world
+            .commands()
+            .queue(play_state_sound::<HsmExitSound>(state));
+        world.flush();
+        assert_eq!(world.query::<&AudioPlayer>().iter(&world).count(), 0);
+
+        world
+            .commands()
+            .queue(play_state_sound::<HsmEnterSound>(state));
+        world.flush();
+        assert_eq!(world.query::<&AudioPlayer>().iter(&world).count(), 1);
+    }
+}
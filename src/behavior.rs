@@ -0,0 +1,395 @@
+//! # 行为树适配\Behavior Tree Adapter
+//!
+//! 让状态将工作委托给行为树（或任何逐帧执行并返回结果的运行器）：运行器在缓冲的更新阶段被调用，
+//! 其结果通过内置守卫 `bt_succeeded` / `bt_failed` 反馈给该状态的退出转换，从而混合使用状态机结构与行为树叶子。
+//!
+//! Lets a state delegate to a behavior tree (or any runner that is ticked every frame and reports a result): the runner is
+//! invoked during the buffered update phase and its result feeds the state's exit transitions through the built-in
+//! `bt_succeeded` / `bt_failed` guards, mixing state machine structure with behavior tree leaves.
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    action_dispatcher::SystemState,
+    context::{ActionContext, GuardContext},
+    labels::ActionKey,
+    state_actions::{OnUpdateSystem, OnUpdateSystems, RegisterStateSystem},
+};
+
+/// 行为树更新动作的名称，注册在 [`Update`] 中
+///
+/// Name of the behavior tree update action, registered in [`Update`]
+pub const BEHAVIOR_TREE: &str = "behavior_tree";
+
+/// 运行器最近一次的结果
+///
+/// Latest result of a runner
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BehaviorStatus {
+    #[default]
+    Running,
+    Success,
+    Failure,
+}
+
+/// # 行为运行器\Behavior Runner
+/// * 由行为树库实现的集成接口，每帧在状态处于活动中时调用一次 [`tick`](Self::tick)，直到返回非 [`BehaviorStatus::Running`]。
+/// - Integration trait implemented for a behavior tree library; [`tick`](Self::tick) is called once per frame while the state
+///   is active, until it returns something other than [`BehaviorStatus::Running`].
+pub trait BehaviorRunner: Send + Sync + 'static {
+    fn tick(&mut self, world: &mut World, context: ActionContext) -> BehaviorStatus;
+
+    /// 退出状态时调用，用于重置运行器以便下次进入
+    ///
+    /// Called when the state is exited, to reset the runner for the next entry
+    fn reset(&mut self, _world: &mut World, _context: ActionContext) {}
+}
+
+impl<F> BehaviorRunner for F
+where
+    F: FnMut(&mut World, ActionContext) -> BehaviorStatus + Send + Sync + 'static,
+{
+    fn tick(&mut self, world: &mut World, context: ActionContext) -> BehaviorStatus {
+        self(world, context)
+    }
+}
+
+/// # 状态行为\State Behavior
+/// * 挂载在状态实体上，将该状态的更新委托给 [`BehaviorRunner`]。
+/// - Lives on a state entity and delegates the state's update to a [`BehaviorRunner`].
+/// * 状态可能被多个状态机共享，因此每个状态机在第一次运行该状态时获得自己的运行器实例（克隆自原型或由工厂创建），
+///   运行器的内部状态互不干扰。
+/// - A state may be shared by several state machines, so every machine gets its own runner instance (cloned from the
+///   prototype or built by the factory) the first time it runs the state, keeping runner state apart.
+/// * 插入时若状态已有指向其他动作的 [`OnUpdateSystem`]，行为树动作会被追加到 [`OnUpdateSystems`] 中。
+/// - If the state already has an [`OnUpdateSystem`] for another action when inserted, the behavior tree action is
+///   appended to [`OnUpdateSystems`] instead.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands) {
+/// commands.spawn((
+///     HsmState::default(),
+///     HsmBehavior::new(|_: &mut World, _: ActionContext| BehaviorStatus::Success),
+///     GuardExit::new(BT_SUCCEEDED),
+/// ));
+/// # }
+/// ```
+#[derive(Component)]
+#[require(OnUpdateSystem = OnUpdateSystem::with_schedule::<Update>(BEHAVIOR_TREE))]
+#[component(on_insert = Self::on_insert)]
+pub struct HsmBehavior(Box<dyn Fn() -> Box<dyn BehaviorRunner> + Send + Sync>);
+
+impl HsmBehavior {
+    /// 以 `runner` 为原型创建，每个状态机使用它的一个克隆
+    ///
+    /// Create with `runner` as the prototype, every state machine using a clone of it
+    pub fn new(runner: impl BehaviorRunner + Clone) -> Self {
+        Self::from_factory(move || runner.clone())
+    }
+
+    /// 由工厂为每个状态机创建运行器
+    ///
+    /// Create a runner for every state machine with the factory
+    pub fn from_factory<R: BehaviorRunner>(
+        factory: impl Fn() -> R + Send + Sync + 'static,
+    ) -> Self {
+        Self(Box::new(move || Box::new(factory())))
+    }
+
+    fn update_key() -> ActionKey {
+        ActionKey::new(Update, BEHAVIOR_TREE)
+    }
+
+    fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let key = Self::update_key();
+        if OnUpdateSystems::labels_of(&world.entity(entity)).contains(&key) {
+            return;
+        }
+        world
+            .commands()
+            .entity(entity)
+            .entry::<OnUpdateSystems>()
+            .or_default()
+            .and_modify(move |mut updates| {
+                if !updates.contains(&key) {
+                    updates.push(key);
+                }
+            });
+    }
+
+    /// 在状态机自己的运行器上执行 `f`，`create` 为 `true` 时按需创建运行器
+    ///
+    /// Run `f` on the state machine's own runner, creating it on demand when `create` is `true`
+    fn scope<R>(
+        world: &mut World,
+        context: ActionContext,
+        create: bool,
+        f: impl FnOnce(&mut World, &mut dyn BehaviorRunner) -> R,
+    ) -> Option<R> {
+        let state = context.state();
+        let existing = world
+            .get_mut::<BehaviorRunners>(context.state_machine)
+            .and_then(|mut runners| runners.0.remove(&state));
+        let mut runner = match existing {
+            Some(runner) => runner,
+            None if create => (world.get::<HsmBehavior>(state)?.0)(),
+            None => return None,
+        };
+        let result = f(world, runner.as_mut());
+        if world.get::<HsmBehavior>(state).is_some()
+            && let Ok(mut state_machine) = world.get_entity_mut(context.state_machine)
+        {
+            state_machine
+                .entry::<BehaviorRunners>()
+                .or_default()
+                .get_mut()
+                .0
+                .insert(state, runner);
+        }
+        Some(result)
+    }
+
+    fn tick_all(
+        In(contexts): In<Vec<ActionContext>>,
+        world: &mut World,
+    ) -> Option<Vec<ActionContext>> {
+        let mut running = Vec::with_capacity(contexts.len());
+        for context in contexts {
+            let Some(status) = Self::scope(world, context, true, |world, runner| {
+                runner.tick(world, context)
+            }) else {
+                continue;
+            };
+            let Ok(mut state_machine) = world.get_entity_mut(context.state_machine) else {
+                continue;
+            };
+            state_machine
+                .entry::<BehaviorStatuses>()
+                .or_default()
+                .get_mut()
+                .0
+                .insert(context.state(), status);
+            if status == BehaviorStatus::Running {
+                running.push(context);
+            }
+        }
+        Some(running)
+    }
+
+    pub(crate) fn exit_command(context: ActionContext) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut statuses) = world.get_mut::<BehaviorStatuses>(context.state_machine) {
+                statuses.0.remove(&context.state());
+            }
+            Self::scope(world, context, false, |world, runner| {
+                runner.reset(world, context)
+            });
+        }
+    }
+}
+
+/// 挂载在状态机实体上，按状态保存该状态机自己的运行器
+///
+/// Lives on the state machine entity and holds the machine's own runner per state
+#[derive(Component, Default)]
+struct BehaviorRunners(HashMap<Entity, Box<dyn BehaviorRunner>>);
+
+/// 运行器成功后返回 `true` 的内置守卫名称
+///
+/// Name of the built-in guard returning `true` once the runner has succeeded
+pub const BT_SUCCEEDED: &str = "bt_succeeded";
+
+/// 运行器失败后返回 `true` 的内置守卫名称
+///
+/// Name of the built-in guard returning `true` once the runner has failed
+pub const BT_FAILED: &str = "bt_failed";
+
+/// # 行为结果\Behavior Statuses
+/// * 挂载在状态机实体上，保存每个状态运行器最近一次的结果，退出状态时清除。
+/// - Lives on the state machine entity and holds the latest runner result of every state, cleared when the state is exited.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct BehaviorStatuses(HashMap<Entity, BehaviorStatus>);
+
+impl BehaviorStatuses {
+    pub fn get(&self, state: Entity) -> Option<BehaviorStatus> {
+        self.0.get(&state).copied()
+    }
+
+    fn is(
+        context: &GuardContext,
+        query: &Query<&BehaviorStatuses>,
+        status: BehaviorStatus,
+    ) -> bool {
+        query
+            .get(context.state_machine)
+            .is_ok_and(|statuses| statuses.get(context.from_state()) == Some(status))
+    }
+
+    fn succeeded(context: In<GuardContext>, query: Query<&BehaviorStatuses>) -> bool {
+        Self::is(&context, &query, BehaviorStatus::Success)
+    }
+
+    fn failed(context: In<GuardContext>, query: Query<&BehaviorStatuses>) -> bool {
+        Self::is(&context, &query, BehaviorStatus::Failure)
+    }
+}
+
+pub(crate) fn install_behavior_runner(app: &mut App) {
+    app.add_action_system(Update, BEHAVIOR_TREE, HsmBehavior::tick_all)
+        .register_guard(BT_SUCCEEDED, BehaviorStatuses::succeeded)
+        .register_guard(BT_FAILED, BehaviorStatuses::failed);
+}
+
+#[cfg(all(test, feature = "hsm"))]
+mod tests {
+    use crate::{StateMachinePlugin, prelude::*};
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Ticks(usize, usize);
+
+    #[derive(Clone)]
+    struct CountDown(usize);
+
+    impl BehaviorRunner for CountDown {
+        fn tick(&mut self, world: &mut World, _: ActionContext) -> BehaviorStatus {
+            world.resource_mut::<Ticks>().0 += 1;
+            self.0 -= 1;
+            match self.0 {
+                0 => BehaviorStatus::Success,
+                _ => BehaviorStatus::Running,
+            }
+        }
+
+        fn reset(&mut self, world: &mut World, _: ActionContext) {
+            world.resource_mut::<Ticks>().1 += 1;
+            self.0 = 3;
+        }
+    }
+
+    #[test]
+    fn test_behavior_runner_feeds_exit_guard() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .init_resource::<Ticks>();
+        let world = app.world_mut();
+        let root = world.spawn(HsmState::default()).id();
+        let leaf = world
+            .spawn((HsmBehavior::new(CountDown(3)), GuardExit::new(BT_SUCCEEDED)))
+            .id();
+        let mut state_tree = StateTree::new(root);
+        state_tree.with_child(root, leaf);
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                state_machine,
+                root,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+        ));
+        app.update();
+
+        app.world_mut()
+            .trigger(HsmTrigger::to_sub(state_machine, leaf));
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let world = app.world();
+        let ticks = world.resource::<Ticks>();
+        assert_eq!((ticks.0, ticks.1), (3, 1));
+        assert_eq!(
+            world
+                .get::<HsmStateMachine>(state_machine)
+                .unwrap()
+                .curr_state_id(),
+            root
+        );
+        assert_eq!(
+            world
+                .get::<BehaviorStatuses>(state_machine)
+                .unwrap()
+                .get(leaf),
+            None
+        );
+    }
+
+    #[test]
+    fn test_behavior_runner_per_state_machine() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .init_resource::<Ticks>();
+        let world = app.world_mut();
+        let root = world.spawn(HsmState::default()).id();
+        let leaf = world
+            .spawn((HsmBehavior::new(CountDown(3)), GuardExit::new(BT_SUCCEEDED)))
+            .id();
+        let mut state_tree = StateTree::new(root);
+        state_tree.with_child(root, leaf);
+        let state_tree = world.spawn(state_tree).id();
+        let state_machines = [(); 2].map(|_| {
+            world
+                .spawn((
+                    HsmStateMachine::with(
+                        state_tree,
+                        root,
+                        #[cfg(feature = "history")]
+                        10,
+                    ),
+                    StateLifecycle::default(),
+                ))
+                .id()
+        });
+        app.update();
+
+        // 共享同一状态的两个状态机各自倒数
+        // Two machines sharing the state count down on their own
+        for state_machine in state_machines {
+            app.world_mut()
+                .trigger(HsmTrigger::to_sub(state_machine, leaf));
+        }
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let world = app.world();
+        let ticks = world.resource::<Ticks>();
+        assert_eq!((ticks.0, ticks.1), (6, 2));
+        for state_machine in state_machines {
+            assert_eq!(
+                world
+                    .get::<HsmStateMachine>(state_machine)
+                    .unwrap()
+                    .curr_state_id(),
+                root
+            );
+        }
+    }
+
+    #[test]
+    fn test_behavior_keeps_existing_update_system() {
+        let mut world = World::new();
+        let state = world
+            .spawn((
+                OnUpdateSystem::new("walk"),
+                HsmBehavior::new(|_: &mut World, _: ActionContext| BehaviorStatus::Success),
+            ))
+            .id();
+        world.flush();
+
+        let labels = OnUpdateSystems::labels_of(&world.entity(state));
+        assert_eq!(labels.len(), 2);
+        assert!(labels.contains(&HsmBehavior::update_key()));
+    }
+}
@@ -0,0 +1,770 @@
+//! # 内置条件\Built-in Conditions
+//!
+//! 常用于待机/变化模式的内置带参数守卫，可以直接写在条件表达式中。
+//! 计数器按 `(起点状态, 目标状态, 表达式)` 保存在状态机上，起点状态退出时清零。
+//!
+//! Built-in parameterized guards covering common idle/variation patterns, usable directly in condition expressions.
+//! Counters are kept on the state machine per `(from state, to state, expression)` and reset when the from state exits.
+//!
+//! | 守卫\Guard | 示例\Example | 说明\Description |
+//! | --- | --- | --- |
+//! | `chance` | `chance(0.3)` | 每次检查以该概率满足，使用状态机的 [`HsmRng`]\Holds with the given probability on every check, drawing from the machine's [`HsmRng`] |
+//! | `every` | `every(5)` | 每第 N 次检查满足\Holds on every Nth check |
+//! | `once` | `once()` | 每次状态激活只满足一次\Holds a single time per state activation |
+//! | `machine_in` | `machine_in("Leader", "Retreat")` | 另一个状态机处于该名称的状态（HSM 包含祖先状态）\Another machine is in the named state (ancestors included for HSM) |
+//! | `above` | `above("speed", 5.0, 4.0)` | 数值超过上限后满足，直到低于下限才不再满足\Holds once the value rises above the upper bound, until it drops below the lower bound |
+//! | `below` | `below("speed", 1.0, 2.0)` | 数值低于下限后满足，直到超过上限才不再满足\Holds once the value drops below the lower bound, until it rises above the upper bound |
+//! | `cooldown` | `cooldown(2.0)` | 该转换上次发生后经过了给定秒数（状态机暂停期间不计时）\The given number of seconds passed since this transition last fired (not counting while the machine is paused) |
+//! | `field` | `field(Switch) == "Open"` | 服务目标上经反射读取的组件字段等于该值\A component field on the service target, read through reflection, equals the value |
+//! | `has_message` | `has_message("retreat_order")` | 状态机的 [`HsmInbox`](crate::inbox::HsmInbox) 中有该名称的消息，转换时消耗\The machine's [`HsmInbox`](crate::inbox::HsmInbox) holds a message with the name, consumed on transition |
+//! | `recently_in` | `recently_in("Cover", 3)` | 本状态机最近的 N 个状态（包括当前状态）中出现过该名称的状态，需要 `history` 特性\The named state appears among this machine's latest N states (the current one included), requires the `history` feature |
+//!
+//! `machine_in` 的第一个参数可以是 `"self"`、实体编号（[`Entity::to_bits`]）或状态机的 [`Name`]；
+//! 按名称查找时优先选择与当前状态机拥有相同父实体的兄弟状态机。
+//!
+//! The first argument of `machine_in` can be `"self"`, an entity number ([`Entity::to_bits`]) or the [`Name`] of a machine;
+//! name lookups prefer sibling machines sharing the current machine's parent.
+//!
+//! `above`/`below` 的第一个参数是通过 [`RegisterStateSystem::register_guard_value`] 注册的数值名称。滞回状态按
+//! `(状态机, 起始状态, 表达式)` 保存在状态机的 [`GuardHysteresis`] 上，离开起始状态时清除，因此共用同一表达式的不同状态
+//! 各自拥有独立的区间。退出守卫应写成阈值互换的反向条件，例如以 `above("speed", 5.0, 4.0)` 进入的状态用
+//! `below("speed", 4.0, 5.0)` 退出，避免在阈值附近来回切换。
+//!
+//! The first argument of `above`/`below` is the name of a value registered with
+//! [`RegisterStateSystem::register_guard_value`]. The hysteresis state is kept per `(state machine, from state, expression)`
+//! in the machine's [`GuardHysteresis`] and cleared when the from state exits, so states sharing an expression each keep
+//! their own band. Write exit guards as the reversed condition with swapped thresholds, e.g. a state entered with
+//! `above("speed", 5.0, 4.0)` exits with `below("speed", 4.0, 5.0)`, preventing flapping around the threshold.
+//!
+//! `field` 的第一个参数是已注册反射（`#[reflect(Component)]` 并通过 [`App::register_type`] 注册）的组件类型名，
+//! 可以用 [`GetPath`] 路径访问其中的字段，例如 `field("Door.lock.0") == "Jammed"`。枚举比较变体名称，字符串、布尔值与
+//! 数字按字面值比较；只写 `field("Door.open")` 时要求该字段为 `true`。`field(Switch) == "Open"` 是
+//! `field("Switch", "Open")` 的简写。
+//!
+//! The first argument of `field` is the type name of a component with registered reflection (`#[reflect(Component)]`
+//! and registered through [`App::register_type`]), optionally followed by a [`GetPath`] path to one of its fields, such
+//! as `field("Door.lock.0") == "Jammed"`. Enums compare their variant name, while strings, booleans and numbers compare
+//! by literal value; writing only `field("Door.open")` requires the field to be `true`. `field(Switch) == "Open"` is
+//! shorthand for `field("Switch", "Open")`.
+//!
+//! `cooldown` 按 `(状态机, 起点状态, 目标状态)` 在状态机的 [`TransitionCooldowns`] 上记录转换距上次发生的时间，
+//! 每次转换都会记录，且仅在状态机未被 [`Paused`] 时推进；状态机带有 [`StateClock`] 时随其推进。
+//!
+//! `cooldown` records the time since each transition last fired per `(state machine, from state, to state)` in the
+//! machine's [`TransitionCooldowns`], recorded on every transition and advancing only while the machine is not
+//! [`Paused`], following the machine's [`StateClock`] when it carries one.
+
+use bevy::{
+    ecs::system::SystemId,
+    platform::collections::HashMap,
+    prelude::*,
+    reflect::{GetPath, PartialReflect, ReflectRef},
+};
+
+use std::time::Duration;
+
+use crate::{
+    clock::StateClock, context::GuardContext, error::StateMachineError, guards::GuardArgs,
+    labels::SystemLabel, markers::Paused, rng::HsmRng, state_actions::RegisterStateSystem,
+};
+
+pub const CHANCE: &str = "chance";
+pub const EVERY: &str = "every";
+pub const ONCE: &str = "once";
+pub const MACHINE_IN: &str = "machine_in";
+pub const ABOVE: &str = "above";
+pub const BELOW: &str = "below";
+pub const COOLDOWN: &str = "cooldown";
+pub const FIELD: &str = "field";
+pub const HAS_MESSAGE: &str = "has_message";
+#[cfg(all(feature = "hsm", feature = "history"))]
+pub const RECENTLY_IN: &str = "recently_in";
+
+/// # 守卫计数器\Guard Counters
+/// * 挂载在状态机实体上，记录 `every`/`once` 的检查次数。
+/// - Lives on the state machine entity and records how often `every`/`once` were checked.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct GuardCounters(HashMap<(Entity, Entity, String), u32>);
+
+impl GuardCounters {
+    /// 获取某个 `(起点状态, 目标状态, 表达式)` 的检查次数
+    ///
+    /// Get the check count of a `(from state, to state, expression)`
+    pub fn get(&self, from_state: Entity, to_state: Entity, expression: &str) -> u32 {
+        self.0
+            .get(&(from_state, to_state, expression.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 清除以该状态为起点的计数器
+    ///
+    /// Clear the counters whose from state is the given state
+    pub fn clear(&mut self, state: Entity) {
+        self.0.retain(|(from_state, _, _), _| *from_state != state);
+    }
+
+    pub(crate) fn clear_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut counters) = world.get_mut::<GuardCounters>(state_machine) {
+                counters.clear(state);
+            }
+        }
+    }
+
+    /// 递增计数并返回递增后的值
+    fn tick(
+        commands: &mut Commands,
+        query: &mut Query<&mut GuardCounters>,
+        context: &GuardContext,
+        expression: String,
+    ) -> u32 {
+        let key = (context.from_state(), context.to_state(), expression);
+        match query.get_mut(context.state_machine) {
+            Ok(mut counters) => {
+                let count = counters.0.entry(key).or_default();
+                *count += 1;
+                *count
+            }
+            Err(_) => {
+                let mut counters = GuardCounters::default();
+                counters.0.insert(key, 1);
+                commands.entity(context.state_machine).try_insert(counters);
+                1
+            }
+        }
+    }
+}
+
+/// # 转换冷却\Transition Cooldowns
+/// * 挂载在状态机实体上，记录每条转换 `(起点状态, 目标状态)` 距上次发生经过的时间，供 `cooldown` 读取。
+///   仅在状态机未被 [`Paused`] 时计时；状态机带有 [`StateClock`] 时随其推进。
+/// - Lives on the state machine entity and records the time since each transition `(from state, to state)` last fired,
+///   read by `cooldown`. Time only advances while the machine is not [`Paused`], following the machine's [`StateClock`]
+///   when it carries one.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransitionCooldowns(HashMap<(Entity, Entity), Duration>);
+
+impl TransitionCooldowns {
+    /// 获取转换距上次发生经过的时间，从未发生时为 `None`
+    ///
+    /// Get the time since the transition last fired, `None` if it never fired
+    pub fn since(&self, from_state: Entity, to_state: Entity) -> Option<Duration> {
+        self.0.get(&(from_state, to_state)).copied()
+    }
+
+    /// 记录转换刚刚发生，状态机尚未带有该组件时插入
+    ///
+    /// Record that the transition just fired, inserting the component when the machine does not carry it yet
+    pub(crate) fn record(world: &mut World, context: GuardContext) {
+        let Ok(mut entity) = world.get_entity_mut(context.state_machine) else {
+            return;
+        };
+        entity
+            .entry::<Self>()
+            .or_default()
+            .get_mut()
+            .0
+            .insert((context.from_state(), context.to_state()), Duration::ZERO);
+    }
+
+    pub(crate) fn advance(&mut self, delta: Duration) {
+        for since in self.0.values_mut() {
+            *since += delta;
+        }
+    }
+
+    fn tick(
+        time: Option<Res<Time>>,
+        mut query: Query<&mut Self, (Without<Paused>, Without<StateClock>)>,
+    ) {
+        let Some(time) = time else {
+            return;
+        };
+        let delta = time.delta();
+        for mut cooldowns in query.iter_mut() {
+            cooldowns.advance(delta);
+        }
+    }
+}
+
+/// # 条件数值\Guard Values
+/// * 按名称注册的数值系统，供 `above`/`below` 等内置条件读取，参见 [`RegisterStateSystem::register_guard_value`]。
+/// - Value systems registered by name, read by built-in conditions such as `above`/`below`, see
+///   [`RegisterStateSystem::register_guard_value`].
+#[derive(Resource, Debug, Default, Clone)]
+pub struct GuardValues(HashMap<SystemLabel, SystemId<In<GuardContext>, f64>>);
+
+impl GuardValues {
+    pub fn insert(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        id: SystemId<In<GuardContext>, f64>,
+    ) -> Option<SystemId<In<GuardContext>, f64>> {
+        self.0.insert(name.into(), id)
+    }
+
+    pub fn get(&self, name: &str) -> Option<SystemId<In<GuardContext>, f64>> {
+        self.0.get(name).copied()
+    }
+
+    /// 在上下文中读取数值
+    ///
+    /// Read a value in the context
+    pub fn read(world: &mut World, name: &str, context: GuardContext) -> Option<f64> {
+        let id = world.get_resource::<Self>()?.get(name)?;
+        world.run_system_with(id, context).ok()
+    }
+}
+
+/// # 守卫滞回\Guard Hysteresis
+/// * 挂载在状态机实体上，按起始状态记录 `above`/`below` 的每个表达式当前是否处于满足区间，离开起始状态时清除。
+/// - Lives on the state machine entity and records, per from state, whether each `above`/`below` expression is currently
+///   within its band, cleared when the from state exits.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct GuardHysteresis(HashMap<(Entity, String), bool>);
+
+impl GuardHysteresis {
+    /// 获取某个起始状态上的表达式当前是否满足
+    ///
+    /// Whether an expression currently holds for a from state
+    pub fn get(&self, from_state: Entity, expression: &str) -> bool {
+        self.0
+            .get(&(from_state, expression.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 清除以该状态为起点的滞回状态
+    ///
+    /// Clear the hysteresis state whose from state is the given state
+    pub fn clear(&mut self, state: Entity) {
+        self.0.retain(|(from_state, _), _| *from_state != state);
+    }
+
+    pub(crate) fn clear_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut hysteresis) = world.get_mut::<GuardHysteresis>(state_machine) {
+                hysteresis.clear(state);
+            }
+        }
+    }
+
+    /// 按滞回规则更新并返回结果：未满足时 `enter` 成立则进入，已满足时 `release` 成立则释放
+    ///
+    /// Update by the hysteresis rule and return the result: enters when `enter` holds while released, releases when
+    /// `release` holds while held
+    fn update(
+        world: &mut World,
+        context: GuardContext,
+        expression: String,
+        enter: bool,
+        release: bool,
+    ) -> bool {
+        let Ok(mut entity) = world.get_entity_mut(context.state_machine) else {
+            return false;
+        };
+        let mut hysteresis = entity.entry::<Self>().or_default();
+        let mut hysteresis = hysteresis.get_mut();
+        let held = hysteresis
+            .0
+            .entry((context.from_state(), expression))
+            .or_default();
+        *held = if *held { !release } else { enter };
+        *held
+    }
+}
+
+/// 带参数守卫收到无法使用的参数时报告的错误
+pub(crate) fn invalid_args(
+    guard: &'static str,
+    args: &GuardArgs,
+    reason: impl Into<String>,
+) -> StateMachineError {
+    StateMachineError::GuardArgsInvalid {
+        guard,
+        args: args.clone(),
+        reason: reason.into(),
+    }
+}
+
+/// 解析 `above`/`below` 的参数：数值名称、进入阈值与释放阈值
+fn threshold_args(
+    world: &mut World,
+    name: &'static str,
+    context: GuardContext,
+    args: &GuardArgs,
+) -> Option<(f64, f64, f64)> {
+    let (Some(value), Some(enter), Some(release)) =
+        (args.first(), args.parse::<f64>(1), args.parse::<f64>(2))
+    else {
+        invalid_args(name, args, "expected a value name and two thresholds").report(world);
+        return None;
+    };
+    let Some(value) = GuardValues::read(world, value, context) else {
+        invalid_args(name, args, format!("value <{}> is not registered", value)).report(world);
+        return None;
+    };
+    Some((value, enter, release))
+}
+
+fn above(In((context, args)): In<(GuardContext, GuardArgs)>, world: &mut World) -> bool {
+    let Some((value, enter, release)) = threshold_args(world, ABOVE, context, &args) else {
+        return false;
+    };
+    GuardHysteresis::update(
+        world,
+        context,
+        format!("{}({})", ABOVE, args),
+        value > enter,
+        value < release,
+    )
+}
+
+fn below(In((context, args)): In<(GuardContext, GuardArgs)>, world: &mut World) -> bool {
+    let Some((value, enter, release)) = threshold_args(world, BELOW, context, &args) else {
+        return false;
+    };
+    GuardHysteresis::update(
+        world,
+        context,
+        format!("{}({})", BELOW, args),
+        value < enter,
+        value > release,
+    )
+}
+
+fn cooldown(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    mut commands: Commands,
+    query: Query<&TransitionCooldowns>,
+) -> bool {
+    let Some(window) = args
+        .parse::<f64>(0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+    else {
+        invalid_args(COOLDOWN, &args, "expected a duration in seconds").report_with(&mut commands);
+        return false;
+    };
+    match query.get(context.state_machine) {
+        Ok(cooldowns) => cooldowns
+            .since(context.from_state(), context.to_state())
+            .is_none_or(|since| since >= window),
+        Err(_) => {
+            // 首次检查时开始记录该状态机的转换
+            commands
+                .entity(context.state_machine)
+                .try_insert(TransitionCooldowns::default());
+            true
+        }
+    }
+}
+
+fn chance(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    mut commands: Commands,
+    query: Query<&HsmRng>,
+) -> bool {
+    let Some(probability) = args.parse::<f32>(0) else {
+        invalid_args(CHANCE, &args, "expected a probability").report_with(&mut commands);
+        return false;
+    };
+    match query.get(context.state_machine) {
+        Ok(rng) => rng.chance(probability),
+        Err(_) => {
+            // 没有随机数流时按实体创建一个，保证结果可复现
+            let rng = HsmRng::new(context.state_machine.to_bits());
+            let result = rng.chance(probability);
+            commands.entity(context.state_machine).try_insert(rng);
+            result
+        }
+    }
+}
+
+fn every(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    mut commands: Commands,
+    mut query: Query<&mut GuardCounters>,
+) -> bool {
+    let Some(n) = args.parse::<u32>(0).filter(|n| *n > 0) else {
+        invalid_args(EVERY, &args, "expected a positive count").report_with(&mut commands);
+        return false;
+    };
+    let count = GuardCounters::tick(
+        &mut commands,
+        &mut query,
+        &context,
+        format!("{}({})", EVERY, args),
+    );
+    count.is_multiple_of(n)
+}
+
+fn once(
+    In((context, _)): In<(GuardContext, GuardArgs)>,
+    mut commands: Commands,
+    mut query: Query<&mut GuardCounters>,
+) -> bool {
+    GuardCounters::tick(&mut commands, &mut query, &context, format!("{}()", ONCE)) == 1
+}
+
+fn is_state_machine(world: &World, entity: Entity) -> bool {
+    #[cfg(feature = "hsm")]
+    if world
+        .get::<crate::hsm::state_machine::HsmStateMachine>(entity)
+        .is_some()
+    {
+        return true;
+    }
+    #[cfg(feature = "fsm")]
+    if world
+        .get::<crate::fsm::state_machine::FsmStateMachine>(entity)
+        .is_some()
+    {
+        return true;
+    }
+    let _ = (world, entity);
+    false
+}
+
+fn resolve_machine(world: &World, context: &GuardContext, machine_ref: &str) -> Option<Entity> {
+    if machine_ref == "self" {
+        return Some(context.state_machine);
+    }
+    if let Ok(bits) = machine_ref.parse::<u64>() {
+        return Entity::try_from_bits(bits).filter(|&entity| is_state_machine(world, entity));
+    }
+
+    let parent = world
+        .get::<ChildOf>(context.state_machine)
+        .map(ChildOf::parent);
+    let mut query = world.try_query::<(Entity, &Name, Option<&ChildOf>)>()?;
+    let mut fallback = None;
+    for (entity, name, child_of) in query.iter(world) {
+        if name.as_str() != machine_ref
+            || entity == context.state_machine
+            || !is_state_machine(world, entity)
+        {
+            continue;
+        }
+        if parent.is_some() && child_of.map(ChildOf::parent) == parent {
+            return Some(entity);
+        }
+        fallback.get_or_insert(entity);
+    }
+    fallback
+}
+
+/// 状态机当前激活的状态：HSM 为当前状态及其所有祖先，FSM 为当前状态
+pub(crate) fn active_states(world: &World, state_machine: Entity) -> Vec<Entity> {
+    #[cfg(feature = "hsm")]
+    if let Some(hsm) = world.get::<crate::hsm::state_machine::HsmStateMachine>(state_machine) {
+        let curr_state_id = hsm.curr_state_id();
+        let ancestors = world
+            .get::<crate::hsm::state_tree::StateTree>(hsm.state_tree())
+            .map(|state_tree| state_tree.path_iter(curr_state_id).collect::<Vec<_>>())
+            .unwrap_or_default();
+        return std::iter::once(curr_state_id).chain(ancestors).collect();
+    }
+    #[cfg(feature = "fsm")]
+    if let Some(fsm) = world.get::<crate::fsm::state_machine::FsmStateMachine>(state_machine) {
+        return vec![fsm.curr_state_id()];
+    }
+    let _ = (world, state_machine);
+    Vec::new()
+}
+
+fn machine_in(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    world: &World,
+    mut commands: Commands,
+) -> bool {
+    let [machine_ref, state_name] = &args[..] else {
+        invalid_args(MACHINE_IN, &args, "expected a machine and a state name")
+            .report_with(&mut commands);
+        return false;
+    };
+    let Some(state_machine) = resolve_machine(world, &context, machine_ref) else {
+        return false;
+    };
+    active_states(world, state_machine)
+        .into_iter()
+        .any(|state| {
+            world
+                .get::<Name>(state)
+                .is_some_and(|name| name.as_str() == state_name)
+        })
+}
+
+#[cfg(all(feature = "hsm", feature = "history"))]
+fn recently_in(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    world: &World,
+    mut commands: Commands,
+) -> bool {
+    let (Some(state_name), Some(n)) = (args.first(), args.parse::<usize>(1)) else {
+        invalid_args(RECENTLY_IN, &args, "expected a state name and a count")
+            .report_with(&mut commands);
+        return false;
+    };
+    let Some(hsm) = world.get::<crate::hsm::state_machine::HsmStateMachine>(context.state_machine)
+    else {
+        return false;
+    };
+    hsm.history.recent_states().take(n).any(|state| {
+        world
+            .get::<Name>(state)
+            .is_some_and(|name| name.as_str() == state_name.as_str())
+    })
+}
+
+fn field(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    world: &World,
+    mut commands: Commands,
+) -> bool {
+    let (path, expected) = match &args[..] {
+        [path] => (path, "true"),
+        [path, expected] => (path, expected.as_str()),
+        _ => {
+            invalid_args(
+                FIELD,
+                &args,
+                "expected a component path and an optional value",
+            )
+            .report_with(&mut commands);
+            return false;
+        }
+    };
+    let (type_name, field_path) = path.split_once('.').unwrap_or((path, ""));
+    let Some(type_registry) = world.get_resource::<AppTypeRegistry>() else {
+        return false;
+    };
+    let type_registry = type_registry.read();
+    let Some(reflect_component) = type_registry
+        .get_with_short_type_path(type_name)
+        .or_else(|| type_registry.get_with_type_path(type_name))
+        .and_then(|registration| registration.data::<ReflectComponent>())
+    else {
+        invalid_args(
+            FIELD,
+            &args,
+            format!("{} is not a reflected component", type_name),
+        )
+        .report_with(&mut commands);
+        return false;
+    };
+    let Ok(service_target) = world.get_entity(context.service_target) else {
+        return false;
+    };
+    let Some(component) = reflect_component.reflect(service_target) else {
+        return false;
+    };
+    let value = match field_path {
+        "" => component.as_partial_reflect(),
+        field_path => match component.reflect_path(field_path) {
+            Ok(value) => value,
+            Err(error) => {
+                invalid_args(FIELD, &args, format!("invalid path {}: {}", path, error))
+                    .report_with(&mut commands);
+                return false;
+            }
+        },
+    };
+    reflect_eq(value, expected)
+}
+
+/// 比较反射值与字面值：枚举比较变体名称，其余按字面值解析后比较
+///
+/// Compare a reflected value with a literal: enums compare their variant name, other values compare after parsing the
+/// literal
+fn reflect_eq(value: &dyn PartialReflect, expected: &str) -> bool {
+    if let ReflectRef::Enum(value) = value.reflect_ref() {
+        return value.variant_name() == expected;
+    }
+    macro_rules! compare {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = value.try_downcast_ref::<$ty>() {
+                    return expected.parse::<$ty>().is_ok_and(|expected| expected == *value);
+                }
+            )*
+        };
+    }
+    compare!(
+        bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, char, String
+    );
+    false
+}
+
+pub(crate) fn register_builtin_guards(app: &mut App) {
+    app.register_param_guard(CHANCE, chance)
+        .register_param_guard(EVERY, every)
+        .register_param_guard(ONCE, once)
+        .register_param_guard(MACHINE_IN, machine_in)
+        .register_param_guard(ABOVE, above)
+        .register_param_guard(BELOW, below)
+        .register_param_guard(COOLDOWN, cooldown)
+        .register_param_guard(FIELD, field)
+        .register_param_guard(HAS_MESSAGE, crate::inbox::has_message)
+        .add_systems(First, TransitionCooldowns::tick);
+    #[cfg(all(feature = "hsm", feature = "history"))]
+    app.register_param_guard(RECENTLY_IN, recently_in);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+
+    use crate::{
+        StateMachinePlugin, error::StateMachineErrorMessage, guards::GuardCondition,
+        prelude::GuardRegistry,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_builtin_guards() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let world = app.world_mut();
+        let state_machine = world.spawn(HsmRng::new(1)).id();
+        let from = world.spawn_empty().id();
+        let to = world.spawn_empty().id();
+        let context = GuardContext::new(state_machine, state_machine, from, to);
+
+        let check = |world: &mut World, condition: &str| {
+            let condition = GuardCondition::parse(condition).unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            let result = guard.run(world, context).unwrap();
+            world.flush();
+            result
+        };
+
+        let every = (0..6).map(|_| check(world, "every(3)")).collect::<Vec<_>>();
+        assert_eq!(every, [false, false, true, false, false, true]);
+        let once = (0..3).map(|_| check(world, "once()")).collect::<Vec<_>>();
+        assert_eq!(once, [true, false, false]);
+
+        GuardCounters::clear_command(state_machine, from).apply(world);
+        assert!(check(world, "once()"));
+
+        assert!(check(world, "chance(1.0)"));
+        assert!(!check(world, "chance(0.0)"));
+        assert!(!check(world, "chance(\"often\")"));
+
+        let messages = world.resource::<Messages<StateMachineErrorMessage>>();
+        assert!(messages.get_cursor().read(messages).any(|message| matches!(
+            &message.0,
+            StateMachineError::GuardArgsInvalid { guard: CHANCE, .. }
+        )));
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        #[derive(Resource)]
+        struct Speed(f64);
+
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .insert_resource(Speed(0.0))
+            .register_guard_value("speed", |_: In<GuardContext>, speed: Res<Speed>| speed.0);
+        let world = app.world_mut();
+        let state_machine = world.spawn_empty().id();
+        let from = world.spawn_empty().id();
+        let to = world.spawn_empty().id();
+        let context = GuardContext::new(state_machine, state_machine, from, to);
+
+        let mut check = |speed: f64, condition: &str| {
+            world.resource_mut::<Speed>().0 = speed;
+            let condition = GuardCondition::parse(condition).unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            guard.run(world, context).unwrap()
+        };
+
+        let above = [3.0, 5.5, 4.5, 4.1, 3.9, 4.5, 6.0]
+            .map(|speed| check(speed, "above(\"speed\", 5.0, 4.0)"));
+        assert_eq!(above, [false, true, true, true, false, false, true]);
+        let below =
+            [2.0, 0.5, 1.5, 2.5, 1.5].map(|speed| check(speed, "below(\"speed\", 1.0, 2.0)"));
+        assert_eq!(below, [false, true, true, false, false]);
+        assert!(!check(1.0, "above(\"missing\", 0.0, 0.0)"));
+
+        let other = world.spawn_empty().id();
+        let other_context = GuardContext::new(state_machine, state_machine, other, to);
+        let mut check = |speed: f64, context: GuardContext| {
+            world.resource_mut::<Speed>().0 = speed;
+            let condition = GuardCondition::parse("above(\"speed\", 5.0, 4.0)").unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            guard.run(world, context).unwrap()
+        };
+        assert!(check(6.0, context));
+        assert!(!check(4.5, other_context));
+        assert!(check(4.5, context));
+
+        GuardHysteresis::clear_command(state_machine, from).apply(world);
+        let hysteresis = world.get::<GuardHysteresis>(state_machine).unwrap();
+        assert!(!hysteresis.get(from, "above(\"speed\", 5.0, 4.0)"));
+    }
+
+    #[test]
+    fn test_field() {
+        #[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+        enum Switch {
+            Open,
+            Close,
+        }
+
+        #[derive(Component, Reflect)]
+        #[reflect(Component)]
+        struct Door {
+            switch: Switch,
+            open: bool,
+            lock: (u32,),
+        }
+
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .register_type::<Door>();
+        let world = app.world_mut();
+        let service_target = world
+            .spawn(Door {
+                switch: Switch::Open,
+                open: true,
+                lock: (3,),
+            })
+            .id();
+        let state_machine = world.spawn_empty().id();
+        let from = world.spawn_empty().id();
+        let to = world.spawn_empty().id();
+        let context = GuardContext::new(service_target, state_machine, from, to);
+
+        let check = |world: &mut World, condition: &str| {
+            let condition = GuardCondition::parse(condition).unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            guard.run(world, context).unwrap()
+        };
+
+        assert!(check(world, r#"field("Door.switch") == "Open""#));
+        assert!(!check(world, r#"field("Door.switch", "Close")"#));
+        assert!(check(world, r#"field("Door.open")"#));
+        assert!(check(world, r#"field("Door.lock.0") == 3"#));
+        assert!(!check(world, r#"field("Door.lock.0") == 4"#));
+        assert!(!check(world, r#"field("Door.missing") == 3"#));
+        assert!(!check(world, r#"field(Missing) == "Open""#));
+
+        world.get_mut::<Door>(service_target).unwrap().switch = Switch::Close;
+        assert!(check(world, r#"not(field("Door.switch") == "Open")"#));
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeSystems};
+
+use crate::{builtin_guards::TransitionCooldowns, markers::Paused};
+
+/// 状态时钟跟随的时间
+///
+/// The time a state clock follows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateClockMode {
+    /// 每帧在 [`First`] 中按 [`Time<Virtual>`] 推进
+    ///
+    /// Advanced by [`Time<Virtual>`] in [`First`] every frame
+    #[default]
+    Update,
+    /// 在每个 [`FixedFirst`] 中按 [`Time<Fixed>`] 推进，一帧内运行多个固定步时累加
+    ///
+    /// Advanced by [`Time<Fixed>`] in every [`FixedFirst`], accumulating over the fixed steps run within a frame
+    FixedUpdate,
+}
+
+/// # 状态时钟\State Clock
+/// * 挂载在状态机实体上，由插件推进的每状态机时钟。[`StateClock::delta`] 是本帧时钟推进的时间：
+///   [`StateClockMode::Update`] 为虚拟时间的帧间隔，[`StateClockMode::FixedUpdate`] 为本帧已运行的所有固定步之和，
+///   因此在任何调度中检查的守卫都能读到一致的值，而不是在 [`Update`] 检查的条件里读取只属于单个固定步的
+///   `Time<Fixed>::delta`。状态机被 [`Paused`] 时时钟不推进。
+/// - A per-machine clock living on the state machine entity and advanced by the plugin. [`StateClock::delta`] is the time
+///   the clock advanced this frame: the virtual frame delta for [`StateClockMode::Update`], and the sum of every fixed step
+///   run this frame for [`StateClockMode::FixedUpdate`], so guards checked in any schedule read a consistent value instead
+///   of a `Time<Fixed>::delta` that belongs to a single fixed step while being checked in [`Update`]. The clock does not
+///   advance while the machine is [`Paused`].
+/// * 内置的时间条件（`cooldown`、`sticky(..., 秒数)`）在状态机带有时钟时改为读取它。
+/// - The built-in time conditions (`cooldown`, `sticky(..., seconds)`) read the clock instead when the machine carries
+///   one.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct LightTimer(Timer);
+///
+/// fn light_timer(context: In<GuardContext>, mut query: Query<(&StateClock, &mut LightTimer)>) -> bool {
+///     let Ok((clock, mut timer)) = query.get_mut(context.state_machine) else {
+///         return false;
+///     };
+///     timer.0.tick(clock.delta()).is_finished()
+/// }
+///
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands
+///     .entity(state_machine)
+///     .insert(StateClock::new(StateClockMode::FixedUpdate));
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StateClock {
+    mode: StateClockMode,
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl StateClock {
+    pub fn new(mode: StateClockMode) -> Self {
+        Self { mode, ..default() }
+    }
+
+    pub fn mode(&self) -> StateClockMode {
+        self.mode
+    }
+
+    /// 本帧时钟推进的时间
+    ///
+    /// The time the clock advanced this frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// 时钟创建以来累计推进的时间
+    ///
+    /// The total time the clock advanced since it was created
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    /// 状态机的当前时刻：带有时钟时为 [`StateClock::elapsed`]，否则为 [`Time::elapsed`]，两者都没有时为 `None`
+    ///
+    /// The current time of a state machine: [`StateClock::elapsed`] when it carries a clock, [`Time::elapsed`] otherwise,
+    /// and `None` without either
+    pub fn now(world: &World, state_machine: Entity) -> Option<Duration> {
+        match world.get::<Self>(state_machine) {
+            Some(clock) => Some(clock.elapsed),
+            None => world.get_resource::<Time>().map(Time::elapsed),
+        }
+    }
+
+    fn advance(&mut self, delta: Duration, cooldowns: Option<Mut<TransitionCooldowns>>) {
+        self.delta += delta;
+        self.elapsed += delta;
+        if let Some(mut cooldowns) = cooldowns {
+            cooldowns.advance(delta);
+        }
+    }
+
+    fn tick_update(
+        time: Res<Time<Virtual>>,
+        mut query: Query<(&mut Self, Option<&mut TransitionCooldowns>, Has<Paused>)>,
+    ) {
+        for (mut clock, cooldowns, paused) in query.iter_mut() {
+            clock.delta = Duration::ZERO;
+            if clock.mode == StateClockMode::Update && !paused {
+                clock.advance(time.delta(), cooldowns);
+            }
+        }
+    }
+
+    fn tick_fixed(
+        time: Res<Time<Fixed>>,
+        mut query: Query<(&mut Self, Option<&mut TransitionCooldowns>), Without<Paused>>,
+    ) {
+        for (mut clock, cooldowns) in query.iter_mut() {
+            if clock.mode == StateClockMode::FixedUpdate {
+                clock.advance(time.delta(), cooldowns);
+            }
+        }
+    }
+}
+
+pub(crate) fn install_state_clocks(app: &mut App) {
+    app.add_systems(
+        First,
+        StateClock::tick_update
+            .after(TimeSystems)
+            .run_if(|time: Option<Res<Time<Virtual>>>| time.is_some()),
+    )
+    .add_systems(
+        FixedFirst,
+        StateClock::tick_fixed.run_if(|time: Option<Res<Time<Fixed>>>| time.is_some()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use crate::StateMachinePlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_state_clock() {
+        let mut app = App::new();
+        app.add_plugins((bevy::time::TimePlugin, StateMachinePlugin::default()))
+            .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(10)))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                25,
+            )));
+        let update = app.world_mut().spawn(StateClock::default()).id();
+        let fixed = app
+            .world_mut()
+            .spawn(StateClock::new(StateClockMode::FixedUpdate))
+            .id();
+        let paused = app.world_mut().spawn((StateClock::default(), Paused)).id();
+
+        // 第一帧没有时间间隔
+        // The first frame has no delta
+        app.update();
+        app.update();
+        let clock = |app: &App, entity| *app.world().get::<StateClock>(entity).unwrap();
+        assert_eq!(clock(&app, update).delta(), Duration::from_millis(25));
+        // 25ms 内运行了两个 10ms 的固定步
+        // Two fixed steps of 10ms ran within 25ms
+        assert_eq!(clock(&app, fixed).delta(), Duration::from_millis(20));
+        assert_eq!(clock(&app, paused).elapsed(), Duration::ZERO);
+
+        app.update();
+        assert_eq!(clock(&app, update).elapsed(), Duration::from_millis(50));
+        assert_eq!(clock(&app, fixed).delta(), Duration::from_millis(30));
+        assert_eq!(clock(&app, fixed).elapsed(), Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,554 @@
+//! # 状态机命令扩展\State Machine Command Extensions
+//!
+//! 为 [`Commands`] 提供销毁整个状态机（状态机实体、状态树/状态图以及所有状态实体）的便捷方法，
+//! 以及对服务于同一目标的所有状态机批量操作的方法。
+//!
+//! Provides convenience methods on [`Commands`] for despawning a whole state machine
+//! (the machine entity, its state tree/graph and every state entity), and for acting on every
+//! state machine serving the same target at once.
+
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+
+use crate::{
+    error::StateMachineError,
+    inbox::HsmInbox,
+    markers::{Paused, Terminated},
+    state_actions::StateMachineForest,
+};
+
+#[cfg(feature = "hsm")]
+use bevy::ecs::entity::{EntityClonerBuilder, EntityHashMap, MapEntities, OptOut};
+
+#[cfg(feature = "hsm")]
+use crate::{
+    fault::HsmFaulted,
+    hsm::{
+        checkpoints::HsmCheckpoints,
+        deferred_links::{PendingStateLinks, StateLink},
+        disabled::StateEviction,
+        event::HsmTrigger,
+        state_lifecycle::{CurrentLifecycle, StateLifecycle},
+        state_machine::HsmStateMachine,
+        state_tree::StateTree,
+        supervisor::SupervisedChildren,
+        transitions::HsmTransitions,
+    },
+};
+
+#[cfg(feature = "fsm")]
+use crate::fsm::{graph::FsmGraph, state_machine::FsmStateMachine};
+
+/// # 状态机命令扩展\State Machine Commands Extension
+///
+/// 如果状态树/状态图仍被其他状态机使用，则只销毁状态机实体本身。
+///
+/// If the state tree/graph is still used by another state machine, only the state machine entity itself is despawned.
+///
+/// 销毁状态只会注销被标记为作用域（[`RegistryUsage::mark_scoped`](crate::registry_usage::RegistryUsage::mark_scoped)）
+/// 且不再被引用的系统，以及状态机的 [`LocalRegistry`](crate::local_registry::LocalRegistry)。
+/// 其余全局注册的守卫与动作即使只被这个状态机引用也会保留：注册表按名称共享，之后生成的状态机（例如从同一份资源加载）
+/// 仍会按名称引用它们，无法判断它们是否属于这个状态机。
+///
+/// Despawning the states only unregisters systems marked as scoped
+/// ([`RegistryUsage::mark_scoped`](crate::registry_usage::RegistryUsage::mark_scoped)) that are no longer referenced,
+/// plus the machine's [`LocalRegistry`](crate::local_registry::LocalRegistry). Other globally registered guards and
+/// actions are kept even when only this machine referenced them: the registries are shared by name, and machines
+/// spawned later (e.g. loaded from the same asset) still refer to them by name, so there is no telling whether they
+/// belong to this machine.
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands.despawn_hsm(state_machine);
+/// # }
+/// ```
+pub trait StateMachineCommandsExt {
+    /// 销毁一个层级状态机及其私有的状态树和所有状态实体
+    ///
+    /// Despawn a hierarchical state machine together with its private state tree and every state entity
+    #[cfg(feature = "hsm")]
+    fn despawn_hsm(&mut self, state_machine: Entity);
+
+    /// 销毁一个有限状态机及其私有的状态图和所有状态实体
+    ///
+    /// Despawn a finite state machine together with its private graph and every state entity
+    #[cfg(feature = "fsm")]
+    fn despawn_fsm(&mut self, state_machine: Entity);
+
+    /// 以一个层级状态机为原型克隆出新的状态机，返回预留的新状态机实体，参见 [`clone_hsm`]
+    ///
+    /// Clone a new hierarchical state machine from a prototype, returning the reserved entity of the new machine, see
+    /// [`clone_hsm`]
+    #[cfg(feature = "hsm")]
+    fn clone_hsm(&mut self, state_machine: Entity) -> Entity;
+
+    /// 在状态树中将 `sub_state` 链接为 `super_state` 的子状态；两端尚未就绪时暂存于 [`PendingStateLinks`]，待其出现后再建立
+    ///
+    /// Link `sub_state` as a sub-state of `super_state` in the state tree; while the ends are not ready the link is parked
+    /// in [`PendingStateLinks`] and made once they appear
+    #[cfg(feature = "hsm")]
+    fn link_state(&mut self, state_tree: Entity, super_state: Entity, sub_state: Entity);
+
+    /// 在运行时将状态连同其子树移动到新的父状态下，参见 [`reparent_state`]
+    ///
+    /// Move a state together with its subtree under a new super state at runtime, see [`reparent_state`]
+    #[cfg(feature = "hsm")]
+    fn reparent_state(
+        &mut self,
+        state_tree: Entity,
+        state: Entity,
+        new_super_state: Entity,
+        eviction: StateEviction,
+    );
+
+    /// 向状态机 `to` 的信箱投递一条消息，参见 [`HsmInbox`]
+    ///
+    /// Deliver a message to the inbox of the state machine `to`, see [`HsmInbox`]
+    fn send_message(&mut self, from: Entity, to: Entity, name: impl Into<Cow<'static, str>>);
+}
+
+impl StateMachineCommandsExt for Commands<'_, '_> {
+    #[cfg(feature = "hsm")]
+    fn despawn_hsm(&mut self, state_machine: Entity) {
+        self.queue(move |world: &mut World| despawn_hsm(world, state_machine));
+    }
+
+    #[cfg(feature = "fsm")]
+    fn despawn_fsm(&mut self, state_machine: Entity) {
+        self.queue(move |world: &mut World| despawn_fsm(world, state_machine));
+    }
+
+    #[cfg(feature = "hsm")]
+    fn clone_hsm(&mut self, state_machine: Entity) -> Entity {
+        let target = self.spawn_empty().id();
+        self.queue(move |world: &mut World| {
+            if !clone_hsm_into(world, state_machine, target) {
+                let _ = world.try_despawn(target);
+            }
+        });
+        target
+    }
+
+    #[cfg(feature = "hsm")]
+    fn link_state(&mut self, state_tree: Entity, super_state: Entity, sub_state: Entity) {
+        let link = StateLink::new(state_tree, super_state, sub_state);
+        self.queue(move |world: &mut World| PendingStateLinks::link(world, link));
+    }
+
+    #[cfg(feature = "hsm")]
+    fn reparent_state(
+        &mut self,
+        state_tree: Entity,
+        state: Entity,
+        new_super_state: Entity,
+        eviction: StateEviction,
+    ) {
+        self.queue(move |world: &mut World| {
+            reparent_state(world, state_tree, state, new_super_state, eviction);
+        });
+    }
+
+    fn send_message(&mut self, from: Entity, to: Entity, name: impl Into<Cow<'static, str>>) {
+        let name = name.into();
+        self.queue(move |world: &mut World| {
+            if !HsmInbox::send(world, from, to, name.clone()) {
+                warn!(
+                    "[send_message] {:?} does not exist, dropping '{}'",
+                    to, name
+                );
+            }
+        });
+    }
+}
+
+/// 立即销毁一个层级状态机，参见 [`StateMachineCommandsExt::despawn_hsm`]
+///
+/// Immediately despawn a hierarchical state machine, see [`StateMachineCommandsExt::despawn_hsm`]
+#[cfg(feature = "hsm")]
+pub fn despawn_hsm(world: &mut World, state_machine: Entity) {
+    let Some(state_tree_id) = world
+        .get::<HsmStateMachine>(state_machine)
+        .map(HsmStateMachine::state_tree)
+    else {
+        StateMachineError::HsmStateMachineMissing(state_machine).report(world);
+        return;
+    };
+
+    let shared = is_shared_state_tree(world, state_machine, state_tree_id);
+
+    let states = match shared {
+        true => Vec::new(),
+        false => world
+            .get::<StateTree>(state_tree_id)
+            .map(|state_tree| state_tree.iter().collect())
+            .unwrap_or_default(),
+    };
+
+    let _ = world.try_despawn(state_machine);
+    if !shared && state_tree_id != state_machine {
+        let _ = world.try_despawn(state_tree_id);
+    }
+    for state in states {
+        let _ = world.try_despawn(state);
+    }
+}
+
+#[cfg(feature = "hsm")]
+fn is_shared_state_tree(world: &mut World, state_machine: Entity, state_tree_id: Entity) -> bool {
+    world
+        .query::<(Entity, &HsmStateMachine)>()
+        .iter(world)
+        .any(|(entity, other)| entity != state_machine && other.state_tree() == state_tree_id)
+}
+
+/// # 克隆层级状态机\Clone a Hierarchical State Machine
+/// * 以一个已配置好的状态机（例如预制体上的原型）为模板，立即生成一个新的状态机并返回其实体，原型不是状态机时返回 `None`。
+/// - Spawn a new state machine right away from a configured one (for example a prototype on a prefab) and return its
+///   entity, or `None` when the prototype is not a state machine.
+/// * 状态机实体的组件被复制；私有的状态树与所有状态实体（连同其条件、动作等组件）被深拷贝，
+///   状态树与命名转换（[`HsmTransitions`]）中的状态引用指向新的状态。状态树被其他状态机共享时继续共享。
+/// - The components of the machine entity are copied; a private state tree and every state entity (together with
+///   their condition, action and other components) are deep copied, and the state references in the tree and in the
+///   named transitions ([`HsmTransitions`]) point at the new states. A state tree shared with other machines stays
+///   shared.
+/// * 运行时数据会被重置：新状态机从初始状态重新进入，历史记录为空（保留容量与压缩方式），没有待处理的转换；
+///   终止与故障标记、受监督的子状态机以及检查点不会被复制。
+/// - Runtime data is reset: the new machine enters its initial state afresh with an empty history (capacity and
+///   compression kept) and no pending transition; termination and fault markers, supervised children and checkpoints
+///   are not copied.
+/// * 其他组件中的实体引用（例如 [`ServiceTarget`](crate::prelude::ServiceTarget)）原样复制，需要时请在克隆后替换。
+/// - Entity references inside other components (such as [`ServiceTarget`](crate::prelude::ServiceTarget)) are copied
+///   as-is; replace them after cloning when needed.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn spawn_guard(world: &mut World, prototype: Entity, body: Entity) {
+///     if let Some(guard) = clone_hsm(world, prototype) {
+///         world.entity_mut(guard).insert(ServiceTarget(body));
+///     }
+/// }
+/// ```
+#[cfg(feature = "hsm")]
+pub fn clone_hsm(world: &mut World, state_machine: Entity) -> Option<Entity> {
+    let target = world.spawn_empty().id();
+    if clone_hsm_into(world, state_machine, target) {
+        return Some(target);
+    }
+    let _ = world.try_despawn(target);
+    None
+}
+
+#[cfg(feature = "hsm")]
+fn clone_hsm_into(world: &mut World, state_machine: Entity, target: Entity) -> bool {
+    let Some(prototype) = world.get::<HsmStateMachine>(state_machine).cloned() else {
+        StateMachineError::HsmStateMachineMissing(state_machine).report(world);
+        return false;
+    };
+    let state_tree_id = prototype.state_tree();
+
+    let mut entity_map = EntityHashMap::<Entity>::default();
+    entity_map.insert(state_machine, target);
+    if !is_shared_state_tree(world, state_machine, state_tree_id) {
+        let states = world
+            .get::<StateTree>(state_tree_id)
+            .map(|state_tree| state_tree.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        for state in states {
+            let clone = world.entity_mut(state).clone_and_spawn();
+            entity_map.insert(state, clone);
+        }
+        if state_tree_id != state_machine {
+            let clone = world.entity_mut(state_tree_id).clone_and_spawn();
+            entity_map.insert(state_tree_id, clone);
+        }
+    }
+
+    world.entity_mut(state_machine).clone_with_opt_out(
+        target,
+        |builder: &mut EntityClonerBuilder<'_, OptOut>| {
+            builder.deny::<(
+                HsmStateMachine,
+                CurrentLifecycle,
+                StateLifecycle,
+                SupervisedChildren,
+                HsmCheckpoints,
+                Terminated,
+                HsmFaulted,
+            )>();
+        },
+    );
+
+    let state_tree_id = entity_map
+        .get(&state_tree_id)
+        .copied()
+        .unwrap_or(state_tree_id);
+    if entity_map.contains_key(&prototype.state_tree())
+        && let Some(mut state_tree) = world.get_mut::<StateTree>(state_tree_id)
+    {
+        state_tree.map_entities(&mut entity_map);
+    }
+    if let Some(mut transitions) = world.get_mut::<HsmTransitions>(target) {
+        transitions.map_entities(&mut entity_map);
+    }
+
+    let init_state = prototype.init_state();
+    let init_state = entity_map.get(&init_state).copied().unwrap_or(init_state);
+    #[cfg_attr(not(feature = "history"), allow(unused_mut))]
+    let mut hsm = HsmStateMachine::with(
+        state_tree_id,
+        init_state,
+        #[cfg(feature = "history")]
+        0,
+    );
+    #[cfg(feature = "history")]
+    {
+        hsm.history = prototype.history.clone();
+        hsm.history.clear();
+    }
+    world
+        .entity_mut(target)
+        .insert((hsm, StateLifecycle::default()));
+    true
+}
+
+/// # 重设父状态\Reparent State
+/// * 立即将状态连同其子树移动到同一状态树中的新父状态下，成为其最后一个子状态。
+///   [`StatePriority`](crate::prelude::StatePriority) 在每次遍历时排序，因此移动后立即按新的兄弟状态生效。
+/// - Immediately moves a state together with its subtree under a new super state of the same state tree, as its last
+///   sub-state. [`StatePriority`](crate::prelude::StatePriority) is sorted on every traversal, so it applies among the
+///   new siblings right away.
+/// * 使用该状态树且当前处于被移动子树中的状态机按 `eviction` 处理：[`StateEviction::Stay`] 跟随子树移动，
+///   [`StateEviction::ToSuper`] 在移动前链式转换到原父状态，[`StateEviction::To`] 链式转换到指定状态。
+/// - Machines using the tree whose current state lies in the moved subtree are handled by `eviction`:
+///   [`StateEviction::Stay`] follows the subtree, [`StateEviction::ToSuper`] chain-transitions to the old super state
+///   before the move, and [`StateEviction::To`] chain-transitions to the given state.
+/// * 移动根状态或移动到自身子树中时不做任何修改并返回 `false`。
+/// - Moving the root, or moving into its own subtree, changes nothing and returns `false`.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn learn_combo(world: &mut World, state_tree: Entity, combo: Entity, melee: Entity) {
+///     reparent_state(world, state_tree, combo, melee, StateEviction::Stay);
+/// }
+/// ```
+#[cfg(feature = "hsm")]
+pub fn reparent_state(
+    world: &mut World,
+    state_tree_id: Entity,
+    state: Entity,
+    new_super_state: Entity,
+    eviction: StateEviction,
+) -> bool {
+    let Some(state_tree) = world.get::<StateTree>(state_tree_id) else {
+        StateMachineError::StateTreeNotFound(state_tree_id).report(world);
+        return false;
+    };
+    let Some(old_super_state) = state_tree
+        .get_super_state(state)
+        .filter(|_| state_tree.can_reparent(state, new_super_state))
+    else {
+        warn!(
+            "[reparent_state] {} cannot be moved under {} in {}",
+            state, new_super_state, state_tree_id
+        );
+        return false;
+    };
+
+    let target = match eviction {
+        StateEviction::Stay => None,
+        StateEviction::ToSuper => Some(old_super_state),
+        StateEviction::To(target) => Some(target),
+    };
+    if let Some(target) = target {
+        let mut query = world.query_filtered::<(Entity, &HsmStateMachine), Without<Terminated>>();
+        let evictions = query
+            .iter(world)
+            .filter(|(_, hsm)| hsm.state_tree() == state_tree_id)
+            .filter(|(_, hsm)| {
+                let curr_state_id = hsm.curr_state_id();
+                curr_state_id == state
+                    || world
+                        .get::<StateTree>(state_tree_id)
+                        .is_some_and(|tree| tree.path_iter(curr_state_id).any(|s| s == state))
+            })
+            .map(|(state_machine_id, _)| state_machine_id)
+            .collect::<Vec<_>>();
+        for state_machine_id in evictions {
+            world.trigger(HsmTrigger::chain(state_machine_id, target));
+        }
+    }
+
+    world
+        .get_mut::<StateTree>(state_tree_id)
+        .is_some_and(|mut state_tree| state_tree.reparent(state, new_super_state))
+}
+
+/// 立即销毁一个有限状态机，参见 [`StateMachineCommandsExt::despawn_fsm`]
+///
+/// Immediately despawn a finite state machine, see [`StateMachineCommandsExt::despawn_fsm`]
+#[cfg(feature = "fsm")]
+pub fn despawn_fsm(world: &mut World, state_machine: Entity) {
+    let Some(graph_id) = world
+        .get::<FsmStateMachine>(state_machine)
+        .map(FsmStateMachine::graph_id)
+    else {
+        StateMachineError::FsmStateMachineMissing(state_machine).report(world);
+        return;
+    };
+
+    let shared = world
+        .query::<(Entity, &FsmStateMachine)>()
+        .iter(world)
+        .any(|(entity, other)| entity != state_machine && other.graph_id() == graph_id);
+
+    let states = match shared {
+        true => Default::default(),
+        false => world
+            .get::<FsmGraph>(graph_id)
+            .map(FsmGraph::states)
+            .unwrap_or_default(),
+    };
+
+    let _ = world.try_despawn(state_machine);
+    if !shared && graph_id != state_machine {
+        let _ = world.try_despawn(graph_id);
+    }
+    for state in states {
+        let _ = world.try_despawn(state);
+    }
+}
+
+/// # 状态机森林命令扩展\State Machine Forest Commands Extension
+/// * 对服务于同一个目标（[`ServiceTarget`]）的所有状态机批量执行操作。
+/// - Acts on every state machine serving the same target ([`ServiceTarget`](crate::prelude::ServiceTarget)) at once.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, player: Entity) {
+/// commands.broadcast_pause(player);
+/// commands.broadcast_trigger(player, HsmTrigger::to_super);
+/// # }
+/// ```
+pub trait StateMachineForestCommandsExt {
+    /// 暂停服务于 `target` 的所有状态机
+    ///
+    /// Pause every state machine serving `target`
+    fn broadcast_pause(&mut self, target: Entity);
+
+    /// 恢复服务于 `target` 的所有未终止的状态机
+    ///
+    /// Resume every non-terminated state machine serving `target`
+    fn broadcast_resume(&mut self, target: Entity);
+
+    /// 终止服务于 `target` 的所有状态机
+    ///
+    /// Terminate every state machine serving `target`
+    fn broadcast_terminate(&mut self, target: Entity);
+
+    /// 为服务于 `target` 的每个状态机触发由 `f` 创建的事件
+    ///
+    /// Trigger the event built by `f` for every state machine serving `target`
+    fn broadcast_trigger<E>(&mut self, target: Entity, f: impl Fn(Entity) -> E + Send + 'static)
+    where
+        for<'a> E: EntityEvent<Trigger<'a>: Default>;
+
+    /// 为服务于 `target` 的每个层级状态机提交一个转换请求，目标是其状态树中名为 `state_name` 的状态；
+    /// 没有该状态的状态机会被跳过。
+    ///
+    /// Submit a transition request for every hierarchical state machine serving `target`, aimed at the state named
+    /// `state_name` in its tree; machines without such a state are skipped.
+    #[cfg(feature = "hsm")]
+    fn broadcast_transition(
+        &mut self,
+        target: Entity,
+        state_name: impl Into<std::borrow::Cow<'static, str>>,
+        priority: i32,
+        reason: impl Into<std::borrow::Cow<'static, str>>,
+    );
+}
+
+fn machines_of(world: &World, target: Entity) -> Vec<Entity> {
+    world
+        .get::<StateMachineForest>(target)
+        .map(|forest| forest.machines().to_vec())
+        .unwrap_or_default()
+}
+
+impl StateMachineForestCommandsExt for Commands<'_, '_> {
+    fn broadcast_pause(&mut self, target: Entity) {
+        self.queue(move |world: &mut World| {
+            for state_machine in machines_of(world, target) {
+                world.entity_mut(state_machine).insert(Paused);
+            }
+        });
+    }
+
+    fn broadcast_resume(&mut self, target: Entity) {
+        self.queue(move |world: &mut World| {
+            for state_machine in machines_of(world, target) {
+                let mut entity = world.entity_mut(state_machine);
+                if !entity.contains::<Terminated>() {
+                    entity.remove::<Paused>();
+                }
+            }
+        });
+    }
+
+    fn broadcast_terminate(&mut self, target: Entity) {
+        self.queue(move |world: &mut World| {
+            for state_machine in machines_of(world, target) {
+                world.entity_mut(state_machine).insert(Terminated);
+            }
+        });
+    }
+
+    fn broadcast_trigger<E>(&mut self, target: Entity, f: impl Fn(Entity) -> E + Send + 'static)
+    where
+        for<'a> E: EntityEvent<Trigger<'a>: Default>,
+    {
+        self.queue(move |world: &mut World| {
+            for state_machine in machines_of(world, target) {
+                world.trigger(f(state_machine));
+            }
+        });
+    }
+
+    #[cfg(feature = "hsm")]
+    fn broadcast_transition(
+        &mut self,
+        target: Entity,
+        state_name: impl Into<std::borrow::Cow<'static, str>>,
+        priority: i32,
+        reason: impl Into<std::borrow::Cow<'static, str>>,
+    ) {
+        let state_name = state_name.into();
+        let reason = reason.into();
+        self.queue(move |world: &mut World| {
+            let requests = machines_of(world, target)
+                .into_iter()
+                .filter_map(|state_machine| {
+                    let state = crate::hsm::name_index::HsmNameIndex::find(
+                        world,
+                        state_machine,
+                        &state_name,
+                    )?;
+                    Some((state_machine, state))
+                })
+                .collect::<Vec<_>>();
+            let mut transition_requests =
+                world.resource_mut::<crate::hsm::requester::TransitionRequests>();
+            for (state_machine, state) in requests {
+                transition_requests.submit(state_machine, state, priority, reason.clone(), None);
+            }
+        });
+    }
+}
@@ -0,0 +1,314 @@
+//! # 调试控制台命令\Debug Console Commands
+//!
+//! 与具体控制台（如 bevy_console）无关的命令处理：把控制台输入的一行文本写入 [`HsmConsoleInput`]，
+//! 本模块会解析并执行命令，再把结果写入 [`HsmConsoleOutput`]。也可以直接调用 [`HsmConsoleCommand::execute`]。
+//!
+//! Command handlers independent of any concrete console (such as bevy_console): write the line typed into the console as
+//! [`HsmConsoleInput`], this module parses and executes the command and writes the result as [`HsmConsoleOutput`].
+//! [`HsmConsoleCommand::execute`] can also be called directly.
+//!
+//! | 命令\Command | 说明\Description |
+//! | --- | --- |
+//! | `hsm list` | 列出所有层级状态机\List every hierarchical state machine |
+//! | `hsm inspect <machine>` | 查看状态机的详细信息\Inspect a state machine |
+//! | `hsm goto <machine> <state>` | 跳转到指定状态\Jump to a state |
+//! | `hsm pause <machine>` | 暂停状态机\Pause a state machine |
+//! | `hsm resume <machine>` | 恢复状态机\Resume a state machine |
+//!
+//! 状态机与状态可以用 [`Name`] 或实体（如 `12v1` 或其 `to_bits` 值）指定。
+//!
+//! State machines and states can be referred to by [`Name`] or by entity (such as `12v1` or its `to_bits` value).
+//!
+//! # 示例\Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_hsm::prelude::*;
+//! // 将控制台的输入转发为 `HsmConsoleInput`，并打印输出
+//! // Forward the console input as `HsmConsoleInput` and print the output
+//! fn forward(mut inputs: MessageWriter<HsmConsoleInput>) {
+//!     inputs.write(HsmConsoleInput::new("hsm goto Player Jump"));
+//! }
+//!
+//! fn print(mut outputs: MessageReader<HsmConsoleOutput>) {
+//!     for output in outputs.read() {
+//!         info!("{}", output.0);
+//!     }
+//! }
+//! ```
+
+use std::{fmt::Write, str::FromStr};
+
+use bevy::prelude::*;
+
+use crate::{
+    hsm::{
+        event::HsmTrigger, name_index::HsmNameIndex, state_lifecycle::CurrentLifecycle,
+        state_machine::HsmStateMachine, state_tree::StateTree,
+        transition_strategy::CheckOnTransitionStates,
+    },
+    markers::{Paused, Terminated},
+};
+
+/// # 控制台命令\Console Command
+/// * 解析后的调试控制台命令，参见[模块文档](self)。
+/// - A parsed debug console command, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HsmConsoleCommand {
+    List,
+    Inspect(String),
+    Goto(String, String),
+    Pause(String),
+    Resume(String),
+}
+
+impl FromStr for HsmConsoleCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace().peekable();
+        if words.peek() == Some(&"hsm") {
+            words.next();
+        }
+        let command = match (words.next(), words.next(), words.next()) {
+            (Some("list"), None, None) => Self::List,
+            (Some("inspect"), Some(machine), None) => Self::Inspect(machine.to_string()),
+            (Some("goto"), Some(machine), Some(state)) => {
+                Self::Goto(machine.to_string(), state.to_string())
+            }
+            (Some("pause"), Some(machine), None) => Self::Pause(machine.to_string()),
+            (Some("resume"), Some(machine), None) => Self::Resume(machine.to_string()),
+            _ => return Err(format!("unknown command: {}", s.trim())),
+        };
+        if words.next().is_some() {
+            return Err(format!("too many arguments: {}", s.trim()));
+        }
+        Ok(command)
+    }
+}
+
+impl HsmConsoleCommand {
+    /// 执行命令并返回要显示的文本
+    ///
+    /// Executes the command and returns the text to display
+    pub fn execute(&self, world: &mut World) -> Result<String, String> {
+        match self {
+            Self::List => {
+                let mut query = world.query::<(Entity, &HsmStateMachine)>();
+                let machines = query
+                    .iter(world)
+                    .map(|(entity, machine)| (entity, machine.curr_state_id()))
+                    .collect::<Vec<_>>();
+                let mut output = String::new();
+                for (machine, state) in machines {
+                    let _ = writeln!(
+                        output,
+                        "{} -> {}{}",
+                        label(world, machine),
+                        label(world, state),
+                        flags(world, machine)
+                    );
+                }
+                Ok(output)
+            }
+            Self::Inspect(machine) => {
+                let machine = resolve_machine(world, machine)?;
+                let state_machine = world.get::<HsmStateMachine>(machine).unwrap();
+                let state_tree = state_machine.state_tree();
+                let mut active = vec![state_machine.curr_state_id()];
+                if let Some(tree) = world.get::<StateTree>(state_tree) {
+                    while let Some(super_state) = tree.get_super_state(*active.last().unwrap()) {
+                        active.push(super_state);
+                    }
+                }
+                let mut output = String::new();
+                let _ = writeln!(
+                    output,
+                    "machine: {}{}",
+                    label(world, machine),
+                    flags(world, machine)
+                );
+                let _ = writeln!(output, "tree: {}", label(world, state_tree));
+                let active = active
+                    .iter()
+                    .rev()
+                    .map(|&state| label(world, state))
+                    .collect::<Vec<_>>();
+                let _ = writeln!(output, "active: {}", active.join(" / "));
+                if let Some(current) = world.get::<CurrentLifecycle>(machine) {
+                    let _ = writeln!(output, "lifecycle: {:?}", current.lifecycle);
+                }
+                Ok(output)
+            }
+            Self::Goto(machine, state) => {
+                let machine = resolve_machine(world, machine)?;
+                let state_tree = world.get::<HsmStateMachine>(machine).unwrap().state_tree();
+                let target = HsmNameIndex::find(world, machine, state)
+                    .or_else(|| {
+                        world.get::<StateTree>(state_tree).and_then(|tree| {
+                            tree.iter()
+                                .find(|&entity| matches_entity(world, entity, state))
+                        })
+                    })
+                    .ok_or_else(|| format!("state not found: {}", state))?;
+                world
+                    .resource_mut::<CheckOnTransitionStates>()
+                    .remove(&machine);
+                world.trigger(HsmTrigger::chain(machine, target));
+                world.flush();
+                Ok(format!(
+                    "{} -> {}",
+                    label(world, machine),
+                    label(world, target)
+                ))
+            }
+            Self::Pause(machine) => {
+                let machine = resolve_machine(world, machine)?;
+                world.entity_mut(machine).insert(Paused);
+                Ok(format!("paused {}", label(world, machine)))
+            }
+            Self::Resume(machine) => {
+                let machine = resolve_machine(world, machine)?;
+                world.entity_mut(machine).remove::<Paused>();
+                Ok(format!("resumed {}", label(world, machine)))
+            }
+        }
+    }
+}
+
+/// 控制台输入的一行命令
+///
+/// A line of command typed into the console
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct HsmConsoleInput(pub String);
+
+impl HsmConsoleInput {
+    pub fn new(line: impl Into<String>) -> Self {
+        Self(line.into())
+    }
+}
+
+/// 执行 [`HsmConsoleInput`] 后的输出，错误信息同样以文本形式给出
+///
+/// The output of executing a [`HsmConsoleInput`]; errors are reported as text as well
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct HsmConsoleOutput(pub String);
+
+fn handle_console_input(mut inputs: MessageReader<HsmConsoleInput>, mut commands: Commands) {
+    for HsmConsoleInput(line) in inputs.read() {
+        let line = line.clone();
+        commands.queue(move |world: &mut World| {
+            let output = line
+                .parse::<HsmConsoleCommand>()
+                .and_then(|command| command.execute(world))
+                .unwrap_or_else(|e| format!("error: {}", e));
+            world.write_message(HsmConsoleOutput(output));
+        });
+    }
+}
+
+fn label(world: &World, entity: Entity) -> String {
+    match world.get::<Name>(entity) {
+        Some(name) => format!("{} ({})", name, entity),
+        None => entity.to_string(),
+    }
+}
+
+fn flags(world: &World, machine: Entity) -> &'static str {
+    match (
+        world.get::<Paused>(machine).is_some(),
+        world.get::<Terminated>(machine).is_some(),
+    ) {
+        (_, true) => " [terminated]",
+        (true, false) => " [paused]",
+        (false, false) => "",
+    }
+}
+
+fn matches_entity(world: &World, entity: Entity, entity_ref: &str) -> bool {
+    entity.to_string() == entity_ref
+        || entity_ref.parse::<u64>() == Ok(entity.to_bits())
+        || world
+            .get::<Name>(entity)
+            .is_some_and(|name| name.as_str() == entity_ref)
+}
+
+fn resolve_machine(world: &mut World, machine_ref: &str) -> Result<Entity, String> {
+    let mut query = world.query_filtered::<Entity, With<HsmStateMachine>>();
+    query
+        .iter(world)
+        .find(|&entity| matches_entity(world, entity, machine_ref))
+        .ok_or_else(|| format!("state machine not found: {}", machine_ref))
+}
+
+pub(crate) fn install_console(app: &mut App) {
+    app.add_message::<HsmConsoleInput>()
+        .add_message::<HsmConsoleOutput>()
+        .add_systems(PreUpdate, handle_console_input);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+
+    use crate::{StateMachinePlugin, hsm::HsmState, prelude::StateLifecycle};
+
+    use super::*;
+
+    #[test]
+    fn test_console_commands() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let world = app.world_mut();
+
+        let idle = world.spawn((Name::new("Idle"), HsmState::default())).id();
+        let jump = world.spawn((Name::new("Jump"), HsmState::default())).id();
+        let mut state_tree = StateTree::new(idle);
+        state_tree.with_child(idle, jump);
+        let machine = world.spawn(Name::new("Player")).id();
+        world.entity_mut(machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                machine,
+                idle,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::Enter,
+        ));
+        world.flush();
+
+        let run = |world: &mut World, line: &str| {
+            line.parse::<HsmConsoleCommand>()
+                .and_then(|command| command.execute(world))
+        };
+
+        assert!(run(world, "hsm list").unwrap().contains("Player"));
+        run(world, "hsm goto Player Jump").unwrap();
+        assert_eq!(
+            world
+                .get::<HsmStateMachine>(machine)
+                .unwrap()
+                .curr_state_id(),
+            jump
+        );
+        assert!(
+            run(world, "hsm inspect Player")
+                .unwrap()
+                .contains("active: Idle")
+        );
+
+        run(world, "hsm pause Player").unwrap();
+        assert!(world.get::<Paused>(machine).is_some());
+        assert!(run(world, &format!("resume {}", machine)).is_ok());
+        assert!(world.get::<Paused>(machine).is_none());
+
+        assert!(run(world, "hsm goto Player Fall").is_err());
+        assert!(run(world, "hsm fly").is_err());
+
+        world.write_message(HsmConsoleInput::new("hsm list"));
+        app.update();
+        let outputs = app.world().resource::<Messages<HsmConsoleOutput>>();
+        let mut cursor = outputs.get_cursor();
+        assert!(cursor.read(outputs).any(|output| output.0.contains("Jump")));
+    }
+}
@@ -8,6 +8,8 @@ use bevy::{
     prelude::*,
 };
 
+use crate::fault::catch_fault;
+
 /// A system ID for a transition, which takes a `TransitionContext` as input.
 ///
 /// 用于标识一个转换的 `SystemId`，该系统接收 `TransitionContext` 作为输入。
@@ -34,13 +36,34 @@ mod context_type {
     use super::{ConditionRelationship, TransitionRelationship};
     use bevy::ecs::entity::Entity;
 
-    pub trait ContextRelationship {}
+    pub trait ContextRelationship {
+        /// 上下文所针对的状态
+        ///
+        /// The state the context is about
+        fn subject(&self) -> Entity;
+    }
 
-    impl ContextRelationship for Entity {}
+    impl ContextRelationship for Entity {
+        fn subject(&self) -> Entity {
+            *self
+        }
+    }
 
-    impl ContextRelationship for ConditionRelationship {}
+    impl ContextRelationship for ConditionRelationship {
+        fn subject(&self) -> Entity {
+            self.from
+        }
+    }
 
-    impl ContextRelationship for TransitionRelationship {}
+    impl ContextRelationship for TransitionRelationship {
+        fn subject(&self) -> Entity {
+            match *self {
+                TransitionRelationship::Initial(state)
+                | TransitionRelationship::Final(state)
+                | TransitionRelationship::Transition(_, state) => state,
+            }
+        }
+    }
 }
 
 /// 状态上下文
@@ -73,6 +96,7 @@ impl<T: context_type::ContextRelationship> StateContext<T> {
     /// * `service_target` - 服务目标实体
     /// * `state_machine` - 状态机实体
     /// * `relationship` - 关系数据
+    #[cfg_attr(not(feature = "hsm"), allow(dead_code))]
     pub(crate) const fn with(
         service_target: Entity,
         state_machine: Entity,
@@ -107,11 +131,16 @@ impl<T: context_type::ContextRelationship> StateContext<T> {
     ) -> impl Command<Result<O, RegisteredSystemError<In<Self>, O>>>
     where
         Self: Send + 'static,
-        O: 'static,
+        O: Default + 'static,
     {
         move |world: &mut World| -> Result<O, RegisteredSystemError<In<Self>, O>> {
             world.flush();
-            world.run_system_with(id, self)
+            catch_fault(
+                world,
+                self.state_machine,
+                self.relationship.subject(),
+                |world| world.run_system_with(id, self),
+            )
         }
     }
 }
@@ -192,6 +221,7 @@ impl Debug for GuardContext {
     }
 }
 
+#[cfg(feature = "fsm")]
 impl TransitionContext {
     pub(crate) const fn with_transition(
         service_target: Entity,
@@ -1,7 +1,13 @@
-use bevy::{ecs::schedule::ScheduleError, prelude::Entity};
+use bevy::{
+    ecs::{message::Messages, schedule::ScheduleError, world::DeferredWorld},
+    prelude::*,
+};
 use std::fmt;
 
-use crate::labels::SystemLabel;
+use crate::{
+    guards::{GuardArgs, GuardResolveError},
+    labels::SystemLabel,
+};
 
 /// The error type for operations within the state machine crate.
 #[derive(Debug)]
@@ -62,10 +68,39 @@ pub enum StateMachineError {
         from_state: Entity,
         to_state: Entity,
     },
+    /// A state machine exceeds one of the configured [`StateMachineLimits`](crate::prelude::StateMachineLimits).
+    #[cfg(feature = "hsm")]
+    LimitExceeded {
+        state_machine: Entity,
+        limit: crate::hsm::limits::StateMachineLimit,
+    },
+    /// A guard condition on a state references a guard that is not registered.
+    GuardUnresolved {
+        state: Entity,
+        source: GuardResolveError,
+    },
+    /// A parameterized guard was given arguments it cannot use, e.g. a malformed number or an unregistered value.
+    GuardArgsInvalid {
+        guard: &'static str,
+        args: GuardArgs,
+        reason: String,
+    },
     ActionBufferAlreadyExists(SystemLabel, &'static str),
     ActionBufferNotExists(SystemLabel, &'static str),
     ActionNotFound(SystemLabel),
     ScheduleError(ScheduleError),
+    /// A registered action, transition or condition system panicked (requires the `catch_panics` feature).
+    SystemPanicked {
+        state_machine: Entity,
+        state: Entity,
+        message: String,
+    },
+    /// A registered system of a state machine with an [`HsmFaultPolicy`](crate::fault::HsmFaultPolicy) returned an error.
+    SystemFailed {
+        state_machine: Entity,
+        state: Entity,
+        message: String,
+    },
 }
 
 impl fmt::Display for StateMachineError {
@@ -173,6 +208,33 @@ impl fmt::Display for StateMachineError {
                     from_state, to_state, graph
                 )
             }
+            #[cfg(feature = "hsm")]
+            StateMachineError::LimitExceeded {
+                state_machine,
+                limit,
+            } => {
+                write!(
+                    f,
+                    "State machine {:?} exceeds its limits: {}",
+                    state_machine, limit
+                )
+            }
+            StateMachineError::GuardUnresolved { state, source } => {
+                write!(
+                    f,
+                    "Guard condition of state {:?} cannot be resolved: {}",
+                    state, source
+                )
+            }
+            StateMachineError::GuardArgsInvalid {
+                guard,
+                args,
+                reason,
+            } => write!(
+                f,
+                "Invalid arguments ({}) for guard {}: {}",
+                args, guard, reason
+            ),
             StateMachineError::ActionBufferAlreadyExists(system_label, schedule_name) => write!(
                 f,
                 "The system<{}> for this ScheduleLabel<{}> already exists",
@@ -187,6 +249,24 @@ impl fmt::Display for StateMachineError {
                 write!(f, "Action with label {} not found", system_label)
             }
             StateMachineError::ScheduleError(schedule_error) => schedule_error.fmt(f),
+            StateMachineError::SystemPanicked {
+                state_machine,
+                state,
+                message,
+            } => write!(
+                f,
+                "A system of state {:?} on state machine {:?} panicked: {}",
+                state, state_machine, message
+            ),
+            StateMachineError::SystemFailed {
+                state_machine,
+                state,
+                message,
+            } => write!(
+                f,
+                "A system of state {:?} on state machine {:?} failed: {}",
+                state, state_machine, message
+            ),
         }
     }
 }
@@ -202,3 +282,118 @@ impl std::error::Error for StateMachineError {
         Some(self)
     }
 }
+
+impl StateMachineError {
+    /// 是否属于严重错误（以 `error!` 级别记录）
+    ///
+    /// Whether the error is severe (logged at `error!` level)
+    fn is_severe(&self) -> bool {
+        match self {
+            #[cfg(feature = "hsm")]
+            StateMachineError::HsmStateMachineMissing(_)
+            | StateMachineError::HsmStateMissing(_)
+            | StateMachineError::GuardRunFailed { .. }
+            | StateMachineError::LimitExceeded { .. } => true,
+            #[cfg(feature = "fsm")]
+            StateMachineError::FsmStateMachineMissing(_)
+            | StateMachineError::GraphMissing(_)
+            | StateMachineError::StateNotInGraph { .. }
+            | StateMachineError::InvalidTransitionTarget { .. } => true,
+            StateMachineError::ScheduleError(_)
+            | StateMachineError::SystemPanicked { .. }
+            | StateMachineError::SystemFailed { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// 按照 [`StateMachineErrorPolicy`] 报告该错误：记录日志、写入 [`StateMachineErrorMessage`]，或直接 panic。
+    ///
+    /// Reports the error according to the [`StateMachineErrorPolicy`]: logs it, writes a [`StateMachineErrorMessage`], or panics.
+    pub fn report(self, world: &mut World) {
+        let policy = world
+            .get_resource::<StateMachineErrorPolicy>()
+            .copied()
+            .unwrap_or_default();
+        if policy.should_panic() {
+            panic!("{}", self);
+        }
+        if policy != StateMachineErrorPolicy::Silent {
+            if self.is_severe() {
+                error!("{}", self);
+            } else {
+                warn!("{}", self);
+            }
+        }
+        if world.contains_resource::<Messages<StateMachineErrorMessage>>() {
+            world.write_message(StateMachineErrorMessage(self));
+        }
+    }
+
+    /// 在钩子或观察者中延迟报告该错误
+    ///
+    /// Defers reporting the error from a hook or observer
+    pub fn report_deferred(self, world: &mut DeferredWorld) {
+        world
+            .commands()
+            .queue(move |world: &mut World| self.report(world));
+    }
+
+    /// 通过 [`Commands`] 延迟报告该错误
+    ///
+    /// Defers reporting the error through [`Commands`]
+    pub fn report_with(self, commands: &mut Commands) {
+        commands.queue(move |world: &mut World| self.report(world));
+    }
+}
+
+/// # 状态机错误消息\State Machine Error Message
+/// * 每当状态机报告一个 [`StateMachineError`] 时写入的消息，可通过 [`MessageReader`] 以编程方式处理。
+/// - Written whenever the state machine reports a [`StateMachineError`], so it can be handled programmatically through a [`MessageReader`].
+///
+/// # 示例\Example
+/// ```rust, ignore
+/// fn on_errors(mut errors: MessageReader<StateMachineErrorMessage>) {
+///     for error in errors.read() {
+///         if let StateMachineError::GuardUnresolved { state, .. } = &error.0 {
+///             // ...
+///         }
+///     }
+/// }
+/// ```
+#[derive(Message, Debug, Deref)]
+pub struct StateMachineErrorMessage(pub StateMachineError);
+
+/// # 错误策略\Error Policy
+/// * 决定状态机如何处理运行时错误的资源，默认为 [`StateMachineErrorPolicy::Log`]。
+/// - Resource deciding how the state machine handles runtime errors, [`StateMachineErrorPolicy::Log`] by default.
+///
+/// 无论策略如何（除 panic 外），错误都会写入 [`StateMachineErrorMessage`]。
+///
+/// Regardless of the policy (unless it panics), errors are written as [`StateMachineErrorMessage`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StateMachineErrorPolicy {
+    /// 记录日志
+    ///
+    /// Log the error
+    #[default]
+    Log,
+    /// 不记录日志，仅写入消息
+    ///
+    /// Do not log, only write the message
+    Silent,
+    /// 在调试构建（`debug_assertions`）中 panic，否则记录日志
+    ///
+    /// Panic in debug builds (`debug_assertions`), log otherwise
+    PanicInDebug,
+    /// 总是 panic
+    ///
+    /// Always panic
+    Panic,
+}
+
+impl StateMachineErrorPolicy {
+    fn should_panic(self) -> bool {
+        self == StateMachineErrorPolicy::Panic
+            || (self == StateMachineErrorPolicy::PanicInDebug && cfg!(debug_assertions))
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::{
+    ecs::system::{RegisteredSystemError, SystemInput},
+    prelude::*,
+};
+
+use crate::{
+    error::StateMachineError,
+    markers::{Paused, Terminated},
+};
+
+/// # 状态机故障\Faulted State Machine
+/// * 开启 `catch_panics` 特性后，注册的动作、转换或条件系统 panic 时不会中止整帧，
+///   而是在所属状态机上插入该组件，记录出错的状态与 panic 信息，并报告
+///   [`StateMachineError::SystemPanicked`](crate::error::StateMachineError::SystemPanicked)（写入 [`StateMachineErrorMessage`](crate::prelude::StateMachineErrorMessage)），
+///   其他状态机照常运行。
+/// - With the `catch_panics` feature, a panicking registered action, transition or condition system no longer aborts the
+///   frame. Instead this component is inserted on the owning state machine, recording the offending state and the panic
+///   message, and [`StateMachineError::SystemPanicked`](crate::error::StateMachineError::SystemPanicked) is reported (written as a
+///   [`StateMachineErrorMessage`](crate::prelude::StateMachineErrorMessage)) while other machines keep running.
+/// * 带有 [`HsmFaultPolicy`] 的状态机在系统返回错误时同样会更新该组件。
+/// - Machines with an [`HsmFaultPolicy`] also update this component when a system returns an error.
+/// * 覆盖进入/退出动作、转换系统、守卫与转换钩子；按调度批量运行的更新动作不在此列。
+/// - Covers enter/exit actions, transition systems, guards and transition hooks; update actions, which run batched
+///   in their schedules, are not covered.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn report_faults(query: Query<(Entity, &HsmFaulted), Added<HsmFaulted>>) {
+///     for (state_machine, fault) in query.iter() {
+///         error!("{:?} faulted in {:?}: {}", state_machine, fault.state, fault.message);
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct HsmFaulted {
+    /// 最近一次出错时所在的状态
+    ///
+    /// The state the latest fault happened in
+    pub state: Entity,
+    /// 最近一次的 panic 或错误信息
+    ///
+    /// The latest panic or error message
+    pub message: String,
+    /// 自上次恢复以来连续出错的次数
+    ///
+    /// Number of failures since the last recovery
+    pub failures: u32,
+    /// [`HsmFaultPolicy`] 已执行恢复的次数
+    ///
+    /// Number of times the [`HsmFaultPolicy`] has applied its recovery
+    pub recoveries: u32,
+}
+
+/// # 故障策略\Fault Policy
+/// * 状态机上的钩子、条件或动作系统反复出错（panic 或 [`RegisteredSystemError`]）时自动执行的处理方式。
+///   每次出错都会更新 [`HsmFaulted`]；连续出错次数超过 `retries` 后执行 `recovery`，并重新计数。
+/// - What to do automatically when hooks, conditions or actions of a state machine keep failing (panicking or returning a
+///   [`RegisteredSystemError`]). Every failure updates [`HsmFaulted`]; once the failures exceed `retries`, `recovery` is
+///   applied and counting starts over.
+/// * 带有该组件的状态机，其系统返回的错误会被视为已处理：报告
+///   [`StateMachineError::SystemFailed`](crate::error::StateMachineError::SystemFailed) 后以输出类型的默认值继续。
+/// - On machines with this component, errors returned by their systems are treated as handled: after reporting
+///   [`StateMachineError::SystemFailed`](crate::error::StateMachineError::SystemFailed), execution goes on with the
+///   output's default value.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn harden(mut commands: Commands, state_machine: Entity) {
+///     commands
+///         .entity(state_machine)
+///         .insert(HsmFaultPolicy::new(3, HsmFaultRecovery::Restart));
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct HsmFaultPolicy {
+    /// 执行恢复前允许的出错次数
+    ///
+    /// Failures tolerated before recovering
+    pub retries: u32,
+    /// 超过重试次数后的恢复方式
+    ///
+    /// How to recover once the retries are used up
+    pub recovery: HsmFaultRecovery,
+}
+
+impl HsmFaultPolicy {
+    pub const fn new(retries: u32, recovery: HsmFaultRecovery) -> Self {
+        Self { retries, recovery }
+    }
+}
+
+impl Default for HsmFaultPolicy {
+    fn default() -> Self {
+        Self::new(3, HsmFaultRecovery::default())
+    }
+}
+
+/// # 故障恢复方式\Fault Recovery
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum HsmFaultRecovery {
+    /// 插入 [`Paused`](crate::markers::Paused) 隔离状态机，保留当前状态
+    ///
+    /// Quarantine the machine by inserting [`Paused`](crate::markers::Paused), keeping the current state
+    #[default]
+    Quarantine,
+    /// 回到初始状态后继续运行
+    ///
+    /// Fall back to the initial state and keep running
+    Restart,
+    /// 插入 [`Terminated`](crate::markers::Terminated)
+    ///
+    /// Insert [`Terminated`](crate::markers::Terminated)
+    Terminate,
+}
+
+impl HsmFaultRecovery {
+    fn apply(self, world: &mut World, state_machine: Entity) {
+        let Ok(mut entity) = world.get_entity_mut(state_machine) else {
+            return;
+        };
+        match self {
+            HsmFaultRecovery::Quarantine => {
+                entity.insert(Paused);
+            }
+            // 移除 `Terminated` 时状态机会被重置到初始状态
+            // Removing `Terminated` resets the machine to its initial state
+            HsmFaultRecovery::Restart => {
+                entity.insert(Terminated).remove::<(Terminated, Paused)>();
+            }
+            HsmFaultRecovery::Terminate => {
+                entity.insert(Terminated);
+            }
+        }
+    }
+}
+
+/// 运行用户系统：开启 `catch_panics` 特性时捕获其中的 panic，带有 [`HsmFaultPolicy`] 时接管返回的错误。
+/// 两种情况都会更新 [`HsmFaulted`] 并返回输出类型的默认值（守卫为 `false`，即阻止转换）。
+///
+/// Run a user system, catching its panics with the `catch_panics` feature and taking over returned errors on machines
+/// with an [`HsmFaultPolicy`]. Both update [`HsmFaulted`] and return the output's default value (`false` for guards,
+/// blocking the transition).
+pub(crate) fn catch_fault<I, O>(
+    world: &mut World,
+    state_machine: Entity,
+    state: Entity,
+    run: impl FnOnce(&mut World) -> Result<O, RegisteredSystemError<I, O>>,
+) -> Result<O, RegisteredSystemError<I, O>>
+where
+    I: SystemInput,
+    O: Default,
+{
+    #[cfg(feature = "catch_panics")]
+    let result = {
+        use std::panic::{AssertUnwindSafe, catch_unwind};
+
+        match catch_unwind(AssertUnwindSafe(|| run(&mut *world))) {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                fault(world, state_machine, state, message, true);
+                return Ok(O::default());
+            }
+        }
+    };
+    #[cfg(not(feature = "catch_panics"))]
+    let result = run(world);
+
+    match result {
+        Err(error) if world.get::<HsmFaultPolicy>(state_machine).is_some() => {
+            fault(world, state_machine, state, error.to_string(), false);
+            Ok(O::default())
+        }
+        result => result,
+    }
+}
+
+fn fault(world: &mut World, state_machine: Entity, state: Entity, message: String, panicked: bool) {
+    let error = if panicked {
+        StateMachineError::SystemPanicked {
+            state_machine,
+            state,
+            message: message.clone(),
+        }
+    } else {
+        StateMachineError::SystemFailed {
+            state_machine,
+            state,
+            message: message.clone(),
+        }
+    };
+    error.report(world);
+
+    let policy = world.get::<HsmFaultPolicy>(state_machine).copied();
+    let Ok(mut entity) = world.get_entity_mut(state_machine) else {
+        return;
+    };
+    let mut faulted = entity.get::<HsmFaulted>().cloned().unwrap_or(HsmFaulted {
+        state,
+        message: String::new(),
+        failures: 0,
+        recoveries: 0,
+    });
+    faulted.state = state;
+    faulted.message = message;
+    faulted.failures += 1;
+
+    let recovery = policy
+        .filter(|policy| faulted.failures > policy.retries)
+        .map(|policy| policy.recovery);
+    if recovery.is_some() {
+        faulted.failures = 0;
+        faulted.recoveries += 1;
+    }
+    entity.insert(faulted);
+    if let Some(recovery) = recovery {
+        recovery.apply(world, state_machine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[cfg(feature = "catch_panics")]
+    #[derive(Resource, Default)]
+    struct Entered(usize);
+
+    #[cfg(feature = "catch_panics")]
+    fn spawn_machine(world: &mut World, action: &'static str) -> Entity {
+        let state = world
+            .spawn((HsmState::default(), AfterEnterSystem::new(action)))
+            .id();
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            StateTree::new(state),
+            HsmStateMachine::with(
+                state_machine,
+                state,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+        ));
+        state_machine
+    }
+
+    #[test]
+    #[cfg(feature = "catch_panics")]
+    fn test_panicking_action_faults_only_its_machine() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StateMachinePlugin::default())
+            .init_resource::<Entered>()
+            .register_action("explode", |_: In<ActionContext>| {
+                panic!("boom");
+            })
+            .register_action(
+                "count",
+                |_: In<ActionContext>, mut entered: ResMut<Entered>| {
+                    entered.0 += 1;
+                },
+            );
+
+        let faulty = spawn_machine(app.world_mut(), "explode");
+        let healthy = spawn_machine(app.world_mut(), "count");
+        app.update();
+
+        let fault = app.world().get::<HsmFaulted>(faulty).unwrap();
+        assert_eq!(fault.message, "boom");
+        assert!(app.world().get::<HsmFaulted>(healthy).is_none());
+        assert_eq!(app.world().resource::<Entered>().0, 1);
+    }
+
+    #[test]
+    fn test_fault_policy_recovers_after_retries() {
+        let mut world = World::new();
+        let id = world.register_system(|| {});
+        world.unregister_system(id).unwrap();
+        let state = world.spawn_empty().id();
+        let quarantined = world
+            .spawn(HsmFaultPolicy::new(1, HsmFaultRecovery::Quarantine))
+            .id();
+        let terminated = world
+            .spawn(HsmFaultPolicy::new(0, HsmFaultRecovery::Terminate))
+            .id();
+        let unguarded = world.spawn_empty().id();
+
+        assert!(catch_fault(&mut world, quarantined, state, |world| world.run_system(id)).is_ok());
+        assert!(!world.entity(quarantined).contains::<Paused>());
+        assert_eq!(world.get::<HsmFaulted>(quarantined).unwrap().failures, 1);
+
+        assert!(catch_fault(&mut world, quarantined, state, |world| world.run_system(id)).is_ok());
+        assert!(world.entity(quarantined).contains::<Paused>());
+        let faulted = world.get::<HsmFaulted>(quarantined).unwrap();
+        assert_eq!((faulted.failures, faulted.recoveries), (0, 1));
+        assert_eq!(faulted.state, state);
+        assert!(!faulted.message.is_empty());
+
+        assert!(catch_fault(&mut world, terminated, state, |world| world.run_system(id)).is_ok());
+        assert!(world.entity(terminated).contains::<Terminated>());
+
+        assert!(catch_fault(&mut world, unguarded, state, |world| world.run_system(id)).is_err());
+        assert!(world.get::<HsmFaulted>(unguarded).is_none());
+    }
+}
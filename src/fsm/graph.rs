@@ -394,6 +394,16 @@ impl FsmGraph {
         self
     }
 
+    /// 获取图中出现的所有状态（包括只作为转换目标出现的状态）
+    ///
+    /// Get every state that appears in the graph, including states that only appear as transition targets
+    pub fn states(&self) -> HashSet<Entity> {
+        self.transitions
+            .iter()
+            .flat_map(|(from, transitions)| std::iter::once(*from).chain(transitions.iter()))
+            .collect()
+    }
+
     pub fn is_bridge(&self, from: Entity, to: Entity) -> bool {
         if from == to {
             return true;
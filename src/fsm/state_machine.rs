@@ -3,10 +3,12 @@ use bevy::{
         lifecycle::HookContext, relationship::Relationship, system::SystemParam,
         world::DeferredWorld,
     },
-    platform::collections::HashMap,
     prelude::*,
 };
 
+#[cfg(feature = "hybrid")]
+use bevy::platform::collections::HashMap;
+
 use crate::{
     context::*,
     error::StateMachineError,
@@ -135,12 +137,12 @@ impl FsmStateMachine {
     fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
         #[cfg(feature = "history")]
         let Some(mut fsm_state_machine) = world.get_mut::<FsmStateMachine>(entity) else {
-            error!("{}", StateMachineError::FsmStateMachineMissing(entity));
+            StateMachineError::FsmStateMachineMissing(entity).report_deferred(&mut world);
             return;
         };
         #[cfg(not(feature = "history"))]
         let Some(fsm_state_machine) = world.get::<FsmStateMachine>(entity) else {
-            error!("{}", StateMachineError::FsmStateMachineMissing(entity));
+            StateMachineError::FsmStateMachineMissing(entity).report_deferred(&mut world);
             return;
         };
         let curr_state = fsm_state_machine.curr_state_id();
@@ -149,7 +151,7 @@ impl FsmStateMachine {
         let service_target = Self::get_service_target(&world, entity);
 
         if let Some(id) =
-            TransitionRegistry::get_transition_id::<BeforeEnterSystem>(&world, curr_state)
+            TransitionRegistry::get_transition_id::<BeforeEnterSystem>(&world, entity, curr_state)
         {
             let context = TransitionContext::with_initial(service_target, entity, curr_state);
             context.run_system(&mut world, id);
@@ -160,7 +162,9 @@ impl FsmStateMachine {
 
         let context = ActionContext::new(service_target, entity, curr_state);
 
-        if let Some(id) = ActionRegistry::get_action_id::<AfterEnterSystem>(&world, curr_state) {
+        if let Some(id) =
+            ActionRegistry::get_action_id::<AfterEnterSystem>(&world, entity, curr_state)
+        {
             context.run_system(&mut world, id);
         }
 
@@ -176,7 +180,7 @@ impl FsmStateMachine {
 
     fn on_remove(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
         let Some(fsm_state_machine) = world.get::<FsmStateMachine>(entity) else {
-            error!("{}", StateMachineError::FsmStateMachineMissing(entity));
+            StateMachineError::FsmStateMachineMissing(entity).report_deferred(&mut world);
             return;
         };
 
@@ -185,7 +189,9 @@ impl FsmStateMachine {
 
         let context = ActionContext::new(service_target, entity, curr_state);
 
-        if let Some(id) = ActionRegistry::get_action_id::<BeforeExitSystem>(&world, curr_state) {
+        if let Some(id) =
+            ActionRegistry::get_action_id::<BeforeExitSystem>(&world, entity, curr_state)
+        {
             context.run_system(&mut world, id);
         }
 
@@ -193,7 +199,7 @@ impl FsmStateMachine {
         StateData::remove_components(&mut world, curr_state, service_target);
 
         if let Some(id) =
-            TransitionRegistry::get_transition_id::<AfterExitSystem>(&world, curr_state)
+            TransitionRegistry::get_transition_id::<AfterExitSystem>(&world, entity, curr_state)
         {
             let context = TransitionContext::with_final(service_target, entity, curr_state);
             context.run_system(&mut world, id);
@@ -226,27 +232,19 @@ impl FsmStateMachine {
         let state_machine_id = *state_machine;
 
         let Ok(mut state_machine) = query.get_mut(state_machine_id) else {
-            error!(
-                "{}",
-                StateMachineError::FsmStateMachineMissing(state_machine_id)
-            );
+            StateMachineError::FsmStateMachineMissing(state_machine_id).report_with(&mut commands);
             return;
         };
         let Ok(fsm_graph) = fsm_graph.get(state_machine.graph_id) else {
-            error!(
-                "{}",
-                StateMachineError::GraphMissing(state_machine.graph_id)
-            );
+            StateMachineError::GraphMissing(state_machine.graph_id).report_with(&mut commands);
             return;
         };
         let Some(state_transitions) = fsm_graph.get(state_machine.curr_state_id()) else {
-            error!(
-                "{}",
-                StateMachineError::StateNotInGraph {
-                    graph: state_machine.graph_id,
-                    state: state_machine.curr_state_id()
-                }
-            );
+            StateMachineError::StateNotInGraph {
+                graph: state_machine.graph_id,
+                state: state_machine.curr_state_id(),
+            }
+            .report_with(&mut commands);
             return;
         };
 
@@ -323,7 +321,7 @@ impl FsmStateMachine {
 
         let context = ActionContext::new(service_target, state_machine_id, from);
 
-        if let Some(get_buff_id) = action_systems.get_buffer_id(from) {
+        for get_buff_id in action_systems.get_buffer_ids(from) {
             commands.queue(move |world: &mut World| {
                 (get_buff_id)(
                     world,
@@ -337,6 +335,8 @@ impl FsmStateMachine {
 
         action_systems.run_exit_action(from, context, commands);
 
+        commands.queue(Self::exit_cleanup(context, to));
+
         #[cfg(feature = "state_data")]
         if let Ok(state_data) = query_state_data.get(from).cloned() {
             commands.queue(state_data.remove_state_data_command(service_target));
@@ -360,15 +360,50 @@ impl FsmStateMachine {
 
         action_systems.run_enter_action(to, context, commands);
 
-        if let Some(get_buff_id) = action_systems.get_buffer_id(to) {
-            commands.queue(move |world: &mut World| {
+        commands.queue(Self::enter_setup(
+            context,
+            action_systems.get_buffer_ids(to),
+        ));
+    }
+
+    /// 离开 `context` 中的状态前往 `to` 时的清理，直接转换与守卫转换共用
+    ///
+    /// Cleanup when leaving the state in `context` for `to`, shared by direct and guard transitions
+    fn exit_cleanup(context: ActionContext, to: Entity) -> impl Command {
+        move |world: &mut World| {
+            let (state_machine, from) = (context.state_machine, context.state());
+            #[cfg(feature = "audio")]
+            crate::audio::play_state_sound::<crate::audio::HsmExitSound>(from).apply(world);
+            crate::tasks::StateTasks::cancel_command(state_machine, from).apply(world);
+            crate::builtin_guards::GuardCounters::clear_command(state_machine, from).apply(world);
+            crate::builtin_guards::GuardHysteresis::clear_command(state_machine, from).apply(world);
+            crate::guards::StickyGuards::consume_command(state_machine, from).apply(world);
+            crate::inbox::HsmInbox::consume_command(state_machine, from).apply(world);
+            crate::builtin_guards::TransitionCooldowns::record(
+                world,
+                GuardContext::new(context.service_target, state_machine, from, to),
+            );
+            crate::behavior::HsmBehavior::exit_command(context).apply(world);
+        }
+    }
+
+    /// 进入 `context` 中的状态后的处理：播放进入音效并加入更新缓冲，直接转换与守卫转换共用
+    ///
+    /// Setup after entering the state in `context`: plays the enter sound and joins the update buffers, shared by direct
+    /// and guard transitions
+    fn enter_setup(context: ActionContext, buffer_ids: Vec<GetBufferId>) -> impl Command {
+        move |world: &mut World| {
+            #[cfg(feature = "audio")]
+            crate::audio::play_state_sound::<crate::audio::HsmEnterSound>(context.state())
+                .apply(world);
+            for get_buff_id in buffer_ids {
                 (get_buff_id)(
                     world,
                     Box::new(move |buffer| {
                         buffer.add(context);
                     }),
-                )
-            });
+                );
+            }
         }
     }
 
@@ -384,11 +419,12 @@ impl FsmStateMachine {
     ) {
         let id = match guard_registry.to_combinator_condition_id(guard) {
             Ok(id) => id,
-            Err(e) => {
-                warn!(
-                    "[GuardRegistry] This guard<{:?}> does not exist for state {:?}: {}",
-                    guard, target, e
-                );
+            Err(source) => {
+                StateMachineError::GuardUnresolved {
+                    state: target,
+                    source,
+                }
+                .report_with(commands);
                 return;
             }
         };
@@ -397,12 +433,8 @@ impl FsmStateMachine {
         let from = state_machine.curr_state_id();
         let context = GuardContext::new(service_target, state_machine_id, from, target);
 
-        let remove_buffer_id = action_systems.get_buffer_id(from).map(|id| {
-            (
-                id,
-                ActionContext::new(service_target, state_machine_id, from),
-            )
-        });
+        let remove_buffer_ids = action_systems.get_buffer_ids(from);
+        let remove_buffer_context = ActionContext::new(service_target, state_machine_id, from);
 
         let on_exit_system_id = action_systems.get_exit_action_id(from).map(|id| {
             (
@@ -422,23 +454,19 @@ impl FsmStateMachine {
             )
         });
 
-        let add_buffer_id = action_systems.get_buffer_id(target).map(|id| {
-            (
-                id,
-                ActionContext::new(service_target, state_machine_id, target),
-            )
-        });
+        let add_buffer_ids = action_systems.get_buffer_ids(target);
+        let add_buffer_context = ActionContext::new(service_target, state_machine_id, target);
 
         commands.queue(move |world: &mut World| -> bevy::prelude::Result<()> {
             if !id.run(world, context)? {
                 return Ok(());
             }
-            if let Some((system, action_context)) = remove_buffer_id {
+            for system in remove_buffer_ids {
                 (system)(
                     world,
                     Box::new(move |buffer| {
-                        buffer.remove_interceptor(action_context);
-                        buffer.add_filter(action_context);
+                        buffer.remove_interceptor(remove_buffer_context);
+                        buffer.add_filter(remove_buffer_context);
                     }),
                 );
             }
@@ -453,6 +481,8 @@ impl FsmStateMachine {
                 }
             }
 
+            Self::exit_cleanup(remove_buffer_context, target).apply(world);
+
             if let Some(id) = after_exit_system_id {
                 let context = TransitionContext::with_transition(
                     service_target,
@@ -487,14 +517,7 @@ impl FsmStateMachine {
                 context.queue_system_command(id).apply(world)?;
             }
 
-            if let Some((system, action_context)) = add_buffer_id {
-                (system)(
-                    world,
-                    Box::new(move |buffer| {
-                        buffer.add(action_context);
-                    }),
-                )
-            }
+            Self::enter_setup(add_buffer_context, add_buffer_ids).apply(world);
             Ok(())
         });
     }
@@ -508,6 +531,7 @@ pub(crate) struct ActionSystems<'w, 's> {
     query_on_exit_system: Query<'w, 's, &'static BeforeExitSystem, With<FsmState>>,
     query_on_enter_system: Query<'w, 's, &'static AfterEnterSystem, With<FsmState>>,
     query_on_update_system: Query<'w, 's, &'static OnUpdateSystem, With<FsmState>>,
+    query_on_update_systems: Query<'w, 's, &'static OnUpdateSystems, With<FsmState>>,
     query_after_exit_system: Query<'w, 's, &'static AfterExitSystem, With<FsmState>>,
     query_before_enter_system: Query<'w, 's, &'static BeforeEnterSystem, With<FsmState>>,
     query_service_target: Query<'w, 's, &'static ServiceTarget, With<FsmStateMachine>>,
@@ -536,10 +560,19 @@ impl<'w, 's> ActionSystems<'w, 's> {
         res.ok().and_then(f)
     }
 
-    pub fn get_buffer_id(&self, state: Entity) -> Option<GetBufferId> {
-        self.ok_and_then(self.query_on_update_system.get(state), |update| {
-            self.action_dispatch.get(update)
-        })
+    #[doc(hidden)]
+    pub fn get_buffer_ids(&self, state: Entity) -> Vec<GetBufferId> {
+        let update = self.query_on_update_system.get(state).ok().map(|s| &**s);
+        let updates = self
+            .query_on_update_systems
+            .get(state)
+            .into_iter()
+            .flat_map(|s| s.iter());
+        update
+            .into_iter()
+            .chain(updates)
+            .filter_map(|update| self.action_dispatch.get(update))
+            .collect()
     }
 
     pub fn get_enter_action_id(&self, state: Entity) -> Option<ActionId> {
@@ -579,7 +612,7 @@ impl<'w, 's> ActionSystems<'w, 's> {
             commands.queue(context.queue_system_command(id));
             return;
         }
-        warn!("{}", enter.not_found_error(state));
+        enter.not_found_error(state).report_with(commands);
     }
 
     #[inline]
@@ -591,7 +624,7 @@ impl<'w, 's> ActionSystems<'w, 's> {
             commands.queue(context.queue_system_command(id));
             return;
         };
-        warn!("{}", enter.not_found_error(state));
+        enter.not_found_error(state).report_with(commands);
     }
 
     #[inline]
@@ -604,7 +637,7 @@ impl<'w, 's> ActionSystems<'w, 's> {
             commands.queue(context.queue_system_command(id));
             return;
         }
-        warn!("{}", exit.not_found_error(state));
+        exit.not_found_error(state).report_with(commands);
     }
 
     pub fn run_after_exit(
@@ -620,7 +653,7 @@ impl<'w, 's> ActionSystems<'w, 's> {
             commands.queue(context.queue_system_command(id));
             return;
         }
-        warn!("{}", exit.not_found_error(state));
+        exit.not_found_error(state).report_with(commands);
     }
 }
 
@@ -3,11 +3,13 @@ use std::{
     fmt::{Debug, Display},
     hash::Hash,
     str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
 
 use bevy::{
     ecs::system::{RegisteredSystemError, SystemId},
-    platform::collections::{Equivalent, HashMap},
+    platform::collections::{Equivalent, HashMap, HashSet},
     prelude::*,
 };
 use smallvec::SmallVec;
@@ -76,6 +78,65 @@ impl std::error::Error for GuardResolveError {}
 /// Used to determine if `State` meets the conditions for entering or exiting, where the context entity is the entity currently being checked
 pub type GuardId = SystemId<In<GuardContext>, bool>;
 
+/// 带参数的状态条件的系统ID
+///
+/// 与 [`GuardId`] 相同，但额外接收在条件中书写的参数，例如 `just_pressed("Space")`
+///
+/// Parameterized state condition system ID
+///
+/// Same as [`GuardId`], but additionally receives the arguments written in the condition, e.g. `just_pressed("Space")`
+pub type ParamGuardId = SystemId<In<(GuardContext, GuardArgs)>, bool>;
+
+/// # 守卫参数\Guard Arguments
+/// * 带参数守卫在条件中书写的字面量参数。
+/// - The literal arguments written for a parameterized guard in a condition.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Deref)]
+pub struct GuardArgs(Arc<[String]>);
+
+impl GuardArgs {
+    pub fn new(args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(args.into_iter().map(Into::into).collect())
+    }
+
+    /// 将第 `index` 个参数解析为 `T`
+    ///
+    /// Parse the `index`-th argument as `T`
+    pub fn parse<T: FromStr>(&self, index: usize) -> Option<T> {
+        self.0.get(index)?.parse().ok()
+    }
+}
+
+impl Display for GuardArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let joined = self
+            .0
+            .iter()
+            .map(|arg| match is_number_literal(arg) {
+                true => arg.clone(),
+                false => format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}", joined)
+    }
+}
+
+/// 是否能被条件表达式按数字读取：可选的 `-`、数字与至多一个 `.`
+///
+/// Whether a condition expression reads it back as a number: an optional `-`, digits and at most one `.`
+fn is_number_literal(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1
+}
+
+impl Debug for GuardArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        <Self as Display>::fmt(self, f)
+    }
+}
+
 /// 注册用于判断`State`是否满足进入或退出的条件
 ///
 /// Register to determine if `State` meets the conditions for entering or exiting
@@ -91,37 +152,80 @@ pub type GuardId = SystemId<In<GuardContext>, bool>;
 /// # }
 /// ```
 #[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
-pub struct GuardRegistry(pub(super) HashMap<SystemLabel, GuardId>);
+pub struct GuardRegistry(
+    pub(super) HashMap<SystemLabel, GuardId>,
+    pub(super) HashMap<SystemLabel, ParamGuardId>,
+    pub(super) HashMap<SystemLabel, ReadOnlyGuardId>,
+);
 
 impl GuardRegistry {
     pub fn to_combinator_condition_id(
         &self,
         condition: &GuardCondition,
     ) -> Result<CompiledGuard, GuardResolveError> {
+        self.compile(None, condition)
+    }
+
+    /// 编译守卫，`self` 中的名称遮蔽 `fallback` 中的同名条件，例如以状态机的
+    /// [`LocalRegistry`](crate::local_registry::LocalRegistry) 遮蔽全局注册表
+    ///
+    /// Compile a guard with the names in `self` shadowing the same names in `fallback`, e.g. a machine's
+    /// [`LocalRegistry`](crate::local_registry::LocalRegistry) shadowing the global registry
+    pub fn to_combinator_condition_id_with(
+        &self,
+        fallback: &GuardRegistry,
+        condition: &GuardCondition,
+    ) -> Result<CompiledGuard, GuardResolveError> {
+        self.compile(Some(fallback), condition)
+    }
+
+    fn compile(
+        &self,
+        fallback: Option<&GuardRegistry>,
+        condition: &GuardCondition,
+    ) -> Result<CompiledGuard, GuardResolveError> {
+        let layers = || std::iter::once(self).chain(fallback);
         match condition {
             GuardCondition::And(conditions) => {
                 let mut condition_ids = SmallVec::new();
                 for condition in conditions {
-                    condition_ids.push(Box::new(self.to_combinator_condition_id(condition)?));
+                    condition_ids.push(Box::new(self.compile(fallback, condition)?));
                 }
                 Ok(CompiledGuard::And(condition_ids))
             }
             GuardCondition::Or(conditions) => {
                 let mut condition_ids = SmallVec::new();
                 for condition in conditions {
-                    condition_ids.push(Box::new(self.to_combinator_condition_id(condition)?));
+                    condition_ids.push(Box::new(self.compile(fallback, condition)?));
                 }
                 Ok(CompiledGuard::Or(condition_ids))
             }
             GuardCondition::Not(condition) => Ok(CompiledGuard::Not(Box::new(
-                self.to_combinator_condition_id(condition)?,
+                self.compile(fallback, condition)?,
             ))),
-            GuardCondition::Id(condition_id) => {
-                let id = self
-                    .get(condition_id)
-                    .ok_or_else(|| GuardResolveError::UnregisteredGuard(condition_id.clone()))?;
-                Ok(CompiledGuard::Id(id))
+            GuardCondition::Id(condition_id) => layers()
+                .find_map(|registry| {
+                    registry
+                        .get_read_only(condition_id)
+                        .map(|id| CompiledGuard::ReadOnly(id, condition_id.clone()))
+                        .or_else(|| {
+                            registry
+                                .get(condition_id)
+                                .map(|id| CompiledGuard::Id(id, condition_id.clone()))
+                        })
+                })
+                .ok_or_else(|| GuardResolveError::UnregisteredGuard(condition_id.clone())),
+            GuardCondition::Call(name, args) => {
+                let id = layers()
+                    .find_map(|registry| registry.get_param(name))
+                    .ok_or_else(|| GuardResolveError::UnregisteredGuard(name.clone()))?;
+                Ok(CompiledGuard::Call(id, name.clone(), args.clone()))
             }
+            GuardCondition::Sticky(inner, timeout) => Ok(CompiledGuard::Sticky(
+                Box::new(self.compile(fallback, inner)?),
+                condition.to_string(),
+                *timeout,
+            )),
         }
     }
 
@@ -157,12 +261,91 @@ impl GuardRegistry {
         self.0.remove(name)
     }
 
+    /// 检查系统是否仍以任一名称注册为条件
+    ///
+    /// Check whether the system is still registered as a condition under any name
+    pub(crate) fn contains_id(&self, condition_id: GuardId) -> bool {
+        self.0.values().any(|id| *id == condition_id)
+    }
+
+    /// 获取一个带参数的条件
+    ///
+    /// Get a parameterized condition
+    pub fn get_param<Q>(&self, name: &Q) -> Option<ParamGuardId>
+    where
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
+    {
+        self.1.get(name).cloned()
+    }
+
+    /// 插入一个带参数的条件
+    ///
+    /// Insert a parameterized condition
+    pub fn insert_param(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        condition_id: ParamGuardId,
+    ) -> Option<ParamGuardId> {
+        self.1.insert(name.into(), condition_id)
+    }
+
+    /// 移除一个带参数的条件
+    ///
+    /// Remove a parameterized condition
+    pub fn remove_param<Q>(&mut self, name: &Q) -> Option<ParamGuardId>
+    where
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
+    {
+        self.1.remove(name)
+    }
+
+    /// 获取一个只读条件
+    ///
+    /// Get a read-only condition
+    pub fn get_read_only<Q>(&self, name: &Q) -> Option<ReadOnlyGuardId>
+    where
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
+    {
+        self.2.get(name).copied()
+    }
+
+    /// 插入一个只读条件
+    ///
+    /// Insert a read-only condition
+    pub fn insert_read_only(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        condition_id: ReadOnlyGuardId,
+    ) -> Option<ReadOnlyGuardId> {
+        self.2.insert(name.into(), condition_id)
+    }
+
+    /// 移除一个只读条件
+    ///
+    /// Remove a read-only condition
+    pub fn remove_read_only<Q>(&mut self, name: &Q) -> Option<ReadOnlyGuardId>
+    where
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
+    {
+        self.2.remove(name)
+    }
+
+    /// 检查是否存在可以在条件中直接书写的同名条件（普通条件或只读条件）
+    ///
+    /// Check whether a condition usable by name in expressions exists (a regular or a read-only one)
+    pub fn contains<Q>(&self, name: &Q) -> bool
+    where
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
+    {
+        self.0.contains_key(name) || self.2.contains_key(name)
+    }
+
     /// 获取已注册守卫的数量
     ///
     /// Get the number of registered guards
     #[inline]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.0.len() + self.1.len() + self.2.len()
     }
 
     /// 检查守卫注册表是否为空
@@ -170,7 +353,7 @@ impl GuardRegistry {
     /// Check if the guard registry is empty
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.0.is_empty() && self.1.is_empty() && self.2.is_empty()
     }
 }
 
@@ -182,7 +365,11 @@ impl<S: Into<SystemLabel>> Extend<(S, GuardId)> for GuardRegistry {
 
 impl<S: Into<SystemLabel>, const N: usize> From<[(S, GuardId); N]> for GuardRegistry {
     fn from(value: [(S, GuardId); N]) -> Self {
-        Self(HashMap::from(value.map(|(s, a)| (s.into(), a))))
+        Self(
+            HashMap::from(value.map(|(s, a)| (s.into(), a))),
+            HashMap::new(),
+            HashMap::new(),
+        )
     }
 }
 
@@ -202,15 +389,19 @@ pub enum CompiledGuard {
     And(SmallVec<[Box<CompiledGuard>; 2]>),
     Or(SmallVec<[Box<CompiledGuard>; 2]>),
     Not(Box<CompiledGuard>),
-    Id(GuardId),
+    Id(GuardId, SystemLabel),
+    Call(ParamGuardId, SystemLabel, GuardArgs),
+    ReadOnly(ReadOnlyGuardId, SystemLabel),
+    Sticky(Box<CompiledGuard>, String, Option<Duration>),
 }
 
 impl CompiledGuard {
-    /// 从一个 `GuardId` 创建一个新的 `CompiledGuard`。
+    /// 从一个 `GuardId` 及其注册名称创建一个新的 `CompiledGuard`，[`GuardOverrides`] 按该名称生效。
     ///
-    /// Creates a new `CompiledGuard` from a `GuardId`.
-    pub fn new(id: GuardId) -> Self {
-        Self::Id(id)
+    /// Creates a new `CompiledGuard` from a `GuardId` and the name it is registered under, which [`GuardOverrides`]
+    /// apply by.
+    pub fn new(id: GuardId, label: impl Into<SystemLabel>) -> Self {
+        Self::Id(id, label.into())
     }
 
     /// 添加一个 `AND` 条件。
@@ -273,9 +464,328 @@ impl CompiledGuard {
                 Ok(false)
             }
             CompiledGuard::Not(not) => Ok(!not.run(world, input)?),
-            CompiledGuard::Id(system_id) => input.queue_system_command(*system_id).apply(world),
+            CompiledGuard::Id(system_id, label) => {
+                if let Some(value) = GuardOverrides::lookup(world, input.state_machine, label) {
+                    return Ok(value);
+                }
+                TargetGuardMemo::run(world, *system_id, input)
+            }
+            CompiledGuard::Call(system_id, label, args) => {
+                if let Some(value) = GuardOverrides::lookup(world, input.state_machine, label) {
+                    return Ok(value);
+                }
+                world.flush();
+                catch_fault(world, input.state_machine, input.from_state(), |world| {
+                    world.run_system_with(*system_id, (input, args.clone()))
+                })
+                .map_err(|e| match e {
+                    RegisteredSystemError::Skipped(e) => RegisteredSystemError::Skipped(e),
+                    RegisteredSystemError::Failed(e) => RegisteredSystemError::Failed(e),
+                    e => RegisteredSystemError::Failed(e.into()),
+                })
+            }
+            CompiledGuard::ReadOnly(id, label) => {
+                if let Some(value) = GuardOverrides::lookup(world, input.state_machine, label) {
+                    return Ok(value);
+                }
+                Ok(ReadOnlyGuards::run(world, *id, input))
+            }
+            CompiledGuard::Sticky(inner, expression, timeout) => {
+                if StickyGuards::holds(world, input, expression, *timeout) {
+                    return Ok(true);
+                }
+                let result = inner.run(world, input)?;
+                if result {
+                    StickyGuards::fire(world, input, expression);
+                }
+                Ok(result)
+            }
+        }
+    }
+}
+
+/// # 粘滞条件\Sticky Conditions
+/// * 挂载在状态机实体上，记录 `sticky(...)` 条件按 `(起点状态, 目标状态, 表达式)` 成立的时刻。
+///   条件一旦成立便保持成立，直到转换将其消耗（起点状态退出或目标状态进入），或超过给定的秒数
+///   （按 [`StateClock::now`] 计时，`Time` 资源与时钟都不存在时不会超时）。
+/// - Lives on the state machine entity and records when each `sticky(...)` condition held, per
+///   `(from state, to state, expression)`. Once the condition holds it stays true until a transition consumes it (the
+///   from state exits or the to state is entered), or until the given number of seconds passes (measured with
+///   [`StateClock::now`], never timing out without either a `Time` resource or a clock).
+/// * `and`/`or` 会短路，粘滞条件应放在前面，保证每次检查都会采样。
+/// - `and`/`or` short-circuit, so put sticky conditions first to have them sampled on every check.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy_hsm::prelude::*;
+/// // 按下跳跃后的 0.2 秒内落地仍会起跳
+/// // A jump pressed up to 0.2 seconds before landing still fires
+/// let condition = GuardCondition::parse("and(sticky(jump_pressed, 0.2), grounded)").unwrap();
+/// # let _ = condition;
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct StickyGuards(HashMap<(Entity, Entity, String), Option<Duration>>);
+
+impl StickyGuards {
+    /// 某个 `(起点状态, 目标状态, 表达式)` 是否已成立且尚未被消耗
+    ///
+    /// Whether a `(from state, to state, expression)` has held and was not consumed yet
+    pub fn contains(&self, from_state: Entity, to_state: Entity, expression: &str) -> bool {
+        self.0
+            .contains_key(&(from_state, to_state, expression.to_string()))
+    }
+
+    /// 消耗以该状态为起点或目标的条件
+    ///
+    /// Consume the conditions whose from or to state is the given state
+    pub fn consume(&mut self, state: Entity) {
+        self.0
+            .retain(|(from_state, to_state, _), _| *from_state != state && *to_state != state);
+    }
+
+    pub(crate) fn consume_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut sticky) = world.get_mut::<StickyGuards>(state_machine) {
+                sticky.consume(state);
+            }
         }
     }
+
+    fn holds(
+        world: &mut World,
+        context: GuardContext,
+        expression: &str,
+        timeout: Option<Duration>,
+    ) -> bool {
+        let now = StateClock::now(world, context.state_machine);
+        let Some(mut sticky) = world.get_mut::<StickyGuards>(context.state_machine) else {
+            return false;
+        };
+        let key = (
+            context.from_state(),
+            context.to_state(),
+            expression.to_string(),
+        );
+        let Some(fired) = sticky.0.get(&key).copied() else {
+            return false;
+        };
+        let expired = match (timeout, fired, now) {
+            (Some(timeout), Some(fired), Some(now)) => now.saturating_sub(fired) > timeout,
+            _ => false,
+        };
+        if expired {
+            sticky.0.remove(&key);
+        }
+        !expired
+    }
+
+    fn fire(world: &mut World, context: GuardContext, expression: &str) {
+        let now = StateClock::now(world, context.state_machine);
+        let Ok(mut entity) = world.get_entity_mut(context.state_machine) else {
+            return;
+        };
+        entity
+            .entry::<StickyGuards>()
+            .or_default()
+            .get_mut()
+            .0
+            .insert(
+                (
+                    context.from_state(),
+                    context.to_state(),
+                    expression.to_string(),
+                ),
+                now,
+            );
+    }
+}
+
+/// # 服务目标条件缓存\Target Condition Memo
+/// * 通过 [`RegisterStateSystem::register_target_guard`](crate::prelude::RegisterStateSystem::register_target_guard)
+///   注册的条件只依赖服务目标，因此按 `(条件, 服务目标)` 缓存结果，同一帧内共享同一服务目标的所有状态机
+///   （例如一个 [`StateMachineForest`](crate::prelude::StateMachineForest)）只运行一次该条件。缓存在每帧的 [`First`] 中清空。
+/// - Conditions registered through
+///   [`RegisterStateSystem::register_target_guard`](crate::prelude::RegisterStateSystem::register_target_guard) only
+///   depend on the service target, so their results are cached per `(condition, service target)` and every machine
+///   sharing a service target (e.g. a [`StateMachineForest`](crate::prelude::StateMachineForest)) runs the condition once
+///   per frame. The cache is cleared in [`First`] every frame.
+/// * [`GuardOverrides`] 仍按状态机优先生效，被覆盖的结果不会进入缓存；运行失败的结果同样不缓存。
+/// - [`GuardOverrides`] still apply per machine first and overridden results never enter the cache; failed runs are not
+///   cached either.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # #[derive(Component)]
+/// # struct Health(f32);
+/// fn target_low_health(context: In<GuardContext>, query: Query<&Health>) -> bool {
+///     query.get(context.service_target).is_ok_and(|health| health.0 < 20.0)
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .register_target_guard("target_low_health", target_low_health);
+/// # }
+/// ```
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TargetGuardMemo {
+    guards: HashSet<GuardId>,
+    cache: HashMap<(GuardId, Entity), bool>,
+}
+
+impl TargetGuardMemo {
+    /// 将一个条件标记为只依赖服务目标
+    ///
+    /// Mark a condition as depending on the service target only
+    pub fn insert(&mut self, id: GuardId) -> bool {
+        self.guards.insert(id)
+    }
+
+    /// 取消标记，并丢弃该条件的缓存结果
+    ///
+    /// Unmark a condition, dropping its cached results
+    pub fn remove(&mut self, id: GuardId) -> bool {
+        self.cache.retain(|(guard, _), _| *guard != id);
+        self.guards.remove(&id)
+    }
+
+    pub fn contains(&self, id: GuardId) -> bool {
+        self.guards.contains(&id)
+    }
+
+    /// 本帧为某个服务目标缓存的结果
+    ///
+    /// The result cached this frame for a service target
+    pub fn get(&self, id: GuardId, service_target: Entity) -> Option<bool> {
+        self.cache.get(&(id, service_target)).copied()
+    }
+
+    /// 本帧缓存的结果数量
+    ///
+    /// Number of results cached this frame
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    pub(crate) fn tick(mut memo: ResMut<Self>) {
+        if !memo.cache.is_empty() {
+            memo.cache.clear();
+        }
+    }
+
+    fn run(
+        world: &mut World,
+        id: GuardId,
+        input: GuardContext,
+    ) -> Result<bool, RegisteredSystemError<In<GuardContext>, bool>> {
+        let memoized = match world.get_resource::<Self>() {
+            Some(memo) if memo.contains(id) => match memo.get(id, input.service_target) {
+                Some(value) => return Ok(value),
+                None => true,
+            },
+            _ => false,
+        };
+        let value = input.queue_system_command(id).apply(world)?;
+        if memoized && let Some(mut memo) = world.get_resource_mut::<Self>() {
+            memo.cache.insert((id, input.service_target), value);
+        }
+        Ok(value)
+    }
+}
+
+/// # 守卫覆盖\Guard Overrides
+/// * 强制指定名称的守卫返回固定结果，绕过已注册的系统，可作用于全部状态机或单个状态机（单个状态机的覆盖优先）。
+///   适用于确定性的集成测试，或在调试时强制触发少见的分支。
+/// - Forces a named guard to return a fixed result, bypassing the registered system, either for every state machine or
+///   for a single one (per-machine overrides win). Useful for deterministic integration tests and for forcing rare
+///   branches while debugging.
+/// * 覆盖按守卫编译时引用的名称匹配，因此同样作用于 [`LocalRegistry`](crate::local_registry::LocalRegistry) 中的守卫。
+/// - Overrides match the name a guard was compiled from, so they apply to guards in a
+///   [`LocalRegistry`](crate::local_registry::LocalRegistry) as well.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut overrides: ResMut<GuardOverrides>, boss: Entity) {
+/// overrides.set("is_enraged", true);
+/// overrides.set_for(boss, "is_enraged", false);
+/// # }
+/// ```
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct GuardOverrides(HashMap<(SystemLabel, Option<Entity>), bool>);
+
+impl GuardOverrides {
+    /// 为所有状态机覆盖一个守卫
+    ///
+    /// Override a guard for every state machine
+    pub fn set(&mut self, name: impl Into<SystemLabel>, value: bool) -> &mut Self {
+        self.0.insert((name.into(), None), value);
+        self
+    }
+
+    /// 为单个状态机覆盖一个守卫
+    ///
+    /// Override a guard for a single state machine
+    pub fn set_for(
+        &mut self,
+        state_machine: Entity,
+        name: impl Into<SystemLabel>,
+        value: bool,
+    ) -> &mut Self {
+        self.0.insert((name.into(), Some(state_machine)), value);
+        self
+    }
+
+    /// 移除一个守卫对所有状态机的覆盖
+    ///
+    /// Remove the override of a guard for every state machine
+    pub fn clear(&mut self, name: impl Into<SystemLabel>) -> Option<bool> {
+        self.0.remove(&(name.into(), None))
+    }
+
+    /// 移除一个守卫对单个状态机的覆盖
+    ///
+    /// Remove the override of a guard for a single state machine
+    pub fn clear_for(
+        &mut self,
+        state_machine: Entity,
+        name: impl Into<SystemLabel>,
+    ) -> Option<bool> {
+        self.0.remove(&(name.into(), Some(state_machine)))
+    }
+
+    /// 移除所有覆盖
+    ///
+    /// Remove every override
+    pub fn clear_all(&mut self) {
+        self.0.clear();
+    }
+
+    /// 获取守卫对某个状态机生效的覆盖结果
+    ///
+    /// Get the override result of a guard that applies to a state machine
+    pub fn get(&self, state_machine: Entity, name: impl Into<SystemLabel>) -> Option<bool> {
+        let name = name.into();
+        self.0
+            .get(&(name.clone(), Some(state_machine)))
+            .or_else(|| self.0.get(&(name, None)))
+            .copied()
+    }
+
+    fn lookup(world: &World, state_machine: Entity, label: &SystemLabel) -> Option<bool> {
+        let overrides = world.get_resource::<Self>()?;
+        if overrides.0.is_empty() {
+            return None;
+        }
+        overrides.get(state_machine, label.clone())
+    }
 }
 
 /// 组合条件
@@ -313,6 +823,8 @@ pub enum GuardCondition {
     Or(SmallVec<[Box<GuardCondition>; 2]>),
     Not(Box<GuardCondition>),
     Id(SystemLabel),
+    Call(SystemLabel, GuardArgs),
+    Sticky(Box<GuardCondition>, Option<Duration>),
 }
 
 impl GuardCondition {
@@ -320,6 +832,16 @@ impl GuardCondition {
         Self::Id(name.into())
     }
 
+    /// 创建一个带参数的条件，参见 [`ParamGuardId`]
+    ///
+    /// Create a parameterized condition, see [`ParamGuardId`]
+    pub fn call(
+        name: impl Into<SystemLabel>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self::Call(name.into(), GuardArgs::new(args))
+    }
+
     /// 创建一个and组合条件, 相同条件则合并
     ///
     /// Create an and combination condition, same condition will be merged
@@ -357,6 +879,13 @@ impl GuardCondition {
         condition.add_not()
     }
 
+    /// 创建一个粘滞条件，参见 [`StickyGuards`]
+    ///
+    /// Create a sticky condition, see [`StickyGuards`]
+    pub fn sticky(condition: GuardCondition, timeout: Option<Duration>) -> Self {
+        Self::Sticky(Box::new(condition), timeout)
+    }
+
     /// 添加一个 `AND` 条件。
     ///
     /// Adds an `AND` condition.
@@ -398,15 +927,37 @@ impl GuardCondition {
             _ => Self::Not(Box::new(self)),
         }
     }
+
+    /// 获取条件中引用的所有守卫名称，包括带参数的守卫
+    ///
+    /// Get every guard name referenced by the condition, parameterized guards included
+    pub fn labels(&self) -> Vec<&SystemLabel> {
+        let mut labels = Vec::new();
+        let mut stack = vec![self];
+        while let Some(condition) = stack.pop() {
+            match condition {
+                Self::And(conditions) | Self::Or(conditions) => {
+                    stack.extend(conditions.iter().rev().map(Box::as_ref));
+                }
+                Self::Not(condition) | Self::Sticky(condition, _) => stack.push(condition),
+                Self::Id(label) | Self::Call(label, _) => labels.push(label),
+            }
+        }
+        labels
+    }
 }
 
 impl GuardCondition {
     ///# 编写规则\Write rules
-    ///- combination_condition := not_condition | and_condition | or_condition | id_condition
+    ///- combination_condition := not_condition | sticky_condition | and_condition | or_condition | id_condition
     ///- not_condition := `not` `(` combination_condition `)`
+    ///- sticky_condition := `sticky` `(` combination_condition ( `,` number )? `)`
     ///- and_condition := `and` `(` combination_condition `,` ( combination_condition )+ `)`
     ///- or_condition := `or` `(` combination_condition `,` ( combination_condition )+ `)`
     ///- id_condition := ident
+    ///- call_condition := ident `(` literal ( `,` literal )* `)`
+    ///- field_condition := `field` `(` ( ident | literal ) `)` ( `==` literal )?，等价于\equivalent to `field(literal, literal)`
+    ///- literal := `"` string `"` | number
     pub fn parse(s: impl AsRef<str>) -> Result<Self, GuardConditionParseError> {
         let input = s.as_ref().trim();
         if input.is_empty() {
@@ -446,6 +997,11 @@ impl Display for GuardCondition {
             }
             GuardCondition::Not(not) => write!(f, "not({})", not),
             GuardCondition::Id(id) => write!(f, "{}", id),
+            GuardCondition::Call(name, args) => write!(f, "{}({})", name, args),
+            GuardCondition::Sticky(inner, None) => write!(f, "sticky({})", inner),
+            GuardCondition::Sticky(inner, Some(timeout)) => {
+                write!(f, "sticky({}, {})", inner, timeout.as_secs_f64())
+            }
         }
     }
 }
@@ -462,15 +1018,31 @@ impl From<SystemLabel> for GuardCondition {
     }
 }
 
+/// 含括号的字符串按 [`GuardCondition::parse`] 解析，例如 `"once()"`；其余字符串或解析失败时作为守卫名称
+///
+/// Strings containing parentheses are parsed with [`GuardCondition::parse`], e.g. `"once()"`; other strings, or ones
+/// that fail to parse, are taken as a guard name
 impl<'a> From<&'a str> for GuardCondition {
     fn from(value: &'a str) -> Self {
+        if value.contains('(')
+            && let Ok(condition) = GuardCondition::parse(value)
+        {
+            return condition;
+        }
         GuardCondition::Id(SystemLabel::from(value.to_string()))
     }
 }
 
 use std::str::Chars;
 
-use crate::{context::GuardContext, labels::SystemLabel};
+use crate::{
+    builtin_guards::FIELD,
+    clock::StateClock,
+    context::GuardContext,
+    fault::catch_fault,
+    labels::SystemLabel,
+    read_only_guards::{ReadOnlyGuardId, ReadOnlyGuards},
+};
 
 /// 用于解析守卫条件的词法分析器。
 ///
@@ -530,6 +1102,42 @@ impl<'a> Lexer<'a> {
                     self.advance();
                     Some(Token::Comma)
                 }
+                '=' => {
+                    self.advance();
+                    if self.current_char != Some('=') {
+                        return None;
+                    }
+                    self.advance();
+                    Some(Token::Eq)
+                }
+                '"' => {
+                    self.advance();
+                    let mut literal = String::new();
+                    while let Some(ch) = self.current_char {
+                        self.advance();
+                        match ch {
+                            '"' => return Some(Token::Literal(literal)),
+                            '\\' => {
+                                literal.push(self.current_char?);
+                                self.advance();
+                            }
+                            ch => literal.push(ch),
+                        }
+                    }
+                    None
+                }
+                c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                    let mut literal = String::new();
+                    while let Some(ch) = self.current_char {
+                        if ch.is_ascii_digit() || ch == '-' || ch == '.' {
+                            literal.push(ch);
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                    is_number_literal(&literal).then_some(Token::Literal(literal))
+                }
                 c if c.is_alphabetic() => {
                     let mut identifier = String::new();
                     while let Some(ch) = self.current_char {
@@ -557,9 +1165,11 @@ impl<'a> Lexer<'a> {
 #[derive(Debug, Clone)]
 enum Token {
     Identifier(String),
+    Literal(String),
     LeftParen,
     RightParen,
     Comma,
+    Eq,
 }
 
 /// 用于解析守卫条件的语法分析器。
@@ -605,15 +1215,16 @@ impl<'a> Parser<'a> {
     fn parse_combination_condition(&mut self) -> Result<GuardCondition, GuardConditionParseError> {
         match &self.current_token {
             Some(Token::Identifier(id)) if id == "not" => self.parse_not_condition(),
+            Some(Token::Identifier(id)) if id == "sticky" => self.parse_sticky_condition(),
             Some(Token::Identifier(id)) if id == "and" => self.parse_and_condition(),
             Some(Token::Identifier(id)) if id == "or" => self.parse_or_condition(),
-            Some(Token::Identifier(id)) => {
+            Some(Token::Identifier(_)) => {
                 let next_token = self.lexer.peek();
+                let id = self.expect_identifier()?;
                 if matches!(next_token, Some('(')) {
-                    return Err(GuardConditionParseError::InvalidOperator(id.clone()));
+                    return self.parse_call_condition(id);
                 }
                 // 否则，这是一个普通的标识符
-                let id = self.expect_identifier()?;
                 Ok(GuardCondition::Id(SystemLabel::from(id)))
             }
             Some(tok) => Err(GuardConditionParseError::UnexpectedToken(format!(
@@ -624,6 +1235,57 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// 解析一个带参数的条件，参数只能是字面量，否则视为无效的操作符。
+    fn parse_call_condition(
+        &mut self,
+        id: String,
+    ) -> Result<GuardCondition, GuardConditionParseError> {
+        self.advance(); // '('
+
+        let mut args = Vec::new();
+        // 允许空参数列表，例如 `once()`
+        if !matches!(self.current_token, Some(Token::RightParen)) {
+            loop {
+                match self.current_token.take() {
+                    Some(Token::Literal(arg)) => args.push(arg),
+                    // `field(Switch)` 中的组件名可以不加引号
+                    Some(Token::Identifier(arg)) if id == FIELD => args.push(arg),
+                    _ => return Err(GuardConditionParseError::InvalidOperator(id)),
+                }
+                self.advance();
+                match self.current_token {
+                    Some(Token::Comma) => self.advance(), // ','
+                    Some(Token::RightParen) => break,
+                    _ => {
+                        return Err(GuardConditionParseError::UnexpectedToken(
+                            "expected ')' after arguments".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        self.advance(); // ')'
+
+        // `field(...) == "value"` 将比较值作为最后一个参数
+        if id == FIELD && matches!(self.current_token, Some(Token::Eq)) {
+            self.advance(); // '=='
+            match self.current_token.take() {
+                Some(Token::Literal(value)) => args.push(value),
+                _ => {
+                    return Err(GuardConditionParseError::UnexpectedToken(
+                        "expected a literal after '=='".to_string(),
+                    ));
+                }
+            }
+            self.advance();
+        }
+
+        Ok(GuardCondition::Call(
+            SystemLabel::from(id),
+            GuardArgs(args.into()),
+        ))
+    }
+
     /// 解析一个 `NOT` 条件。
     fn parse_not_condition(&mut self) -> Result<GuardCondition, GuardConditionParseError> {
         // 期望 "not("
@@ -647,6 +1309,42 @@ impl<'a> Parser<'a> {
         Ok(GuardCondition::Not(Box::new(inner_condition)))
     }
 
+    /// 解析一个 `STICKY` 条件，可选的第二个参数为超时秒数。
+    fn parse_sticky_condition(&mut self) -> Result<GuardCondition, GuardConditionParseError> {
+        // 期望 "sticky("
+        self.expect_identifier()?; // "sticky"
+        if !matches!(self.current_token, Some(Token::LeftParen)) {
+            return Err(GuardConditionParseError::UnexpectedToken(
+                "expected '(' after 'sticky'".to_string(),
+            ));
+        }
+        self.advance(); // '('
+
+        let inner_condition = self.parse_combination_condition()?;
+
+        let mut timeout = None;
+        if matches!(self.current_token, Some(Token::Comma)) {
+            self.advance(); // ','
+            let seconds = match self.current_token.take() {
+                Some(Token::Literal(literal)) => literal.parse::<f64>().ok(),
+                _ => None,
+            }
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .ok_or_else(|| GuardConditionParseError::InvalidOperator("sticky".to_string()))?;
+            timeout = Some(seconds);
+            self.advance();
+        }
+
+        if !matches!(self.current_token, Some(Token::RightParen)) {
+            return Err(GuardConditionParseError::UnexpectedToken(
+                "expected ')' after inner condition".to_string(),
+            ));
+        }
+        self.advance(); // ')'
+
+        Ok(GuardCondition::Sticky(Box::new(inner_condition), timeout))
+    }
+
     /// 解析一个 `AND` 条件。
     fn parse_and_condition(&mut self) -> Result<GuardCondition, GuardConditionParseError> {
         // 期望 "and("
@@ -862,5 +1560,78 @@ mod test {
         // 无效的操作符
         // Invalid operator
         assert!(GuardCondition::parse("and(Op(a, b), c)").is_err());
+        // 参数只能是字面量
+        // Arguments must be literals
+        assert!(GuardCondition::parse("Op(\"a\", b)").is_err());
+        // 未闭合的参数
+        // Unclosed arguments
+        assert!(GuardCondition::parse("Op(\"a\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_call_condition() {
+        let condition = GuardCondition::parse(r#"and(just_pressed("Space"), chance(0.5))"#)
+            .expect("failed to parse guard condition with arguments");
+        assert_eq!(
+            condition,
+            GuardCondition::call("just_pressed", ["Space"])
+                .add_and(GuardCondition::call("chance", ["0.5"]))
+        );
+        assert_eq!(
+            format!("{}", condition),
+            r#"and(just_pressed("Space"), chance(0.5))"#
+        );
+        assert_eq!(
+            GuardCondition::parse(format!("{}", condition)),
+            Ok(condition)
+        );
+        assert_eq!(
+            GuardCondition::call("chance", ["0.5"]).labels(),
+            [&SystemLabel::from("chance")]
+        );
+        assert_eq!(
+            GuardCondition::parse("once()"),
+            Ok(GuardCondition::call("once", [] as [&str; 0]))
+        );
+    }
+
+    #[test]
+    fn test_call_condition_round_trip() {
+        let condition = GuardCondition::call(
+            "say",
+            [r#"a "quoted" \path"#, "-2", "1.5", "1-2-3", "inf", "-", ""],
+        );
+        assert_eq!(
+            format!("{}", condition),
+            r#"say("a \"quoted\" \\path", -2, 1.5, "1-2-3", "inf", "-", "")"#
+        );
+        assert_eq!(
+            GuardCondition::parse(format!("{}", condition)),
+            Ok(condition)
+        );
+        assert!(GuardCondition::parse("range(1-2-3)").is_err());
+        assert!(GuardCondition::parse("range(1..2)").is_err());
+        assert!(GuardCondition::parse("range(-)").is_err());
+        assert!(GuardCondition::parse(r#"say("unterminated\")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_field_condition() {
+        let condition = GuardCondition::parse(r#"field(Switch) == "Open""#)
+            .expect("failed to parse field comparison");
+        assert_eq!(condition, GuardCondition::call("field", ["Switch", "Open"]));
+        assert_eq!(format!("{}", condition), r#"field("Switch", "Open")"#);
+        assert_eq!(
+            GuardCondition::parse(format!("{}", condition)),
+            Ok(condition)
+        );
+        assert_eq!(
+            GuardCondition::parse(r#"and(field("Door.open"), field(Door) == 3)"#),
+            Ok(GuardCondition::call("field", ["Door.open"])
+                .add_and(GuardCondition::call("field", ["Door", "3"])))
+        );
+        assert!(GuardCondition::parse(r#"field(Switch) == Open"#).is_err());
+        assert!(GuardCondition::parse(r#"other(Switch) == "Open""#).is_err());
+        assert!(GuardCondition::parse(r#"once() == "Open""#).is_err());
     }
 }
@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Display},
+};
+
+use crate::{guards::GuardCondition, hsm::diff::HsmDefinition};
+
+/// 转换永远不会发生的原因
+///
+/// Why a transition can never fire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadTransitionReason {
+    /// 条件恒为假，例如 `false` 或 `and(a, not(a))`
+    ///
+    /// The condition is always false, such as `false` or `and(a, not(a))`
+    NeverFires,
+    /// 有优先级更高、条件完全相同的兄弟状态
+    ///
+    /// A sibling with a higher priority has the identical condition
+    Shadowed { by: String },
+}
+
+/// 一条永远不会发生的转换
+///
+/// A transition that can never fire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadTransition {
+    pub from: String,
+    pub to: String,
+    pub reason: DeadTransitionReason,
+}
+
+/// # 定义分析\Definition Analysis
+/// * 离线分析一个 [`HsmDefinition`]，只考虑由守卫驱动的转换（父到子的进入守卫、子到父的退出守卫），不考虑手动发送的
+///   [`HsmTrigger`](crate::prelude::HsmTrigger)。报告：
+///   - 从根状态出发可以到达的状态与无法到达的状态；
+///   - 永远不会发生的转换：条件恒为假，或者被优先级更高、条件完全相同的兄弟状态遮蔽；
+///   - 没有任何可发生的转出的状态。
+/// - Analyzes an [`HsmDefinition`] offline, considering only guard-driven transitions (enter guards from parent to child,
+///   exit guards from child to parent) and not manually sent [`HsmTrigger`](crate::prelude::HsmTrigger)s. It reports:
+///   - the states reachable from the roots and the unreachable ones;
+///   - transitions that can never fire: their condition is always false, or a sibling with a higher priority and the
+///     identical condition shadows them;
+///   - states without any transition out that can fire.
+/// * 条件 `true`/`false` 视为常量；[`Display`] 输出每行一条制表符分隔的结果，便于在 CI 中处理。
+/// - The conditions `true`/`false` are treated as constants; [`Display`] writes one tab-separated finding per line for CI
+///   to consume.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy_hsm::prelude::*;
+/// let mut definition = HsmDefinition::default();
+/// definition.states.insert("Root".into(), StateDefinition::default());
+/// definition.states.insert("Idle".into(), StateDefinition {
+///     parent: Some("Root".into()),
+///     guard_enter: Some("false".into()),
+///     ..Default::default()
+/// });
+///
+/// let analysis = HsmAnalysis::analyze(&definition);
+/// assert_eq!(analysis.unreachable, ["Idle"]);
+/// assert!(!analysis.is_clean());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmAnalysis {
+    pub reachable: Vec<String>,
+    pub unreachable: Vec<String>,
+    pub dead_transitions: Vec<DeadTransition>,
+    /// 没有可发生的转出的状态
+    ///
+    /// States without a transition out that can fire
+    pub dead_ends: Vec<String>,
+}
+
+impl HsmAnalysis {
+    pub fn analyze(definition: &HsmDefinition) -> Self {
+        let mut analysis = Self::default();
+
+        // 同一父状态下按条件分组，条件相同时优先级最高者遮蔽其余
+        // Group siblings by condition; among identical conditions the highest priority shadows the rest
+        let mut siblings = BTreeMap::<(&str, String), Vec<(&str, i32)>>::new();
+        for (name, state) in &definition.states {
+            if let (Some(parent), Some(guard)) = (&state.parent, &state.guard_enter) {
+                siblings
+                    .entry((parent.as_str(), normalize(guard)))
+                    .or_default()
+                    .push((name.as_str(), state.priority));
+            }
+        }
+        let mut shadowed = BTreeMap::new();
+        for group in siblings.values() {
+            let Some(&(winner, highest)) = group
+                .iter()
+                .max_by_key(|(name, priority)| (*priority, std::cmp::Reverse(*name)))
+            else {
+                continue;
+            };
+            for &(name, priority) in group {
+                if priority < highest {
+                    shadowed.insert(name, winner.to_string());
+                }
+            }
+        }
+
+        let mut edges = BTreeMap::<String, Vec<String>>::new();
+        for ((from, to), guard) in definition.transitions() {
+            let Some(guard) = guard else {
+                continue;
+            };
+            let is_enter = definition
+                .states
+                .get(&to)
+                .and_then(|state| state.parent.as_ref())
+                == Some(&from);
+            let reason = if constant(&guard) == Some(false) {
+                Some(DeadTransitionReason::NeverFires)
+            } else if is_enter && let Some(by) = shadowed.get(to.as_str()) {
+                Some(DeadTransitionReason::Shadowed { by: by.clone() })
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => analysis
+                    .dead_transitions
+                    .push(DeadTransition { from, to, reason }),
+                None => edges.entry(from).or_default().push(to),
+            }
+        }
+
+        let mut reachable = BTreeSet::new();
+        let mut queue = definition
+            .states
+            .iter()
+            .filter(|(_, state)| state.parent.is_none())
+            .map(|(name, _)| name)
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            if reachable.insert(state) {
+                queue.extend(edges.get(state).into_iter().flatten());
+            }
+        }
+
+        for name in definition.states.keys() {
+            if reachable.contains(name) {
+                analysis.reachable.push(name.clone());
+            } else {
+                analysis.unreachable.push(name.clone());
+            }
+            if !edges.contains_key(name) {
+                analysis.dead_ends.push(name.clone());
+            }
+        }
+        analysis
+    }
+
+    /// 是否没有无法到达的状态与永远不会发生的转换
+    ///
+    /// Whether there are no unreachable states and no transitions that can never fire
+    pub fn is_clean(&self) -> bool {
+        self.unreachable.is_empty() && self.dead_transitions.is_empty()
+    }
+}
+
+impl Display for HsmAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for state in &self.unreachable {
+            writeln!(f, "unreachable\t{}", state)?;
+        }
+        for transition in &self.dead_transitions {
+            match &transition.reason {
+                DeadTransitionReason::NeverFires => {
+                    writeln!(f, "never_fires\t{}\t{}", transition.from, transition.to)?
+                }
+                DeadTransitionReason::Shadowed { by } => writeln!(
+                    f,
+                    "shadowed\t{}\t{}\t{}",
+                    transition.from, transition.to, by
+                )?,
+            }
+        }
+        for state in &self.dead_ends {
+            writeln!(f, "dead_end\t{}", state)?;
+        }
+        Ok(())
+    }
+}
+
+/// 规范化条件文本，无法解析时保留原文
+///
+/// Normalize the condition text, keeping it as is when it does not parse
+fn normalize(guard: &str) -> String {
+    GuardCondition::parse(guard).map_or_else(|_| guard.to_string(), |guard| guard.to_string())
+}
+
+/// 条件的常量值，取决于运行时结果时为 `None`
+///
+/// The constant value of a condition, `None` when it depends on runtime results
+fn constant(guard: &str) -> Option<bool> {
+    fn eval(condition: &GuardCondition) -> Option<bool> {
+        match condition {
+            GuardCondition::Id(label) => match label.as_ref() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            GuardCondition::Call(..) => None,
+            GuardCondition::Not(inner) => eval(inner).map(|value| !value),
+            GuardCondition::Sticky(inner, _) => eval(inner).filter(|value| !value),
+            GuardCondition::And(conditions) => {
+                let values = conditions.iter().map(|c| eval(c)).collect::<Vec<_>>();
+                if values.contains(&Some(false)) || complementary(conditions) {
+                    Some(false)
+                } else if values.iter().all(|value| *value == Some(true)) {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            GuardCondition::Or(conditions) => {
+                let values = conditions.iter().map(|c| eval(c)).collect::<Vec<_>>();
+                if values.contains(&Some(true)) || complementary(conditions) {
+                    Some(true)
+                } else if values.iter().all(|value| *value == Some(false)) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// 是否同时包含 `a` 与 `not(a)`
+    ///
+    /// Whether both `a` and `not(a)` are present
+    fn complementary(conditions: &[Box<GuardCondition>]) -> bool {
+        conditions.iter().any(|condition| {
+            let GuardCondition::Not(inner) = condition.as_ref() else {
+                return false;
+            };
+            conditions.iter().any(|other| other == inner)
+        })
+    }
+
+    eval(&GuardCondition::parse(guard).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hsm::diff::StateDefinition;
+
+    use super::*;
+
+    fn state(parent: &str, guard_enter: Option<&str>, guard_exit: Option<&str>) -> StateDefinition {
+        StateDefinition {
+            parent: Some(parent.into()),
+            guard_enter: guard_enter.map(Into::into),
+            guard_exit: guard_exit.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let mut definition = HsmDefinition::default();
+        let states = &mut definition.states;
+        states.insert("Root".into(), StateDefinition::default());
+        states.insert("Idle".into(), state("Root", Some("rested"), Some("tired")));
+        states.insert(
+            "Walk".into(),
+            StateDefinition {
+                priority: 1,
+                ..state("Root", Some("or(moving,  rested)"), Some("stopped"))
+            },
+        );
+        states.insert(
+            "Run".into(),
+            state("Root", Some("or(moving, rested)"), None),
+        );
+        states.insert("Sprint".into(), state("Run", Some("true"), None));
+        states.insert(
+            "Fly".into(),
+            state("Root", Some("and(winged, not(winged))"), None),
+        );
+        states.insert("Dash".into(), state("Walk", Some("sticky(false)"), None));
+
+        let analysis = HsmAnalysis::analyze(&definition);
+        assert_eq!(analysis.reachable, ["Idle", "Root", "Walk"]);
+        assert_eq!(analysis.unreachable, ["Dash", "Fly", "Run", "Sprint"]);
+        assert_eq!(
+            analysis.dead_transitions,
+            [
+                DeadTransition {
+                    from: "Root".into(),
+                    to: "Fly".into(),
+                    reason: DeadTransitionReason::NeverFires,
+                },
+                DeadTransition {
+                    from: "Root".into(),
+                    to: "Run".into(),
+                    reason: DeadTransitionReason::Shadowed { by: "Walk".into() },
+                },
+                DeadTransition {
+                    from: "Walk".into(),
+                    to: "Dash".into(),
+                    reason: DeadTransitionReason::NeverFires,
+                },
+            ]
+        );
+        assert_eq!(analysis.dead_ends, ["Dash", "Fly", "Sprint"]);
+        assert!(!analysis.is_clean());
+        assert!(
+            analysis
+                .to_string()
+                .lines()
+                .any(|line| line == "shadowed\tRoot\tRun\tWalk")
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! # 状态捆绑包\State Bundles
+//!
+//! 用一个结构体字面量生成完整配置的 HSM 状态，而不是手写多个组件组成的元组。
+//!
+//! Spawn a fully configured HSM state from one struct literal instead of a hand-written tuple of components.
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{
+    hsm::{
+        HsmState,
+        guards::{GuardEnter, GuardExit},
+        transition_strategy::{ExitTransitionBehavior, StateTransitionStrategy},
+    },
+    state_actions::{
+        AfterEnterSystem, AfterExitSystem, BeforeEnterSystem, BeforeExitSystem, OnUpdateSystem,
+    },
+};
+
+/// # 状态捆绑包\State Bundle
+/// * 一次性的“安装器”组件：插入后会展开为对应的状态组件，然后移除自身。
+/// - A one-time "installer" component: once inserted it expands into the matching state components and removes itself.
+/// * 用 [`HsmStateBundle::leaf`] 创建叶子状态，用 [`HsmStateBundle::composite`] 创建指定子状态进入方式的复合状态。
+/// - Create leaf states with [`HsmStateBundle::leaf`], and composite states with a sub-state entry strategy with
+///   [`HsmStateBundle::composite`].
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands) {
+/// commands.spawn(HsmStateBundle {
+///     enter: Some(AfterEnterSystem::new("debug_on_enter")),
+///     exit: Some(BeforeExitSystem::new("debug_on_exit")),
+///     enter_guard: Some(GuardEnter::new("is_open")),
+///     ..HsmStateBundle::leaf("Open")
+/// });
+/// commands.spawn(HsmStateBundle {
+///     after_exit: Some(AfterExitSystem::new("debug_after_exit")),
+///     ..HsmStateBundle::composite("Combat", StateTransitionStrategy::Parallel)
+/// });
+/// # }
+/// ```
+#[derive(Component, Default, Debug)]
+#[component(on_insert = Self::on_insert)]
+pub struct HsmStateBundle {
+    /// 状态名称
+    ///
+    /// State name
+    pub name: Name,
+    /// 状态配置
+    ///
+    /// State configuration
+    pub state: HsmState,
+    /// 进入前调用的转换系统
+    ///
+    /// Transition system called before entering
+    pub before_enter: Option<BeforeEnterSystem>,
+    /// 进入时调用的动作系统
+    ///
+    /// Action system called on enter
+    pub enter: Option<AfterEnterSystem>,
+    /// 更新时调用的动作系统
+    ///
+    /// Action system called on update
+    pub update: Option<OnUpdateSystem>,
+    /// 退出时调用的动作系统
+    ///
+    /// Action system called on exit
+    pub exit: Option<BeforeExitSystem>,
+    /// 退出后调用的转换系统
+    ///
+    /// Transition system called after exiting
+    pub after_exit: Option<AfterExitSystem>,
+    /// 进入守卫
+    ///
+    /// Enter guard
+    pub enter_guard: Option<GuardEnter>,
+    /// 退出守卫
+    ///
+    /// Exit guard
+    pub exit_guard: Option<GuardExit>,
+}
+
+impl HsmStateBundle {
+    /// 叶子状态
+    ///
+    /// A leaf state
+    pub fn leaf(name: impl Into<Name>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 以 `strategy` 进入子状态的复合状态
+    ///
+    /// A composite state entering its sub-states with `strategy`
+    pub fn composite(name: impl Into<Name>, strategy: StateTransitionStrategy) -> Self {
+        Self::leaf(name).with_strategy(strategy)
+    }
+
+    pub fn with_strategy(mut self, strategy: StateTransitionStrategy) -> Self {
+        self.state.strategy = strategy;
+        self
+    }
+
+    pub fn with_behavior(mut self, behavior: ExitTransitionBehavior) -> Self {
+        self.state.behavior = behavior;
+        self
+    }
+
+    fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        world.commands().queue(move |world: &mut World| {
+            let Ok(mut e) = world.get_entity_mut(entity) else {
+                return;
+            };
+            let Some(bundle) = e.take::<Self>() else {
+                return;
+            };
+            e.insert((bundle.name, bundle.state));
+            if let Some(before_enter) = bundle.before_enter {
+                e.insert(before_enter);
+            }
+            if let Some(enter) = bundle.enter {
+                e.insert(enter);
+            }
+            if let Some(update) = bundle.update {
+                e.insert(update);
+            }
+            if let Some(exit) = bundle.exit {
+                e.insert(exit);
+            }
+            if let Some(after_exit) = bundle.after_exit {
+                e.insert(after_exit);
+            }
+            if let Some(enter_guard) = bundle.enter_guard {
+                e.insert(enter_guard);
+            }
+            if let Some(exit_guard) = bundle.exit_guard {
+                e.insert(exit_guard);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StateMachinePlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_bundle_expands_into_components() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let world = app.world_mut();
+        let leaf = world
+            .spawn(HsmStateBundle {
+                enter: Some(AfterEnterSystem::new("enter")),
+                after_exit: Some(AfterExitSystem::new("after_exit")),
+                exit_guard: Some(GuardExit::new("is_done")),
+                ..HsmStateBundle::leaf("Leaf")
+            })
+            .id();
+        let composite = world
+            .spawn(HsmStateBundle::composite(
+                "Root",
+                StateTransitionStrategy::Parallel,
+            ))
+            .id();
+        world.flush();
+
+        let leaf = world.entity(leaf);
+        assert!(!leaf.contains::<HsmStateBundle>());
+        assert_eq!(leaf.get::<Name>().unwrap().as_str(), "Leaf");
+        assert!(leaf.contains::<HsmState>());
+        assert!(leaf.contains::<AfterEnterSystem>());
+        assert!(leaf.contains::<AfterExitSystem>());
+        assert!(leaf.contains::<GuardExit>());
+        assert!(!leaf.contains::<BeforeExitSystem>());
+        assert!(!leaf.contains::<GuardEnter>());
+
+        let composite = world.entity(composite);
+        assert_eq!(
+            composite.get::<HsmState>().unwrap().strategy,
+            StateTransitionStrategy::Parallel
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::borrow::Cow;
+
+use bevy::{
+    ecs::{
+        component::ComponentId,
+        entity::{EntityClonerBuilder, OptIn},
+        entity_disabling::Disabled,
+        lifecycle::HookContext,
+        world::DeferredWorld,
+    },
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::hsm::{
+    event::HsmTrigger, state_machine::HsmStateMachine, transition_strategy::get_service_target,
+};
+
+/// # 命名检查点\Named Checkpoints
+/// * 状态机上按名称保存的配置：一个状态，以及可选的服务目标数据快照（由 `track_checkpoint_data` 指定的组件）。
+///   检查点既可以在构建状态机时声明（[`HsmCheckpoints::with`]），也可以在运行时保存当前配置
+///   （[`HsmCheckpointCommandsExt::checkpoint`]）。
+/// - Configurations saved by name on a state machine: a state plus an optional snapshot of the service target's data
+///   (the components named through `track_checkpoint_data`). Checkpoints can be declared while building the machine
+///   ([`HsmCheckpoints::with`]) or saved from the current configuration at runtime
+///   ([`HsmCheckpointCommandsExt::checkpoint`]).
+/// * 回到检查点时先写回数据快照，再以链式转换（[`HsmTrigger::chain`]）跳转，沿途的状态照常退出和进入。
+///   适合 Boss 阶段重试与调试。
+/// - Restoring a checkpoint writes the data snapshot back first, then jumps through a chain transition
+///   ([`HsmTrigger::chain`]) so the states along the way exit and enter as usual. Useful for boss phase retries and
+///   debugging.
+/// * 快照保存在带有 [`Disabled`] 的实体上，不会出现在普通查询中；被跟踪的组件需要实现 [`Clone`]。
+/// - Snapshots live on [`Disabled`] entities, hidden from regular queries; tracked components need to implement
+///   [`Clone`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component, Clone)]
+/// struct Health(u32);
+///
+/// fn enter_phase2(mut commands: Commands, boss: Single<Entity, With<HsmStateMachine>>) {
+///     commands
+///         .entity(*boss)
+///         .track_checkpoint_data::<Health>()
+///         .checkpoint("phase2");
+/// }
+///
+/// fn retry(mut commands: Commands, boss: Single<Entity, With<HsmStateMachine>>) {
+///     commands.entity(*boss).restore_checkpoint("phase2");
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+#[component(on_remove = Self::on_remove)]
+pub struct HsmCheckpoints {
+    checkpoints: HashMap<Cow<'static, str>, HsmCheckpoint>,
+    tracked: Vec<ComponentId>,
+}
+
+/// 一个检查点
+///
+/// A single checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HsmCheckpoint {
+    pub state: Entity,
+    snapshot: Option<Entity>,
+}
+
+impl HsmCheckpoint {
+    /// 保存数据快照的实体，声明的检查点没有快照
+    ///
+    /// The entity holding the data snapshot; declared checkpoints have none
+    pub fn snapshot(&self) -> Option<Entity> {
+        self.snapshot
+    }
+}
+
+impl HsmCheckpoints {
+    /// 声明一个只包含状态的检查点
+    ///
+    /// Declare a checkpoint holding only a state
+    pub fn with(mut self, name: impl Into<Cow<'static, str>>, state: Entity) -> Self {
+        self.checkpoints.insert(
+            name.into(),
+            HsmCheckpoint {
+                state,
+                snapshot: None,
+            },
+        );
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HsmCheckpoint> {
+        self.checkpoints.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.checkpoints.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.checkpoints.keys().map(AsRef::as_ref)
+    }
+
+    /// 保存快照时复制的服务目标组件
+    ///
+    /// The service target components copied into snapshots
+    pub fn tracked(&self) -> &[ComponentId] {
+        &self.tracked
+    }
+
+    pub fn track(&mut self, component_id: ComponentId) {
+        if !self.tracked.contains(&component_id) {
+            self.tracked.push(component_id);
+        }
+    }
+
+    /// 将状态机的当前状态与被跟踪的数据保存为检查点，覆盖同名检查点
+    ///
+    /// Save the machine's current state and tracked data as a checkpoint, replacing one with the same name
+    pub fn save(world: &mut World, state_machine: Entity, name: impl Into<Cow<'static, str>>) {
+        let Some(state) = world
+            .get::<HsmStateMachine>(state_machine)
+            .map(HsmStateMachine::curr_state_id)
+        else {
+            warn!(
+                "[HsmCheckpoints] {:?} is not a state machine",
+                state_machine
+            );
+            return;
+        };
+        let tracked = world
+            .entity_mut(state_machine)
+            .entry::<Self>()
+            .or_default()
+            .get()
+            .tracked
+            .clone();
+
+        let snapshot = match tracked.is_empty() {
+            true => None,
+            false => {
+                let snapshot = world.spawn(Disabled).id();
+                let service_target = get_service_target(world, state_machine);
+                world.entity_mut(service_target).clone_with_opt_in(
+                    snapshot,
+                    move |builder: &mut EntityClonerBuilder<'_, OptIn>| {
+                        builder.allow_by_ids(tracked.as_slice());
+                    },
+                );
+                Some(snapshot)
+            }
+        };
+
+        let previous = world
+            .get_mut::<Self>(state_machine)
+            .unwrap()
+            .checkpoints
+            .insert(name.into(), HsmCheckpoint { state, snapshot });
+        if let Some(snapshot) = previous.and_then(|checkpoint| checkpoint.snapshot) {
+            let _ = world.try_despawn(snapshot);
+        }
+    }
+
+    /// 回到检查点：写回数据快照并链式转换至其状态，检查点不存在时返回 `false`
+    ///
+    /// Restore a checkpoint: write the data snapshot back and chain to its state, returning `false` when it does not
+    /// exist
+    pub fn restore(world: &mut World, state_machine: Entity, name: &str) -> bool {
+        let Some((checkpoint, tracked)) = world
+            .get::<Self>(state_machine)
+            .and_then(|checkpoints| Some((*checkpoints.get(name)?, checkpoints.tracked.clone())))
+        else {
+            warn!(
+                "[HsmCheckpoints] {:?} has no checkpoint named {}",
+                state_machine, name
+            );
+            return false;
+        };
+
+        if let Some(snapshot) = checkpoint.snapshot {
+            let service_target = get_service_target(world, state_machine);
+            let missing = tracked
+                .iter()
+                .copied()
+                .filter(|&id| !world.entity(snapshot).contains_id(id))
+                .collect::<Vec<_>>();
+            world.entity_mut(service_target).remove_by_ids(&missing);
+            world.entity_mut(snapshot).clone_with_opt_in(
+                service_target,
+                move |builder: &mut EntityClonerBuilder<'_, OptIn>| {
+                    builder.allow_by_ids(tracked.as_slice());
+                },
+            );
+        }
+        world.trigger(HsmTrigger::chain(state_machine, checkpoint.state));
+        true
+    }
+
+    fn on_remove(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let Some(checkpoints) = world.get::<Self>(entity) else {
+            return;
+        };
+        let snapshots = checkpoints
+            .checkpoints
+            .values()
+            .filter_map(HsmCheckpoint::snapshot)
+            .collect::<Vec<_>>();
+        let mut commands = world.commands();
+        for snapshot in snapshots {
+            commands.entity(snapshot).try_despawn();
+        }
+    }
+}
+
+/// # 检查点命令扩展\Checkpoint Commands Extension
+/// * 在状态机实体的 [`EntityCommands`] 上保存、回到检查点，参见 [`HsmCheckpoints`]。
+/// - Save and restore checkpoints on the [`EntityCommands`] of a state machine, see [`HsmCheckpoints`].
+pub trait HsmCheckpointCommandsExt {
+    /// 保存当前配置为检查点
+    ///
+    /// Save the current configuration as a checkpoint
+    fn checkpoint(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self;
+
+    /// 回到检查点
+    ///
+    /// Restore a checkpoint
+    fn restore_checkpoint(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self;
+
+    /// 在之后保存的检查点中包含服务目标上的组件 `T`
+    ///
+    /// Include the service target's component `T` in checkpoints saved from now on
+    fn track_checkpoint_data<T: Component + Clone>(&mut self) -> &mut Self;
+}
+
+impl HsmCheckpointCommandsExt for EntityCommands<'_> {
+    fn checkpoint(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self {
+        let name = name.into();
+        self.queue(move |entity: EntityWorldMut| {
+            let state_machine = entity.id();
+            HsmCheckpoints::save(entity.into_world_mut(), state_machine, name);
+        })
+    }
+
+    fn restore_checkpoint(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self {
+        let name = name.into();
+        self.queue(move |entity: EntityWorldMut| {
+            let state_machine = entity.id();
+            HsmCheckpoints::restore(entity.into_world_mut(), state_machine, &name);
+        })
+    }
+
+    fn track_checkpoint_data<T: Component + Clone>(&mut self) -> &mut Self {
+        self.queue(|mut entity: EntityWorldMut| {
+            let component_id = entity.world_scope(|world| world.register_component::<T>());
+            entity
+                .entry::<HsmCheckpoints>()
+                .or_default()
+                .get_mut()
+                .track(component_id);
+        })
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::hsm::{HsmState, state_tree::StateTree};
+
+/// 状态树中的一条父子链接
+///
+/// A parent-child link in a state tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateLink {
+    /// 持有 [`StateTree`] 的实体
+    ///
+    /// The entity holding the [`StateTree`]
+    pub state_tree: Entity,
+    /// 父状态
+    ///
+    /// The super state
+    pub super_state: Entity,
+    /// 子状态
+    ///
+    /// The sub-state
+    pub sub_state: Entity,
+}
+
+impl StateLink {
+    pub fn new(state_tree: Entity, super_state: Entity, sub_state: Entity) -> Self {
+        Self {
+            state_tree,
+            super_state,
+            sub_state,
+        }
+    }
+
+    /// 状态树存在、父状态已在树中且子状态已带有 [`HsmState`] 时可以建立链接
+    ///
+    /// The link can be made once the state tree exists, the super state is part of it and the sub-state carries [`HsmState`]
+    fn is_ready(&self, world: &World) -> bool {
+        world
+            .get::<StateTree>(self.state_tree)
+            .is_some_and(|state_tree| state_tree.contains(self.super_state))
+            && world
+                .get_entity(self.sub_state)
+                .is_ok_and(|entity| entity.contains::<HsmState>())
+    }
+}
+
+/// # 延迟链接\Deferred Links
+/// * 按顺序加载资源时，子状态可能早于其父状态、状态树或自身的 [`HsmState`] 出现。
+///   通过 [`StateMachineCommandsExt::link_state`](crate::prelude::StateMachineCommandsExt::link_state)
+///   添加的链接在两端尚未就绪时会被暂存于此，并在目标出现（插入 [`HsmState`] 或 [`StateTree`]）时自动建立，
+///   而不是被丢弃。仍未解决的链接可通过 [`PendingStateLinks::links`] 检查。
+/// - When assets load out of order, a sub-state may appear before its super state, its state tree or its own
+///   [`HsmState`]. Links added through [`StateMachineCommandsExt::link_state`](crate::prelude::StateMachineCommandsExt::link_state)
+///   whose ends are not ready yet are parked here and made automatically once the targets appear (when [`HsmState`] or
+///   [`StateTree`] is inserted) instead of being dropped. Links still unresolved can be inspected with
+///   [`PendingStateLinks::links`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn load(mut commands: Commands, state_tree: Entity, root: Entity) {
+///     // 子状态先于其父状态加载
+///     // The sub-state is loaded before its super state
+///     let super_state = commands.spawn_empty().id();
+///     let sub_state = commands.spawn(HsmState::default()).id();
+///     commands.link_state(state_tree, root, super_state);
+///     commands.link_state(state_tree, super_state, sub_state);
+///     // ...之后父状态加载完成，两条链接依次建立
+///     // ...later the super state finishes loading and both links are made in turn
+///     commands.entity(super_state).insert(HsmState::default());
+/// }
+/// ```
+#[derive(Resource, Debug, Default)]
+pub struct PendingStateLinks(Vec<StateLink>);
+
+impl PendingStateLinks {
+    /// 尚未建立的链接
+    ///
+    /// The links not made yet
+    pub fn links(&self) -> &[StateLink] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 建立链接，两端尚未就绪时暂存
+    ///
+    /// Make the link, parking it while its ends are not ready
+    pub fn link(world: &mut World, link: StateLink) {
+        let mut pending = world.get_resource_or_init::<Self>();
+        if !pending.0.contains(&link) {
+            pending.0.push(link);
+        }
+        Self::resolve(world);
+    }
+
+    /// 建立所有已就绪的链接，直到没有新的链接可以建立
+    ///
+    /// Make every link that is ready, until no further link can be made
+    pub fn resolve(world: &mut World) {
+        loop {
+            let Some(pending) = world.get_resource::<Self>() else {
+                return;
+            };
+            let ready = pending
+                .0
+                .iter()
+                .copied()
+                .filter(|link| link.is_ready(world))
+                .collect::<Vec<_>>();
+            if ready.is_empty() {
+                return;
+            }
+            world
+                .resource_mut::<Self>()
+                .0
+                .retain(|link| !ready.contains(link));
+            for link in ready {
+                if let Some(mut state_tree) = world.get_mut::<StateTree>(link.state_tree) {
+                    state_tree.with_child(link.super_state, link.sub_state);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn on_insert_state(
+        _: On<Insert, (HsmState, StateTree)>,
+        pending: Res<Self>,
+        mut commands: Commands,
+    ) {
+        if !pending.is_empty() {
+            commands.queue(Self::resolve);
+        }
+    }
+}
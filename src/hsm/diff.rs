@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    hsm::{
+        HsmState,
+        guards::{GuardEnter, GuardExit},
+        priority::StatePriority,
+        state_tree::StateTree,
+        transition_strategy::{ExitTransitionBehavior, StateTransitionStrategy},
+    },
+    state_actions::{
+        AfterEnterSystem, AfterExitSystem, BeforeEnterSystem, BeforeExitSystem, OnUpdateSystem,
+        OnUpdateSystems,
+    },
+};
+
+/// # 状态定义\State Definition
+/// * 一个状态与实体无关的描述，用于比较两个状态机定义。
+/// - An entity-independent description of a state, used to compare two state machine definitions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateDefinition {
+    pub parent: Option<String>,
+    pub strategy: StateTransitionStrategy,
+    pub behavior: ExitTransitionBehavior,
+    pub guard_enter: Option<String>,
+    pub guard_exit: Option<String>,
+    /// 兄弟状态之间的进入优先级，参见 [`StatePriority`]
+    ///
+    /// Enter priority among siblings, see [`StatePriority`]
+    pub priority: i32,
+    /// 生命周期动作，键为组件名称，例如 `"AfterEnterSystem"`
+    ///
+    /// Lifecycle actions keyed by component name, e.g. `"AfterEnterSystem"`
+    pub actions: BTreeMap<&'static str, String>,
+}
+
+/// # 状态机定义\State Machine Definition
+/// * 以状态路径为键的状态集合，父子关系与守卫共同构成转换：
+///   父状态到子状态的转换由子状态的进入守卫控制，子状态到父状态的转换由子状态的退出守卫控制。
+/// - A set of states keyed by path; parent links and guards together form the transitions:
+///   a parent-to-child transition is guarded by the child's enter guard, a child-to-parent transition by the child's exit guard.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmDefinition {
+    pub states: BTreeMap<String, StateDefinition>,
+}
+
+impl HsmDefinition {
+    /// 从状态树捕获当前定义。状态以从根状态起、以 `/` 连接的名称路径为键，例如 `"Root/Idle"`；
+    /// 未命名的状态使用实体编号作为名称，路径重复的状态在路径后追加 `#` 与实体编号。已销毁的状态会被跳过。
+    ///
+    /// Capture the current definition from a state tree. States are keyed by their path of names from the root joined
+    /// with `/`, e.g. `"Root/Idle"`; unnamed states use their entity id as the name, and states sharing a path get `#`
+    /// and their entity id appended. Despawned states are skipped.
+    pub fn capture(world: &World, state_tree: Entity) -> Option<Self> {
+        let tree = world.get::<StateTree>(state_tree)?;
+        let name_of = |state: Entity| {
+            world
+                .get::<Name>(state)
+                .map_or_else(|| state.to_string(), |name| name.to_string())
+        };
+        let paths = tree
+            .iter()
+            .map(|state| {
+                let mut names = tree.path_iter(state).map(name_of).collect::<Vec<_>>();
+                names.reverse();
+                names.push(name_of(state));
+                (state, names.join("/"))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut counts = HashMap::<&str, usize>::new();
+        for path in paths.values() {
+            *counts.entry(path).or_default() += 1;
+        }
+        let key_of = |state: Entity| {
+            let path = &paths[&state];
+            match counts[path.as_str()] {
+                1 => path.clone(),
+                _ => format!("{path}#{state}"),
+            }
+        };
+        let mut definition = Self::default();
+        for state in tree.iter() {
+            let Ok(entity) = world.get_entity(state) else {
+                continue;
+            };
+            let hsm_state = entity.get::<HsmState>().copied().unwrap_or_default();
+            let mut actions = BTreeMap::new();
+            macro_rules! capture_action {
+                ($($component:ident),*) => {$(
+                    if let Some(label) = entity.get::<$component>() {
+                        actions.insert(stringify!($component), label.to_string());
+                    }
+                )*};
+            }
+            capture_action!(
+                BeforeEnterSystem,
+                AfterEnterSystem,
+                OnUpdateSystem,
+                BeforeExitSystem,
+                AfterExitSystem
+            );
+            if let Some(updates) = entity.get::<OnUpdateSystems>() {
+                let labels = updates.iter().map(ToString::to_string).collect::<Vec<_>>();
+                actions.insert("OnUpdateSystems", labels.join(", "));
+            }
+            definition.states.insert(
+                key_of(state),
+                StateDefinition {
+                    parent: tree.get_super_state(state).map(key_of),
+                    strategy: hsm_state.strategy,
+                    behavior: hsm_state.behavior,
+                    guard_enter: entity.get::<GuardEnter>().map(|guard| guard.0.to_string()),
+                    guard_exit: entity.get::<GuardExit>().map(|guard| guard.0.to_string()),
+                    priority: StatePriority::of(world, state),
+                    actions,
+                },
+            );
+        }
+        Some(definition)
+    }
+
+    /// 以 `(起点, 终点) -> 守卫` 的形式列出所有转换
+    ///
+    /// List every transition as `(from, to) -> guard`
+    pub fn transitions(&self) -> BTreeMap<(String, String), Option<String>> {
+        let mut transitions = BTreeMap::new();
+        for (name, state) in &self.states {
+            let Some(parent) = &state.parent else {
+                continue;
+            };
+            transitions.insert((parent.clone(), name.clone()), state.guard_enter.clone());
+            transitions.insert((name.clone(), parent.clone()), state.guard_exit.clone());
+        }
+        transitions
+    }
+}
+
+/// # 定义差异\Definition Diff
+/// * 比较两个 [`HsmDefinition`]，列出新增、移除与修改的状态和转换，供编辑器预览定义变更对运行中状态机的影响。
+/// - Compares two [`HsmDefinition`]s and lists the added, removed and changed states and transitions, so editor tooling can
+///   preview what a definition change will do to live machines.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy_hsm::prelude::*;
+/// let mut old = HsmDefinition::default();
+/// old.states.insert("Idle".into(), StateDefinition::default());
+/// let mut new = old.clone();
+/// new.states.insert("Run".into(), StateDefinition { parent: Some("Idle".into()), ..Default::default() });
+///
+/// let diff = HsmDefinitionDiff::diff(&old, &new);
+/// assert_eq!(diff.added_states, ["Run"]);
+/// assert_eq!(diff.added_transitions.len(), 2);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmDefinitionDiff {
+    pub added_states: Vec<String>,
+    pub removed_states: Vec<String>,
+    /// 修改的状态及其修改的字段
+    ///
+    /// Changed states and the fields that changed
+    pub changed_states: Vec<(String, Vec<&'static str>)>,
+    pub added_transitions: Vec<(String, String)>,
+    pub removed_transitions: Vec<(String, String)>,
+    /// 守卫发生变化的转换
+    ///
+    /// Transitions whose guard changed
+    pub changed_transitions: Vec<(String, String)>,
+}
+
+impl HsmDefinitionDiff {
+    pub fn diff(old: &HsmDefinition, new: &HsmDefinition) -> Self {
+        let mut diff = Self::default();
+
+        for (name, old_state) in &old.states {
+            let Some(new_state) = new.states.get(name) else {
+                diff.removed_states.push(name.clone());
+                continue;
+            };
+            let mut fields = Vec::new();
+            if old_state.parent != new_state.parent {
+                fields.push("parent");
+            }
+            if old_state.strategy != new_state.strategy {
+                fields.push("strategy");
+            }
+            if old_state.behavior != new_state.behavior {
+                fields.push("behavior");
+            }
+            if old_state.priority != new_state.priority {
+                fields.push("priority");
+            }
+            if old_state.actions != new_state.actions {
+                fields.push("actions");
+            }
+            if !fields.is_empty() {
+                diff.changed_states.push((name.clone(), fields));
+            }
+        }
+        diff.added_states = new
+            .states
+            .keys()
+            .filter(|name| !old.states.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let old_transitions = old.transitions();
+        let new_transitions = new.transitions();
+        for (key, old_guard) in &old_transitions {
+            match new_transitions.get(key) {
+                None => diff.removed_transitions.push(key.clone()),
+                Some(new_guard) if new_guard != old_guard => {
+                    diff.changed_transitions.push(key.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added_transitions = new_transitions
+            .keys()
+            .filter(|key| !old_transitions.contains_key(*key))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// 两个定义是否完全相同
+    ///
+    /// Whether the two definitions are identical
+    pub fn is_empty(&self) -> bool {
+        self.added_states.is_empty()
+            && self.removed_states.is_empty()
+            && self.changed_states.is_empty()
+            && self.added_transitions.is_empty()
+            && self.removed_transitions.is_empty()
+            && self.changed_transitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StateMachinePlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_definition_diff() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let world = app.world_mut();
+        let root = world.spawn((Name::new("Root"), HsmState::default())).id();
+        let idle = world
+            .spawn((Name::new("Idle"), GuardEnter::new("is_idle")))
+            .id();
+        let run = world
+            .spawn((Name::new("Run"), AfterEnterSystem::new("start")))
+            .id();
+        let mut tree = StateTree::new(root);
+        tree.with_child(root, idle).with_child(root, run);
+        let tree = world.spawn(tree).id();
+
+        let old = HsmDefinition::capture(world, tree).unwrap();
+        assert!(HsmDefinitionDiff::diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.states.remove("Root/Run");
+        let idle = new.states.get_mut("Root/Idle").unwrap();
+        idle.guard_enter = Some("is_tired".into());
+        idle.strategy = StateTransitionStrategy::Parallel;
+        new.states.insert(
+            "Root/Idle/Jump".into(),
+            StateDefinition {
+                parent: Some("Root/Idle".into()),
+                ..default()
+            },
+        );
+
+        let diff = HsmDefinitionDiff::diff(&old, &new);
+        assert_eq!(diff.added_states, ["Root/Idle/Jump"]);
+        assert_eq!(diff.removed_states, ["Root/Run"]);
+        assert_eq!(
+            diff.changed_states,
+            [("Root/Idle".to_string(), vec!["strategy"])]
+        );
+        assert_eq!(
+            diff.added_transitions,
+            [
+                ("Root/Idle".to_string(), "Root/Idle/Jump".to_string()),
+                ("Root/Idle/Jump".to_string(), "Root/Idle".to_string())
+            ]
+        );
+        assert_eq!(
+            diff.removed_transitions,
+            [
+                ("Root".to_string(), "Root/Run".to_string()),
+                ("Root/Run".to_string(), "Root".to_string())
+            ]
+        );
+        assert_eq!(
+            diff.changed_transitions,
+            [("Root".to_string(), "Root/Idle".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_capture_duplicate_and_despawned_states() {
+        let mut world = World::new();
+        let root = world.spawn(Name::new("Root")).id();
+        let first = world.spawn(Name::new("Idle")).id();
+        let second = world.spawn(Name::new("Idle")).id();
+        let gone = world.spawn(Name::new("Gone")).id();
+        let mut tree = StateTree::new(root);
+        tree.with_child(root, first)
+            .with_child(root, second)
+            .with_child(root, gone);
+        let tree = world.spawn(tree).id();
+        world.despawn(gone);
+
+        let definition = HsmDefinition::capture(&world, tree).unwrap();
+        assert_eq!(definition.states.len(), 3);
+        assert!(
+            definition
+                .states
+                .contains_key(&format!("Root/Idle#{first}"))
+        );
+        assert!(
+            definition
+                .states
+                .contains_key(&format!("Root/Idle#{second}"))
+        );
+        assert!(!definition.states.contains_key("Root/Gone"));
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{
+    hsm::{event::HsmTrigger, state_machine::HsmStateMachine, state_tree::StateTree},
+    markers::Terminated,
+};
+
+/// 被禁用的状态处于激活状态时的驱逐策略
+///
+/// Eviction policy applied when a disabled state is currently active
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateEviction {
+    /// 不驱逐，状态机保持在当前状态，直到自行离开
+    ///
+    /// Do not evict; the machine stays until it leaves on its own
+    Stay,
+    /// 链式转换到被禁用状态的父状态
+    ///
+    /// Chain-transition to the super-state of the disabled state
+    #[default]
+    ToSuper,
+    /// 链式转换到指定状态
+    ///
+    /// Chain-transition to the given state
+    To(Entity),
+}
+
+/// # 禁用状态\Disabled State
+/// * 存在时，该状态及其子树在进入条件检查中被跳过，[`HsmTransitions`](crate::prelude::HsmTransitions) 也不会转换到它。
+///   插入时若状态机正处于该状态或其子状态中，则按 [`StateEviction`] 将状态机移出。
+/// - While present, the state and its subtree are skipped by enter-condition checks, and
+///   [`HsmTransitions`](crate::prelude::HsmTransitions) will not move into it. When inserted while a machine is in the
+///   state or one of its sub-states, the machine is moved out according to [`StateEviction`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, double_jump: Entity) {
+/// // 解锁前禁用二段跳\Keep double jump disabled until unlocked
+/// commands.entity(double_jump).insert(DisabledState::default());
+/// // 解锁\Unlock
+/// commands.entity(double_jump).remove::<DisabledState>();
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[component(on_insert = Self::on_insert)]
+pub struct DisabledState {
+    pub eviction: StateEviction,
+}
+
+impl DisabledState {
+    pub const fn new(eviction: StateEviction) -> Self {
+        Self { eviction }
+    }
+
+    fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let eviction = world.get::<DisabledState>(entity).unwrap().eviction;
+        if eviction == StateEviction::Stay {
+            return;
+        }
+        world.commands().queue(move |world: &mut World| {
+            let mut query =
+                world.query_filtered::<(Entity, &HsmStateMachine), Without<Terminated>>();
+            let evictions = query
+                .iter(world)
+                .filter_map(|(state_machine_id, state_machine)| {
+                    let curr_state_id = state_machine.curr_state_id();
+                    let state_tree = world.get::<StateTree>(state_machine.state_tree())?;
+                    if curr_state_id != entity
+                        && !state_tree
+                            .path_iter(curr_state_id)
+                            .any(|state| state == entity)
+                    {
+                        return None;
+                    }
+                    let target = match eviction {
+                        StateEviction::Stay => return None,
+                        StateEviction::ToSuper => state_tree.get_super_state(entity),
+                        StateEviction::To(target) => Some(target),
+                    };
+                    if target.is_none() {
+                        warn!(
+                            "[DisabledState] {} is the root of {}, it cannot be evicted",
+                            entity, state_machine_id
+                        );
+                    }
+                    Some((state_machine_id, target?))
+                })
+                .collect::<Vec<_>>();
+
+            for (state_machine_id, target) in evictions {
+                world.trigger(HsmTrigger::chain(state_machine_id, target));
+            }
+        });
+    }
+}
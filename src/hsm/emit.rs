@@ -0,0 +1,178 @@
+use std::{any::TypeId, sync::Arc};
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+use smallvec::SmallVec;
+
+use crate::context::ActionContext;
+
+/// 发送事件的时机
+///
+/// When the event is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HsmEmitPhase {
+    /// 进入状态时（在 [`AfterEnterSystem`](crate::prelude::AfterEnterSystem) 之后）
+    ///
+    /// On entering the state (after [`AfterEnterSystem`](crate::prelude::AfterEnterSystem))
+    #[default]
+    Enter,
+    /// 退出状态时（在 [`BeforeExitSystem`](crate::prelude::BeforeExitSystem) 之后）
+    ///
+    /// On exiting the state (after [`BeforeExitSystem`](crate::prelude::BeforeExitSystem))
+    Exit,
+}
+
+enum EmitPayload<E> {
+    Event(E),
+    ServiceTarget(Arc<dyn Fn(Entity) -> E + Send + Sync>),
+}
+
+impl<E: Clone> Clone for EmitPayload<E> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Event(event) => Self::Event(event.clone()),
+            Self::ServiceTarget(f) => Self::ServiceTarget(f.clone()),
+        }
+    }
+}
+
+/// # 状态事件\State Event
+/// * 挂载在状态实体上，进入或退出该状态时触发事件 `E`，只需要“进入时发送某个事件”的集成无需为此注册一次性的动作系统。
+/// - Lives on a state entity and triggers the event `E` when the state is entered or exited, so integrations that only need
+///   "send this event on enter" don't have to register a disposable action system for it.
+/// * [`HsmEmit::new`] 触发全局事件；[`HsmEmit::to_service_target`] 用服务目标构造实体事件并在其上触发。
+/// - [`HsmEmit::new`] triggers a global event; [`HsmEmit::to_service_target`] builds an entity event from the service target
+///   and triggers it there.
+/// * 同一状态上可以挂载多种 `E`，按挂载顺序发送。
+/// - Several `E` can live on the same state and are sent in the order they were inserted.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Event, Clone)]
+/// struct AlarmRaised;
+///
+/// #[derive(EntityEvent, Clone)]
+/// struct Stunned(Entity);
+///
+/// # fn foo(mut commands: Commands, alarm: Entity, stun: Entity) {
+/// commands.entity(alarm).insert(HsmEmit::new(AlarmRaised));
+/// commands
+///     .entity(stun)
+///     .insert(HsmEmit::to_service_target(Stunned).on_exit());
+/// # }
+/// ```
+#[derive(Component)]
+#[component(on_add = Self::on_add, on_remove = Self::on_remove)]
+#[require(HsmEmitters)]
+pub struct HsmEmit<E>
+where
+    for<'a> E: Event<Trigger<'a>: Default> + Clone,
+{
+    payload: EmitPayload<E>,
+    phase: HsmEmitPhase,
+}
+
+impl<E> Clone for HsmEmit<E>
+where
+    for<'a> E: Event<Trigger<'a>: Default> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            payload: self.payload.clone(),
+            phase: self.phase,
+        }
+    }
+}
+
+impl<E> HsmEmit<E>
+where
+    for<'a> E: Event<Trigger<'a>: Default> + Clone,
+{
+    /// 进入时触发 `event` 的副本
+    ///
+    /// Trigger a copy of `event` on enter
+    pub fn new(event: E) -> Self {
+        Self {
+            payload: EmitPayload::Event(event),
+            phase: HsmEmitPhase::Enter,
+        }
+    }
+
+    /// 进入时以服务目标调用 `f` 构造事件并触发
+    ///
+    /// Build the event by calling `f` with the service target on enter, then trigger it
+    pub fn to_service_target(f: impl Fn(Entity) -> E + Send + Sync + 'static) -> Self {
+        Self {
+            payload: EmitPayload::ServiceTarget(Arc::new(f)),
+            phase: HsmEmitPhase::Enter,
+        }
+    }
+
+    /// 改为退出时发送
+    ///
+    /// Send on exit instead
+    pub fn on_exit(mut self) -> Self {
+        self.phase = HsmEmitPhase::Exit;
+        self
+    }
+
+    pub fn phase(&self) -> HsmEmitPhase {
+        self.phase
+    }
+
+    fn emit(world: &mut World, context: ActionContext, phase: HsmEmitPhase) {
+        let Some(emit) = world.get::<Self>(context.state()) else {
+            return;
+        };
+        if emit.phase != phase {
+            return;
+        }
+        let event = match &emit.payload {
+            EmitPayload::Event(event) => event.clone(),
+            EmitPayload::ServiceTarget(f) => f(context.service_target),
+        };
+        world.trigger(event);
+    }
+
+    fn on_add(mut world: DeferredWorld, hook_context: HookContext) {
+        if let Some(mut emitters) = world.get_mut::<HsmEmitters>(hook_context.entity) {
+            emitters.0.push((TypeId::of::<E>(), Self::emit));
+        }
+    }
+
+    fn on_remove(mut world: DeferredWorld, hook_context: HookContext) {
+        if let Some(mut emitters) = world.get_mut::<HsmEmitters>(hook_context.entity) {
+            emitters
+                .0
+                .retain(|(type_id, _)| *type_id != TypeId::of::<E>());
+        }
+    }
+}
+
+type EmitFn = fn(&mut World, ActionContext, HsmEmitPhase);
+
+/// 状态上所有 [`HsmEmit`] 的发送函数，由其钩子维护
+///
+/// The send functions of every [`HsmEmit`] on a state, maintained by its hooks
+#[derive(Component, Default, Clone)]
+pub(crate) struct HsmEmitters(SmallVec<[(TypeId, EmitFn); 1]>);
+
+impl HsmEmitters {
+    /// 发送状态上所有在 `phase` 阶段触发的事件
+    ///
+    /// Send every event on the state that fires in `phase`
+    pub(crate) fn emit_command(context: ActionContext, phase: HsmEmitPhase) -> impl Command {
+        move |world: &mut World| {
+            let Some(emitters) = world.get::<Self>(context.state()).cloned() else {
+                return;
+            };
+            for (_, emit) in emitters.0 {
+                emit(world, context, phase);
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
-use bevy::prelude::*;
+use std::marker::PhantomData;
 
-use crate::guards::GuardCondition;
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{guards::GuardCondition, state_actions::ServiceTarget};
 
 /// # HSM 触发器
 /// * 用于驱动层级状态机（HSM）进行状态转换的核心事件。
@@ -133,3 +138,97 @@ pub enum HsmTriggerType {
     /// Directly jump to specified state
     Chain(Entity),
 }
+
+/// # 观察者转换适配器\Observer Transition Adapter
+/// * 挂载在 [`HsmStateMachine`](crate::prelude::HsmStateMachine) 实体上，会在状态机（或其 [`ServiceTarget`]）实体上注册一个 `E` 的观察者，
+///   当 `E` 被触发时发送对应的 [`HsmTrigger`]，从而无需轮询即可将碰撞、UI 点击等观察者事件接入状态机。
+/// - Lives on the [`HsmStateMachine`](crate::prelude::HsmStateMachine) entity and registers an observer for `E` on the machine
+///   (or its [`ServiceTarget`]) entity. When `E` fires the matching [`HsmTrigger`] is sent, bridging observer events such as
+///   collisions or UI clicks into the state machine without polling.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(EntityEvent)]
+/// struct Hit(Entity);
+///
+/// # fn foo(mut commands: Commands, state_machine: Entity, hurt: Entity) {
+/// commands
+///     .entity(state_machine)
+///     .insert(HsmOnObserverTransition::<Hit>::to(hurt).on_service_target());
+/// # }
+/// ```
+#[derive(Component)]
+#[component(on_insert = Self::on_insert, on_replace = Self::on_replace)]
+pub struct HsmOnObserverTransition<E: EntityEvent> {
+    typed: HsmTriggerType,
+    on_service_target: bool,
+    observer: Option<Entity>,
+    _marker: PhantomData<fn(E)>,
+}
+
+impl<E: EntityEvent> HsmOnObserverTransition<E> {
+    pub const fn new(typed: HsmTriggerType) -> Self {
+        Self {
+            typed,
+            on_service_target: false,
+            observer: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 当 `E` 触发时链式过渡到目标状态，参见 [`HsmTrigger::chain`]
+    ///
+    /// Chain to the target state when `E` fires, see [`HsmTrigger::chain`]
+    pub const fn to(target: Entity) -> Self {
+        Self::new(HsmTriggerType::Chain(target))
+    }
+
+    /// 当 `E` 触发时返回父状态，参见 [`HsmTrigger::to_super`]
+    ///
+    /// Return to the parent state when `E` fires, see [`HsmTrigger::to_super`]
+    pub const fn to_super() -> Self {
+        Self::new(HsmTriggerType::ToSuper)
+    }
+
+    /// 观察服务目标而不是状态机实体；没有 [`ServiceTarget`] 时仍观察状态机实体
+    ///
+    /// Observe the service target instead of the state machine entity; falls back to the state machine without a [`ServiceTarget`]
+    pub const fn on_service_target(mut self) -> Self {
+        self.on_service_target = true;
+        self
+    }
+
+    /// 注册的观察者实体
+    ///
+    /// The registered observer entity
+    pub const fn observer(&self) -> Option<Entity> {
+        self.observer
+    }
+
+    fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let Some(adapter) = world.get::<Self>(entity) else {
+            return;
+        };
+        let typed = adapter.typed.clone();
+        let watched = match adapter.on_service_target {
+            true => world.get::<ServiceTarget>(entity).map_or(entity, |t| t.0),
+            false => entity,
+        };
+        let observer = Observer::new(move |_: On<E>, mut commands: Commands| {
+            commands.trigger(HsmTrigger::new(entity, typed.clone()));
+        })
+        .with_entity(watched);
+        let observer = world.commands().spawn(observer).id();
+        if let Some(mut adapter) = world.get_mut::<Self>(entity) {
+            adapter.observer = Some(observer);
+        }
+    }
+
+    fn on_replace(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        if let Some(observer) = world.get::<Self>(entity).and_then(|a| a.observer) {
+            world.commands().entity(observer).try_despawn();
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Write},
+};
+
+use bevy::{diagnostic::FrameCount, prelude::*};
+
+use crate::{
+    context::GuardContext,
+    fault::HsmFaulted,
+    hsm::{
+        loop_detection::HsmLoopDetected,
+        requester::{RequestRejection, TransitionRequestRejected},
+    },
+};
+
+/// # 事件日志\Event Log
+/// * 可选挂载在状态机上的环形缓冲区，按帧记录最近 `capacity` 条值得注意的事件（转换、故障、被拒绝的请求、循环检测），
+///   供 UI 覆盖层读取；状态机被标记为 [`HsmFaulted`] 时会把日志整体输出到 `error!`。
+/// - An optional ring buffer on a state machine recording the last `capacity` notable events (transitions, faults,
+///   rejected requests, loop detections) with frame stamps, readable by UI overlays; the whole log is dumped to `error!`
+///   when the machine gets marked [`HsmFaulted`].
+/// * 帧号取自 [`FrameCount`]，未安装 [`FrameCountPlugin`](bevy::diagnostic::FrameCountPlugin) 时为 0。
+/// - Frame stamps come from [`FrameCount`] and are 0 without the [`FrameCountPlugin`](bevy::diagnostic::FrameCountPlugin).
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn overlay(query: Query<&HsmEventLog>) {
+///     for log in query.iter() {
+///         for entry in log.iter() {
+///             info!("#{} {}", entry.frame, entry.event);
+///         }
+///     }
+/// }
+///
+/// # fn my_fn(mut commands: Commands, state_machine: Entity) {
+/// commands
+///     .entity(state_machine)
+///     .insert(HsmEventLog::new(32).with_verbosity(HsmEventLogVerbosity::Verbose));
+/// # }
+/// ```
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct HsmEventLog {
+    capacity: usize,
+    verbosity: HsmEventLogVerbosity,
+    entries: VecDeque<HsmLogEntry>,
+}
+
+impl Default for HsmEventLog {
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+/// 事件日志的详细程度，每一级都包含上一级的事件
+///
+/// Verbosity of an event log, each level including the events of the previous one
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HsmEventLogVerbosity {
+    /// 故障与循环检测
+    ///
+    /// Faults and loop detections
+    Errors,
+    /// 以及完成的转换与被拒绝的请求
+    ///
+    /// Plus completed transitions and rejected requests
+    #[default]
+    Transitions,
+    /// 以及被转换前钩子否决的转换
+    ///
+    /// Plus transitions vetoed by before-transition hooks
+    Verbose,
+}
+
+/// 一条带帧号的日志
+///
+/// A frame-stamped log entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HsmLogEntry {
+    pub frame: u32,
+    pub event: HsmLogEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HsmLogEvent {
+    Transition {
+        from: Entity,
+        to: Entity,
+    },
+    TransitionVetoed {
+        from: Entity,
+        to: Entity,
+    },
+    RequestRejected {
+        target: Entity,
+        rejection: RequestRejection,
+    },
+    Faulted {
+        state: Entity,
+        message: String,
+    },
+    LoopDetected {
+        states: Vec<Entity>,
+    },
+}
+
+impl HsmLogEvent {
+    /// 记录该事件所需的最低详细程度
+    ///
+    /// The lowest verbosity recording this event
+    pub fn verbosity(&self) -> HsmEventLogVerbosity {
+        match self {
+            HsmLogEvent::Faulted { .. } | HsmLogEvent::LoopDetected { .. } => {
+                HsmEventLogVerbosity::Errors
+            }
+            HsmLogEvent::Transition { .. } | HsmLogEvent::RequestRejected { .. } => {
+                HsmEventLogVerbosity::Transitions
+            }
+            HsmLogEvent::TransitionVetoed { .. } => HsmEventLogVerbosity::Verbose,
+        }
+    }
+}
+
+impl Display for HsmLogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HsmLogEvent::Transition { from, to } => write!(f, "transition {:?} -> {:?}", from, to),
+            HsmLogEvent::TransitionVetoed { from, to } => {
+                write!(f, "transition {:?} -> {:?} vetoed by a hook", from, to)
+            }
+            HsmLogEvent::RequestRejected { target, rejection } => {
+                write!(f, "request to {:?} rejected: {}", target, rejection)
+            }
+            HsmLogEvent::Faulted { state, message } => {
+                write!(f, "faulted in {:?}: {}", state, message)
+            }
+            HsmLogEvent::LoopDetected { states } => write!(f, "loop detected over {:?}", states),
+        }
+    }
+}
+
+impl HsmEventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            verbosity: HsmEventLogVerbosity::default(),
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn with_verbosity(mut self, verbosity: HsmEventLogVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn verbosity(&self) -> HsmEventLogVerbosity {
+        self.verbosity
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: HsmEventLogVerbosity) {
+        self.verbosity = verbosity;
+    }
+
+    /// 按从旧到新的顺序遍历日志
+    ///
+    /// Iterate the entries from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &HsmLogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 追加一条事件，低于当前详细程度的事件会被忽略，超出容量时丢弃最旧的一条
+    ///
+    /// Append an event, ignoring it below the current verbosity and dropping the oldest entry once over capacity
+    pub fn push(&mut self, frame: u32, event: HsmLogEvent) {
+        if event.verbosity() > self.verbosity {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HsmLogEntry { frame, event });
+    }
+
+    /// 将日志格式化为多行文本
+    ///
+    /// Format the log as multi-line text
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for entry in self.entries.iter() {
+            let _ = writeln!(out, "#{} {}", entry.frame, entry.event);
+        }
+        out
+    }
+
+    fn record(world: &mut World, state_machine: Entity, event: HsmLogEvent) {
+        let frame = world
+            .get_resource::<FrameCount>()
+            .map_or(0, |frame| frame.0);
+        if let Some(mut log) = world.get_mut::<Self>(state_machine) {
+            log.push(frame, event);
+        }
+    }
+
+    pub(crate) fn record_transition(world: &mut World, context: GuardContext) {
+        Self::record(
+            world,
+            context.state_machine,
+            HsmLogEvent::Transition {
+                from: context.from_state(),
+                to: context.to_state(),
+            },
+        );
+    }
+
+    pub(crate) fn record_vetoed(world: &mut World, context: GuardContext) {
+        Self::record(
+            world,
+            context.state_machine,
+            HsmLogEvent::TransitionVetoed {
+                from: context.from_state(),
+                to: context.to_state(),
+            },
+        );
+    }
+
+    pub(crate) fn on_request_rejected(
+        on: On<TransitionRequestRejected>,
+        frame: Option<Res<FrameCount>>,
+        mut query: Query<&mut Self>,
+    ) {
+        let Ok(mut log) = query.get_mut(on.state_machine) else {
+            return;
+        };
+        log.push(
+            frame.map_or(0, |frame| frame.0),
+            HsmLogEvent::RequestRejected {
+                target: on.target,
+                rejection: on.rejection,
+            },
+        );
+    }
+
+    pub(crate) fn on_loop_detected(
+        on: On<HsmLoopDetected>,
+        frame: Option<Res<FrameCount>>,
+        mut query: Query<&mut Self>,
+    ) {
+        let Ok(mut log) = query.get_mut(on.state_machine) else {
+            return;
+        };
+        log.push(
+            frame.map_or(0, |frame| frame.0),
+            HsmLogEvent::LoopDetected {
+                states: on.states.clone(),
+            },
+        );
+    }
+
+    pub(crate) fn on_faulted(
+        insert: On<Insert, HsmFaulted>,
+        frame: Option<Res<FrameCount>>,
+        mut query: Query<(&mut Self, &HsmFaulted)>,
+    ) {
+        let Ok((mut log, faulted)) = query.get_mut(insert.entity) else {
+            return;
+        };
+        log.push(
+            frame.map_or(0, |frame| frame.0),
+            HsmLogEvent::Faulted {
+                state: faulted.state,
+                message: faulted.message.clone(),
+            },
+        );
+        error!(
+            "[HsmEventLog] state machine {:?} faulted, recent events:\n{}",
+            insert.entity,
+            log.dump()
+        );
+    }
+}
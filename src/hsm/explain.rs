@@ -0,0 +1,175 @@
+use std::fmt::Display;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::context::GuardContext;
+
+/// # 转换解释\Transition Explain
+/// * 可选的调试模式：插入该资源后，每个状态机每帧记录评估过的条件及其结果，以及被选中或被拒绝的转换，
+///   用于回答“为什么发生了这次转换”与“为什么没有转换”。
+/// - Opt-in debugging mode: once this resource is inserted, every state machine records per frame which conditions were
+///   evaluated with what result and which transitions were selected or rejected, answering "why did it transition" and
+///   "why didn't it".
+/// * 报告在状态机本帧首次被评估时重置，因此 [`HsmExplain::last_report`] 总是返回最近一次被评估那一帧的内容。
+/// - A report is reset the first time its machine is evaluated in a frame, so [`HsmExplain::last_report`] always returns
+///   the latest frame the machine was evaluated in.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn why_not(explain: Res<HsmExplain>, state_machine: Single<Entity, With<HsmStateMachine>>) {
+///     let Some(report) = explain.last_report(*state_machine) else {
+///         return;
+///     };
+///     for condition in report.conditions.iter() {
+///         info!("{:?} -> {:?}: {} = {:?}", condition.from, condition.to, condition.condition, condition.result);
+///     }
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .init_resource::<HsmExplain>()
+///     .add_systems(Update, why_not);
+/// # }
+/// ```
+#[derive(Resource, Debug, Default, Clone)]
+pub struct HsmExplain {
+    frame: u64,
+    reports: HashMap<Entity, HsmExplainReport>,
+}
+
+/// # 转换解释报告\Explain Report
+/// * 单个状态机在一帧内的评估记录，按发生顺序排列。
+/// - What a single state machine evaluated within one frame, in order of occurrence.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmExplainReport {
+    /// 记录所在的帧（自插入 [`HsmExplain`] 起计数）
+    ///
+    /// The frame the report belongs to (counted since [`HsmExplain`] was inserted)
+    pub frame: u64,
+    pub conditions: Vec<ExplainedCondition>,
+    pub transitions: Vec<ExplainedTransition>,
+}
+
+/// 一次条件评估
+///
+/// A single condition evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedCondition {
+    pub from: Entity,
+    pub to: Entity,
+    /// 条件表达式
+    ///
+    /// The condition expression
+    pub condition: String,
+    /// 评估结果，守卫运行失败时为错误信息
+    ///
+    /// The result, or the error message when the guard failed to run
+    pub result: Result<bool, String>,
+}
+
+/// 一条候选转换的结局
+///
+/// What became of a candidate transition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedTransition {
+    pub from: Entity,
+    pub to: Entity,
+    pub outcome: TransitionOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransitionOutcome {
+    /// 被选中并执行
+    ///
+    /// Selected and carried out
+    Selected,
+    /// 条件满足，但另一个优先级更高的候选被选中
+    ///
+    /// The condition held, but a candidate with a higher priority was selected
+    Outranked,
+    /// 被转换前钩子否决
+    ///
+    /// Vetoed by a before-transition hook
+    RejectedByHook,
+    /// 服务目标不满足目标状态的 [`HsmEnterRequirements`](crate::prelude::HsmEnterRequirements)，守卫未运行
+    ///
+    /// The service target does not meet the target's [`HsmEnterRequirements`](crate::prelude::HsmEnterRequirements);
+    /// the guard was not run
+    MissingRequirements,
+}
+
+impl HsmExplain {
+    /// 状态机最近一次被评估那一帧的报告
+    ///
+    /// The report of the latest frame the state machine was evaluated in
+    pub fn last_report(&self, state_machine: Entity) -> Option<&HsmExplainReport> {
+        self.reports.get(&state_machine)
+    }
+
+    /// 清空所有报告
+    ///
+    /// Drop all reports
+    pub fn clear(&mut self) {
+        self.reports.clear();
+    }
+
+    pub(crate) fn tick(mut explain: ResMut<Self>) {
+        explain.frame += 1;
+    }
+
+    fn report_mut(world: &mut World, state_machine: Entity) -> Option<Mut<'_, HsmExplainReport>> {
+        let explain = world.get_resource_mut::<Self>()?;
+        Some(explain.map_unchanged(|explain| {
+            let frame = explain.frame;
+            let report = explain.reports.entry(state_machine).or_default();
+            if report.frame != frame {
+                *report = HsmExplainReport { frame, ..default() };
+            }
+            report
+        }))
+    }
+
+    /// 记录一次条件评估；只有开启解释模式时才会生成条件表达式
+    ///
+    /// Record a condition evaluation; the expression is only built while explain mode is on
+    pub(crate) fn record_condition<E: Display>(
+        world: &mut World,
+        context: GuardContext,
+        condition: impl FnOnce(&World) -> Option<String>,
+        result: &Result<bool, E>,
+    ) {
+        if !world.contains_resource::<Self>() {
+            return;
+        }
+        let condition = condition(world).unwrap_or_default();
+        let result = result.as_ref().copied().map_err(ToString::to_string);
+        if let Some(mut report) = Self::report_mut(world, context.state_machine) {
+            report.conditions.push(ExplainedCondition {
+                from: context.from_state(),
+                to: context.to_state(),
+                condition,
+                result,
+            });
+        }
+    }
+
+    /// 记录一条候选转换的结局
+    ///
+    /// Record what became of a candidate transition
+    pub(crate) fn record_transition(
+        world: &mut World,
+        context: GuardContext,
+        outcome: TransitionOutcome,
+    ) {
+        if let Some(mut report) = Self::report_mut(world, context.state_machine) {
+            report.transitions.push(ExplainedTransition {
+                from: context.from_state(),
+                to: context.to_state(),
+                outcome,
+            });
+        }
+    }
+}
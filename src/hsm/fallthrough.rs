@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    clock::StateClock,
+    error::StateMachineError,
+    hsm::{
+        HsmState,
+        state_lifecycle::{CurrentLifecycle, StateLifecycle},
+        state_machine::HsmStateMachine,
+        state_tree::StateTree,
+        transition_strategy::{handle_enter_transition, handle_exit_transition},
+    },
+};
+
+/// 超时后采取的措施
+///
+/// What to do once the timeout elapses
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FallthroughPolicy {
+    /// 进入指定的默认子状态，该实体必须是当前状态的子状态
+    ///
+    /// Enter the designated default sub-state, which must be a sub-state of the current state
+    Enter(Entity),
+    /// 退出当前状态，回到父状态
+    ///
+    /// Exit the current state back to its super state
+    Exit,
+    /// 只触发 [`HsmFallthroughTimeout`]
+    ///
+    /// Only trigger [`HsmFallthroughTimeout`]
+    #[default]
+    Warn,
+}
+
+/// # 子状态落空策略\Fallthrough Policy
+/// * 挂载在组合状态上：状态机停留在该状态的 [`StateLifecycle::Update`] 中，且连续 `after` 时间没有任何子状态的进入条件成立时，
+///   按 `policy` 进入默认子状态、退出该状态或仅发出警告，避免状态机无声地卡住。
+/// - Placed on a composite state: when the machine sits in the [`StateLifecycle::Update`] of this state and no sub-state's
+///   enter condition passes for `after`, `policy` either enters a default sub-state, exits the state or only warns, so
+///   machines do not get silently stuck.
+/// * 每次超时都会触发 [`HsmFallthroughTimeout`]，随后重新计时。时间取自状态机的 [`StateClock`]，暂停期间不计时。
+/// - Every timeout triggers [`HsmFallthroughTimeout`] and then restarts the timing. Time is read from the machine's
+///   [`StateClock`], so it does not run while the machine is paused.
+/// * 只在进入条件被检查时判断是否超时，休眠（[`HsmSleep`](crate::hsm::sleep::HsmSleep)）的状态机不会超时。
+/// - The timeout is only evaluated while enter conditions are being checked, so a sleeping
+///   ([`HsmSleep`](crate::hsm::sleep::HsmSleep)) machine never times out.
+///
+/// # 示例\Example
+/// ```
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, combat: Entity, idle: Entity) {
+/// commands.entity(combat).insert(HsmFallthrough::new(
+///     Duration::from_secs(2),
+///     FallthroughPolicy::Enter(idle),
+/// ));
+/// # }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .add_observer(|on: On<HsmFallthroughTimeout>| {
+///         warn!("{:?} is stuck in {:?}", on.state_machine, on.state);
+///     });
+/// # }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HsmFallthrough {
+    /// 没有子状态可进入的最长时间
+    ///
+    /// The longest time allowed without a sub-state to enter
+    pub after: Duration,
+    /// 超时后采取的措施
+    ///
+    /// What to do once the timeout elapses
+    pub policy: FallthroughPolicy,
+}
+
+impl HsmFallthrough {
+    pub fn new(after: Duration, policy: FallthroughPolicy) -> Self {
+        Self { after, policy }
+    }
+
+    /// 状态机进入某个带有 [`HsmFallthrough`] 的状态的 [`StateLifecycle::Update`] 时开始计时，离开时停止
+    ///
+    /// Start timing when a machine reaches the [`StateLifecycle::Update`] of a state carrying [`HsmFallthrough`], and
+    /// stop when it leaves
+    pub(crate) fn track(
+        mut commands: Commands,
+        query: Query<
+            (
+                Entity,
+                &CurrentLifecycle,
+                &StateClock,
+                Has<FallthroughTimer>,
+            ),
+            Changed<CurrentLifecycle>,
+        >,
+        query_states: Query<(), With<Self>>,
+    ) {
+        for (state_machine, current, clock, timed) in query.iter() {
+            if current.lifecycle == StateLifecycle::Update && query_states.contains(current.state) {
+                commands.entity(state_machine).insert(FallthroughTimer {
+                    state: current.state,
+                    since: clock.elapsed(),
+                });
+            } else if timed {
+                commands.entity(state_machine).remove::<FallthroughTimer>();
+            }
+        }
+    }
+
+    /// 当前状态没有可进入的子状态时调用：超时则触发 [`HsmFallthroughTimeout`] 并执行策略
+    ///
+    /// Called when the current state has no sub-state to enter: once timed out, triggers [`HsmFallthroughTimeout`]
+    /// and applies the policy
+    pub(crate) fn expire(world: &mut World, state_machine: Entity, state: Entity) -> Result<()> {
+        let Some(fallthrough) = world.get::<Self>(state).copied() else {
+            return Ok(());
+        };
+        let Some(since) = world
+            .get::<FallthroughTimer>(state_machine)
+            .filter(|timer| timer.state == state)
+            .map(|timer| timer.since)
+        else {
+            return Ok(());
+        };
+        let now = StateClock::now(world, state_machine).unwrap_or_default();
+        let waited = now.saturating_sub(since);
+        if waited < fallthrough.after {
+            return Ok(());
+        }
+
+        world
+            .entity_mut(state_machine)
+            .insert(FallthroughTimer { state, since: now });
+        world.trigger(HsmFallthroughTimeout {
+            state_machine,
+            state,
+            waited,
+            policy: fallthrough.policy,
+        });
+
+        let Some(state_tree_id) = world
+            .get::<HsmStateMachine>(state_machine)
+            .map(HsmStateMachine::state_tree)
+        else {
+            return Ok(());
+        };
+        let Some(state_tree) = world.get::<StateTree>(state_tree_id) else {
+            StateMachineError::StateTreeNotFound(state_tree_id).report(world);
+            return Ok(());
+        };
+        match fallthrough.policy {
+            FallthroughPolicy::Enter(sub_state) => {
+                if state_tree.get_super_state(sub_state) != Some(state) {
+                    StateMachineError::SubStateNotFound {
+                        state_tree: state_tree_id,
+                        state: sub_state,
+                    }
+                    .report(world);
+                    return Ok(());
+                }
+                let Some(strategy) = world.get::<HsmState>(state).map(|s| s.strategy) else {
+                    return Ok(());
+                };
+                handle_enter_transition(state_machine, state, sub_state, strategy).apply(world)
+            }
+            FallthroughPolicy::Exit => {
+                let Some(super_state) = state_tree.get_super_state(state) else {
+                    StateMachineError::SuperStateNotFound {
+                        state_tree: state_tree_id,
+                        state,
+                    }
+                    .report(world);
+                    return Ok(());
+                };
+                handle_exit_transition(state_machine, state_tree_id, state, super_state)
+                    .apply(world)
+            }
+            FallthroughPolicy::Warn => Ok(()),
+        }
+    }
+}
+
+/// 状态机在带有 [`HsmFallthrough`] 的状态中开始等待子状态的时刻
+///
+/// When the machine started waiting for a sub-state in a state carrying [`HsmFallthrough`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FallthroughTimer {
+    state: Entity,
+    since: Duration,
+}
+
+/// # 子状态落空超时\Fallthrough Timeout
+/// * 状态机在带有 [`HsmFallthrough`] 的状态中超时时触发，`waited` 为已等待的时间，`policy` 为随后执行的策略。
+/// - Triggered when a machine times out in a state carrying [`HsmFallthrough`]; `waited` is the time spent waiting and
+///   `policy` the policy applied next.
+#[derive(EntityEvent, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HsmFallthroughTimeout {
+    #[event_target]
+    pub state_machine: Entity,
+    pub state: Entity,
+    pub waited: Duration,
+    pub policy: FallthroughPolicy,
+}
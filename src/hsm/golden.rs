@@ -0,0 +1,246 @@
+use std::{fmt::Write, fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{guards::GuardOverrides, hsm::state_lifecycle::StateLifecycle, labels::SystemLabel};
+
+/// 设置该环境变量后，[`assert_golden_trace`] 会用实际轨迹覆盖黄金文件
+///
+/// With this environment variable set, [`assert_golden_trace`] overwrites the golden file with the actual trace
+pub const BLESS_ENV_VAR: &str = "BEVY_HSM_BLESS";
+
+/// # 生命周期轨迹\Lifecycle Trace
+/// * 可选挂载在状态机上，按处理顺序记录每一个被处理的状态与生命周期阶段（进入、更新、退出），供 [`HsmTraceScript`] 读取。
+/// - Optionally lives on a state machine and records every processed state and lifecycle phase (enter, update, exit) in
+///   processing order, read by [`HsmTraceScript`].
+/// * 与状态机一起生成，才能记录启动时的进入阶段。
+/// - Spawn it together with the state machine to also record the enter phase at start.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmTrace(Vec<(Entity, StateLifecycle)>);
+
+impl HsmTrace {
+    /// 按从旧到新的顺序遍历记录
+    ///
+    /// Iterate the records from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(Entity, StateLifecycle)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub(crate) fn push(&mut self, state: Entity, lifecycle: StateLifecycle) {
+        self.0.push((state, lifecycle));
+    }
+}
+
+/// # 轨迹脚本\Trace Script
+/// * 按帧运行一个状态机：每一步先通过 [`GuardOverrides`] 为该状态机设置脚本中的守卫结果，再调用一次 [`App::update`]，
+///   最后把 [`HsmTrace`] 格式化为每行 `#<步骤> <阶段> <状态名称>` 的文本，步骤 0 为脚本开始前已记录的阶段。
+/// - Runs a state machine frame by frame: each step first sets the scripted guard outcomes for the machine through
+///   [`GuardOverrides`], then calls [`App::update`] once, and the [`HsmTrace`] is finally formatted as text with one
+///   `#<step> <phase> <state name>` per line, step 0 holding the phases recorded before the script started.
+/// * 设置的结果在之后的步骤中保持不变，脚本结束后移除；未命名的状态使用实体编号作为名称。
+/// - Outcomes stay in place for the following steps and are removed once the script ends; unnamed states use their
+///   entity id as the name.
+/// * 只有已注册名称的守卫可以被覆盖，参见 [`GuardOverrides`]。
+/// - Only guards registered under a name can be overridden, see [`GuardOverrides`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn my_fn(mut app: App, state_machine: Entity) {
+/// let trace = HsmTraceScript::new()
+///     .step([("is_alert", false)])
+///     .step([("is_alert", true)])
+///     .step([("is_alert", false)])
+///     .run(&mut app, state_machine);
+/// assert_golden_trace(&trace, "tests/golden/guard.trace");
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmTraceScript {
+    steps: Vec<Vec<(SystemLabel, bool)>>,
+}
+
+impl HsmTraceScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一步，在该步的更新之前设置给定的守卫结果
+    ///
+    /// Append a step, setting the given guard outcomes before its update
+    pub fn step<L: Into<SystemLabel>>(
+        mut self,
+        outcomes: impl IntoIterator<Item = (L, bool)>,
+    ) -> Self {
+        self.steps.push(
+            outcomes
+                .into_iter()
+                .map(|(label, value)| (label.into(), value))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// 运行脚本并返回格式化的轨迹，状态机上没有 [`HsmTrace`] 时会先插入一个
+    ///
+    /// Run the script and return the formatted trace, inserting an [`HsmTrace`] on the machine first if it has none
+    pub fn run(&self, app: &mut App, state_machine: Entity) -> String {
+        let world = app.world_mut();
+        world.init_resource::<GuardOverrides>();
+        let records = match world.get_entity_mut(state_machine) {
+            Ok(mut entity) => {
+                std::mem::take(&mut entity.entry::<HsmTrace>().or_default().get_mut().0)
+            }
+            Err(_) => Vec::new(),
+        };
+        let mut out = String::new();
+        Self::format(world, 0, records, &mut out);
+
+        for (index, outcomes) in self.steps.iter().enumerate() {
+            let mut overrides = app.world_mut().resource_mut::<GuardOverrides>();
+            for (label, value) in outcomes {
+                overrides.set_for(state_machine, label.clone(), *value);
+            }
+            app.update();
+
+            let world = app.world_mut();
+            let records = world
+                .get_mut::<HsmTrace>(state_machine)
+                .map(|mut trace| std::mem::take(&mut trace.0))
+                .unwrap_or_default();
+            Self::format(world, index + 1, records, &mut out);
+        }
+
+        let mut overrides = app.world_mut().resource_mut::<GuardOverrides>();
+        for (label, _) in self.steps.iter().flatten() {
+            overrides.clear_for(state_machine, label.clone());
+        }
+        out
+    }
+
+    fn format(
+        world: &World,
+        step: usize,
+        records: Vec<(Entity, StateLifecycle)>,
+        out: &mut String,
+    ) {
+        for (state, lifecycle) in records {
+            let name = world
+                .get::<Name>(state)
+                .map_or_else(|| state.to_string(), |name| name.to_string());
+            let _ = writeln!(out, "#{} {:?} {}", step, lifecycle, name);
+        }
+    }
+}
+
+/// # 黄金轨迹断言\Golden Trace Assertion
+/// * 将轨迹与 `path` 处存储的黄金文件比较，不一致时以逐行差异 panic（`-` 为期望，`+` 为实际）。
+/// - Compares a trace with the golden file stored at `path`, panicking with a line diff when they differ (`-` expected,
+///   `+` actual).
+/// * 文件不存在或设置了 [`BLESS_ENV_VAR`] 时写入实际轨迹而不比较。
+/// - Writes the actual trace instead of comparing when the file does not exist or [`BLESS_ENV_VAR`] is set.
+#[track_caller]
+pub fn assert_golden_trace(actual: &str, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    if std::env::var_os(BLESS_ENV_VAR).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .unwrap_or_else(|e| panic!("failed to create {}: {}", parent.display(), e));
+        }
+        fs::write(path, actual)
+            .unwrap_or_else(|e| panic!("failed to write {}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e))
+        .replace("\r\n", "\n");
+    if expected != actual {
+        panic!(
+            "trace differs from golden file {} (set {}=1 to update it):\n{}",
+            path.display(),
+            BLESS_ENV_VAR,
+            line_diff(&expected, actual)
+        );
+    }
+}
+
+/// 基于最长公共子序列的逐行差异
+///
+/// Line diff based on the longest common subsequence
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            let _ = writeln!(out, "  {}", expected[i]);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            let _ = writeln!(out, "- {}", expected[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(out, "+ {}", actual[j]);
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff(
+            "#0 Enter Idle\n#0 Update Idle\n#1 Exit Idle\n",
+            "#0 Enter Idle\n#0 Update Idle\n#1 Exit Idle\n#1 Enter Alert\n",
+        );
+        assert_eq!(
+            diff,
+            "  #0 Enter Idle\n  #0 Update Idle\n  #1 Exit Idle\n+ #1 Enter Alert\n"
+        );
+
+        let diff = line_diff(
+            "#1 Exit Idle\n#1 Enter Walk\n",
+            "#1 Exit Idle\n#1 Enter Run\n",
+        );
+        assert_eq!(diff, "  #1 Exit Idle\n- #1 Enter Walk\n+ #1 Enter Run\n");
+    }
+}
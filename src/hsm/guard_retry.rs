@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{clock::StateClock, context::GuardContext, markers::Paused};
+
+/// # 守卫重试\Guard Retry
+/// * 可选挂载在状态机上：守卫运行失败（返回 `Err`，例如生成期间某一帧缺少组件）后，该守卫按指数退避暂停运行并视为不成立，
+///   退避结束后再次尝试；成功运行一次即清除失败记录。每次失败仍会作为 [`StateMachineError::GuardRunFailed`]
+///   报告（写入 [`StateMachineErrorMessage`](crate::error::StateMachineErrorMessage)），退避期间则不再重复报告。
+/// - Opt-in on a state machine: after a guard fails to run (returns `Err`, e.g. a component missing for one frame during
+///   spawning), that guard is held off with exponential backoff and treated as not holding, then tried again once the
+///   backoff elapses; a single successful run clears the failure. Every failure is still reported as
+///   [`StateMachineError::GuardRunFailed`] (written as a
+///   [`StateMachineErrorMessage`](crate::error::StateMachineErrorMessage)), but not again while backing off.
+/// * 连续失败达到 `max_attempts` 次时插入 [`Paused`] 并触发 [`HsmGuardRetriesExhausted`]，使持续的故障不会无声地卡住状态机。
+/// - After `max_attempts` consecutive failures, [`Paused`] is inserted and [`HsmGuardRetriesExhausted`] is triggered,
+///   so a persistent fault does not stall the machine unnoticed.
+/// * 退避时间取自状态机的 [`StateClock`]；仍在退避的状态机不会进入休眠（[`HsmSleep`](crate::hsm::sleep::HsmSleep)）。
+/// - Backoff is measured on the machine's [`StateClock`]; a machine still backing off does not go to sleep
+///   ([`HsmSleep`](crate::hsm::sleep::HsmSleep)).
+///
+/// [`StateMachineError::GuardRunFailed`]: crate::error::StateMachineError::GuardRunFailed
+///
+/// # 示例\Example
+/// ```
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands.entity(state_machine).insert(
+///     HsmGuardRetry::new(Duration::from_millis(50))
+///         .with_max_backoff(Duration::from_secs(2))
+///         .with_max_attempts(8),
+/// );
+/// # }
+/// ```
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct HsmGuardRetry {
+    /// 首次失败后的退避时间，之后每次失败翻倍
+    ///
+    /// The backoff after the first failure, doubled on every further failure
+    pub backoff: Duration,
+    /// 退避时间的上限
+    ///
+    /// The upper bound of the backoff
+    pub max_backoff: Duration,
+    /// 暂停状态机前允许的连续失败次数，`None` 表示一直重试
+    ///
+    /// Consecutive failures allowed before the machine is paused, `None` to retry forever
+    pub max_attempts: Option<u32>,
+    failures: HashMap<(Entity, Entity), GuardFailure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GuardFailure {
+    attempts: u32,
+    retry_at: Duration,
+}
+
+impl Default for HsmGuardRetry {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100))
+    }
+}
+
+impl HsmGuardRetry {
+    pub fn new(backoff: Duration) -> Self {
+        Self {
+            backoff,
+            max_backoff: Duration::from_secs(5),
+            max_attempts: None,
+            failures: HashMap::default(),
+        }
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// 从 `from_state` 到 `to_state` 的守卫连续失败的次数
+    ///
+    /// How many times in a row the guard from `from_state` to `to_state` failed
+    pub fn attempts(&self, from_state: Entity, to_state: Entity) -> u32 {
+        self.failures
+            .get(&(from_state, to_state))
+            .map_or(0, |failure| failure.attempts)
+    }
+
+    /// 是否有从 `state` 出发的守卫正在退避
+    ///
+    /// Whether a guard leaving `state` is backing off
+    pub fn is_retrying(&self, state: Entity) -> bool {
+        self.failures
+            .keys()
+            .any(|&(from_state, _)| from_state == state)
+    }
+
+    fn delay(&self, attempts: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+            .min(self.max_backoff)
+    }
+
+    /// 按重试策略运行守卫：退避期间不运行并视为不成立，运行失败时记录并返回错误，由调用者报告
+    ///
+    /// Run a guard under the retry policy: while backing off it is not run and treated as not holding; a failed run is
+    /// recorded and the error returned for the caller to report
+    pub(crate) fn run<E>(
+        world: &mut World,
+        context: GuardContext,
+        run: impl FnOnce(&mut World) -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        let state_machine = context.state_machine;
+        let key = (context.from_state(), context.to_state());
+        let Some(retry) = world.get::<Self>(state_machine) else {
+            return run(world);
+        };
+        let now = StateClock::now(world, state_machine).unwrap_or_default();
+        if retry
+            .failures
+            .get(&key)
+            .is_some_and(|failure| now < failure.retry_at)
+        {
+            return Ok(false);
+        }
+
+        let result = run(world);
+        let Some(mut retry) = world.get_mut::<Self>(state_machine) else {
+            return result;
+        };
+        if result.is_ok() {
+            retry.failures.remove(&key);
+            return result;
+        }
+        let attempts = retry.attempts(key.0, key.1) + 1;
+        if retry.max_attempts.is_some_and(|max| attempts >= max) {
+            retry.failures.remove(&key);
+            world.entity_mut(state_machine).insert(Paused);
+            world.trigger(HsmGuardRetriesExhausted {
+                state_machine,
+                from_state: key.0,
+                to_state: key.1,
+                attempts,
+            });
+        } else {
+            let retry_at = now + retry.delay(attempts);
+            retry
+                .failures
+                .insert(key, GuardFailure { attempts, retry_at });
+        }
+        result
+    }
+}
+
+/// # 守卫重试耗尽\Guard Retries Exhausted
+/// * 守卫连续失败达到 [`HsmGuardRetry::max_attempts`] 次、状态机被暂停时触发。
+/// - Triggered when a guard failed [`HsmGuardRetry::max_attempts`] times in a row and the machine got paused.
+#[derive(EntityEvent, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HsmGuardRetriesExhausted {
+    #[event_target]
+    pub state_machine: Entity,
+    pub from_state: Entity,
+    pub to_state: Entity,
+    pub attempts: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let retry =
+            HsmGuardRetry::new(Duration::from_millis(100)).with_max_backoff(Duration::from_secs(1));
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(4), Duration::from_millis(800));
+        assert_eq!(retry.delay(5), Duration::from_secs(1));
+        assert_eq!(retry.delay(u32::MAX), Duration::from_secs(1));
+    }
+}
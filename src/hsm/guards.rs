@@ -1,14 +1,33 @@
+use std::borrow::Cow;
+
 use bevy::{
-    ecs::{lifecycle::HookContext, world::DeferredWorld},
-    platform::collections::HashMap,
+    ecs::{
+        lifecycle::HookContext,
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::RegisteredSystemError,
+        world::DeferredWorld,
+    },
+    platform::collections::{HashMap, HashSet},
     prelude::*,
 };
 
 use crate::{
+    context::GuardContext,
+    error::StateMachineError,
     guards::{CompiledGuard, GuardRegistry},
-    hsm::HsmState,
+    hsm::{
+        HsmState,
+        disabled::DisabledState,
+        guard_retry::HsmGuardRetry,
+        state_machine::HsmStateMachine,
+        state_tree::StateTree,
+        transition_strategy::{CheckOnTransitionStates, HsmTransitionSystems, get_service_target},
+    },
     labels::SystemLabel,
+    local_registry::LocalRegistry,
+    markers::Paused,
     prelude::GuardCondition,
+    registry_usage::{RegistryKind, RegistryUsage},
 };
 
 /// # 进入守卫
@@ -23,8 +42,9 @@ use crate::{
 ///
 /// When the state machine attempts to transition to a state with an [`GuardEnter`], this guard
 /// condition is evaluated. The transition is only permitted if the condition evaluates to `true`.
-#[derive(Component, PartialEq, Eq, Debug, Deref, DerefMut)]
-#[component(immutable, on_insert = Self::on_insert, on_remove = Self::on_remove)]
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+#[component(immutable, on_insert = Self::on_insert, on_replace = Self::on_replace, on_remove = Self::on_remove)]
+#[require(HsmState)]
 pub struct GuardEnter(pub GuardCondition);
 
 impl GuardEnter {
@@ -32,7 +52,19 @@ impl GuardEnter {
         Self(GuardCondition::Id(name.into()))
     }
 
+    pub fn parse(s: impl AsRef<str>) -> bevy::prelude::Result<Self> {
+        Ok(Self(GuardCondition::parse(s)?))
+    }
+
+    /// 在指定调度中评估该守卫，结果保存至下一次转换评估，见 [`GuardScheduleAppExt`]
+    ///
+    /// Evaluate this guard in the given schedule, keeping the verdict for the next transition pass, see [`GuardScheduleAppExt`]
+    pub fn in_schedule(self, schedule: impl ScheduleLabel) -> (Self, GuardEnterSchedule) {
+        (self, GuardEnterSchedule(schedule.intern()))
+    }
+
     fn on_insert(mut world: DeferredWorld, hook_context: HookContext) {
+        acquire_guard_labels::<Self>(&mut world, hook_context.entity);
         let conditions = world.resource::<GuardRegistry>();
         let enter = world
             .get::<Self>(hook_context.entity)
@@ -42,46 +74,159 @@ impl GuardEnter {
                 let mut buffer = world.resource_mut::<GuardEnterCache>();
                 buffer.insert(hook_context.entity, id);
             }
-            Err(e) => {
-                warn!(
-                    "[GuardRegistry] This condition<{:?}> does not exist for state {:?}: {}",
-                    enter.0, hook_context.entity, e
-                );
+            Err(source) => {
+                LocalRegistry::report_unresolved_deferred(&mut world, hook_context.entity, source);
             }
         }
     }
 
+    fn on_replace(mut world: DeferredWorld, hook_context: HookContext) {
+        release_guard_labels::<Self>(&mut world, hook_context.entity);
+    }
+
     fn on_remove(mut world: DeferredWorld, hook_context: HookContext) {
         let mut buffer = world.resource_mut::<GuardEnterCache>();
         buffer.remove(&hook_context.entity);
     }
 }
 
+fn guard_labels<T: Component + std::ops::Deref<Target = GuardCondition>>(
+    world: &DeferredWorld,
+    entity: Entity,
+) -> Vec<SystemLabel> {
+    world
+        .get::<T>(entity)
+        .map(|condition| condition.labels().into_iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn acquire_guard_labels<T: Component + std::ops::Deref<Target = GuardCondition>>(
+    world: &mut DeferredWorld,
+    entity: Entity,
+) {
+    for label in guard_labels::<T>(world, entity) {
+        RegistryUsage::acquire(world, RegistryKind::Guard, label);
+    }
+}
+
+fn release_guard_labels<T: Component + std::ops::Deref<Target = GuardCondition>>(
+    world: &mut DeferredWorld,
+    entity: Entity,
+) {
+    for label in guard_labels::<T>(world, entity) {
+        RegistryUsage::release(world, RegistryKind::Guard, label);
+    }
+}
+
+/// 编译所有状态上的 `T` 守卫，无法解析的守卫会被报告并跳过
+///
+/// Compile the `T` guards of every state; guards that do not resolve are reported and skipped
+fn compile_guard_cache<T: Component + std::ops::Deref<Target = GuardCondition>>(
+    world: &mut World,
+) -> HashMap<Entity, CompiledGuard> {
+    let (compiled, unresolved) =
+        world.resource_scope(|world: &mut World, conditions: Mut<GuardRegistry>| {
+            let mut compiled = HashMap::new();
+            let mut unresolved = Vec::new();
+            let mut query = world.query_filtered::<(Entity, &T), With<HsmState>>();
+            for (state, condition) in query.iter(world) {
+                match conditions.to_combinator_condition_id(condition) {
+                    Ok(guard) => {
+                        compiled.insert(state, guard);
+                    }
+                    Err(source) => {
+                        unresolved.push(StateMachineError::GuardUnresolved { state, source })
+                    }
+                }
+            }
+            (compiled, unresolved)
+        });
+    for error in unresolved {
+        error.report(world);
+    }
+    compiled
+}
+
 #[derive(Debug, Resource, Deref, DerefMut)]
 pub(crate) struct GuardEnterCache(HashMap<Entity, CompiledGuard>);
 
 impl FromWorld for GuardEnterCache {
     fn from_world(world: &mut World) -> Self {
-        let collect = world.resource_scope(|world: &mut World, conditions: Mut<GuardRegistry>| {
-            let mut query = world.query_filtered::<(Entity, &GuardEnter), With<HsmState>>();
-            query
-                .iter(world)
-                .filter_map(|(id, condition)| {
-                    match conditions.to_combinator_condition_id(condition) {
-                        Ok(condition_id) => Some((id, condition_id)),
-                        Err(e) => {
-                            warn!(
-                                "[GuardRegistry] This condition<{:?}> does not exist: {}",
-                                condition.0, e
-                            );
-                            None
-                        }
-                    }
-                })
-                .collect::<Vec<_>>()
-        });
+        Self(compile_guard_cache::<GuardEnter>(world))
+    }
+}
+
+/// # 昂贵进入守卫\Expensive Enter Guard
+/// * 与 [`GuardEnter`] 一起构成进入状态的条件，两者都成立才会进入，但属于更昂贵的一层（例如视线检测、寻路）。
+///   选择子状态时，先对所有候选子状态评估廉价的 [`GuardEnter`]，只对通过廉价层的子状态评估本守卫；
+///   没有 [`GuardEnter`] 的状态视为通过廉价层。
+/// - Forms the condition for entering a state together with [`GuardEnter`], both having to hold, but belongs to a more
+///   expensive tier (line of sight checks, path finding, ...). When selecting a sub-state, the cheap [`GuardEnter`] is
+///   evaluated for every candidate first, and this guard only for the candidates that passed the cheap tier; states
+///   without a [`GuardEnter`] count as passing the cheap tier.
+/// * 使用 [`EnterSelection::First`](crate::prelude::EnterSelection::First) 时，遇到第一个没有昂贵守卫且通过廉价层的子状态即停止廉价层的评估，
+///   之后仍按遍历顺序选择第一个两层都通过的子状态。
+/// - With [`EnterSelection::First`](crate::prelude::EnterSelection::First), the cheap tier stops at the first candidate that
+///   passes it without an expensive guard, and the first candidate in traversal order passing both tiers is still picked.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, attack: Entity) {
+/// commands.entity(attack).insert((
+///     GuardEnter::new("in_range"),
+///     GuardEnterExpensive::new("has_line_of_sight"),
+/// ));
+/// # }
+/// ```
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+#[component(immutable, on_insert = Self::on_insert, on_replace = Self::on_replace, on_remove = Self::on_remove)]
+#[require(HsmState)]
+pub struct GuardEnterExpensive(pub GuardCondition);
+
+impl GuardEnterExpensive {
+    pub fn new(name: impl Into<SystemLabel>) -> Self {
+        Self(GuardCondition::Id(name.into()))
+    }
+
+    pub fn parse(s: impl AsRef<str>) -> bevy::prelude::Result<Self> {
+        Ok(Self(GuardCondition::parse(s)?))
+    }
+
+    fn on_insert(mut world: DeferredWorld, hook_context: HookContext) {
+        acquire_guard_labels::<Self>(&mut world, hook_context.entity);
+        let conditions = world.resource::<GuardRegistry>();
+        let expensive = world
+            .get::<Self>(hook_context.entity)
+            .expect("Component should be present in on_insert hook");
+        match conditions.to_combinator_condition_id(&expensive.0) {
+            Ok(id) => {
+                let mut buffer = world.resource_mut::<GuardEnterExpensiveCache>();
+                buffer.insert(hook_context.entity, id);
+            }
+            Err(source) => {
+                LocalRegistry::report_unresolved_deferred(&mut world, hook_context.entity, source);
+            }
+        }
+    }
+
+    fn on_replace(mut world: DeferredWorld, hook_context: HookContext) {
+        release_guard_labels::<Self>(&mut world, hook_context.entity);
+    }
 
-        Self(HashMap::from_iter(collect))
+    fn on_remove(mut world: DeferredWorld, hook_context: HookContext) {
+        let mut buffer = world.resource_mut::<GuardEnterExpensiveCache>();
+        buffer.remove(&hook_context.entity);
+    }
+}
+
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub(crate) struct GuardEnterExpensiveCache(HashMap<Entity, CompiledGuard>);
+
+impl FromWorld for GuardEnterExpensiveCache {
+    fn from_world(world: &mut World) -> Self {
+        Self(compile_guard_cache::<GuardEnterExpensive>(world))
     }
 }
 
@@ -97,8 +242,9 @@ impl FromWorld for GuardEnterCache {
 ///
 /// When the state machine attempts to transition away from a state with an [`GuardExit`], this
 /// guard condition is evaluated. The transition is only permitted if the condition evaluates to `true`.
-#[derive(Component, PartialEq, Eq, Debug, Deref, DerefMut)]
-#[component(immutable, on_insert = Self::on_insert, on_remove = Self::on_remove)]
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+#[component(immutable, on_insert = Self::on_insert, on_replace = Self::on_replace, on_remove = Self::on_remove)]
+#[require(HsmState)]
 pub struct GuardExit(pub GuardCondition);
 
 impl GuardExit {
@@ -110,7 +256,15 @@ impl GuardExit {
         Ok(Self(GuardCondition::parse(s)?))
     }
 
+    /// 在指定调度中评估该守卫，结果保存至下一次转换评估，见 [`GuardScheduleAppExt`]
+    ///
+    /// Evaluate this guard in the given schedule, keeping the verdict for the next transition pass, see [`GuardScheduleAppExt`]
+    pub fn in_schedule(self, schedule: impl ScheduleLabel) -> (Self, GuardExitSchedule) {
+        (self, GuardExitSchedule(schedule.intern()))
+    }
+
     fn on_insert(mut world: DeferredWorld, hook_context: HookContext) {
+        acquire_guard_labels::<Self>(&mut world, hook_context.entity);
         let conditions = world.resource::<GuardRegistry>();
         let exit = world
             .get::<Self>(hook_context.entity)
@@ -120,15 +274,16 @@ impl GuardExit {
                 let mut buffer = world.resource_mut::<GuardExitCache>();
                 buffer.insert(hook_context.entity, id);
             }
-            Err(e) => {
-                warn!(
-                    "[GuardRegistry] This condition<{:?}> does not exist for state {:?}: {}",
-                    exit.0, hook_context.entity, e
-                );
+            Err(source) => {
+                LocalRegistry::report_unresolved_deferred(&mut world, hook_context.entity, source);
             }
         }
     }
 
+    fn on_replace(mut world: DeferredWorld, hook_context: HookContext) {
+        release_guard_labels::<Self>(&mut world, hook_context.entity);
+    }
+
     fn on_remove(mut world: DeferredWorld, hook_context: HookContext) {
         let mut buffer = world.resource_mut::<GuardExitCache>();
         buffer.remove(&hook_context.entity);
@@ -140,25 +295,245 @@ pub(crate) struct GuardExitCache(HashMap<Entity, CompiledGuard>);
 
 impl FromWorld for GuardExitCache {
     fn from_world(world: &mut World) -> Self {
-        let collect = world.resource_scope(|world: &mut World, conditions: Mut<GuardRegistry>| {
-            let mut query = world.query_filtered::<(Entity, &GuardExit), With<HsmState>>();
-            query
-                .iter(world)
-                .filter_map(|(id, condition)| {
-                    match conditions.to_combinator_condition_id(condition) {
-                        Ok(condition_id) => Some((id, condition_id)),
-                        Err(e) => {
-                            warn!(
-                                "[GuardRegistry] This condition<{:?}> does not exist: {}",
-                                condition.0, e
-                            );
-                            None
+        Self(compile_guard_cache::<GuardExit>(world))
+    }
+}
+
+/// 重新编译引用了 `labels` 中任一条件的进入/退出守卫缓存，无法解析的守卫从缓存中移除并报告
+///
+/// Recompile the cached enter/exit guards referencing any of `labels`; guards that no longer resolve are dropped from the
+/// cache and reported
+pub(crate) fn refresh_guard_caches(world: &mut World, labels: &HashSet<SystemLabel>) {
+    fn refresh<T, C>(world: &mut World, labels: &HashSet<SystemLabel>)
+    where
+        T: Component + std::ops::Deref<Target = GuardCondition>,
+        C: Resource + std::ops::DerefMut<Target = HashMap<Entity, CompiledGuard>>,
+    {
+        let mut query = world.query_filtered::<(Entity, &T), With<HsmState>>();
+        let compiled = query
+            .iter(world)
+            .filter(|(_, guard)| {
+                guard
+                    .labels()
+                    .into_iter()
+                    .any(|label| labels.contains(label))
+            })
+            .map(|(state, guard)| {
+                let registry = world.resource::<GuardRegistry>();
+                (state, registry.to_combinator_condition_id(guard))
+            })
+            .collect::<Vec<_>>();
+        for (state, result) in compiled {
+            match result {
+                Ok(guard) => {
+                    world.resource_mut::<C>().insert(state, guard);
+                }
+                Err(source) => {
+                    world.resource_mut::<C>().remove(&state);
+                    LocalRegistry::report_unresolved(world, state, source);
+                }
+            }
+        }
+    }
+
+    refresh::<GuardEnter, GuardEnterCache>(world, labels);
+    refresh::<GuardEnterExpensive, GuardEnterExpensiveCache>(world, labels);
+    refresh::<GuardExit, GuardExitCache>(world, labels);
+}
+
+/// 进入守卫的评估调度，由 [`GuardEnter::in_schedule`] 插入
+///
+/// The schedule an enter guard is evaluated in, inserted by [`GuardEnter::in_schedule`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(HsmState)]
+pub struct GuardEnterSchedule(pub InternedScheduleLabel);
+
+/// 退出守卫的评估调度，由 [`GuardExit::in_schedule`] 插入
+///
+/// The schedule an exit guard is evaluated in, inserted by [`GuardExit::in_schedule`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(HsmState)]
+pub struct GuardExitSchedule(pub InternedScheduleLabel);
+
+/// 在数据生产者所在调度中得出的守卫结论，以 `(状态机, 起始状态, 目标状态)` 为键，在转换评估后清空
+///
+/// Guard verdicts reached in the schedule of the data producers, keyed by `(state machine, from state, to state)` and
+/// cleared after transition evaluation
+#[derive(Resource, Debug, Default)]
+pub(crate) struct ScheduledGuardVerdicts {
+    schedules: HashSet<InternedScheduleLabel>,
+    verdicts: HashMap<(Entity, Entity, Entity), bool>,
+}
+
+impl ScheduledGuardVerdicts {
+    fn key(context: &GuardContext) -> (Entity, Entity, Entity) {
+        (
+            context.state_machine,
+            context.from_state(),
+            context.to_state(),
+        )
+    }
+
+    /// 运行守卫；若守卫指定了已安装的评估调度，则改为读取该调度中得出的结论，尚无结论时视为 `false`
+    ///
+    /// Run a guard; if it names an installed evaluation schedule, read the verdict reached in that schedule instead,
+    /// treating a missing verdict as `false`
+    pub(crate) fn run(
+        world: &mut World,
+        guard: &CompiledGuard,
+        context: GuardContext,
+        schedule: Option<InternedScheduleLabel>,
+    ) -> Result<bool, RegisteredSystemError<In<GuardContext>, bool>> {
+        if let Some(schedule) = schedule
+            && let Some(verdicts) = world.get_resource::<Self>()
+            && verdicts.schedules.contains(&schedule)
+        {
+            return Ok(verdicts
+                .verdicts
+                .get(&Self::key(&context))
+                .copied()
+                .unwrap_or(false));
+        }
+        HsmGuardRetry::run(world, context, |world| guard.run(world, context))
+    }
+
+    pub(crate) fn clear(mut verdicts: ResMut<Self>) {
+        if !verdicts.verdicts.is_empty() {
+            verdicts.verdicts.clear();
+        }
+    }
+
+    fn evaluate(schedule: InternedScheduleLabel) -> impl FnMut(&mut World) {
+        move |world: &mut World| {
+            let state_machines = world
+                .resource::<CheckOnTransitionStates>()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>();
+            for state_machine_id in state_machines {
+                let Some(curr_state_id) = world
+                    .get_entity(state_machine_id)
+                    .ok()
+                    .filter(|entity| !entity.contains::<Paused>())
+                    .and_then(|entity| entity.get::<HsmStateMachine>())
+                    .map(HsmStateMachine::curr_state_id)
+                else {
+                    continue;
+                };
+                let Some(state_tree) = world
+                    .get::<HsmStateMachine>(state_machine_id)
+                    .and_then(|hsm| world.get::<StateTree>(hsm.state_tree()))
+                else {
+                    continue;
+                };
+                let in_schedule = |hint: Option<InternedScheduleLabel>| hint == Some(schedule);
+
+                let mut pending = Vec::new();
+                if in_schedule(world.get::<GuardExitSchedule>(curr_state_id).map(|s| s.0))
+                    && let Some(super_state_id) = state_tree.get_super_state(curr_state_id)
+                {
+                    pending.push((curr_state_id, super_state_id, false));
+                }
+                for &sub_state_id in state_tree.get_sub_states(curr_state_id).unwrap_or(&[]) {
+                    if in_schedule(world.get::<GuardEnterSchedule>(sub_state_id).map(|s| s.0))
+                        && !world.entity(sub_state_id).contains::<DisabledState>()
+                    {
+                        pending.push((curr_state_id, sub_state_id, true));
+                    }
+                }
+
+                let service_target = get_service_target(world, state_machine_id);
+                for (from_state, to_state, enter) in pending {
+                    let guard = if enter {
+                        LocalRegistry::guard::<GuardEnter>(
+                            world,
+                            state_machine_id,
+                            to_state,
+                            world.resource::<GuardEnterCache>(),
+                        )
+                    } else {
+                        LocalRegistry::guard::<GuardExit>(
+                            world,
+                            state_machine_id,
+                            from_state,
+                            world.resource::<GuardExitCache>(),
+                        )
+                    }
+                    .map(Cow::into_owned);
+                    let Some(guard) = guard else {
+                        continue;
+                    };
+                    let context =
+                        GuardContext::new(service_target, state_machine_id, from_state, to_state);
+                    match HsmGuardRetry::run(world, context, |world| guard.run(world, context)) {
+                        Ok(verdict) => {
+                            world
+                                .resource_mut::<Self>()
+                                .verdicts
+                                .insert(Self::key(&context), verdict);
+                        }
+                        Err(e) => StateMachineError::GuardRunFailed {
+                            state_machine: state_machine_id,
+                            from_state,
+                            to_state: enter.then_some(to_state),
+                            source: e.into(),
                         }
+                        .report(world),
                     }
-                })
-                .collect::<Vec<_>>()
-        });
+                }
+            }
+        }
+    }
+}
 
-        Self(HashMap::from_iter(collect))
+/// 按调度评估守卫的系统集，数据生产者应排序在其之前
+///
+/// The system set evaluating guards per schedule; data producers should be ordered before it
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GuardScheduleSystems;
+
+/// # 守卫调度扩展\Guard Schedule Extension
+/// * 在数据生产者所在的调度中评估带有 [`GuardEnterSchedule`]/[`GuardExitSchedule`] 的守卫，并将结论保存至转换评估，
+///   从而避免例如在 [`Update`] 中产生、在 [`Last`] 中已被清除的数据导致的时序问题。
+///   未安装的调度会被忽略，对应守卫仍在转换评估时直接运行。
+/// - Evaluates guards carrying [`GuardEnterSchedule`]/[`GuardExitSchedule`] in the schedule of the data producers and keeps
+///   the verdicts for the transition pass, removing timing bugs such as data produced in [`Update`] being gone by [`Last`].
+///   Schedules that were not installed are ignored, their guards still running directly during transition evaluation.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn my_fn(world: &mut World, state: Entity) {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .evaluate_guards_in(Update);
+/// world.entity_mut(state).insert(GuardEnter::new("is_up").in_schedule(Update));
+/// # }
+/// ```
+pub trait GuardScheduleAppExt {
+    /// 在调度中的 [`GuardScheduleSystems`] 内评估指定该调度的守卫
+    ///
+    /// Evaluate the guards naming the schedule within [`GuardScheduleSystems`] in it
+    fn evaluate_guards_in(&mut self, schedule: impl ScheduleLabel) -> &mut Self;
+}
+
+impl GuardScheduleAppExt for App {
+    fn evaluate_guards_in(&mut self, schedule: impl ScheduleLabel) -> &mut Self {
+        let schedule = schedule.intern();
+        let newly_installed = self
+            .world_mut()
+            .get_resource_or_init::<ScheduledGuardVerdicts>()
+            .schedules
+            .insert(schedule);
+        if newly_installed {
+            self.add_systems(
+                schedule,
+                ScheduledGuardVerdicts::evaluate(schedule)
+                    .in_set(GuardScheduleSystems)
+                    .before(HsmTransitionSystems),
+            );
+        }
+        self
     }
 }
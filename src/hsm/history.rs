@@ -1,6 +1,5 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, time::Duration};
 
-#[cfg(all(feature = "history", feature = "hybrid"))]
 use bevy::ecs::entity::Entity;
 
 use crate::hsm::state_lifecycle::StateLifecycle;
@@ -28,6 +27,32 @@ pub struct StateHistory {
     ///
     /// Max history size
     max_size: usize,
+    compression: HistoryCompression,
+}
+
+/// # 历史压缩\History Compression
+/// * 决定推送到 [`StateHistory`] 的记录如何去重或压缩，避免快速来回切换把有用的历史挤出队列。
+/// - Decides how records pushed into a [`StateHistory`] are de-duplicated or compressed, so rapid ping-pongs do not
+///   evict useful history.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistoryCompression {
+    /// 记录每一次推送
+    ///
+    /// Record every push
+    #[default]
+    None,
+    /// 跳过与最新记录相同（状态与生命周期阶段均相同）的连续记录
+    ///
+    /// Skip consecutive records equal to the latest one (same state and lifecycle phase)
+    SkipDuplicates,
+    /// 连续相同的记录合并为一条，并累加 [`HistoricalNode::count`]
+    ///
+    /// Merge consecutive equal records into one, accumulating [`HistoricalNode::count`]
+    RunLength,
+    /// 只在当前（叶）状态改变时记录，忽略同一状态的后续生命周期阶段
+    ///
+    /// Only record when the current (leaf) state changes, ignoring later lifecycle phases of the same state
+    LeafChanges,
 }
 
 impl StateHistory {
@@ -35,9 +60,24 @@ impl StateHistory {
         Self {
             history: VecDeque::with_capacity(max_size),
             max_size,
+            compression: HistoryCompression::None,
         }
     }
 
+    /// 获取历史压缩方式
+    ///
+    /// Get the history compression
+    pub fn compression(&self) -> HistoryCompression {
+        self.compression
+    }
+
+    /// 设置历史压缩方式，只影响之后推送的记录
+    ///
+    /// Set the history compression, only affecting records pushed afterwards
+    pub fn set_compression(&mut self, compression: HistoryCompression) {
+        self.compression = compression;
+    }
+
     /// 设置当前状态的FSM历史记录
     #[cfg(all(feature = "history", feature = "hybrid"))]
     pub fn set_last_state_fsm_history(
@@ -45,7 +85,7 @@ impl StateHistory {
         state: Entity,
         fsm_history: crate::fsm::history::FsmStateHistory,
     ) {
-        for HistoricalNode { left_cycle, id } in self.history.iter_mut().rev() {
+        for HistoricalNode { left_cycle, id, .. } in self.history.iter_mut().rev() {
             if state == *id
                 && let HsmStateLifecycleRecord::Update(history) = left_cycle
             {
@@ -59,6 +99,19 @@ impl StateHistory {
     ///
     /// Push a state into the history
     pub fn push(&mut self, node: HistoricalNode) {
+        if let Some(last) = self.history.back_mut() {
+            match self.compression {
+                HistoryCompression::None => {}
+                HistoryCompression::SkipDuplicates if last.same_record(&node) => return,
+                HistoryCompression::RunLength if last.same_record(&node) => {
+                    last.count = last.count.saturating_add(node.count);
+                    last.timestamp = node.timestamp.or(last.timestamp);
+                    return;
+                }
+                HistoryCompression::LeafChanges if last.id == node.id => return,
+                _ => {}
+            }
+        }
         if self.history.len() >= self.max_size {
             self.history.pop_front();
         }
@@ -90,6 +143,38 @@ impl StateHistory {
         self.history.get(self.history.len().checked_sub(index + 1)?)
     }
 
+    /// 从最新开始按状态迭代历史，同一状态的连续记录只算一次
+    ///
+    /// Iterate the history by state from the newest, consecutive records of the same state counting once
+    pub fn recent_states(&self) -> impl Iterator<Item = Entity> + '_ {
+        let mut last = None;
+        self.history
+            .iter()
+            .rev()
+            .filter_map(move |node| (last.replace(node.id) != Some(node.id)).then_some(node.id))
+    }
+
+    /// 最近的 `n` 个状态（包括当前状态）中是否出现过该状态
+    ///
+    /// Whether the state appears among the latest `n` states (the current one included)
+    pub fn was_in_state_within(&self, state: Entity, n: usize) -> bool {
+        self.recent_states().take(n).any(|id| id == state)
+    }
+
+    /// 该状态最近一次的记录
+    ///
+    /// The latest record of the state
+    pub fn last_record_of(&self, state: Entity) -> Option<&HistoricalNode> {
+        self.history.iter().rev().find(|node| node.id == state)
+    }
+
+    /// 最近一次处于该状态的时刻，需要记录带有时间戳
+    ///
+    /// When the machine was last in the state, requiring timestamped records
+    pub fn last_time_in(&self, state: Entity) -> Option<Duration> {
+        self.last_record_of(state)?.timestamp
+    }
+
     /// 清除历史记录
     ///
     /// Clear the history
@@ -114,10 +199,7 @@ impl StateHistory {
 
 impl Default for StateHistory {
     fn default() -> Self {
-        Self {
-            history: VecDeque::with_capacity(10),
-            max_size: 10,
-        }
+        Self::new(10)
     }
 }
 
@@ -154,11 +236,46 @@ impl<'a> DoubleEndedIterator for StateHistoryIterator<'a> {
 pub struct HistoricalNode {
     id: Entity,
     left_cycle: HsmStateLifecycleRecord,
+    count: u32,
+    timestamp: Option<Duration>,
 }
 
 impl HistoricalNode {
     pub fn new(id: Entity, left_cycle: HsmStateLifecycleRecord) -> Self {
-        Self { id, left_cycle }
+        Self {
+            id,
+            left_cycle,
+            count: 1,
+            timestamp: None,
+        }
+    }
+
+    /// 附加记录时刻
+    ///
+    /// Attach the time of the record
+    pub fn with_timestamp(mut self, timestamp: Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// 记录时刻（[`Time::elapsed`](bevy::time::Time::elapsed)），`Time` 资源不存在时为 `None`
+    ///
+    /// The time of the record ([`Time::elapsed`](bevy::time::Time::elapsed)), `None` without a `Time` resource
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// 该记录合并的连续次数，仅在 [`HistoryCompression::RunLength`] 下可能大于 1
+    ///
+    /// How many consecutive records were merged into this one, only above 1 with [`HistoryCompression::RunLength`]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    fn same_record(&self, other: &Self) -> bool {
+        self.id == other.id
+            && StateLifecycle::from(self.left_cycle.clone())
+                == StateLifecycle::from(other.left_cycle.clone())
     }
 
     pub fn left_cycle(&self) -> &HsmStateLifecycleRecord {
@@ -205,3 +322,85 @@ impl From<StateLifecycle> for HsmStateLifecycleRecord {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(compression: HistoryCompression) -> Vec<(Entity, StateLifecycle, u32)> {
+        let a = Entity::from_raw_u32(1).unwrap();
+        let b = Entity::from_raw_u32(2).unwrap();
+        let mut history = StateHistory::new(10);
+        history.set_compression(compression);
+        for (id, lifecycle) in [
+            (a, StateLifecycle::Enter),
+            (a, StateLifecycle::Update),
+            (a, StateLifecycle::Update),
+            (b, StateLifecycle::Enter),
+            (b, StateLifecycle::Enter),
+            (a, StateLifecycle::Update),
+        ] {
+            history.push(HistoricalNode::new(id, lifecycle.into()));
+        }
+        history
+            .iter()
+            .map(|node| (node.id(), node.left_cycle().clone().into(), node.count()))
+            .collect()
+    }
+
+    #[test]
+    fn test_history_compression() {
+        let a = Entity::from_raw_u32(1).unwrap();
+        let b = Entity::from_raw_u32(2).unwrap();
+        let (enter, update) = (StateLifecycle::Enter, StateLifecycle::Update);
+
+        assert_eq!(records(HistoryCompression::None).len(), 6);
+        assert_eq!(
+            records(HistoryCompression::SkipDuplicates),
+            [(a, enter, 1), (a, update, 1), (b, enter, 1), (a, update, 1)]
+        );
+        assert_eq!(
+            records(HistoryCompression::RunLength),
+            [(a, enter, 1), (a, update, 2), (b, enter, 2), (a, update, 1)]
+        );
+        assert_eq!(
+            records(HistoryCompression::LeafChanges),
+            [(a, enter, 1), (b, enter, 1), (a, update, 1)]
+        );
+    }
+
+    #[test]
+    fn test_history_queries() {
+        let [a, b, c] = [1, 2, 3].map(|index| Entity::from_raw_u32(index).unwrap());
+        let mut history = StateHistory::new(10);
+        for (i, (id, lifecycle)) in [
+            (a, StateLifecycle::Enter),
+            (a, StateLifecycle::Exit),
+            (b, StateLifecycle::Enter),
+            (b, StateLifecycle::Exit),
+            (c, StateLifecycle::Enter),
+            (c, StateLifecycle::Update),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            history.push(
+                HistoricalNode::new(id, lifecycle.into())
+                    .with_timestamp(Duration::from_secs(i as u64)),
+            );
+        }
+
+        assert_eq!(history.recent_states().collect::<Vec<_>>(), [c, b, a]);
+        assert!(history.was_in_state_within(b, 2));
+        assert!(!history.was_in_state_within(a, 2));
+        assert!(history.was_in_state_within(a, 3));
+        assert_eq!(history.last_time_in(a), Some(Duration::from_secs(1)));
+        assert_eq!(history.last_time_in(c), Some(Duration::from_secs(5)));
+        assert_eq!(
+            history
+                .last_record_of(b)
+                .map(|node| node.left_cycle().clone().into()),
+            Some(StateLifecycle::Exit)
+        );
+    }
+}
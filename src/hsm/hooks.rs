@@ -0,0 +1,199 @@
+use bevy::{ecs::system::SystemId, prelude::*};
+
+use crate::{context::GuardContext, fault::catch_fault, guards::GuardId, labels::SystemLabel};
+
+/// 转换后钩子的系统ID
+///
+/// System ID of an after-transition hook
+pub type TransitionHookId = SystemId<In<GuardContext>, ()>;
+
+/// # 转换钩子\Transition Hooks
+/// * 在层级状态机的每一次转换（进入子状态、退出到父状态、链式跳转）前后按注册顺序运行的系统，
+///   适合日志、成就、网络同步等需要统一拦截点的插件。转换前钩子返回 `false` 会取消本次转换，后续钩子不再运行。
+/// - Systems run in registration order before and after every transition of a hierarchical state machine (entering a
+///   sub-state, exiting to the super state, chained jumps), giving plugins such as logging, achievements or networking a
+///   single interception point. A before-hook returning `false` cancels the transition and skips the remaining hooks.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn log_transition(context: In<GuardContext>) -> bool {
+///     info!("{:?} -> {:?}", context.from_state(), context.to_state());
+///     true
+/// }
+/// fn unlock_achievement(context: In<GuardContext>) {}
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .add_before_transition_hook("log", log_transition)
+///     .add_after_transition_hook("achievement", unlock_achievement);
+/// # }
+/// ```
+#[derive(Resource, Default, Debug)]
+pub struct HsmTransitionHooks {
+    before: Vec<(SystemLabel, GuardId)>,
+    after: Vec<(SystemLabel, TransitionHookId)>,
+}
+
+impl HsmTransitionHooks {
+    /// 转换前钩子的名称，按运行顺序排列
+    ///
+    /// Names of the before-transition hooks, in running order
+    pub fn before_labels(&self) -> impl Iterator<Item = &SystemLabel> {
+        self.before.iter().map(|(label, _)| label)
+    }
+
+    /// 转换后钩子的名称，按运行顺序排列
+    ///
+    /// Names of the after-transition hooks, in running order
+    pub fn after_labels(&self) -> impl Iterator<Item = &SystemLabel> {
+        self.after.iter().map(|(label, _)| label)
+    }
+
+    /// 依次运行转换前钩子，任一钩子返回 `false` 时返回 `false`
+    ///
+    /// Runs the before-transition hooks in order, returning `false` as soon as one of them does
+    pub(crate) fn run_before(world: &mut World, context: GuardContext) -> bool {
+        let Some(hooks) = world.get_resource::<Self>() else {
+            return true;
+        };
+        let ids = hooks.before.iter().map(|(_, id)| *id).collect::<Vec<_>>();
+        for id in ids {
+            match catch_fault(
+                world,
+                context.state_machine,
+                context.from_state(),
+                |world| world.run_system_with(id, context),
+            ) {
+                Ok(true) => {}
+                Ok(false) => return false,
+                Err(e) => warn!("[HsmTransitionHooks] {:?}: {}", context, e),
+            }
+        }
+        true
+    }
+
+    /// 依次运行转换后钩子
+    ///
+    /// Runs the after-transition hooks in order
+    pub(crate) fn run_after(world: &mut World, context: GuardContext) {
+        let Some(hooks) = world.get_resource::<Self>() else {
+            return;
+        };
+        let ids = hooks.after.iter().map(|(_, id)| *id).collect::<Vec<_>>();
+        for id in ids {
+            if let Err(e) = catch_fault(
+                world,
+                context.state_machine,
+                context.from_state(),
+                |world| world.run_system_with(id, context),
+            ) {
+                warn!("[HsmTransitionHooks] {:?}: {}", context, e);
+            }
+        }
+    }
+}
+
+/// # 注册转换钩子\Register Transition Hooks
+/// * 直接在 [`App`] 或 [`World`] 上注册或移除 [`HsmTransitionHooks`]。
+/// - Register or remove [`HsmTransitionHooks`] directly on an [`App`] or [`World`].
+pub trait HsmTransitionHookExt {
+    /// 注册一个转换前钩子，返回 `false` 会取消转换
+    ///
+    /// Register a before-transition hook; returning `false` cancels the transition
+    fn add_before_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个转换后钩子
+    ///
+    /// Register an after-transition hook
+    fn add_after_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, (), M> + 'static,
+    ) -> &mut Self;
+
+    /// 按名称移除转换前后的钩子
+    ///
+    /// Remove the before and after hooks registered under a name
+    fn remove_transition_hook(&mut self, name: impl Into<SystemLabel>) -> &mut Self;
+}
+
+impl HsmTransitionHookExt for World {
+    fn add_before_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(system);
+        self.get_resource_or_init::<HsmTransitionHooks>()
+            .before
+            .push((name.into(), id));
+        self
+    }
+
+    fn add_after_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, (), M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(system);
+        self.get_resource_or_init::<HsmTransitionHooks>()
+            .after
+            .push((name.into(), id));
+        self
+    }
+
+    fn remove_transition_hook(&mut self, name: impl Into<SystemLabel>) -> &mut Self {
+        let name = name.into();
+        let Some(mut hooks) = self.get_resource_mut::<HsmTransitionHooks>() else {
+            return self;
+        };
+        let before = hooks
+            .before
+            .extract_if(.., |(label, _)| *label == name)
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>();
+        let after = hooks
+            .after
+            .extract_if(.., |(label, _)| *label == name)
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>();
+        for id in before {
+            let _ = self.unregister_system(id);
+        }
+        for id in after {
+            let _ = self.unregister_system(id);
+        }
+        self
+    }
+}
+
+impl HsmTransitionHookExt for App {
+    fn add_before_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().add_before_transition_hook(name, system);
+        self
+    }
+
+    fn add_after_transition_hook<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, (), M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().add_after_transition_hook(name, system);
+        self
+    }
+
+    fn remove_transition_hook(&mut self, name: impl Into<SystemLabel>) -> &mut Self {
+        self.world_mut().remove_transition_hook(name);
+        self
+    }
+}
@@ -0,0 +1,177 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::schedule::{ScheduleLabel, SystemCondition},
+    prelude::*,
+};
+
+use crate::{
+    context::GuardContext,
+    hsm::transition_strategy::{HsmTransitionSchedule, HsmTransitionSystems},
+    labels::SystemLabel,
+    state_actions::RegisterStateSystem,
+};
+
+/// # 条件锁存\Condition Latch
+/// * 捕获帧内发生的事件式布尔值（如 `just_pressed`），一直保持到转换调度中的条件评估结束后才清除。
+///   转换系统默认在 [`Last`] 中运行，而输入等事件往往只在 [`Update`] 中可见，直接在守卫中读取会错过它们。
+///   `T` 仅作为区分不同锁存的标记类型。既可作为全局资源使用，也可作为组件挂在服务目标或状态机上，守卫优先读取组件。
+/// - Captures event-like booleans (such as `just_pressed`) raised during the frame and keeps them until the conditions of
+///   the transition schedule have been evaluated. Transition systems run in [`Last`] by default while input and similar
+///   events are often only visible in [`Update`], so reading them directly in a guard misses them. `T` is only a marker
+///   type telling latches apart. It works as a global resource, or as a component on the service target or the state
+///   machine, which the guard checks first.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// struct Jump;
+///
+/// #[derive(Resource, Default)]
+/// struct JumpPressed(bool);
+///
+/// fn jump_pressed(pressed: Res<JumpPressed>) -> bool {
+///     pressed.0
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .init_resource::<JumpPressed>()
+///     .add_latch::<Jump>("jump")
+///     .latch_when::<Jump, _>(Update, jump_pressed);
+/// // `GuardEnter::parse("jump")` 现在可以在 `Last` 中看到 `Update` 中的按键
+/// // `GuardEnter::parse("jump")` now sees presses from `Update` while running in `Last`
+/// # }
+/// ```
+#[derive(Resource, Component)]
+pub struct HsmLatch<T: Send + Sync + 'static> {
+    latched: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + Sync + 'static> Default for HsmLatch<T> {
+    fn default() -> Self {
+        Self {
+            latched: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> std::fmt::Debug for HsmLatch<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HsmLatch")
+            .field("type", &std::any::type_name::<T>())
+            .field("latched", &self.latched)
+            .finish()
+    }
+}
+
+impl<T: Send + Sync + 'static> HsmLatch<T> {
+    /// 锁存，直到下一次转换评估之后
+    ///
+    /// Latch until after the next transition evaluation
+    pub fn set(&mut self) {
+        self.latched = true;
+    }
+
+    /// 值为真时锁存
+    ///
+    /// Latch if the value is true
+    pub fn capture(&mut self, value: bool) {
+        self.latched |= value;
+    }
+
+    /// 是否已锁存
+    ///
+    /// Whether the latch is set
+    pub fn is_set(&self) -> bool {
+        self.latched
+    }
+
+    /// 清除锁存
+    ///
+    /// Clear the latch
+    pub fn clear(&mut self) {
+        self.latched = false;
+    }
+
+    /// 守卫系统：服务目标或状态机上的组件，或全局资源已锁存时通过
+    ///
+    /// Guard system: passes when the component on the service target or the state machine, or the global resource, is latched
+    pub fn latched(
+        context: In<GuardContext>,
+        query: Query<&Self>,
+        resource: Option<Res<Self>>,
+    ) -> bool {
+        for entity in [context.service_target, context.state_machine] {
+            if let Ok(latch) = query.get(entity) {
+                return latch.is_set();
+            }
+        }
+        resource.is_some_and(|latch| latch.is_set())
+    }
+
+    fn clear_all(mut query: Query<&mut Self>, resource: Option<ResMut<Self>>) {
+        for mut latch in query.iter_mut() {
+            if latch.latched {
+                latch.clear();
+            }
+        }
+        if let Some(mut latch) = resource
+            && latch.latched
+        {
+            latch.clear();
+        }
+    }
+}
+
+/// # 条件锁存扩展\Condition Latch Extension
+/// * 注册 [`HsmLatch`] 及其守卫，并在转换系统（[`HsmTransitionSystems`]）之后清除它们。需要先添加
+///   [`StateMachinePlugin`](crate::StateMachinePlugin)，否则假定转换系统在 [`Last`] 中运行。
+/// - Registers [`HsmLatch`]es and their guards, and clears them after the transition systems ([`HsmTransitionSystems`]).
+///   [`StateMachinePlugin`](crate::StateMachinePlugin) should be added first, otherwise the transition systems are
+///   assumed to run in [`Last`].
+pub trait HsmLatchAppExt {
+    /// 初始化锁存资源，以 `name` 注册其守卫，并在转换评估后清除所有 `HsmLatch<T>`
+    ///
+    /// Initialize the latch resource, register its guard as `name`, and clear every `HsmLatch<T>` after transition evaluation
+    fn add_latch<T: Send + Sync + 'static>(&mut self, name: impl Into<SystemLabel>) -> &mut Self;
+
+    /// 在调度中每当条件为真时锁存全局的 `HsmLatch<T>`
+    ///
+    /// Latch the global `HsmLatch<T>` whenever the condition is true in the schedule
+    fn latch_when<T: Send + Sync + 'static, M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        condition: impl SystemCondition<M>,
+    ) -> &mut Self;
+}
+
+impl HsmLatchAppExt for App {
+    fn add_latch<T: Send + Sync + 'static>(&mut self, name: impl Into<SystemLabel>) -> &mut Self {
+        let schedule = self
+            .world()
+            .get_resource::<HsmTransitionSchedule>()
+            .map_or(Last.intern(), |schedule| schedule.0);
+        self.init_resource::<HsmLatch<T>>()
+            .register_guard(name, HsmLatch::<T>::latched)
+            .add_systems(
+                schedule,
+                HsmLatch::<T>::clear_all.after(HsmTransitionSystems),
+            )
+    }
+
+    fn latch_when<T: Send + Sync + 'static, M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        condition: impl SystemCondition<M>,
+    ) -> &mut Self {
+        self.init_resource::<HsmLatch<T>>().add_systems(
+            schedule,
+            (|mut latch: ResMut<HsmLatch<T>>| latch.set()).run_if(condition),
+        )
+    }
+}
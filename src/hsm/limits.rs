@@ -0,0 +1,162 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{
+    error::StateMachineError,
+    hsm::{state_machine::HsmStateMachine, state_tree::StateTree},
+    markers::Paused,
+};
+
+/// # 状态机限制\State Machine Limits
+/// * 针对用户生成或数据驱动内容的可配置上限：层级深度、单个状态的子状态数量以及层级状态机的总数。
+///   新的 [`HsmStateMachine`] 插入时进行校验，超出限制时报告 [`StateMachineError::LimitExceeded`] 并插入 [`Paused`]，
+///   避免异常资源造成过深的退出级联或过大的缓冲。`None` 表示不限制，默认全部不限制。
+/// - Configurable caps for user-generated or data-driven content: hierarchy depth, sub-states per state and the total
+///   number of hierarchical state machines. New [`HsmStateMachine`]s are validated on insert; when a limit is exceeded,
+///   [`StateMachineError::LimitExceeded`] is reported and [`Paused`] is inserted, so pathological assets cannot drive deep
+///   exit cascades or blow up buffers. `None` means unlimited, which is the default for all of them.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .insert_resource(StateMachineLimits {
+///         max_depth: Some(16),
+///         max_children: Some(64),
+///         max_machines: Some(1024),
+///     });
+/// # }
+/// ```
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StateMachineLimits {
+    /// 最大层级深度，根状态的深度为 0
+    ///
+    /// Maximum hierarchy depth, the root state being at depth 0
+    pub max_depth: Option<usize>,
+    /// 单个状态的最大子状态数量
+    ///
+    /// Maximum number of sub-states per state
+    pub max_children: Option<usize>,
+    /// 层级状态机的最大数量
+    ///
+    /// Maximum number of hierarchical state machines
+    pub max_machines: Option<usize>,
+}
+
+/// 超出的限制
+///
+/// The limit that was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateMachineLimit {
+    /// 状态 `state` 的深度超过了 `max`
+    ///
+    /// The depth of `state` exceeds `max`
+    Depth { state: Entity, max: usize },
+    /// 状态 `state` 拥有 `count` 个子状态，超过了 `max`
+    ///
+    /// `state` has `count` sub-states, more than `max`
+    Children {
+        state: Entity,
+        count: usize,
+        max: usize,
+    },
+    /// 层级状态机共有 `count` 个，超过了 `max`
+    ///
+    /// There are `count` hierarchical state machines, more than `max`
+    Machines { count: usize, max: usize },
+}
+
+impl fmt::Display for StateMachineLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateMachineLimit::Depth { state, max } => {
+                write!(
+                    f,
+                    "state {:?} is deeper than the maximum depth {}",
+                    state, max
+                )
+            }
+            StateMachineLimit::Children { state, count, max } => write!(
+                f,
+                "state {:?} has {} sub-states, more than the maximum {}",
+                state, count, max
+            ),
+            StateMachineLimit::Machines { count, max } => write!(
+                f,
+                "{} hierarchical state machines exist, more than the maximum {}",
+                count, max
+            ),
+        }
+    }
+}
+
+impl StateMachineLimits {
+    /// 检查状态树是否满足深度与子状态数量的限制
+    ///
+    /// Check whether a state tree respects the depth and sub-state limits
+    pub fn check_tree(&self, state_tree: &StateTree) -> Result<(), StateMachineLimit> {
+        for state in state_tree.iter() {
+            if let Some(max) = self.max_children {
+                let count = state_tree.get_sub_states(state).map_or(0, <[Entity]>::len);
+                if count > max {
+                    return Err(StateMachineLimit::Children { state, count, max });
+                }
+            }
+            if let Some(max) = self.max_depth
+                && state_tree.path_iter(state).nth(max).is_some()
+            {
+                return Err(StateMachineLimit::Depth { state, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查状态机是否满足所有限制
+    ///
+    /// Check whether a state machine respects every limit
+    pub fn check(&self, world: &mut World, state_machine: Entity) -> Result<(), StateMachineLimit> {
+        if let Some(max) = self.max_machines {
+            let count = world
+                .query_filtered::<(), With<HsmStateMachine>>()
+                .iter(world)
+                .count();
+            if count > max {
+                return Err(StateMachineLimit::Machines { count, max });
+            }
+        }
+        let state_tree = world
+            .get::<HsmStateMachine>(state_machine)
+            .and_then(|hsm| world.get::<StateTree>(hsm.state_tree()));
+        match state_tree {
+            Some(state_tree) => self.check_tree(state_tree),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn on_insert_state_machine(
+        insert: On<Insert, HsmStateMachine>,
+        mut commands: Commands,
+    ) {
+        let state_machine = insert.entity;
+        commands.queue(move |world: &mut World| {
+            let Some(limits) = world.get_resource::<Self>().copied() else {
+                return;
+            };
+            if limits == Self::default() {
+                return;
+            }
+            if let Err(limit) = limits.check(world, state_machine) {
+                world.entity_mut(state_machine).insert(Paused);
+                StateMachineError::LimitExceeded {
+                    state_machine,
+                    limit,
+                }
+                .report(world);
+            }
+        });
+    }
+}
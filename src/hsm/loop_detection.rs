@@ -0,0 +1,128 @@
+use bevy::{ecs::world::DeferredWorld, platform::collections::HashMap, prelude::*};
+
+use crate::markers::Paused;
+
+/// # 转换循环检测\Transition Loop Detection
+/// * 统计每个状态机在 `window` 帧内进入状态的次数，超过 `limit` 时暂停该状态机（插入 [`Paused`]）
+///   并触发 [`HsmLoopDetected`]，用于诊断进入/退出条件同时成立造成的来回转换。
+/// - Counts how many states each state machine enters within `window` frames; once `limit` is exceeded the machine is
+///   paused (by inserting [`Paused`]) and [`HsmLoopDetected`] is triggered, diagnosing ping-pong transitions caused by
+///   enter and exit conditions holding at the same time.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .insert_resource(TransitionLoopDetection::new(20, 10))
+///     .add_observer(|on: On<HsmLoopDetected>| {
+///         warn!("{:?} is cycling through {:?}", on.state_machine, on.states);
+///     });
+/// # }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct TransitionLoopDetection {
+    /// 窗口内允许的最大进入次数
+    ///
+    /// Maximum number of enters allowed within the window
+    pub limit: u32,
+    /// 窗口长度（帧）
+    ///
+    /// Window length in frames
+    pub window: u32,
+    frame: u64,
+    counters: HashMap<Entity, LoopCounter>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LoopCounter {
+    since: u64,
+    count: u32,
+    states: Vec<Entity>,
+}
+
+impl Default for TransitionLoopDetection {
+    fn default() -> Self {
+        Self::new(100, 1)
+    }
+}
+
+impl TransitionLoopDetection {
+    pub fn new(limit: u32, window: u32) -> Self {
+        Self {
+            limit,
+            window: window.max(1),
+            frame: 0,
+            counters: HashMap::default(),
+        }
+    }
+
+    /// 推进帧计数并清理已过期的计数器
+    ///
+    /// Advances the frame counter and drops expired counters
+    pub(crate) fn tick(mut detection: ResMut<Self>) {
+        detection.frame += 1;
+        let detection = &mut *detection;
+        let (frame, window) = (detection.frame, u64::from(detection.window.max(1)));
+        detection
+            .counters
+            .retain(|_, counter| frame - counter.since < window);
+    }
+
+    /// 记录状态机进入了一个状态，超过限制时暂停状态机并触发 [`HsmLoopDetected`]
+    ///
+    /// Records that a state machine entered a state, pausing it and triggering [`HsmLoopDetected`] once over the limit
+    pub(crate) fn record(world: &mut DeferredWorld, state_machine: Entity, state: Entity) {
+        let Some(mut detection) = world.get_resource_mut::<Self>() else {
+            return;
+        };
+        let detection = &mut *detection;
+        let (frame, limit) = (detection.frame, detection.limit);
+        let window = u64::from(detection.window.max(1));
+        let counter = detection
+            .counters
+            .entry(state_machine)
+            .or_insert_with(|| LoopCounter {
+                since: frame,
+                ..default()
+            });
+        if frame - counter.since >= window {
+            *counter = LoopCounter {
+                since: frame,
+                ..default()
+            };
+        }
+        counter.count += 1;
+        if !counter.states.contains(&state) {
+            counter.states.push(state);
+        }
+        if counter.count <= limit {
+            return;
+        }
+
+        let states = detection
+            .counters
+            .remove(&state_machine)
+            .map(|counter| counter.states)
+            .unwrap_or_default();
+        let mut commands = world.commands();
+        commands.entity(state_machine).insert(Paused);
+        commands.trigger(HsmLoopDetected {
+            state_machine,
+            states,
+        });
+    }
+}
+
+/// # 检测到转换循环\Transition Loop Detected
+/// * 状态机超过 [`TransitionLoopDetection`] 的限制而被暂停时触发，`states` 为窗口内进入过的状态（按首次进入顺序）。
+/// - Triggered when a state machine exceeds the limit of [`TransitionLoopDetection`] and gets paused; `states` lists the
+///   states entered within the window, in order of first entry.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct HsmLoopDetected {
+    #[event_target]
+    pub state_machine: Entity,
+    pub states: Vec<Entity>,
+}
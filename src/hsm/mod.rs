@@ -1,15 +1,49 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
 
 use crate::hsm::transition_strategy::{ExitTransitionBehavior, StateTransitionStrategy};
 
+pub mod analysis;
+pub mod bundles;
+pub mod checkpoints;
+pub mod deferred_links;
+pub mod diff;
+pub mod disabled;
+pub mod emit;
 pub mod event;
+pub mod event_log;
+pub mod explain;
+pub mod fallthrough;
+pub mod golden;
+pub mod guard_retry;
 pub mod guards;
 #[cfg(feature = "history")]
 pub mod history;
+pub mod hooks;
+pub mod latch;
+pub mod limits;
+pub mod loop_detection;
+pub mod name_index;
+pub mod payload;
+pub mod phase_schedules;
+pub mod pipeline;
+pub mod priority;
+pub mod requester;
+pub mod requirements;
+pub mod sleep;
+pub mod start_selector;
 pub mod state_lifecycle;
 pub mod state_machine;
 pub mod state_tree;
+#[cfg(feature = "stats")]
+pub mod stats;
+pub mod supervisor;
 pub mod transition_strategy;
+pub mod transitions;
+pub mod validation;
+pub mod vars;
 
 /// # HSM 状态
 /// * 一个组件，用于将一个实体标识为层级状态机（HSM）中的一个状态，并配置其行为。
@@ -20,7 +54,15 @@ pub mod transition_strategy;
 /// * A component that identifies an entity as a state within a Hierarchical State Machine (HSM) and configures its behavior.
 ///
 /// Unlike the simple `FsmState`, `HsmState` contains key configurations that define how it interacts within the hierarchy.
+///
+/// # 必需组件\Required Components
+/// * [`Name`]：未命名的状态以实体编号命名，便于诊断输出。
+/// - [`Name`]: unnamed states are named after their entity id for diagnostics.
+/// * [`StatePriority`](priority::StatePriority)：默认为 0。
+/// - [`StatePriority`](priority::StatePriority): 0 by default.
 #[derive(Component, Hash, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[require(Name, priority::StatePriority)]
+#[component(on_add = Self::on_add)]
 pub struct HsmState {
     /// 定义了当转换到这个状态时所采用的策略（例如，是浅进入、深进入还是恢复历史状态）。
     /// Defines the strategy to be used when transitioning *into* this state (e.g., shallow, deep, or history).
@@ -35,6 +77,14 @@ pub struct HsmState {
 }
 
 impl HsmState {
+    fn on_add(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        if let Some(mut name) = world.get_mut::<Name>(entity)
+            && name.as_str().is_empty()
+        {
+            name.set(entity.to_string());
+        }
+    }
+
     pub fn with(strategy: StateTransitionStrategy, behavior: ExitTransitionBehavior) -> Self {
         Self {
             strategy,
@@ -0,0 +1,99 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use smallvec::SmallVec;
+
+use crate::hsm::{HsmState, state_machine::HsmStateMachine, state_tree::StateTree};
+
+/// # 状态名称索引\State Name Index
+/// * 由插件维护的状态名称（[`Name`]）与状态实体之间的双向索引，在状态生成、重命名与销毁时自动更新，
+///   使按名称查找状态无需每次遍历所有 [`Name`]。
+/// - A two-way index between state names ([`Name`]) and state entities maintained by the plugin, updated automatically
+///   when states are spawned, renamed or despawned, so that looking a state up by name no longer scans every [`Name`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn find_chase(world: &World, state_machine: Entity) -> Option<Entity> {
+///     HsmNameIndex::find(world, state_machine, "Chase")
+/// }
+/// ```
+#[derive(Resource, Default, Debug)]
+pub struct HsmNameIndex {
+    states: HashMap<String, SmallVec<[Entity; 1]>>,
+    names: HashMap<Entity, String>,
+}
+
+impl HsmNameIndex {
+    /// 获取拥有该名称的所有状态
+    ///
+    /// Get every state with the name
+    pub fn states_named(&self, name: &str) -> &[Entity] {
+        self.states
+            .get(name)
+            .map_or(&[], |states| states.as_slice())
+    }
+
+    /// 获取状态的名称
+    ///
+    /// Get the name of a state
+    pub fn name_of(&self, state: Entity) -> Option<&str> {
+        self.names.get(&state).map(String::as_str)
+    }
+
+    /// 在状态机的状态树中按名称查找状态
+    ///
+    /// Find a state by name within the state tree of a state machine
+    pub fn find(world: &World, state_machine: Entity, name: &str) -> Option<Entity> {
+        let state_tree = world
+            .get::<HsmStateMachine>(state_machine)
+            .and_then(|hsm| world.get::<StateTree>(hsm.state_tree()))?;
+        world
+            .get_resource::<Self>()?
+            .states_named(name)
+            .iter()
+            .copied()
+            .find(|&state| state_tree.contains(state))
+    }
+
+    fn insert(&mut self, state: Entity, name: &str) {
+        self.remove(state);
+        self.states.entry(name.to_owned()).or_default().push(state);
+        self.names.insert(state, name.to_owned());
+    }
+
+    fn remove(&mut self, state: Entity) {
+        let Some(name) = self.names.remove(&state) else {
+            return;
+        };
+        if let Some(states) = self.states.get_mut(&name) {
+            states.retain(|entity| *entity != state);
+            if states.is_empty() {
+                self.states.remove(&name);
+            }
+        }
+    }
+
+    pub(crate) fn on_insert_name(
+        insert: On<Insert, Name>,
+        query: Query<&Name, With<HsmState>>,
+        mut index: ResMut<Self>,
+    ) {
+        if let Ok(name) = query.get(insert.entity) {
+            index.insert(insert.entity, name.as_str());
+        }
+    }
+
+    pub(crate) fn on_insert_state(
+        insert: On<Insert, HsmState>,
+        query: Query<&Name, With<HsmState>>,
+        mut index: ResMut<Self>,
+    ) {
+        if let Ok(name) = query.get(insert.entity) {
+            index.insert(insert.entity, name.as_str());
+        }
+    }
+
+    pub(crate) fn on_remove(remove: On<Remove, (Name, HsmState)>, mut index: ResMut<Self>) {
+        index.remove(remove.entity);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{any::Any, sync::Arc};
+
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
+
+use crate::{
+    context::ActionContext,
+    hsm::state_lifecycle::{CurrentLifecycle, StateLifecycle},
+};
+
+/// # 转换载荷\Transition Payload
+/// * 挂载在状态机上：转换的发起方（条件、动作或外部请求）为目标状态附加一个值，例如冲刺的目标位置，
+///   目标状态的进入动作再通过 [`HsmPayload`] 按上下文读取。
+/// - Lives on the state machine: the side starting a transition (a condition, an action or an external
+///   request) attaches a value for the target state, e.g. the position to dash to, which the target state's enter
+///   actions then read by context through [`HsmPayload`].
+/// * 载荷一直保留到状态机停留在（[`StateLifecycle::Update`]）另一个状态或退出目标状态为止，因此进入与更新动作都能读取。
+///   条件只应在返回 `true` 时附加载荷，否则载荷会留到状态机下一次稳定为止。
+/// - A payload is kept until the machine settles ([`StateLifecycle::Update`]) in another state or exits the target, so
+///   both enter and update actions can read it. Conditions should only attach a payload when returning `true`,
+///   otherwise it lingers until the machine next settles.
+/// * 同一目标的新载荷会替换旧载荷。
+/// - A new payload for the same target replaces the old one.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Debug, Clone, Copy)]
+/// struct DashTo(Vec2);
+///
+/// fn can_dash(context: In<GuardContext>, mut commands: Commands) -> bool {
+///     commands
+///         .entity(context.state_machine)
+///         .attach_payload(context.to_state(), DashTo(Vec2::new(4.0, 2.0)));
+///     true
+/// }
+///
+/// fn dash(context: In<ActionContext>, payload: HsmPayload) {
+///     if let Some(DashTo(target)) = payload.get::<DashTo>(&context) {
+///         info!("dashing to {target}");
+///     }
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .register_guard("can_dash", can_dash)
+///     .register_action("dash", dash);
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone)]
+pub struct HsmTransitionPayload {
+    payloads: HashMap<Entity, Arc<dyn Any + Send + Sync>>,
+}
+
+impl HsmTransitionPayload {
+    /// 为目标状态 `target` 附加载荷
+    ///
+    /// Attach a payload for the target state `target`
+    pub fn attach<T: Any + Send + Sync>(&mut self, target: Entity, payload: T) {
+        self.payloads.insert(target, Arc::new(payload));
+    }
+
+    pub(crate) fn attach_shared(&mut self, target: Entity, payload: Arc<dyn Any + Send + Sync>) {
+        self.payloads.insert(target, payload);
+    }
+
+    /// 获取附加给状态 `state` 的载荷，类型不符时返回 `None`
+    ///
+    /// Get the payload attached for `state`, `None` if its type does not match
+    pub fn get<T: Any>(&self, state: Entity) -> Option<&T> {
+        self.payloads.get(&state)?.downcast_ref()
+    }
+
+    /// 是否有附加给状态 `state` 的载荷
+    ///
+    /// Whether a payload is attached for `state`
+    pub fn contains(&self, state: Entity) -> bool {
+        self.payloads.contains_key(&state)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+
+    /// 状态机停留在某个状态时丢弃其他状态的载荷，退出状态时丢弃该状态的载荷
+    ///
+    /// Drop the payloads of other states once the machine settles in a state, and a state's payload once it exits
+    pub(crate) fn expire(
+        mut commands: Commands,
+        mut query: Query<(Entity, &CurrentLifecycle, &mut Self), Changed<CurrentLifecycle>>,
+    ) {
+        for (state_machine, current, mut payload) in query.iter_mut() {
+            match current.lifecycle {
+                StateLifecycle::Update => {
+                    payload.payloads.retain(|&state, _| state == current.state)
+                }
+                StateLifecycle::Exit => {
+                    payload.payloads.remove(&current.state);
+                }
+                StateLifecycle::Enter => {}
+            }
+            if payload.is_empty() {
+                commands.entity(state_machine).remove::<Self>();
+            }
+        }
+    }
+}
+
+/// # 载荷读取\Payload Access
+/// * 按动作上下文读取 [`HsmTransitionPayload`] 中附加给该状态的载荷。
+/// - Reads the payload attached for a state in [`HsmTransitionPayload`] by action context.
+#[derive(SystemParam)]
+pub struct HsmPayload<'w, 's> {
+    query: Query<'w, 's, &'static HsmTransitionPayload>,
+}
+
+impl HsmPayload<'_, '_> {
+    /// 附加给动作所属状态的载荷
+    ///
+    /// The payload attached for the state the action belongs to
+    pub fn get<T: Any>(&self, context: &ActionContext) -> Option<&T> {
+        self.query
+            .get(context.state_machine)
+            .ok()?
+            .get(context.state())
+    }
+}
+
+/// # 载荷附加扩展\Payload Attach Extension
+/// * 在状态机实体上为目标状态附加载荷，按需插入 [`HsmTransitionPayload`]。
+/// - Attaches a payload for a target state on a state machine entity, inserting an [`HsmTransitionPayload`] as needed.
+pub trait TransitionPayloadExt {
+    fn attach_payload<T: Any + Send + Sync>(&mut self, target: Entity, payload: T) -> &mut Self;
+}
+
+impl TransitionPayloadExt for EntityWorldMut<'_> {
+    fn attach_payload<T: Any + Send + Sync>(&mut self, target: Entity, payload: T) -> &mut Self {
+        self.entry::<HsmTransitionPayload>()
+            .or_default()
+            .get_mut()
+            .attach(target, payload);
+        self
+    }
+}
+
+impl TransitionPayloadExt for EntityCommands<'_> {
+    fn attach_payload<T: Any + Send + Sync>(&mut self, target: Entity, payload: T) -> &mut Self {
+        self.queue(move |mut entity: EntityWorldMut| {
+            entity.attach_payload(target, payload);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_downcast() {
+        let target = Entity::from_raw_u32(1).unwrap();
+        let mut payload = HsmTransitionPayload::default();
+        payload.attach(target, 3u32);
+        assert_eq!(payload.get::<u32>(target), Some(&3));
+        assert_eq!(payload.get::<i32>(target), None);
+        assert!(!payload.contains(Entity::PLACEHOLDER));
+
+        payload.attach(target, "replaced");
+        assert_eq!(payload.get::<&str>(target), Some(&"replaced"));
+    }
+}
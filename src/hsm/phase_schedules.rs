@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+
+use bevy::{
+    ecs::{schedule::ScheduleLabel, system::SystemParam},
+    prelude::*,
+};
+
+use crate::context::ActionContext;
+
+/// # 状态进入调度\State Enter Schedule
+/// * 名为 `.0` 的状态进入时（在 [`AfterEnterSystem`](crate::prelude::AfterEnterSystem) 之后）运行的调度，
+///   可以直接用 `app.add_systems` 添加普通系统，通过 [`HsmPhaseContext`] 获取上下文，无需注册一次性系统并在组件中引用其名称。
+/// - The schedule run when the state named `.0` is entered (after [`AfterEnterSystem`](crate::prelude::AfterEnterSystem)).
+///   Plain systems can be added with `app.add_systems` and read the context through [`HsmPhaseContext`], instead of
+///   registering disposable systems and naming them in components.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn attack(context: HsmPhaseContext, query: Query<&Name>) {
+///     if let Ok(name) = query.get(context.service_target) {
+///         info!("{} attacks", name);
+///     }
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .add_systems(HsmEnterOf::new("Attack"), attack);
+/// # }
+/// ```
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HsmEnterOf(pub Cow<'static, str>);
+
+impl HsmEnterOf {
+    pub fn new(state: impl Into<Cow<'static, str>>) -> Self {
+        Self(state.into())
+    }
+}
+
+/// # 状态退出调度\State Exit Schedule
+/// * 名为 `.0` 的状态退出时（在 [`BeforeExitSystem`](crate::prelude::BeforeExitSystem) 之后）运行的调度，见 [`HsmEnterOf`]。
+/// - The schedule run when the state named `.0` is exited (after [`BeforeExitSystem`](crate::prelude::BeforeExitSystem)),
+///   see [`HsmEnterOf`].
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HsmExitOf(pub Cow<'static, str>);
+
+impl HsmExitOf {
+    pub fn new(state: impl Into<Cow<'static, str>>) -> Self {
+        Self(state.into())
+    }
+}
+
+/// 正在运行的阶段调度的上下文，嵌套运行时后进先出
+///
+/// The contexts of the phase schedules being run, last in first out when nested
+#[derive(Resource, Debug, Default)]
+pub(crate) struct PhaseContextStack(Vec<ActionContext>);
+
+/// # 阶段上下文\Phase Context
+/// * 在 [`HsmEnterOf`] 与 [`HsmExitOf`] 调度中获取正在进入或退出的状态的上下文，可直接解引用为 [`ActionContext`]。
+///   在这些调度之外读取上下文会 panic。
+/// - Gives the context of the state being entered or exited within the [`HsmEnterOf`] and [`HsmExitOf`] schedules, and
+///   dereferences to [`ActionContext`]. Reading the context outside of them panics.
+#[derive(SystemParam)]
+pub struct HsmPhaseContext<'w> {
+    stack: Res<'w, PhaseContextStack>,
+}
+
+impl HsmPhaseContext<'_> {
+    /// 当前阶段的上下文
+    ///
+    /// The context of the current phase
+    pub fn context(&self) -> ActionContext {
+        **self
+    }
+}
+
+impl std::ops::Deref for HsmPhaseContext<'_> {
+    type Target = ActionContext;
+
+    fn deref(&self) -> &Self::Target {
+        self.stack
+            .0
+            .last()
+            .expect("HsmPhaseContext is only available within HsmEnterOf and HsmExitOf schedules")
+    }
+}
+
+/// 如果存在以状态名称命名的阶段调度，则带着上下文运行它
+///
+/// Run the phase schedule named after the state, if any, with the context available
+pub(crate) fn run_phase_schedule<L: ScheduleLabel>(
+    label: impl FnOnce(Cow<'static, str>) -> L,
+    context: ActionContext,
+) -> impl FnOnce(&mut World) {
+    move |world: &mut World| {
+        let Some(name) = world.get::<Name>(context.state()) else {
+            return;
+        };
+        let label = label(Cow::Owned(name.as_str().to_owned()));
+        if !world
+            .get_resource::<Schedules>()
+            .is_some_and(|schedules| schedules.contains(label.intern()))
+        {
+            return;
+        }
+        world
+            .get_resource_or_init::<PhaseContextStack>()
+            .0
+            .push(context);
+        let _ = world.try_run_schedule(label);
+        world.resource_mut::<PhaseContextStack>().0.pop();
+    }
+}
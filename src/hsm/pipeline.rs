@@ -0,0 +1,154 @@
+use bevy::{ecs::world::DeferredWorld, platform::collections::HashMap, prelude::*};
+
+use crate::hsm::{
+    state_lifecycle::LifecycleQueue,
+    state_machine::HsmStateMachine,
+    transition_strategy::{
+        CheckOnTransitionStates, handle_enter_transitions, handle_exit_transitions,
+    },
+    transitions::HsmTransitions,
+};
+
+/// # 转换流水线\Transition Pipeline
+/// * 控制单个状态机的一次完整转换跨越多少帧。默认每帧只进行一次转换评估，从叶状态 A 到叶状态 B 需要逐层跨越多帧。
+///   `passes` 大于 1 时，同一转换调度内会对该状态机重复评估（有界循环），使退出、进入与阶段推进在同一帧内完成；
+///   `max_phases_per_frame` 则限制每帧最多推进多少个生命周期阶段（[`StateLifecycle`](crate::prelude::StateLifecycle)），
+///   其余阶段延后到之后的帧处理。
+/// - Controls how many frames a full transition of a single state machine spans. By default the transitions are evaluated
+///   once per frame, so going from leaf A to leaf B crosses the hierarchy one level per frame. With `passes` greater than
+///   1 the machine is evaluated again within the same transition schedule (a bounded loop), so exits, enters and phase
+///   advancement complete in the same frame; `max_phases_per_frame` instead caps how many lifecycle phases
+///   ([`StateLifecycle`](crate::prelude::StateLifecycle)) advance per frame, deferring the rest to later frames.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn collapse(mut commands: Commands, state_machine: Single<Entity, With<HsmStateMachine>>) {
+///     commands
+///         .entity(*state_machine)
+///         .insert(TransitionPipeline::collapsed(8));
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransitionPipeline {
+    /// 每帧的最大转换评估次数，至少为 1
+    ///
+    /// Maximum number of transition passes per frame, at least 1
+    pub passes: u32,
+    /// 每帧最多推进的生命周期阶段数量，`None` 表示不限制
+    ///
+    /// Maximum number of lifecycle phases advanced per frame, `None` meaning unlimited
+    pub max_phases_per_frame: Option<u32>,
+}
+
+impl Default for TransitionPipeline {
+    fn default() -> Self {
+        Self {
+            passes: 1,
+            max_phases_per_frame: None,
+        }
+    }
+}
+
+impl TransitionPipeline {
+    /// 每帧最多进行 `passes` 次转换评估
+    ///
+    /// At most `passes` transition passes per frame
+    pub fn collapsed(passes: u32) -> Self {
+        Self {
+            passes: passes.max(1),
+            max_phases_per_frame: None,
+        }
+    }
+
+    /// 每帧最多推进 `phases` 个生命周期阶段
+    ///
+    /// At most `phases` lifecycle phases advanced per frame
+    pub fn throttled(phases: u32) -> Self {
+        Self {
+            passes: 1,
+            max_phases_per_frame: Some(phases.max(1)),
+        }
+    }
+
+    /// 为需要的状态机追加转换评估，直到没有状态机继续推进或达到各自的 `passes`
+    ///
+    /// Runs extra transition passes for the machines asking for them, until none advances or each reaches its `passes`
+    pub(crate) fn run_extra_passes(world: &mut World) {
+        let mut stalled = Vec::new();
+        for pass in 1.. {
+            let mut query = world.query::<(Entity, &HsmStateMachine, &TransitionPipeline)>();
+            let candidates = world
+                .resource::<CheckOnTransitionStates>()
+                .iter()
+                .filter_map(|&state_machine| query.get(world, state_machine).ok())
+                .filter(|(state_machine, _, pipeline)| {
+                    pipeline.passes > pass && !stalled.contains(state_machine)
+                })
+                .map(|(state_machine, hsm, _)| (state_machine, hsm.curr_state_id()))
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                return;
+            }
+
+            let mut others = std::mem::take(&mut **world.resource_mut::<CheckOnTransitionStates>());
+            others.retain(|state_machine| candidates.iter().all(|(id, _)| id != state_machine));
+            world
+                .resource_mut::<CheckOnTransitionStates>()
+                .extend(candidates.iter().map(|(id, _)| *id));
+
+            let _ = world.run_system_cached(HsmTransitions::handle_automatic_transitions);
+            let _ = world.run_system_cached(handle_enter_transitions);
+            let _ = world.run_system_cached(handle_exit_transitions);
+            LifecycleQueue::process(world);
+
+            world
+                .resource_mut::<CheckOnTransitionStates>()
+                .extend(others);
+            for (state_machine, state) in candidates {
+                let advanced = world
+                    .get::<HsmStateMachine>(state_machine)
+                    .is_some_and(|hsm| hsm.curr_state_id() != state);
+                if !advanced {
+                    stalled.push(state_machine);
+                }
+            }
+        }
+    }
+}
+
+/// 本帧各状态机已推进的生命周期阶段数量
+///
+/// Lifecycle phases each state machine advanced this frame
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub(crate) struct PipelinePhaseCounts(HashMap<Entity, u32>);
+
+impl PipelinePhaseCounts {
+    /// 判断状态机本帧能否再推进一个阶段，能则计数
+    ///
+    /// Whether the state machine may advance one more phase this frame, counting it if so
+    pub(crate) fn admit(world: &mut DeferredWorld, state_machine: Entity) -> bool {
+        let Some(max) = world
+            .get::<TransitionPipeline>(state_machine)
+            .and_then(|pipeline| pipeline.max_phases_per_frame)
+        else {
+            return true;
+        };
+        let Some(mut counts) = world.get_resource_mut::<Self>() else {
+            return true;
+        };
+        let count = counts.entry(state_machine).or_default();
+        if *count >= max {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    pub(crate) fn reset(mut counts: ResMut<Self>) {
+        if !counts.is_empty() {
+            counts.clear();
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// # 状态优先级\State Priority
+/// * 决定子状态进入条件的检查顺序：优先级高的子状态先检查，相同优先级（未设置时为 0）按声明顺序检查。
+///   排序发生在每次遍历时，因此运行时修改优先级会立即生效，无需重建状态树。
+///   状态节点设置了自定义 [`TraversalStrategy`](crate::prelude::TraversalStrategy) 时，以遍历策略为准，
+///   除非父状态使用 [`EnterSelection::Best`]。
+/// - Decides the order in which the enter conditions of sub-states are checked: higher priorities go first, and equal
+///   priorities (0 when unset) keep their declaration order. Sorting happens on every traversal, so changing a priority at
+///   runtime takes effect immediately without rebuilding the state tree. When the node has a custom
+///   [`TraversalStrategy`](crate::prelude::TraversalStrategy), the strategy wins unless the super state uses
+///   [`EnterSelection::Best`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, flee: Entity) {
+/// commands.entity(flee).insert(StatePriority(10));
+/// # }
+/// fn panic_mode(mut query: Query<&mut StatePriority>) {
+///     for mut priority in query.iter_mut() {
+///         priority.0 += 1;
+///     }
+/// }
+/// ```
+#[derive(
+    Component, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deref, DerefMut,
+)]
+pub struct StatePriority(pub i32);
+
+impl StatePriority {
+    /// 获取状态的优先级，未设置时为 0
+    ///
+    /// Get the priority of a state, 0 when unset
+    pub fn of(world: &World, state: Entity) -> i32 {
+        world
+            .get::<StatePriority>(state)
+            .map_or(0, |priority| priority.0)
+    }
+
+    /// 按优先级从高到低稳定排序
+    ///
+    /// Stable sort from highest to lowest priority
+    pub fn sort(world: &World, states: &mut [Entity]) {
+        states.sort_by_key(|&state| std::cmp::Reverse(Self::of(world, state)));
+    }
+}
+
+/// # 进入选择\Enter Selection
+/// * 放在父状态上，决定多个子状态的进入条件同时成立时选择哪一个。
+///   默认的 [`EnterSelection::First`] 按遍历顺序检查，遇到第一个成立的子状态即停止，不再运行后续（可能昂贵的）条件；
+///   [`EnterSelection::Best`] 会检查所有子状态，并在成立的子状态中选择优先级最高者，优先级相同时按遍历顺序。
+///   后者适用于自定义 [`TraversalStrategy`](crate::prelude::TraversalStrategy) 打乱了顺序、但仍希望优先级决定结果的情况。
+/// - Placed on a super state, decides which sub-state is picked when the enter conditions of several hold at once. The
+///   default [`EnterSelection::First`] checks them in traversal order and stops at the first one that passes, skipping the
+///   remaining (possibly expensive) conditions. [`EnterSelection::Best`] checks every sub-state and picks the passing one
+///   with the highest priority, falling back to traversal order on ties. The latter suits a custom
+///   [`TraversalStrategy`](crate::prelude::TraversalStrategy) that shuffles the order while priority should still decide.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, combat: Entity) {
+/// commands.entity(combat).insert(EnterSelection::Best);
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnterSelection {
+    #[default]
+    First,
+    Best,
+}
+
+impl EnterSelection {
+    /// 获取状态的进入选择方式，未设置时为 [`EnterSelection::First`]
+    ///
+    /// Get the enter selection of a state, [`EnterSelection::First`] when unset
+    pub fn of(world: &World, state: Entity) -> Self {
+        world.get::<Self>(state).copied().unwrap_or_default()
+    }
+}
+
+/// # 偏好上次的子状态\Prefer Last Child
+/// * 放在父状态上，让状态机记住从该父状态最近进入的子状态，并在下次选择子状态时优先检查它；
+///   其进入条件不成立时仍按常规顺序检查其余子状态。无需启用完整的历史模式，
+///   即可避免 [`Parallel`](crate::prelude::StateTransitionStrategy::Parallel) + [`Rebirth`](crate::prelude::ExitTransitionBehavior::Rebirth)
+///   每次往返后在多个同样成立的子状态之间来回切换。
+///   使用 [`EnterSelection::Best`] 时，记住的子状态在优先级相同时胜出。
+/// - Placed on a super state, makes the state machine remember the sub-state last entered from it and check that one first
+///   the next time a sub-state is selected; when its enter condition fails the remaining sub-states are checked in the
+///   usual order. This avoids oscillating between equally valid sub-states on every
+///   [`Parallel`](crate::prelude::StateTransitionStrategy::Parallel) + [`Rebirth`](crate::prelude::ExitTransitionBehavior::Rebirth)
+///   round trip without enabling full history mode. With [`EnterSelection::Best`], the remembered sub-state wins ties.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, patrol: Entity) {
+/// commands.entity(patrol).insert(PreferLastChild);
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreferLastChild;
+
+/// # 上次的子状态\Last Children
+/// * 维护在状态机实体上，按父状态记录最近进入的子状态，仅记录带有 [`PreferLastChild`] 的父状态。
+/// - Kept on the state machine entity, recording the sub-state last entered per super state, only for super states with
+///   [`PreferLastChild`].
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LastChildren(HashMap<Entity, Entity>);
+
+impl LastChildren {
+    /// 获取从父状态最近进入的子状态
+    ///
+    /// Get the sub-state last entered from a super state
+    pub fn get(&self, super_state: Entity) -> Option<Entity> {
+        self.0.get(&super_state).copied()
+    }
+
+    /// 忘记父状态的记录，下次按常规顺序选择
+    ///
+    /// Forget the record of a super state, selecting in the usual order next time
+    pub fn forget(&mut self, super_state: Entity) -> Option<Entity> {
+        self.0.remove(&super_state)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// 若父状态带有 [`PreferLastChild`]，记录进入的子状态
+    ///
+    /// Record the entered sub-state if the super state has [`PreferLastChild`]
+    pub(crate) fn record(
+        world: &mut World,
+        state_machine: Entity,
+        super_state: Entity,
+        sub_state: Entity,
+    ) {
+        if world.get::<PreferLastChild>(super_state).is_none() {
+            return;
+        }
+        let Ok(mut entity) = world.get_entity_mut(state_machine) else {
+            return;
+        };
+        entity
+            .entry::<Self>()
+            .or_default()
+            .get_mut()
+            .0
+            .insert(super_state, sub_state);
+    }
+
+    /// 将记住的子状态移到候选列表最前面
+    ///
+    /// Move the remembered sub-state to the front of the candidates
+    pub(crate) fn prefer(
+        world: &World,
+        state_machine: Entity,
+        super_state: Entity,
+        candidates: &mut [Entity],
+    ) {
+        if world.get::<PreferLastChild>(super_state).is_none() {
+            return;
+        }
+        let Some(last) = world
+            .get::<Self>(state_machine)
+            .and_then(|children| children.get(super_state))
+        else {
+            return;
+        };
+        if let Some(index) = candidates.iter().position(|&state| state == last) {
+            candidates[..=index].rotate_right(1);
+        }
+    }
+}
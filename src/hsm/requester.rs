@@ -0,0 +1,294 @@
+//! # 转换请求\Transition Requests
+//!
+//! 外部规划器按优先级提交目标状态，每个状态机每次处理接受一个可执行的请求。
+//!
+//! External planners submit target states by priority, and each state machine accepts one feasible request per pass.
+
+use std::{any::Any, borrow::Cow, sync::Arc};
+
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
+
+use crate::{
+    hsm::{
+        event::HsmTrigger, payload::HsmTransitionPayload, state_machine::HsmStateMachine,
+        state_tree::StateTree,
+    },
+    markers::{Paused, Terminated},
+};
+
+/// 转换请求的编号
+///
+/// Id of a transition request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TransitionRequestId(u64);
+
+/// 转换请求被拒绝的原因
+///
+/// Why a transition request was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestRejection {
+    /// 同一帧内有更高优先级的请求被接受
+    ///
+    /// A higher-priority request submitted in the same frame was accepted
+    Superseded,
+    /// 状态机不存在、已暂停或已终止
+    ///
+    /// The state machine is missing, paused or terminated
+    Unavailable,
+    /// 目标状态不在状态机的状态树中
+    ///
+    /// The target state is not in the state machine's tree
+    NotInTree,
+    /// 状态机已处于目标状态
+    ///
+    /// The state machine is already in the target state
+    AlreadyInState,
+}
+
+impl std::fmt::Display for RequestRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestRejection::Superseded => write!(f, "superseded by a higher-priority request"),
+            RequestRejection::Unavailable => {
+                write!(f, "state machine is missing, paused or terminated")
+            }
+            RequestRejection::NotInTree => write!(f, "target state is not in the state tree"),
+            RequestRejection::AlreadyInState => write!(f, "already in the target state"),
+        }
+    }
+}
+
+/// 转换请求的结果
+///
+/// Outcome of a transition request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestOutcome {
+    /// 等待下一次处理
+    ///
+    /// Waiting to be processed
+    Pending,
+    /// 已作为链式转换（[`HsmTrigger::chain`]）触发；只表示触发已发送，转换本身仍可能被守卫等阻止
+    ///
+    /// Triggered as a chain transition ([`HsmTrigger::chain`]); this only means the trigger was sent, the transition
+    /// itself may still be blocked, e.g. by guards
+    Accepted,
+    Rejected(RequestRejection),
+}
+
+/// # 转换请求被拒绝事件\Transition Request Rejected Event
+/// * 当 [`HsmTransitionRequester`] 提交的请求被拒绝时在状态机实体上触发。
+/// - Triggered on the state machine entity when a request submitted through [`HsmTransitionRequester`] is rejected.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct TransitionRequestRejected {
+    #[event_target]
+    pub state_machine: Entity,
+    pub id: TransitionRequestId,
+    pub target: Entity,
+    pub reason: Cow<'static, str>,
+    pub rejection: RequestRejection,
+}
+
+#[derive(Debug, Clone)]
+struct TransitionRequest {
+    id: TransitionRequestId,
+    state_machine: Entity,
+    target: Entity,
+    priority: i32,
+    reason: Cow<'static, str>,
+    payload: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+#[derive(Resource, Debug, Default)]
+pub(crate) struct TransitionRequests {
+    next_id: u64,
+    pending: Vec<TransitionRequest>,
+    outcomes: HashMap<TransitionRequestId, RequestOutcome>,
+}
+
+impl TransitionRequests {
+    pub(crate) fn submit(
+        &mut self,
+        state_machine: Entity,
+        target: Entity,
+        priority: i32,
+        reason: Cow<'static, str>,
+        payload: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> TransitionRequestId {
+        let id = TransitionRequestId(self.next_id);
+        self.next_id += 1;
+        self.pending.push(TransitionRequest {
+            id,
+            state_machine,
+            target,
+            priority,
+            reason,
+            payload,
+        });
+        id
+    }
+}
+
+/// # 转换请求器\Transition Requester
+/// * 面向外部规划器（GOAP、效用 AI 等）的入口：提交带优先级和原因的目标状态，之后查询请求是否被接受，
+///   或通过观察 [`TransitionRequestRejected`] 订阅拒绝事件，无需接触状态机内部。
+///   每个状态机每次处理只接受优先级最高的可执行请求（同优先级时先提交者优先），被拒绝的请求不会压制后续请求，
+///   结果保留到下一次处理为止。被接受只表示已发送 [`HsmTrigger::chain`]，转换本身仍可能被守卫阻止。
+/// - Entry point for external planners (GOAP, utility AI, ...): submit a target state with a priority and a reason, then
+///   query whether the request was accepted, or observe [`TransitionRequestRejected`] to subscribe to rejections, without
+///   touching state machine internals. Only the highest-priority feasible request per machine is accepted on each pass
+///   (the earliest wins ties), a rejected request does not supersede the ones after it, and outcomes are kept until the
+///   next pass. Being accepted only means an [`HsmTrigger::chain`] was sent; guards may still block the transition.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn planner(mut requester: HsmTransitionRequester, state_machine: Single<Entity, With<HsmStateMachine>>) {
+///     # let flee = Entity::PLACEHOLDER;
+///     let id = requester.submit(*state_machine, flee, 10, "low health");
+///     assert_eq!(requester.outcome(id), Some(RequestOutcome::Pending));
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct HsmTransitionRequester<'w> {
+    requests: ResMut<'w, TransitionRequests>,
+}
+
+impl HsmTransitionRequester<'_> {
+    /// 提交一个转换请求
+    ///
+    /// Submit a transition request
+    pub fn submit(
+        &mut self,
+        state_machine: Entity,
+        target: Entity,
+        priority: i32,
+        reason: impl Into<Cow<'static, str>>,
+    ) -> TransitionRequestId {
+        self.requests
+            .submit(state_machine, target, priority, reason.into(), None)
+    }
+
+    /// 提交一个携带载荷的转换请求，请求被接受时载荷附加到 [`HsmTransitionPayload`]
+    ///
+    /// Submit a transition request carrying a payload, attached to [`HsmTransitionPayload`] once the request is accepted
+    pub fn submit_with_payload(
+        &mut self,
+        state_machine: Entity,
+        target: Entity,
+        priority: i32,
+        reason: impl Into<Cow<'static, str>>,
+        payload: impl Any + Send + Sync,
+    ) -> TransitionRequestId {
+        self.requests.submit(
+            state_machine,
+            target,
+            priority,
+            reason.into(),
+            Some(Arc::new(payload)),
+        )
+    }
+
+    /// 查询请求的结果，未知或已过期的请求返回 `None`
+    ///
+    /// Query the outcome of a request; unknown or expired requests return `None`
+    pub fn outcome(&self, id: TransitionRequestId) -> Option<RequestOutcome> {
+        match self.requests.pending.iter().any(|request| request.id == id) {
+            true => Some(RequestOutcome::Pending),
+            false => self.requests.outcomes.get(&id).copied(),
+        }
+    }
+
+    /// 请求是否已被接受，即已发送链式转换触发
+    ///
+    /// Whether the request was accepted, i.e. its chain transition trigger was sent
+    pub fn is_accepted(&self, id: TransitionRequestId) -> bool {
+        self.outcome(id) == Some(RequestOutcome::Accepted)
+    }
+}
+
+impl TransitionRequests {
+    pub(crate) fn resolve(
+        mut commands: Commands,
+        mut requests: ResMut<TransitionRequests>,
+        query_state_machine: Query<&HsmStateMachine, (Without<Paused>, Without<Terminated>)>,
+        query_state_tree: Query<&StateTree>,
+    ) {
+        if requests.pending.is_empty() && requests.outcomes.is_empty() {
+            return;
+        }
+        let mut pending = std::mem::take(&mut requests.pending);
+        requests.outcomes.clear();
+
+        // 按状态机分组，每组优先级最高、最早提交者在前
+        pending.sort_by(|a, b| {
+            a.state_machine
+                .cmp(&b.state_machine)
+                .then(b.priority.cmp(&a.priority))
+                .then(a.id.cmp(&b.id))
+        });
+
+        // 被拒绝的请求不压制同一状态机的后续请求
+        let mut accepted_state_machine = None;
+        for request in pending {
+            let outcome = if accepted_state_machine == Some(request.state_machine) {
+                Err(RequestRejection::Superseded)
+            } else {
+                Self::check(&request, &query_state_machine, &query_state_tree)
+            };
+            if outcome.is_ok() {
+                accepted_state_machine = Some(request.state_machine);
+            }
+
+            let outcome = match outcome {
+                Ok(()) => {
+                    if let Some(payload) = request.payload {
+                        let target = request.target;
+                        commands.entity(request.state_machine).queue(
+                            move |mut entity: EntityWorldMut| {
+                                entity
+                                    .entry::<HsmTransitionPayload>()
+                                    .or_default()
+                                    .get_mut()
+                                    .attach_shared(target, payload);
+                            },
+                        );
+                    }
+                    commands.trigger(HsmTrigger::chain(request.state_machine, request.target));
+                    RequestOutcome::Accepted
+                }
+                Err(rejection) => {
+                    commands.trigger(TransitionRequestRejected {
+                        state_machine: request.state_machine,
+                        id: request.id,
+                        target: request.target,
+                        reason: request.reason,
+                        rejection,
+                    });
+                    RequestOutcome::Rejected(rejection)
+                }
+            };
+            requests.outcomes.insert(request.id, outcome);
+        }
+    }
+
+    fn check(
+        request: &TransitionRequest,
+        query_state_machine: &Query<&HsmStateMachine, (Without<Paused>, Without<Terminated>)>,
+        query_state_tree: &Query<&StateTree>,
+    ) -> Result<(), RequestRejection> {
+        let state_machine = query_state_machine
+            .get(request.state_machine)
+            .map_err(|_| RequestRejection::Unavailable)?;
+        let state_tree = query_state_tree
+            .get(state_machine.state_tree())
+            .map_err(|_| RequestRejection::Unavailable)?;
+        if !state_tree.contains(request.target) {
+            return Err(RequestRejection::NotInTree);
+        }
+        if state_machine.curr_state_id() == request.target {
+            return Err(RequestRejection::AlreadyInState);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::any::{TypeId, type_name};
+
+use bevy::prelude::*;
+use smallvec::SmallVec;
+
+use crate::{
+    context::GuardContext,
+    hsm::explain::{HsmExplain, TransitionOutcome},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Requirement {
+    Component(TypeId, &'static str),
+    Resource(TypeId, &'static str),
+}
+
+/// # 进入需求\Enter Requirements
+/// * 放在状态上，列出服务目标必须拥有的组件以及世界中必须存在的资源。需求不满足时，进入条件检查与
+///   [`HsmTransitions`](crate::prelude::HsmTransitions) 都会直接跳过该状态，不再运行其守卫；开启 [`HsmExplain`] 时记录为
+///   [`TransitionOutcome::MissingRequirements`]。
+/// - Placed on a state, lists the components the service target must have and the resources that must exist. While they are
+///   not met, both the enter-condition pass and [`HsmTransitions`](crate::prelude::HsmTransitions) skip the state without
+///   running its guard; with [`HsmExplain`] on, this is recorded as [`TransitionOutcome::MissingRequirements`].
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct Weapon;
+///
+/// #[derive(Resource)]
+/// struct Arena;
+///
+/// # fn foo(mut commands: Commands, attack: Entity) {
+/// commands.entity(attack).insert(
+///     HsmEnterRequirements::default()
+///         .with_component::<Weapon>()
+///         .with_resource::<Arena>(),
+/// );
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmEnterRequirements(SmallVec<[Requirement; 2]>);
+
+impl HsmEnterRequirements {
+    /// 要求服务目标拥有组件 `T`
+    ///
+    /// Require the service target to have the component `T`
+    pub fn with_component<T: Component>(mut self) -> Self {
+        self.0
+            .push(Requirement::Component(TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    /// 要求世界中存在资源 `T`
+    ///
+    /// Require the resource `T` to exist
+    pub fn with_resource<T: Resource>(mut self) -> Self {
+        self.0
+            .push(Requirement::Resource(TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 服务目标缺少的需求，按声明顺序返回类型名
+    ///
+    /// The requirements the service target is missing, as type names in declaration order
+    pub fn missing(&self, world: &World, service_target: Entity) -> Vec<&'static str> {
+        let target = world.get_entity(service_target).ok();
+        self.0
+            .iter()
+            .filter_map(|requirement| match *requirement {
+                Requirement::Component(type_id, name) => target
+                    .is_none_or(|target| !target.contains_type_id(type_id))
+                    .then_some(name),
+                Requirement::Resource(type_id, name) => world
+                    .components()
+                    .get_resource_id(type_id)
+                    .is_none_or(|id| !world.contains_resource_by_id(id))
+                    .then_some(name),
+            })
+            .collect()
+    }
+
+    pub fn is_met(&self, world: &World, service_target: Entity) -> bool {
+        self.missing(world, service_target).is_empty()
+    }
+
+    /// 检查转换目标的需求，不满足时记录到解释报告
+    ///
+    /// Check the requirements of the transition target, noting a failure in the explain report
+    pub(crate) fn check(world: &mut World, context: GuardContext) -> bool {
+        let met = world
+            .get::<Self>(context.to_state())
+            .is_none_or(|requirements| requirements.is_met(world, context.service_target));
+        if !met {
+            HsmExplain::record_transition(world, context, TransitionOutcome::MissingRequirements);
+        }
+        met
+    }
+}
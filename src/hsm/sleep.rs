@@ -0,0 +1,173 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{
+    hsm::{
+        guard_retry::HsmGuardRetry,
+        state_lifecycle::StateLifecycle,
+        state_machine::HsmStateMachine,
+        transition_strategy::{
+            CheckOnTransitionStates, HsmTransitionSchedule, HsmTransitionSystems,
+        },
+    },
+    markers::Paused,
+    state_actions::{OnUpdateSystem, OnUpdateSystems, StateMachineForest},
+};
+
+/// # 休眠\Sleep
+/// * 可选挂载在状态机上的优化：当前状态没有更新系统、且条件在一整轮检查后仍未满足时，状态机被移出每帧的转换检查，
+///   直到被唤醒。大量大多空闲的状态机因此几乎不产生开销。
+/// - An opt-in optimization on a state machine: when the current state has no update system and its conditions stayed
+///   false for a full round of checks, the machine is taken out of the per-frame transition checks until it is woken.
+///   Large numbers of mostly idle machines then cost next to nothing.
+/// * 只适用于条件仅依赖会唤醒状态机的数据的状态机，唤醒来源有：
+///   [`HsmVar`](crate::hsm::vars::HsmVar) 的修改、[`HsmSleepAppExt::wake_on_change`] 关注的组件变化、
+///   任何转换（包括请求、[`HsmTrigger`](crate::hsm::event::HsmTrigger) 与命名转换），以及 [`HsmSleepCommandsExt::wake_hsm`]。
+///   依赖时间或每帧轮询的条件在休眠期间不会被检查。
+/// - Only suited to machines whose conditions depend solely on data that wakes them. Wake-up sources are: mutations of
+///   an [`HsmVar`](crate::hsm::vars::HsmVar), changes of components watched through [`HsmSleepAppExt::wake_on_change`],
+///   any transition (requests, [`HsmTrigger`](crate::hsm::event::HsmTrigger) and named transitions included), and
+///   [`HsmSleepCommandsExt::wake_hsm`]. Conditions depending on time or per-frame polling are not checked while asleep.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct Noise(f32);
+///
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands.entity(state_machine).insert(HsmSleep::default());
+/// # }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .wake_on_change::<Noise>();
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HsmSleep {
+    checked: Option<Entity>,
+    asleep: bool,
+}
+
+impl HsmSleep {
+    /// 状态机是否正在休眠
+    ///
+    /// Whether the machine is asleep
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// 唤醒处于 [`StateLifecycle::Update`] 的状态机，使其在下一次转换调度中被检查
+    ///
+    /// Wake a machine in [`StateLifecycle::Update`] so it gets checked in the next transition schedule
+    pub fn wake(world: &mut World, state_machine: Entity) {
+        if world.get::<StateLifecycle>(state_machine) == Some(&StateLifecycle::Update) {
+            world
+                .resource_mut::<CheckOnTransitionStates>()
+                .insert(state_machine);
+        }
+    }
+
+    /// 让经过一整轮检查仍未转换、且当前状态没有更新系统的状态机休眠
+    ///
+    /// Put to sleep the machines that stayed put over a full round of checks and whose current state has no update
+    /// system
+    pub(crate) fn sleep(
+        mut check_on_transition_states: ResMut<CheckOnTransitionStates>,
+        mut query: Query<
+            (
+                Entity,
+                &HsmStateMachine,
+                &StateLifecycle,
+                &mut Self,
+                Option<&HsmGuardRetry>,
+            ),
+            Without<Paused>,
+        >,
+        query_states: Query<(), Or<(With<OnUpdateSystem>, With<OnUpdateSystems>)>>,
+    ) {
+        let mut asleep = Vec::new();
+        let mut iter = query.iter_many_mut(check_on_transition_states.iter());
+        while let Some((state_machine, hsm, lifecycle, mut sleep, retry)) = iter.fetch_next() {
+            let curr_state_id = hsm.curr_state_id();
+            sleep.asleep = false;
+            if *lifecycle != StateLifecycle::Update
+                || query_states.contains(curr_state_id)
+                || retry.is_some_and(|retry| retry.is_retrying(curr_state_id))
+            {
+                sleep.checked = None;
+                continue;
+            }
+            if sleep.checked == Some(curr_state_id) {
+                sleep.checked = None;
+                sleep.asleep = true;
+                asleep.push(state_machine);
+            } else {
+                sleep.checked = Some(curr_state_id);
+            }
+        }
+        for state_machine in asleep {
+            check_on_transition_states.remove(&state_machine);
+        }
+    }
+
+    fn wake_on_change<C: Component>(
+        mut check_on_transition_states: ResMut<CheckOnTransitionStates>,
+        changed: Query<(Entity, Option<&StateMachineForest>), Changed<C>>,
+        query: Query<&StateLifecycle, (With<Self>, Without<Paused>)>,
+    ) {
+        for (entity, forest) in changed.iter() {
+            let machines = forest.map_or(&[][..], StateMachineForest::machines);
+            for &state_machine in std::iter::once(&entity).chain(machines) {
+                if query.get(state_machine) == Ok(&StateLifecycle::Update) {
+                    check_on_transition_states.insert(state_machine);
+                }
+            }
+        }
+    }
+}
+
+/// # 休眠扩展\Sleep Extension
+/// * 注册唤醒休眠状态机的数据变化，位于转换系统（[`HsmTransitionSystems`]）之前。需要先添加
+///   [`StateMachinePlugin`](crate::StateMachinePlugin)，否则假定转换系统在 [`Last`] 中运行。
+/// - Registers data changes waking sleeping machines, ordered before the transition systems
+///   ([`HsmTransitionSystems`]). [`StateMachinePlugin`](crate::StateMachinePlugin) should be added first, otherwise the
+///   transition systems are assumed to run in [`Last`].
+pub trait HsmSleepAppExt {
+    /// 组件 `C` 被插入或修改时，唤醒该实体本身（若是状态机）以及以它为服务目标的休眠状态机
+    ///
+    /// Whenever component `C` is inserted or mutated, wake the entity itself (if it is a machine) and the sleeping
+    /// machines serving it
+    fn wake_on_change<C: Component>(&mut self) -> &mut Self;
+}
+
+impl HsmSleepAppExt for App {
+    fn wake_on_change<C: Component>(&mut self) -> &mut Self {
+        let schedule = self
+            .world()
+            .get_resource::<HsmTransitionSchedule>()
+            .map_or(Last.intern(), |schedule| schedule.0);
+        self.add_systems(
+            schedule,
+            HsmSleep::wake_on_change::<C>.before(HsmTransitionSystems),
+        )
+    }
+}
+
+/// # 休眠命令扩展\Sleep Commands Extension
+/// * 在状态机实体的 [`EntityCommands`] 上唤醒休眠的状态机，参见 [`HsmSleep`]。
+/// - Wake a sleeping machine through the [`EntityCommands`] of a state machine, see [`HsmSleep`].
+pub trait HsmSleepCommandsExt {
+    fn wake_hsm(&mut self) -> &mut Self;
+}
+
+impl HsmSleepCommandsExt for EntityCommands<'_> {
+    fn wake_hsm(&mut self) -> &mut Self {
+        self.queue(|entity: EntityWorldMut| {
+            let state_machine = entity.id();
+            HsmSleep::wake(entity.into_world_mut(), state_machine);
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::{ecs::world::DeferredWorld, prelude::*};
+
+use crate::{
+    context::GuardContext,
+    guards::{GuardCondition, GuardRegistry},
+    hsm::{
+        state_lifecycle::StateLifecycle, state_machine::HsmStateMachine,
+        transition_strategy::get_service_target,
+    },
+};
+
+/// # 启动选择器\Start Selector
+/// * 放在状态机上，在状态机首次进入时按顺序评估一组 `(条件, 状态)`，第一个成立的状态成为状态机的初始状态与当前状态；
+///   都不成立时保持原本的初始状态。评估只进行一次，之后该组件被移除。
+/// - Placed on a state machine, evaluates an ordered list of `(condition, state)` once when the machine first enters; the
+///   first state whose condition holds becomes the machine's initial and current state, and the original initial state
+///   is kept when none does. The component is removed after this single evaluation.
+/// * 条件的 `from` 为原本的初始状态，`to` 为候选状态。
+/// - Conditions see the original initial state as `from` and the candidate as `to`.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn spawn_game(mut commands: Commands) {
+///     commands.spawn(hsm!(
+///         init(start = ["has_save" => Resume])
+///         #[state]:Boot(
+///             #[state]:Intro,
+///             #[state]:Resume,
+///         )
+///         StateLifecycle::default(),
+///     ));
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmStartSelector(Vec<(GuardCondition, Entity)>);
+
+impl HsmStartSelector {
+    /// 追加一个候选状态
+    ///
+    /// Append a candidate state
+    pub fn with(mut self, condition: impl Into<GuardCondition>, state: Entity) -> Self {
+        self.0.push((condition.into(), state));
+        self
+    }
+
+    pub fn candidates(&self) -> &[(GuardCondition, Entity)] {
+        &self.0
+    }
+
+    /// 状态机带有选择器时推迟首次进入，改为在评估后进入
+    ///
+    /// Postpone the first enter of a machine carrying a selector until it has been evaluated
+    pub(crate) fn defer(world: &mut DeferredWorld, state_machine_id: Entity) -> bool {
+        if world.get::<Self>(state_machine_id).is_none() {
+            return false;
+        }
+        world
+            .commands()
+            .queue(move |world: &mut World| Self::resolve(world, state_machine_id));
+        true
+    }
+
+    fn resolve(world: &mut World, state_machine_id: Entity) {
+        let Ok(mut entity) = world.get_entity_mut(state_machine_id) else {
+            return;
+        };
+        let Some(selector) = entity.take::<Self>() else {
+            return;
+        };
+        let Some(init_state) = entity.get::<HsmStateMachine>().map(|hsm| hsm.init_state()) else {
+            return;
+        };
+
+        let service_target = get_service_target(world, state_machine_id);
+        let mut selected = None;
+        for (condition, state) in selector.0 {
+            let guard = match world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+            {
+                Ok(guard) => guard,
+                Err(e) => {
+                    warn!("[HsmStartSelector] {}: {}", condition, e);
+                    continue;
+                }
+            };
+            let context = GuardContext::new(service_target, state_machine_id, init_state, state);
+            match guard.run(world, context) {
+                Ok(true) => {
+                    selected = Some(state);
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("[HsmStartSelector] {}: {}", condition, e),
+            }
+        }
+
+        let mut entity = world.entity_mut(state_machine_id);
+        if let Some(state) = selected
+            && let Some(mut hsm) = entity.get_mut::<HsmStateMachine>()
+        {
+            hsm.set_init_state(state);
+            hsm.set_curr_state(state);
+        }
+        entity.insert(StateLifecycle::Enter);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use bevy::{
     ecs::{lifecycle::HookContext, world::DeferredWorld},
     prelude::*,
@@ -8,16 +10,30 @@ use crate::hsm::history::HistoricalNode;
 #[cfg(feature = "state_data")]
 use crate::prelude::StateData;
 use crate::{
+    behavior::HsmBehavior,
+    builtin_guards::{GuardCounters, GuardHysteresis},
     context::{ActionContext, TransitionContext},
     error::StateMachineError,
-    hsm::state_machine::*,
+    guards::StickyGuards,
+    hsm::{
+        emit::{HsmEmitPhase, HsmEmitters},
+        golden::HsmTrace,
+        loop_detection::TransitionLoopDetection,
+        phase_schedules::{HsmEnterOf, HsmExitOf, run_phase_schedule},
+        pipeline::PipelinePhaseCounts,
+        start_selector::HsmStartSelector,
+        state_machine::*,
+        supervisor::HsmSupervisor,
+    },
+    inbox::HsmInbox,
     labels::SystemLabel,
     markers::Terminated,
     prelude::{
         ActionRegistry, AfterEnterSystem, AfterExitSystem, BeforeEnterSystem, BeforeExitSystem,
-        CheckOnTransitionStates, OnUpdateSystem, ServiceTarget, StateActionBuffer,
+        CheckOnTransitionStates, OnUpdateSystem, OnUpdateSystems, ServiceTarget, StateActionBuffer,
         TransitionRegistry,
     },
+    tasks::StateTasks,
 };
 
 struct TransitionInfo {
@@ -53,7 +69,9 @@ impl StateLifecycle {
         state_id: Entity,
         state_context: ActionContext,
     ) {
-        let Some(action_system_id) = ActionRegistry::get_action_id::<T>(world, state_id) else {
+        let Some(action_system_id) =
+            ActionRegistry::get_action_id::<T>(world, state_context.state_machine, state_id)
+        else {
             return;
         };
 
@@ -68,8 +86,11 @@ impl StateLifecycle {
         state_id: Entity,
         state_context: TransitionContext,
     ) {
-        let Some(action_system_id) = TransitionRegistry::get_transition_id::<T>(world, state_id)
-        else {
+        let Some(action_system_id) = TransitionRegistry::get_transition_id::<T>(
+            world,
+            state_context.state_machine,
+            state_id,
+        ) else {
             return;
         };
         state_context.run_system(world, action_system_id);
@@ -118,7 +139,9 @@ impl StateLifecycle {
 
     #[cfg(feature = "hybrid")]
     fn handle_hybrid_exit(world: &mut DeferredWorld, state_machine_id: Entity, state_id: Entity) {
-        use crate::{fsm::state_machine::HsmOwnedFsms, prelude::FsmStateMachine};
+        use crate::fsm::state_machine::HsmOwnedFsms;
+        #[cfg(feature = "history")]
+        use crate::prelude::FsmStateMachine;
 
         let Some(mut mapping) = world.get_mut::<HsmOwnedFsms>(state_machine_id) else {
             return;
@@ -150,18 +173,19 @@ impl StateLifecycle {
 
     fn prepare_transition(
         world: &mut DeferredWorld,
-        hook_context: HookContext,
+        state_machine_id: Entity,
+        lifecycle: StateLifecycle,
     ) -> Result<TransitionInfo, StateMachineError> {
-        let state_machine_id = hook_context.entity;
-
+        #[cfg(feature = "history")]
+        let now = world
+            .get_resource::<bevy::time::Time>()
+            .map(bevy::time::Time::elapsed);
+        #[cfg(feature = "stats")]
+        let clock_now = crate::clock::StateClock::now(world, state_machine_id);
         let Ok(mut entity_mut) = world.get_entity_mut(state_machine_id) else {
             return Err(StateMachineError::HsmStateMachineMissing(state_machine_id));
         };
 
-        let Some(lifecycle) = entity_mut.get::<StateLifecycle>().copied() else {
-            return Err(StateMachineError::StateLifecycleMissing(state_machine_id));
-        };
-
         let service_target = match entity_mut.get::<ServiceTarget>() {
             Some(service_target) => service_target.0,
             None => state_machine_id,
@@ -175,7 +199,24 @@ impl StateLifecycle {
         let curr = Transition::with_lifecycle(curr_state_id, lifecycle);
         let prev = state_machine.push_prev_state(curr);
         #[cfg(feature = "history")]
-        state_machine.push_history(HistoricalNode::new(curr_state_id, lifecycle.into()));
+        state_machine.push_history(match now {
+            Some(now) => HistoricalNode::new(curr_state_id, lifecycle.into()).with_timestamp(now),
+            None => HistoricalNode::new(curr_state_id, lifecycle.into()),
+        });
+
+        if let Some(mut current) = entity_mut.get_mut::<CurrentLifecycle>() {
+            current.set_if_neq(CurrentLifecycle {
+                state: curr_state_id,
+                lifecycle,
+            });
+        }
+        if let Some(mut trace) = entity_mut.get_mut::<HsmTrace>() {
+            trace.push(curr_state_id, lifecycle);
+        }
+        #[cfg(feature = "stats")]
+        if let Some(mut stats) = entity_mut.get_mut::<crate::hsm::stats::HsmStats>() {
+            stats.record(curr_state_id, clock_now.unwrap_or_default());
+        }
 
         let state_context = ActionContext::new(service_target, state_machine_id, curr_state_id);
 
@@ -190,14 +231,45 @@ impl StateLifecycle {
     }
 
     fn on_insert(mut world: DeferredWorld, hook_context: HookContext) {
-        let transition_info = match Self::prepare_transition(&mut world, hook_context) {
-            Ok(info) => info,
-            Err(e) => {
-                warn!("{}", e);
-                return;
-            }
+        let state_machine_id = hook_context.entity;
+        let Some(lifecycle) = world.get::<StateLifecycle>(state_machine_id).copied() else {
+            StateMachineError::StateLifecycleMissing(state_machine_id).report_deferred(&mut world);
+            return;
         };
 
+        if lifecycle == StateLifecycle::Enter
+            && HsmStartSelector::defer(&mut world, state_machine_id)
+        {
+            return;
+        }
+
+        if world
+            .get_resource::<LifecycleDriver>()
+            .is_some_and(|driver| *driver == LifecycleDriver::Queue)
+            || !PipelinePhaseCounts::admit(&mut world, state_machine_id)
+        {
+            world
+                .resource_mut::<LifecycleQueue>()
+                .push_back((state_machine_id, lifecycle));
+            return;
+        }
+
+        Self::process(world, state_machine_id, lifecycle);
+    }
+
+    /// 处理状态机进入某一生命周期阶段时的动作与后续转换
+    ///
+    /// Handles the actions and follow-up transitions of a state machine entering a lifecycle phase
+    fn process(mut world: DeferredWorld, state_machine_id: Entity, lifecycle: StateLifecycle) {
+        let transition_info =
+            match Self::prepare_transition(&mut world, state_machine_id, lifecycle) {
+                Ok(info) => info,
+                Err(e) => {
+                    e.report_deferred(&mut world);
+                    return;
+                }
+            };
+
         let TransitionInfo {
             state_machine_id,
             prev_transition,
@@ -213,6 +285,8 @@ impl StateLifecycle {
                     return;
                 };
 
+                TransitionLoopDetection::record(&mut world, state_machine_id, curr_state_id);
+
                 // 运行进入之前的系统
                 Self::run_transition_action_system::<BeforeEnterSystem>(
                     &mut world,
@@ -228,7 +302,7 @@ impl StateLifecycle {
                 if let Err(e) =
                     Self::handle_hybrid_entry(&mut world, state_machine_id, curr_state_id)
                 {
-                    error!("{}", e);
+                    e.report_deferred(&mut world);
                 }
 
                 #[cfg(feature = "state_data")]
@@ -244,6 +318,28 @@ impl StateLifecycle {
                     curr_state_id,
                     state_context,
                 );
+                world
+                    .commands()
+                    .queue(run_phase_schedule(HsmEnterOf, state_context));
+                world.commands().queue(StickyGuards::consume_command(
+                    state_machine_id,
+                    curr_state_id,
+                ));
+                world
+                    .commands()
+                    .queue(HsmInbox::consume_command(state_machine_id, curr_state_id));
+                world
+                    .commands()
+                    .queue(HsmSupervisor::enter_command(state_context));
+
+                #[cfg(feature = "audio")]
+                world.commands().queue(
+                    crate::audio::play_state_sound::<crate::audio::HsmEnterSound>(curr_state_id),
+                );
+                world.commands().queue(HsmEmitters::emit_command(
+                    state_context,
+                    HsmEmitPhase::Enter,
+                ));
 
                 world
                     .commands()
@@ -257,7 +353,10 @@ impl StateLifecycle {
                 check_on_transition_states.insert(state_machine_id);
 
                 // 运行更新系统
-                if world.entity(curr_state_id).contains::<OnUpdateSystem>() {
+                let curr_state = world.entity(curr_state_id);
+                if curr_state.contains::<OnUpdateSystem>()
+                    || curr_state.contains::<OnUpdateSystems>()
+                {
                     StateActionBuffer::buffer_scope(
                         world.as_unsafe_world_cell(),
                         curr_state_id,
@@ -285,6 +384,43 @@ impl StateLifecycle {
                     curr_state_id,
                     state_context,
                 );
+                world
+                    .commands()
+                    .queue(run_phase_schedule(HsmExitOf, state_context));
+
+                #[cfg(feature = "audio")]
+                world.commands().queue(
+                    crate::audio::play_state_sound::<crate::audio::HsmExitSound>(curr_state_id),
+                );
+                world
+                    .commands()
+                    .queue(HsmEmitters::emit_command(state_context, HsmEmitPhase::Exit));
+
+                // 取消该状态的任务与行为
+                world
+                    .commands()
+                    .queue(StateTasks::cancel_command(state_machine_id, curr_state_id));
+                world.commands().queue(GuardCounters::clear_command(
+                    state_machine_id,
+                    curr_state_id,
+                ));
+                world.commands().queue(GuardHysteresis::clear_command(
+                    state_machine_id,
+                    curr_state_id,
+                ));
+                world.commands().queue(StickyGuards::consume_command(
+                    state_machine_id,
+                    curr_state_id,
+                ));
+                world
+                    .commands()
+                    .queue(HsmInbox::consume_command(state_machine_id, curr_state_id));
+                world
+                    .commands()
+                    .queue(HsmBehavior::exit_command(state_context));
+                world
+                    .commands()
+                    .queue(HsmSupervisor::exit_command(state_machine_id, curr_state_id));
 
                 #[cfg(feature = "hybrid")]
                 Self::handle_hybrid_exit(&mut world, state_machine_id, curr_state_id);
@@ -300,10 +436,7 @@ impl StateLifecycle {
                     let Some(mut state_machine) =
                         world.get_mut::<HsmStateMachine>(state_machine_id)
                     else {
-                        warn!(
-                            "{}",
-                            StateMachineError::HsmStateMachineMissing(state_machine_id)
-                        );
+                        StateMachineError::HsmStateMachineMissing(state_machine_id).report(world);
                         return;
                     };
                     let next_transition = state_machine.pop_next_state();
@@ -366,3 +499,100 @@ impl StateLifecycle {
         });
     }
 }
+
+/// # 当前生命周期\Current Lifecycle
+/// * 维护在状态机实体上的可变组件，记录最近一次被处理的状态及其生命周期阶段，
+///   可通过 `Changed<CurrentLifecycle>` 检测，而无需观察不可变的 [`StateLifecycle`] 插入。
+/// - A mutable component kept on the state machine entity, recording the state and lifecycle phase processed last.
+///   It can be detected with `Changed<CurrentLifecycle>` instead of observing the immutable [`StateLifecycle`] inserts.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn on_phase(query: Query<(Entity, &CurrentLifecycle), Changed<CurrentLifecycle>>) {
+///     for (state_machine, current) in query.iter() {
+///         info!("{:?}: {:?} {:?}", state_machine, current.state, current.lifecycle);
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CurrentLifecycle {
+    /// 当前状态
+    ///
+    /// The current state
+    pub state: Entity,
+    /// 当前生命周期阶段
+    ///
+    /// The current lifecycle phase
+    pub lifecycle: StateLifecycle,
+}
+
+impl Default for CurrentLifecycle {
+    fn default() -> Self {
+        Self {
+            state: Entity::PLACEHOLDER,
+            lifecycle: StateLifecycle::default(),
+        }
+    }
+}
+
+/// # 生命周期驱动方式\Lifecycle Driver
+/// * 决定插入 [`StateLifecycle`] 后各阶段在何时被处理，默认为 [`LifecycleDriver::Hooks`]。
+/// - Decides when the phases are processed after [`StateLifecycle`] is inserted, [`LifecycleDriver::Hooks`] by default.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .insert_resource(LifecycleDriver::Queue);
+/// # }
+/// ```
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LifecycleDriver {
+    /// 在 [`StateLifecycle`] 的插入钩子中立即处理
+    ///
+    /// Processed immediately in the insert hook of [`StateLifecycle`]
+    #[default]
+    Hooks,
+    /// 插入钩子只记录阶段，由转换调度中的独占系统按插入顺序统一处理，[`StateLifecycle`] 仅作为可读的状态组件
+    ///
+    /// The insert hook only records the phase, and an exclusive system in the transition schedule processes them in
+    /// insertion order, leaving [`StateLifecycle`] as a plain readable status component
+    Queue,
+}
+
+/// 等待 [`LifecycleDriver::Queue`] 处理的生命周期阶段
+///
+/// Lifecycle phases waiting to be processed by [`LifecycleDriver::Queue`]
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+pub(crate) struct LifecycleQueue(VecDeque<(Entity, StateLifecycle)>);
+
+impl LifecycleQueue {
+    /// 按插入顺序处理队列中的所有阶段，包括处理过程中新插入的阶段；超出 [`TransitionPipeline`](crate::hsm::pipeline::TransitionPipeline) 每帧阶段上限的阶段留待之后的帧
+    ///
+    /// Processes every queued phase in insertion order, including phases inserted while processing; phases over the
+    /// per-frame limit of a [`TransitionPipeline`](crate::hsm::pipeline::TransitionPipeline) are left for later frames
+    pub(crate) fn process(world: &mut World) {
+        let mut deferred = VecDeque::new();
+        while let Some((state_machine_id, lifecycle)) =
+            world.resource_mut::<LifecycleQueue>().pop_front()
+        {
+            if world.get_entity(state_machine_id).is_err() {
+                continue;
+            }
+            if deferred.iter().any(|(id, _)| *id == state_machine_id)
+                || !PipelinePhaseCounts::admit(&mut world.into(), state_machine_id)
+            {
+                deferred.push_back((state_machine_id, lifecycle));
+                continue;
+            }
+            StateLifecycle::process(world.into(), state_machine_id, lifecycle);
+            world.flush();
+        }
+        world.resource_mut::<LifecycleQueue>().append(&mut deferred);
+    }
+}
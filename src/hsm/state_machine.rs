@@ -3,15 +3,21 @@ use std::{collections::VecDeque, fmt::Debug};
 use bevy::prelude::*;
 
 use crate::{
+    builtin_guards::TransitionCooldowns,
+    clock::StateClock,
     context::{GuardContext, TransitionRelationship},
     error::StateMachineError,
-    guards::{CompiledGuard, GuardCondition, GuardRegistry},
+    guards::{CompiledGuard, GuardCondition, GuardRegistry, GuardResolveError},
     hsm::{
         HsmState,
         event::HsmTrigger,
-        state_lifecycle::StateLifecycle,
+        event_log::HsmEventLog,
+        explain::{HsmExplain, TransitionOutcome},
+        hooks::HsmTransitionHooks,
+        state_lifecycle::{CurrentLifecycle, StateLifecycle},
         transition_strategy::{handle_enter_transition, handle_exit_transition},
     },
+    local_registry::LocalRegistry,
     markers::Paused,
     prelude::{ServiceTarget, StateTransitionStrategy, StateTree},
 };
@@ -35,7 +41,14 @@ use crate::hsm::history::*;
 /// let state_machine = HsmStateMachine::with(tree_id, id,#[cfg(feature = "history")] 10);
 /// # }
 /// ```
+/// # 必需组件\Required Components
+/// * [`CurrentLifecycle`] 与一个默认（[`StateClockMode::Update`](crate::clock::StateClockMode::Update)）的
+///   [`StateClock`]，需要固定步时钟时显式插入。
+/// - [`CurrentLifecycle`] and a default ([`StateClockMode::Update`](crate::clock::StateClockMode::Update))
+///   [`StateClock`]; insert one explicitly for a fixed step clock.
 #[derive(Component, Clone, PartialEq, Eq)]
+#[require(CurrentLifecycle, StateClock)]
+#[cfg_attr(feature = "stats", require(crate::hsm::stats::HsmStats))]
 pub struct HsmStateMachine {
     /// 历史记录
     ///
@@ -101,6 +114,15 @@ impl HsmStateMachine {
         )
     }
 
+    /// 设置状态历史的压缩方式
+    ///
+    /// Set the compression of the state history
+    #[cfg(feature = "history")]
+    pub fn with_history_compression(mut self, compression: HistoryCompression) -> Self {
+        self.history.set_compression(compression);
+        self
+    }
+
     /// 获取状态树
     /// Get the state tree
     pub const fn state_tree(&self) -> Entity {
@@ -203,6 +225,23 @@ impl HsmStateMachine {
         self.history.iter()
     }
 
+    /// 最近的 `n` 个状态（包括当前状态）中是否出现过该状态，参见 [`StateHistory::was_in_state_within`]
+    ///
+    /// Whether the state appears among the latest `n` states (the current one included), see
+    /// [`StateHistory::was_in_state_within`]
+    #[cfg(feature = "history")]
+    pub fn was_in_state_within(&self, state: Entity, n: usize) -> bool {
+        self.history.was_in_state_within(state, n)
+    }
+
+    /// 最近一次处于该状态的时刻，参见 [`StateHistory::last_time_in`]
+    ///
+    /// When the machine was last in the state, see [`StateHistory::last_time_in`]
+    #[cfg(feature = "history")]
+    pub fn last_time_in(&self, state: Entity) -> Option<std::time::Duration> {
+        self.history.last_time_in(state)
+    }
+
     /// 获取历史记录长度
     ///
     /// Obtain the length of historical records
@@ -257,13 +296,14 @@ impl HsmStateMachine {
 
     #[inline]
     fn get_state_tree<'w>(
+        commands: &mut Commands,
         query_state_tree: &'w Query<&StateTree>,
         state_tree_id: Entity,
     ) -> Option<&'w StateTree> {
         match query_state_tree.get(state_tree_id) {
             Ok(tree) => Some(tree),
             Err(_) => {
-                warn!("{}", StateMachineError::StateTreeNotFound(state_tree_id));
+                StateMachineError::StateTreeNotFound(state_tree_id).report_with(commands);
                 None
             }
         }
@@ -271,13 +311,14 @@ impl HsmStateMachine {
 
     #[inline]
     fn get_state_strategy(
+        commands: &mut Commands,
         query_state: &Query<&HsmState>,
         state_id: Entity,
     ) -> Option<StateTransitionStrategy> {
         match query_state.get(state_id) {
             Ok(hsm_state) => Some(hsm_state.strategy),
             Err(_) => {
-                warn!("{}", StateMachineError::HsmStateMissing(state_id));
+                StateMachineError::HsmStateMissing(state_id).report_with(commands);
                 None
             }
         }
@@ -285,16 +326,14 @@ impl HsmStateMachine {
 
     #[inline]
     fn get_hsm_state_machine<'w>(
+        commands: &mut Commands,
         query: &'w mut Query<&mut HsmStateMachine, Without<Paused>>,
         state_machine_id: Entity,
     ) -> Option<Mut<'w, HsmStateMachine>> {
         match query.get_mut(state_machine_id) {
             Ok(machine) => Some(machine),
             Err(_) => {
-                error!(
-                    "{}",
-                    StateMachineError::HsmStateMachineMissing(state_machine_id)
-                );
+                StateMachineError::HsmStateMachineMissing(state_machine_id).report_with(commands);
                 None
             }
         }
@@ -308,6 +347,7 @@ impl HsmStateMachine {
         query_state_tree: Query<&StateTree>,
         mut query: Query<&mut HsmStateMachine, Without<Paused>>,
         query_service_target: Query<&ServiceTarget, With<HsmStateMachine>>,
+        query_local: Query<&LocalRegistry>,
         guard_registry: Res<GuardRegistry>,
     ) {
         let HsmTrigger {
@@ -316,7 +356,8 @@ impl HsmStateMachine {
         } = on.event();
         let state_machine_id = *state_machine;
 
-        let Some(mut state_machine) = Self::get_hsm_state_machine(&mut query, state_machine_id)
+        let Some(state_machine) =
+            Self::get_hsm_state_machine(&mut commands, &mut query, state_machine_id)
         else {
             return;
         };
@@ -324,10 +365,19 @@ impl HsmStateMachine {
         let state_tree_id = state_machine.state_tree();
         let curr_state_id = state_machine.curr_state_id();
 
-        let Some(state_tree) = Self::get_state_tree(&query_state_tree, state_tree_id) else {
+        let Some(state_tree) =
+            Self::get_state_tree(&mut commands, &query_state_tree, state_tree_id)
+        else {
             return;
         };
 
+        let compile = |guard: &GuardCondition| match query_local.get(state_machine_id) {
+            Ok(local) if !local.guards.is_empty() => local
+                .guards
+                .to_combinator_condition_id_with(&guard_registry, guard),
+            _ => guard_registry.to_combinator_condition_id(guard),
+        };
+
         match typed {
             super::event::HsmTriggerType::ToSuper => {
                 Self::handle_to_super(
@@ -350,13 +400,13 @@ impl HsmStateMachine {
                 );
             }
             super::event::HsmTriggerType::Chain(next_state_id) => {
-                state_machine.handle_chain(
-                    &mut commands,
-                    state_machine_id,
-                    *next_state_id,
-                    state_tree,
-                    &query_state,
-                );
+                if curr_state_id != *next_state_id {
+                    commands.queue(Self::handle_chain_transition(
+                        state_machine_id,
+                        curr_state_id,
+                        *next_state_id,
+                    ));
+                }
             }
             _ => {
                 let service_target = match query_service_target.get(state_machine_id) {
@@ -376,8 +426,7 @@ impl HsmStateMachine {
                             state_tree_id,
                             state_tree,
                             context,
-                            guard,
-                            &guard_registry,
+                            compile(guard),
                             &query_state,
                         );
                     }
@@ -389,8 +438,7 @@ impl HsmStateMachine {
                             state_tree_id,
                             curr_state_id,
                             state_tree,
-                            guard,
-                            &guard_registry,
+                            compile(guard),
                         );
                     }
                     _ => unreachable!("Unexpected HsmTriggerType: {:?}", typed),
@@ -446,17 +494,15 @@ impl HsmStateMachine {
             .get_sub_states(curr_state_id)
             .is_none_or(|sub_states| !sub_states.contains(&enter_state_id))
         {
-            warn!(
-                "{}",
-                StateMachineError::SubStateNotFound {
-                    state_tree: state_tree_id,
-                    state: curr_state_id,
-                }
-            );
+            StateMachineError::SubStateNotFound {
+                state_tree: state_tree_id,
+                state: curr_state_id,
+            }
+            .report_with(commands);
             return;
         }
 
-        let Some(strategy) = Self::get_state_strategy(query_state, curr_state_id) else {
+        let Some(strategy) = Self::get_state_strategy(commands, query_state, curr_state_id) else {
             return;
         };
 
@@ -476,24 +522,25 @@ impl HsmStateMachine {
         state_tree_id: Entity,
         curr_state_id: Entity,
         state_tree: &StateTree,
-        guard: &GuardCondition,
-        guard_registry: &GuardRegistry,
+        guard: Result<CompiledGuard, GuardResolveError>,
     ) {
         let Some(exit_state_id) = state_tree.get_super_state(curr_state_id) else {
-            warn!(
-                "{}",
-                StateMachineError::SuperStateNotFound {
-                    state_tree: state_tree_id,
-                    state: curr_state_id
-                }
-            );
+            StateMachineError::SuperStateNotFound {
+                state_tree: state_tree_id,
+                state: curr_state_id,
+            }
+            .report_with(commands);
             return;
         };
 
-        let guard = match guard_registry.to_combinator_condition_id(guard) {
+        let guard = match guard {
             Ok(guard) => guard,
-            Err(err) => {
-                warn!("{}", err);
+            Err(source) => {
+                StateMachineError::GuardUnresolved {
+                    state: curr_state_id,
+                    source,
+                }
+                .report_with(commands);
                 return;
             }
         };
@@ -518,32 +565,34 @@ impl HsmStateMachine {
         state_tree_id: Entity,
         state_tree: &StateTree,
         context: GuardContext,
-        guard: &GuardCondition,
-        guard_registry: &GuardRegistry,
+        guard: Result<CompiledGuard, GuardResolveError>,
         query_state: &Query<&HsmState>,
     ) {
         if state_tree
             .get_sub_states(context.from_state())
             .is_none_or(|sub_states| !sub_states.contains(&context.to_state()))
         {
-            warn!(
-                "{}",
-                StateMachineError::SubStateNotFound {
-                    state_tree: state_tree_id,
-                    state: context.from_state(),
-                }
-            );
+            StateMachineError::SubStateNotFound {
+                state_tree: state_tree_id,
+                state: context.from_state(),
+            }
+            .report_with(commands);
             return;
         }
 
-        let Some(strategy) = Self::get_state_strategy(query_state, context.from_state()) else {
+        let Some(strategy) = Self::get_state_strategy(commands, query_state, context.from_state())
+        else {
             return;
         };
 
-        let guard = match guard_registry.to_combinator_condition_id(guard) {
+        let guard = match guard {
             Ok(guard) => guard,
-            Err(err) => {
-                warn!("{}", err);
+            Err(source) => {
+                StateMachineError::GuardUnresolved {
+                    state: context.from_state(),
+                    source,
+                }
+                .report_with(commands);
                 return;
             }
         };
@@ -557,6 +606,61 @@ impl HsmStateMachine {
         }));
     }
 
+    fn handle_chain_transition(
+        state_machine_id: Entity,
+        curr_state_id: Entity,
+        next_state_id: Entity,
+    ) -> impl Command<Result<()>> {
+        move |world: &mut World| -> Result<()> {
+            let service_target = world
+                .get::<ServiceTarget>(state_machine_id)
+                .map_or(state_machine_id, |st| st.0);
+            let context = GuardContext::new(
+                service_target,
+                state_machine_id,
+                curr_state_id,
+                next_state_id,
+            );
+            if !HsmTransitionHooks::run_before(world, context) {
+                HsmExplain::record_transition(world, context, TransitionOutcome::RejectedByHook);
+                HsmEventLog::record_vetoed(world, context);
+                return Ok(());
+            }
+            HsmExplain::record_transition(world, context, TransitionOutcome::Selected);
+            world.run_system_cached_with(Self::apply_chain, (state_machine_id, next_state_id))?;
+            HsmTransitionHooks::run_after(world, context);
+            TransitionCooldowns::record(world, context);
+            HsmEventLog::record_transition(world, context);
+            Ok(())
+        }
+    }
+
+    fn apply_chain(
+        In((state_machine_id, next_state_id)): In<(Entity, Entity)>,
+        mut commands: Commands,
+        mut query: Query<&mut HsmStateMachine, Without<Paused>>,
+        query_state_tree: Query<&StateTree>,
+        query_state: Query<&HsmState>,
+    ) {
+        let Some(mut state_machine) =
+            Self::get_hsm_state_machine(&mut commands, &mut query, state_machine_id)
+        else {
+            return;
+        };
+        let Some(state_tree) =
+            Self::get_state_tree(&mut commands, &query_state_tree, state_machine.state_tree())
+        else {
+            return;
+        };
+        state_machine.handle_chain(
+            &mut commands,
+            state_machine_id,
+            next_state_id,
+            state_tree,
+            &query_state,
+        );
+    }
+
     fn handle_chain(
         &mut self,
         commands: &mut Commands,
@@ -612,6 +716,12 @@ impl HsmStateMachine {
 
         Self::process_enter_path(&mut next_state_table, &enter_path, query_state);
 
+        // 目标是当前状态的祖先时没有需要进入的状态，退出完成后恢复目标状态
+        // When the target is an ancestor there is nothing to enter, so resume the target once the exits are done
+        if let [target] = enter_path[..] {
+            next_state_table.push(Transition::Update(target));
+        }
+
         next_state_table
     }
 
@@ -33,23 +33,50 @@
 
 use std::ops::ControlFlow;
 
-use bevy::{platform::collections::HashMap, prelude::*};
+use bevy::{
+    ecs::entity::{EntityMapper, MapEntities},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use smallvec::SmallVec;
 
-use crate::hsm::transition_strategy::TraversalStrategy;
+use crate::hsm::{priority::StatePriority, transition_strategy::TraversalStrategy};
 
 ///# 状态树结构/StateTree
 ///
 /// 管理状态之间的层次关系，支持父子状态的添加、删除和查询操作。
 ///
 /// Manage the hierarchical relationships between states, supporting add, delete, and query operations for parent-child states.
-#[derive(Component, Clone, PartialEq, Eq, Debug)]
+///
+/// # 封存\Sealing
+/// * 状态树被封存（[`StateTree::seal`]，或状态机首次启动时自动封存）后会构建 [`SealedStateTree`] 查找表，
+///   转换时按表中已按优先级排好的子状态列表遍历，而不是每次都从 [`World`] 读取优先级并排序。
+/// - Once sealed ([`StateTree::seal`], or automatically when a machine first starts), the tree builds a
+///   [`SealedStateTree`] lookup table and transitions walk its priority-sorted child lists instead of reading and sorting
+///   priorities from the [`World`] every time.
+/// * 只有显式的拓扑修改（添加、移除、移动状态）会使封存失效，之后需要再次调用 [`StateTree::seal`]；
+///   封存状态树中的 [`StatePriority`] 变化会在下一次转换前自动重建查找表。
+/// - Only explicit topology edits (adding, removing or moving states) invalidate the seal, after which
+///   [`StateTree::seal`] has to be called again; [`StatePriority`] changes within a sealed tree rebuild the table
+///   before the next transition.
+#[derive(Component, Clone, Debug)]
 pub struct StateTree {
     /// 根状态实体/Root state entity
     root: Entity,
     /// 状态树节点映射/State tree node map
     tree: HashMap<Entity, StateTreeNode>,
+    /// 封存后的查找表/Lookup table built once sealed
+    sealed: Option<SealedStateTree>,
+}
+
+impl PartialEq for StateTree {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.tree == other.tree
+    }
 }
 
+impl Eq for StateTree {}
+
 impl StateTree {
     /// 创建新的状态树
     /// # 示例
@@ -65,6 +92,88 @@ impl StateTree {
         Self {
             root,
             tree: HashMap::from([(root, StateTreeNode::new(None))]),
+            sealed: None,
+        }
+    }
+
+    /// 按当前的拓扑与 [`StatePriority`] 封存状态树，返回状态树是否存在
+    ///
+    /// Seal the state tree with the current topology and [`StatePriority`], returning whether the tree exists
+    pub fn seal(world: &mut World, state_tree: Entity) -> bool {
+        let Some(tree) = world.get::<StateTree>(state_tree) else {
+            return false;
+        };
+        let sealed = SealedStateTree::build(tree, |state| StatePriority::of(world, state));
+        let states = tree.iter().collect::<Vec<_>>();
+        if let Some(mut tree) = world.get_mut::<StateTree>(state_tree) {
+            tree.sealed = Some(sealed);
+        }
+        world
+            .get_resource_or_init::<SealedTreeIndex>()
+            .insert(state_tree, states);
+        true
+    }
+
+    /// 丢弃封存的查找表
+    ///
+    /// Drop the sealed lookup table
+    pub fn unseal(&mut self) {
+        self.sealed = None;
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.is_some()
+    }
+
+    /// 封存的查找表，未封存或封存已失效时为 `None`
+    ///
+    /// The sealed lookup table, `None` when not sealed or invalidated
+    pub fn sealed(&self) -> Option<&SealedStateTree> {
+        self.sealed.as_ref()
+    }
+
+    /// 状态的深度，根状态为 0
+    ///
+    /// The depth of a state, 0 for the root
+    pub fn depth(&self, state: Entity) -> Option<usize> {
+        match &self.sealed {
+            Some(sealed) => sealed.depth(state),
+            None => self.contains(state).then(|| self.path_iter(state).count()),
+        }
+    }
+
+    /// 封存状态树中的优先级发生变化时重建查找表
+    ///
+    /// Rebuild the lookup table when a priority within a sealed tree changed
+    pub(crate) fn refresh_sealed(
+        changed: Query<Entity, Changed<StatePriority>>,
+        index: Option<ResMut<SealedTreeIndex>>,
+        mut trees: Query<&mut StateTree>,
+        priorities: Query<&StatePriority>,
+    ) {
+        let Some(mut index) = index else {
+            return;
+        };
+        if index.0.is_empty() || changed.is_empty() {
+            return;
+        }
+        let dirty = changed
+            .iter()
+            .filter_map(|state| index.0.get(&state))
+            .flatten()
+            .copied()
+            .collect::<HashSet<_>>();
+        for state_tree in dirty {
+            let Ok(mut tree) = trees.get_mut(state_tree) else {
+                index.remove(state_tree);
+                continue;
+            };
+            if tree.is_sealed() {
+                let sealed = SealedStateTree::build(&tree, |state| {
+                    priorities.get(state).map_or(0, |priority| priority.0)
+                });
+                tree.bypass_change_detection().sealed = Some(sealed);
+            }
         }
     }
 
@@ -104,6 +213,7 @@ impl StateTree {
         if let Some(node) = self.tree.get_mut(&from) {
             node.push(to);
             self.tree.insert(to, StateTreeNode::new(Some(from)));
+            self.sealed = None;
         }
         self
     }
@@ -123,6 +233,7 @@ impl StateTree {
             to.iter().for_each(|to| {
                 self.tree.insert(*to, StateTreeNode::new(Some(from)));
             });
+            self.sealed = None;
         }
         self
     }
@@ -135,9 +246,11 @@ impl StateTree {
             node.sub_states.retain(|&s| s != to);
 
             let mut node = self.tree.remove(&to)?;
+            self.sealed = None;
             let mut new_tree = Self {
                 root: to,
                 tree: HashMap::default(),
+                sealed: None,
             };
             node.super_state = None;
             self.extract_subtree(&mut new_tree, to, node);
@@ -147,6 +260,41 @@ impl StateTree {
         None
     }
 
+    /// 将一个状态连同其子树移动到新的父状态下，成为其最后一个子状态。
+    /// 根状态、不在树中的状态，以及新父状态位于被移动子树内时，不做任何修改并返回 `false`
+    ///
+    /// Move a state together with its subtree under a new super state, as its last sub-state.
+    /// Returns `false` without changing anything for the root, for states outside the tree, and when the new super state
+    /// lies inside the moved subtree
+    pub fn reparent(&mut self, state: Entity, new_super_state: Entity) -> bool {
+        if !self.can_reparent(state, new_super_state) {
+            return false;
+        }
+        if let Some(old_super_state) = self.get_super_state(state)
+            && let Some(node) = self.tree.get_mut(&old_super_state)
+        {
+            node.sub_states.retain(|&s| s != state);
+        }
+        if let Some(node) = self.tree.get_mut(&new_super_state) {
+            node.push(state);
+        }
+        if let Some(node) = self.tree.get_mut(&state) {
+            node.super_state = Some(new_super_state);
+        }
+        self.sealed = None;
+        true
+    }
+
+    /// 检查 [`StateTree::reparent`] 能否将状态移动到新的父状态下
+    ///
+    /// Check whether [`StateTree::reparent`] can move the state under the new super state
+    pub fn can_reparent(&self, state: Entity, new_super_state: Entity) -> bool {
+        self.get_super_state(state).is_some()
+            && self.contains(new_super_state)
+            && new_super_state != state
+            && !self.path_iter(new_super_state).any(|s| s == state)
+    }
+
     /// 将指定节点及其所有子节点从源树移动到目标树
     fn extract_subtree(
         &mut self,
@@ -240,7 +388,14 @@ impl StateTree {
                 sub_states,
             }) => match traversal {
                 Some(traversal) => traversal.0.traverse(world, sub_states.as_slice()),
-                None => sub_states.to_vec(),
+                None => match &self.sealed {
+                    Some(sealed) => sealed.children(state).to_vec(),
+                    None => {
+                        let mut sub_states = sub_states.to_vec();
+                        StatePriority::sort(world, &mut sub_states);
+                        sub_states
+                    }
+                },
             },
             None => Vec::new(),
         }
@@ -261,8 +416,12 @@ impl StateTree {
                 traversal,
                 sub_states,
             }) => {
-                let sub_states = world
-                    .entity(sub_states.as_slice())
+                let sub_states = match (traversal, &self.sealed) {
+                    (None, Some(sealed)) => sealed.children(state),
+                    _ => sub_states.as_slice(),
+                };
+                let mut sub_states = world
+                    .entity(sub_states)
                     .into_iter()
                     .filter(|e| f(e))
                     .map(|e| e.id())
@@ -270,7 +429,56 @@ impl StateTree {
 
                 match traversal {
                     Some(traversal) => traversal.0.traverse(world, sub_states.as_slice()),
-                    None => sub_states,
+                    None if self.sealed.is_some() => sub_states,
+                    None => {
+                        StatePriority::sort(world, &mut sub_states);
+                        sub_states
+                    }
+                }
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// 在某个状态机的上下文中迭代子状态，遍历策略可以使用该状态机上的数据（例如 [`HsmRng`](crate::prelude::HsmRng)）
+    ///
+    /// Iterate over sub-states in the context of a state machine, letting the traversal strategy use data on that machine
+    /// (e.g. [`HsmRng`](crate::prelude::HsmRng))
+    pub fn traversal_iter_for(
+        &self,
+        world: &World,
+        state_machine: Entity,
+        state: Entity,
+        f: impl Fn(&EntityRef) -> bool,
+    ) -> Vec<Entity> {
+        match self.tree.get(&state) {
+            Some(StateTreeNode {
+                super_state: _,
+                traversal,
+                sub_states,
+            }) => {
+                let sub_states = match (traversal, &self.sealed) {
+                    (None, Some(sealed)) => sealed.children(state),
+                    _ => sub_states.as_slice(),
+                };
+                let mut sub_states = world
+                    .entity(sub_states)
+                    .into_iter()
+                    .filter(|e| f(e))
+                    .map(|e| e.id())
+                    .collect::<Vec<_>>();
+
+                match traversal {
+                    Some(traversal) => {
+                        traversal
+                            .0
+                            .traverse_for(world, state_machine, sub_states.as_slice())
+                    }
+                    None if self.sealed.is_some() => sub_states,
+                    None => {
+                        StatePriority::sort(world, &mut sub_states);
+                        sub_states
+                    }
                 }
             }
             None => Vec::new(),
@@ -325,6 +533,133 @@ impl StateTree {
     }
 }
 
+impl MapEntities for StateTree {
+    fn map_entities<E: EntityMapper>(&mut self, entity_mapper: &mut E) {
+        self.sealed = None;
+        self.root = entity_mapper.get_mapped(self.root);
+        self.tree = std::mem::take(&mut self.tree)
+            .into_iter()
+            .map(|(state, mut node)| {
+                node.super_state = node
+                    .super_state
+                    .map(|state| entity_mapper.get_mapped(state));
+                for sub_state in node.sub_states.iter_mut() {
+                    *sub_state = entity_mapper.get_mapped(*sub_state);
+                }
+                (entity_mapper.get_mapped(state), node)
+            })
+            .collect();
+    }
+}
+
+/// 封存状态树中的状态到所属状态树的映射，使优先级变化时无需遍历所有状态树
+///
+/// Maps the states of sealed trees to their trees, so a priority change does not scan every state tree
+#[derive(Resource, Default, Debug)]
+pub(crate) struct SealedTreeIndex(HashMap<Entity, SmallVec<[Entity; 1]>>);
+
+impl SealedTreeIndex {
+    fn insert(&mut self, state_tree: Entity, states: impl IntoIterator<Item = Entity>) {
+        for state in states {
+            let trees = self.0.entry(state).or_default();
+            if !trees.contains(&state_tree) {
+                trees.push(state_tree);
+            }
+        }
+    }
+
+    fn remove(&mut self, state_tree: Entity) {
+        self.0.retain(|_, trees| {
+            trees.retain(|tree| *tree != state_tree);
+            !trees.is_empty()
+        });
+    }
+}
+
+/// # 封存的状态树\Sealed State Tree
+/// * [`StateTree`] 封存后构建的扁平查找表：每个状态的父状态、深度，以及按 [`StatePriority`] 从高到低排好的子状态列表
+///   （优先级相同时保持添加顺序），所有子状态列表连续存放在同一个数组中。
+/// - The flat lookup table a [`StateTree`] builds once sealed: the super state and depth of every state, and its sub-states
+///   sorted by [`StatePriority`] from high to low (keeping insertion order on ties), all child lists stored back to back in
+///   a single array.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SealedStateTree {
+    index: HashMap<Entity, u32>,
+    nodes: Vec<SealedNode>,
+    children: Vec<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SealedNode {
+    super_state: Option<Entity>,
+    depth: u32,
+    first_child: u32,
+    child_count: u32,
+}
+
+impl SealedStateTree {
+    fn build(tree: &StateTree, priority: impl Fn(Entity) -> i32) -> Self {
+        let mut sealed = Self {
+            index: HashMap::with_capacity(tree.len()),
+            nodes: Vec::with_capacity(tree.len()),
+            children: Vec::with_capacity(tree.len()),
+        };
+        let mut queue = std::collections::VecDeque::from([(tree.root, 0)]);
+        while let Some((state, depth)) = queue.pop_front() {
+            let Some(node) = tree.tree.get(&state) else {
+                continue;
+            };
+            let mut sub_states = node.sub_states.clone();
+            sub_states.sort_by_key(|&state| std::cmp::Reverse(priority(state)));
+            sealed.index.insert(state, sealed.nodes.len() as u32);
+            sealed.nodes.push(SealedNode {
+                super_state: node.super_state,
+                depth,
+                first_child: sealed.children.len() as u32,
+                child_count: sub_states.len() as u32,
+            });
+            queue.extend(sub_states.iter().map(|&sub_state| (sub_state, depth + 1)));
+            sealed.children.extend(sub_states);
+        }
+        sealed
+    }
+
+    fn node(&self, state: Entity) -> Option<&SealedNode> {
+        self.index
+            .get(&state)
+            .map(|&index| &self.nodes[index as usize])
+    }
+
+    /// 按优先级排好的子状态
+    ///
+    /// The sub-states sorted by priority
+    pub fn children(&self, state: Entity) -> &[Entity] {
+        self.node(state).map_or(&[], |node| {
+            let start = node.first_child as usize;
+            &self.children[start..start + node.child_count as usize]
+        })
+    }
+
+    pub fn super_state(&self, state: Entity) -> Option<Entity> {
+        self.node(state).and_then(|node| node.super_state)
+    }
+
+    /// 状态的深度，根状态为 0
+    ///
+    /// The depth of a state, 0 for the root
+    pub fn depth(&self, state: Entity) -> Option<usize> {
+        self.node(state).map(|node| node.depth as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
 /// 状态树节点
 ///
 /// State tree node
@@ -428,6 +763,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_seal() {
+        let mut world = World::new();
+        let v = (0..5).map(|_| world.spawn_empty().id()).collect::<Vec<_>>();
+        world.entity_mut(v[3]).insert(StatePriority(5));
+        let mut tree = StateTree::new(v[0]);
+        tree.with_children(v[0], &[v[1], v[2], v[3]])
+            .with_child(v[1], v[4]);
+        let state_tree = world.spawn(tree).id();
+
+        assert!(StateTree::seal(&mut world, state_tree));
+        let tree = world.get::<StateTree>(state_tree).unwrap();
+        let sealed = tree.sealed().unwrap();
+        assert_eq!(sealed.len(), 5);
+        assert_eq!(sealed.children(v[0]), [v[3], v[1], v[2]]);
+        assert_eq!(sealed.super_state(v[4]), Some(v[1]));
+        assert_eq!(tree.depth(v[4]), Some(2));
+        assert_eq!(tree.traversal_iter(&world, v[0]), [v[3], v[1], v[2]]);
+
+        // 优先级变化只重建所属的封存状态树
+        // A priority change only rebuilds the sealed tree the state belongs to
+        let other = world.spawn_empty().id();
+        let other_tree = world.spawn(StateTree::new(other)).id();
+        assert!(StateTree::seal(&mut world, other_tree));
+        world.entity_mut(v[2]).insert(StatePriority(9));
+        world.run_system_cached(StateTree::refresh_sealed).unwrap();
+        let tree = world.get::<StateTree>(state_tree).unwrap();
+        assert_eq!(tree.sealed().unwrap().children(v[0]), [v[2], v[3], v[1]]);
+
+        // 拓扑修改使封存失效
+        // A topology edit invalidates the seal
+        let mut tree = world.get_mut::<StateTree>(state_tree).unwrap();
+        tree.reparent(v[4], v[2]);
+        assert!(!tree.is_sealed());
+        assert_eq!(tree.depth(v[4]), Some(2));
+    }
+
     #[test]
     fn test_has_link() {
         let v = (0..3u32)
@@ -443,6 +815,33 @@ mod tests {
         assert!(tree.has_link(v[1], v[2]));
     }
 
+    #[test]
+    fn test_reparent() {
+        let v = (0..5u32)
+            .filter_map(Entity::from_raw_u32)
+            .collect::<Vec<_>>();
+        let mut tree = StateTree::new(v[0]);
+        tree.with_child(v[0], v[1]);
+        tree.with_child(v[0], v[2]);
+        tree.with_child(v[1], v[3]);
+        tree.with_child(v[3], v[4]);
+
+        assert!(tree.reparent(v[3], v[2]));
+        assert_eq!(tree.get_sub_states(v[1]), Some([].as_slice()));
+        assert_eq!(tree.get_sub_states(v[2]), Some([v[3]].as_slice()));
+        assert_eq!(
+            tree.path_iter(v[4]).collect::<Vec<_>>(),
+            vec![v[3], v[2], v[0]]
+        );
+
+        // 不能移动根状态，也不能移动到自身的子树中
+        // The root cannot move, nor can a state move into its own subtree
+        assert!(!tree.reparent(v[0], v[2]));
+        assert!(!tree.reparent(v[2], v[4]));
+        assert!(!tree.reparent(v[3], v[3]));
+        assert_eq!(tree.get_super_state(v[3]), Some(v[2]));
+    }
+
     #[test]
     fn test_path_iter() {
         let v = (0..3u32)
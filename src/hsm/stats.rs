@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// # 状态机统计\State Machine Statistics
+/// * 开启 `stats` 特性后，每个 [`HsmStateMachine`](crate::prelude::HsmStateMachine) 都会带有该组件，
+///   用于数值平衡与行为分析，例如统计 AI 在各个状态中停留时间的分布。
+/// - With the `stats` feature, every [`HsmStateMachine`](crate::prelude::HsmStateMachine) carries this component, for
+///   balancing and analytics such as the distribution of time an AI spends in each state.
+/// * 活动状态每改变一次计为一次转换（进入子状态与退回父状态都算），启动时进入的初始状态不计入。
+/// - Every change of the active state counts as a transition (entering a sub-state and returning to the parent alike);
+///   the initial state entered at start does not count.
+/// * 时间取自状态机的 [`StateClock`](crate::prelude::StateClock)，没有时钟时取自 [`Time`]，因此暂停期间不计入停留时间。
+/// - Time is read from the machine's [`StateClock`](crate::prelude::StateClock), or from [`Time`] without one, so a
+///   paused clock does not count towards the time in a state.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn report(query: Query<(&HsmStats, &StateClock)>, names: Query<&Name>) {
+///     for (stats, clock) in query.iter() {
+///         for (state, time) in stats.total_times(clock.elapsed()) {
+///             let name = names.get(state).map_or("?", Name::as_str);
+///             info!("{}: {:.1}s over {} transitions", name, time.as_secs_f32(), stats.transitions);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmStats {
+    /// 活动状态改变的次数
+    ///
+    /// How many times the active state changed
+    pub transitions: u64,
+    /// 最近一次转换的时刻
+    ///
+    /// The time of the most recent transition
+    pub last_transition: Duration,
+    /// 每个状态已结束的停留时间之和，不包含当前活动状态正在进行的停留
+    ///
+    /// The summed time of every finished stay per state, excluding the ongoing stay of the active state
+    pub total_time_by_state: HashMap<Entity, Duration>,
+    active: Option<(Entity, Duration)>,
+}
+
+impl HsmStats {
+    /// 当前活动状态与进入它的时刻
+    ///
+    /// The active state and the time it was entered
+    pub fn active(&self) -> Option<(Entity, Duration)> {
+        self.active
+    }
+
+    /// 某个状态的总停留时间，包含当前活动状态截至 `now` 的停留
+    ///
+    /// The total time spent in a state, including the ongoing stay of the active state up to `now`
+    pub fn total_time(&self, state: Entity, now: Duration) -> Duration {
+        let finished = self
+            .total_time_by_state
+            .get(&state)
+            .copied()
+            .unwrap_or_default();
+        match self.active {
+            Some((active, since)) if active == state => finished + now.saturating_sub(since),
+            _ => finished,
+        }
+    }
+
+    /// 每个状态的总停留时间，包含当前活动状态截至 `now` 的停留
+    ///
+    /// The total time spent in every state, including the ongoing stay of the active state up to `now`
+    pub fn total_times(&self, now: Duration) -> HashMap<Entity, Duration> {
+        let mut times = self.total_time_by_state.clone();
+        if let Some((active, since)) = self.active {
+            *times.entry(active).or_default() += now.saturating_sub(since);
+        }
+        times
+    }
+
+    /// 清空统计，当前活动状态从 `now` 重新计时
+    ///
+    /// Clear the statistics, restarting the stay of the active state at `now`
+    pub fn reset(&mut self, now: Duration) {
+        self.transitions = 0;
+        self.last_transition = Duration::ZERO;
+        self.total_time_by_state.clear();
+        if let Some((_, since)) = &mut self.active {
+            *since = now;
+        }
+    }
+
+    pub(crate) fn record(&mut self, state: Entity, now: Duration) {
+        match self.active {
+            Some((active, _)) if active == state => return,
+            Some((active, since)) => {
+                *self.total_time_by_state.entry(active).or_default() += now.saturating_sub(since);
+                self.transitions += 1;
+                self.last_transition = now;
+            }
+            None => {}
+        }
+        self.active = Some((state, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hsm_stats() {
+        let idle = Entity::from_raw_u32(1).unwrap();
+        let alert = Entity::from_raw_u32(2).unwrap();
+        let secs = Duration::from_secs;
+
+        let mut stats = HsmStats::default();
+        stats.record(idle, secs(0));
+        stats.record(idle, secs(1));
+        assert_eq!(stats.transitions, 0);
+
+        stats.record(alert, secs(3));
+        stats.record(idle, secs(4));
+        stats.record(alert, secs(6));
+        assert_eq!(stats.transitions, 3);
+        assert_eq!(stats.last_transition, secs(6));
+        assert_eq!(stats.total_time_by_state[&idle], secs(5));
+        assert_eq!(stats.total_time(alert, secs(10)), secs(5));
+        assert_eq!(stats.total_times(secs(10))[&alert], secs(5));
+
+        stats.reset(secs(10));
+        assert_eq!(stats.transitions, 0);
+        assert_eq!(stats.total_time(alert, secs(12)), secs(2));
+    }
+}
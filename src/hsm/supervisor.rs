@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    context::{ActionContext, GuardContext},
+    fault::HsmFaulted,
+    markers::Terminated,
+    state_actions::RegisterStateSystem,
+};
+
+/// 所有子状态机都已终止（或已被销毁）时返回 `true` 的内置守卫名称
+///
+/// Name of the built-in guard returning `true` once every child machine has terminated (or been despawned)
+pub const ALL_CHILDREN_TERMINATED: &str = "all_children_terminated";
+
+/// 任一子状态机被标记为 [`HsmFaulted`] 时返回 `true` 的内置守卫名称
+///
+/// Name of the built-in guard returning `true` once any child machine is marked [`HsmFaulted`]
+pub const ANY_CHILD_FAULTED: &str = "any_child_faulted";
+
+type SpawnChild = dyn Fn(EntityCommands, ActionContext, usize) + 'static + Send + Sync;
+
+/// # 监督状态\Supervisor State
+/// * 挂载在状态实体上：进入该状态时按模板生成 `count` 个子状态机，退出时将它们销毁。
+///   模板收到新实体的 [`EntityCommands`]、监督状态的 [`ActionContext`] 与子状态机的序号。
+/// - Lives on a state entity: entering the state spawns `count` child machines from the template, and exiting it
+///   despawns them. The template receives the new entity's [`EntityCommands`], the supervising state's
+///   [`ActionContext`] and the index of the child.
+/// * 销毁的只是子状态机实体（及其 [`Children`]），多个子状态机共享的状态树由模板的提供者自行管理。
+/// - Only the child machine entities (and their [`Children`]) are despawned; state trees shared between children are
+///   left to whoever provides the template.
+/// * 状态的条件可以通过内置守卫 [`ALL_CHILDREN_TERMINATED`] 与 [`ANY_CHILD_FAULTED`] 查询子状态机，
+///   子状态机带有指回监督者的 [`SupervisedBy`]。
+/// - The state's conditions can query the children through the built-in guards [`ALL_CHILDREN_TERMINATED`] and
+///   [`ANY_CHILD_FAULTED`]; children carry a [`SupervisedBy`] pointing back at their supervisor.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, worker_tree: Entity, worker_root: Entity) {
+/// commands.spawn((
+///     HsmState::default(),
+///     HsmSupervisor::new(4, move |mut child: EntityCommands, context: ActionContext, _index: usize| {
+///         child.insert((
+///             ServiceTarget(context.service_target),
+///             HsmStateMachine::with(worker_tree, worker_root, 10),
+///             StateLifecycle::default(),
+///         ));
+///     }),
+///     GuardExit::new(ALL_CHILDREN_TERMINATED),
+/// ));
+/// # }
+/// ```
+#[derive(Component, Clone)]
+pub struct HsmSupervisor {
+    count: usize,
+    template: Arc<SpawnChild>,
+}
+
+impl HsmSupervisor {
+    pub fn new<F>(count: usize, template: F) -> Self
+    where
+        F: Fn(EntityCommands, ActionContext, usize) + 'static + Send + Sync,
+    {
+        Self {
+            count,
+            template: Arc::new(template),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn enter_command(context: ActionContext) -> impl Command {
+        move |world: &mut World| {
+            let Some(supervisor) = world.get::<Self>(context.state()).cloned() else {
+                return;
+            };
+            let children = (0..supervisor.count)
+                .map(|index| {
+                    let child = world
+                        .spawn(SupervisedBy {
+                            state_machine: context.state_machine,
+                            state: context.state(),
+                        })
+                        .id();
+                    (supervisor.template)(world.commands().entity(child), context, index);
+                    child
+                })
+                .collect::<Vec<_>>();
+            world.flush();
+            world
+                .entity_mut(context.state_machine)
+                .entry::<SupervisedChildren>()
+                .or_default()
+                .get_mut()
+                .0
+                .insert(context.state(), children);
+        }
+    }
+
+    pub(crate) fn exit_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            let Some(children) = world
+                .get_mut::<SupervisedChildren>(state_machine)
+                .and_then(|mut supervised| supervised.0.remove(&state))
+            else {
+                return;
+            };
+            for child in children {
+                let _ = world.try_despawn(child);
+            }
+        }
+    }
+}
+
+/// # 监督者\Supervised By
+/// * 由 [`HsmSupervisor`] 生成的子状态机上的组件，指向监督它的状态机与状态。
+/// - Component on the child machines spawned by a [`HsmSupervisor`], pointing at the supervising machine and state.
+#[derive(Component, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SupervisedBy {
+    pub state_machine: Entity,
+    pub state: Entity,
+}
+
+/// # 受监督的子状态机\Supervised Children
+/// * 挂载在状态机实体上，按监督状态保存其生成的子状态机，监督状态退出时清除；
+///   该组件被移除（例如状态机被销毁）时，所有子状态机也会被销毁。
+/// - Lives on the state machine entity and holds the children spawned by each supervising state, cleared when that state
+///   exits; removing the component (for example by despawning the machine) despawns all children as well.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+#[component(on_remove = Self::on_remove)]
+pub struct SupervisedChildren(HashMap<Entity, Vec<Entity>>);
+
+impl SupervisedChildren {
+    /// 获取某个监督状态生成的子状态机
+    ///
+    /// Get the children spawned by a supervising state
+    pub fn get(&self, state: Entity) -> &[Entity] {
+        self.0.get(&state).map_or(&[], Vec::as_slice)
+    }
+
+    /// 遍历所有处于活动中的监督状态生成的子状态机
+    ///
+    /// Iterate the children of all active supervising states
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.values().flatten().copied()
+    }
+
+    fn on_remove(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let Some(children) = world
+            .get::<Self>(entity)
+            .map(|supervised| supervised.iter().collect::<Vec<_>>())
+        else {
+            return;
+        };
+        let mut commands = world.commands();
+        for child in children {
+            commands.entity(child).try_despawn();
+        }
+    }
+
+    fn children<'a>(
+        context: &GuardContext,
+        query: &'a Query<&SupervisedChildren>,
+    ) -> Option<impl Iterator<Item = Entity> + 'a> {
+        let supervised = query.get(context.state_machine).ok()?;
+        supervised
+            .0
+            .values()
+            .any(|children| !children.is_empty())
+            .then(|| supervised.iter())
+    }
+
+    fn all_terminated(
+        context: In<GuardContext>,
+        query: Query<&SupervisedChildren>,
+        children: Query<Has<Terminated>>,
+    ) -> bool {
+        Self::children(&context, &query)
+            .is_some_and(|mut iter| iter.all(|child| children.get(child).unwrap_or(true)))
+    }
+
+    fn any_faulted(
+        context: In<GuardContext>,
+        query: Query<&SupervisedChildren>,
+        children: Query<Has<HsmFaulted>>,
+    ) -> bool {
+        Self::children(&context, &query)
+            .is_some_and(|mut iter| iter.any(|child| children.get(child).unwrap_or(false)))
+    }
+}
+
+pub(crate) fn register_supervisor_guards(app: &mut App) {
+    app.register_guard(ALL_CHILDREN_TERMINATED, SupervisedChildren::all_terminated)
+        .register_guard(ANY_CHILD_FAULTED, SupervisedChildren::any_faulted);
+}
@@ -1,18 +1,40 @@
-use std::{any::type_name, fmt::Debug, sync::Arc};
+use std::{any::type_name, cell::RefCell, fmt::Debug, sync::Arc};
 
-use bevy::{ecs::schedule::ScheduleLabel, platform::collections::HashSet, prelude::*};
+use bevy::{
+    ecs::schedule::{InternedScheduleLabel, ScheduleLabel},
+    platform::collections::HashSet,
+    prelude::*,
+};
 
 use crate::{
+    builtin_guards::TransitionCooldowns,
     context::GuardContext,
     error::StateMachineError,
+    guards::{CompiledGuard, GuardCondition},
     hsm::{
         HsmState,
-        state_lifecycle::StateLifecycle,
+        disabled::DisabledState,
+        event_log::HsmEventLog,
+        explain::{HsmExplain, TransitionOutcome},
+        fallthrough::HsmFallthrough,
+        guards::{
+            GuardEnterExpensive, GuardEnterExpensiveCache, GuardEnterSchedule, GuardExitSchedule,
+            ScheduledGuardVerdicts,
+        },
+        hooks::HsmTransitionHooks,
+        payload::HsmTransitionPayload,
+        pipeline::TransitionPipeline,
+        priority::{EnterSelection, LastChildren, StatePriority},
+        requirements::HsmEnterRequirements,
+        sleep::HsmSleep,
+        state_lifecycle::{LifecycleQueue, StateLifecycle},
         state_machine::{Transition, *},
         state_tree::StateTree,
     },
+    local_registry::LocalRegistry,
     markers::*,
     prelude::{GuardEnter, GuardEnterCache, GuardExit, GuardExitCache, ServiceTarget},
+    rng::HsmRng,
 };
 
 /// 状态转换策略，用于控制状态转换行为
@@ -107,6 +129,17 @@ pub trait StateTraversalStrategy: Send + Sync + 'static {
     /// 给定一个子状态实体列表，按照期望的遍历顺序返回它们。
     fn traverse(&self, world: &World, children: &[Entity]) -> Vec<Entity>;
 
+    /// 在某个状态机的上下文中遍历子状态，默认等同于 [`StateTraversalStrategy::traverse`]。
+    fn traverse_for(
+        &self,
+        world: &World,
+        state_machine: Entity,
+        children: &[Entity],
+    ) -> Vec<Entity> {
+        let _ = state_machine;
+        self.traverse(world, children)
+    }
+
     /// 返回遍历策略的名称。
     fn name(&self) -> &'static str {
         type_name::<Self>()
@@ -173,6 +206,30 @@ impl StateTraversalStrategy for ReverseTraversal {
     }
 }
 
+/// 一个随机遍历策略。
+///
+/// 此策略使用状态机上的 [`HsmRng`] 打乱子状态的顺序；状态机没有 [`HsmRng`] 或不在状态机上下文中时按原顺序返回。
+pub struct RandomTraversal;
+
+impl StateTraversalStrategy for RandomTraversal {
+    fn traverse(&self, _world: &World, children: &[Entity]) -> Vec<Entity> {
+        children.to_vec()
+    }
+
+    fn traverse_for(
+        &self,
+        world: &World,
+        state_machine: Entity,
+        children: &[Entity],
+    ) -> Vec<Entity> {
+        let mut children = children.to_vec();
+        if let Some(rng) = world.get::<HsmRng>(state_machine) {
+            rng.shuffle(&mut children);
+        }
+        children
+    }
+}
+
 fn get_state_tree(world: &World, state_tree_id: Entity) -> Result<&StateTree, StateMachineError> {
     world
         .get::<StateTree>(state_tree_id)
@@ -186,33 +243,57 @@ fn get_hsm_state(world: &World, state: Entity) -> Result<HsmState, StateMachineE
         .ok_or(StateMachineError::HsmStateMissing(state))
 }
 
-fn build_exit_transition_plan(
+/// # 退出转换计划\Exit Transition Plan
+/// * 计算状态 `state_id` 以给定的策略与退出行为退出时依次经过的转换：沿父状态逐级向上，
+///   直到遇到复活（[`ExitTransitionBehavior::Resurrection`]）、重生（[`ExitTransitionBehavior::Rebirth`]）的状态或根状态为止。
+///   使用显式循环而非递归实现，层级再深也不会增加调用栈，可供强制转换、任意状态转换等功能复用。
+/// - Computes the transitions that state `state_id` goes through when exiting with the given strategy and exit behavior:
+///   walks up the super states until one that resurrects ([`ExitTransitionBehavior::Resurrection`]), is reborn
+///   ([`ExitTransitionBehavior::Rebirth`]) or is the root. Implemented as an explicit loop rather than recursion, so deep
+///   hierarchies do not grow the call stack, and reusable by forced transitions, any-state transitions and the like.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn plan(world: &World, state_tree: Entity, leaf: Entity) -> Vec<Transition> {
+///     let hsm_state = world.get::<HsmState>(leaf).copied().unwrap_or_default();
+///     build_exit_transition_plan(world, state_tree, leaf, hsm_state.strategy, hsm_state.behavior)
+///         .unwrap_or_default()
+/// }
+/// ```
+pub fn build_exit_transition_plan(
     world: &World,
     state_tree_id: Entity,
     mut state_id: Entity,
-    strategy: StateTransitionStrategy,
+    mut strategy: StateTransitionStrategy,
     mut behavior: ExitTransitionBehavior,
 ) -> Result<Vec<Transition>, StateMachineError> {
-    match (strategy, behavior) {
-        (
-            StateTransitionStrategy::Nested | StateTransitionStrategy::Parallel,
-            ExitTransitionBehavior::Resurrection,
-        ) => Ok(vec![Transition::Update(state_id)]),
-        (
-            StateTransitionStrategy::Nested | StateTransitionStrategy::Parallel,
-            ExitTransitionBehavior::Rebirth,
-        ) => Ok(vec![Transition::Enter(state_id)]),
-        (StateTransitionStrategy::Nested, ExitTransitionBehavior::Death) => {
-            let state_tree = get_state_tree(world, state_tree_id)?;
-            let mut transition_queue = vec![Transition::Exit(state_id)];
-
-            if state_tree.get_root() == state_id {
+    let mut transition_queue = Vec::new();
+    loop {
+        match (strategy, behavior) {
+            (_, ExitTransitionBehavior::Resurrection) => {
+                transition_queue.push(Transition::Update(state_id));
+                return Ok(transition_queue);
+            }
+            (_, ExitTransitionBehavior::Rebirth) => {
+                transition_queue.push(Transition::Enter(state_id));
                 return Ok(transition_queue);
             }
+            (StateTransitionStrategy::Nested, ExitTransitionBehavior::Death) => {
+                let state_tree = get_state_tree(world, state_tree_id)?;
+                transition_queue.push(Transition::Exit(state_id));
 
-            while let Some(super_state) = state_tree.get_super_state(state_id) {
+                if state_tree.get_root() == state_id {
+                    return Ok(transition_queue);
+                }
+                let Some(super_state) = state_tree.get_super_state(state_id) else {
+                    return Ok(transition_queue);
+                };
                 let next_hsm_state = get_hsm_state(world, super_state)?;
 
+                // 根状态按自身的退出行为处理，不再继续向上
+                // The root follows its own exit behavior and stops the walk
                 if state_tree.get_root() == super_state {
                     transition_queue.push(Transition::with_behavior(
                         super_state,
@@ -221,53 +302,22 @@ fn build_exit_transition_plan(
                     return Ok(transition_queue);
                 }
 
-                if next_hsm_state.strategy == StateTransitionStrategy::Nested
-                    && next_hsm_state.behavior == ExitTransitionBehavior::Death
-                {
-                    transition_queue.push(Transition::Exit(super_state));
-                    state_id = super_state;
-                    continue;
-                }
-
-                transition_queue.extend(build_exit_transition_plan(
-                    world,
-                    state_tree_id,
-                    super_state,
-                    next_hsm_state.strategy,
-                    next_hsm_state.behavior,
-                )?);
-                return Ok(transition_queue);
+                state_id = super_state;
+                strategy = next_hsm_state.strategy;
+                behavior = next_hsm_state.behavior;
             }
-
-            Ok(transition_queue)
-        }
-        (StateTransitionStrategy::Parallel, ExitTransitionBehavior::Death) => {
-            let state_tree = get_state_tree(world, state_tree_id)?;
-
-            while let Some(super_state) = state_tree.get_super_state(state_id) {
+            (StateTransitionStrategy::Parallel, ExitTransitionBehavior::Death) => {
+                let state_tree = get_state_tree(world, state_tree_id)?;
+                let Some(super_state) = state_tree.get_super_state(state_id) else {
+                    transition_queue.push(Transition::End);
+                    return Ok(transition_queue);
+                };
                 let next_hsm_state = get_hsm_state(world, super_state)?;
 
-                if !(next_hsm_state.strategy == StateTransitionStrategy::Parallel
-                    && next_hsm_state.behavior == ExitTransitionBehavior::Death)
-                {
-                    return build_exit_transition_plan(
-                        world,
-                        state_tree_id,
-                        super_state,
-                        next_hsm_state.strategy,
-                        next_hsm_state.behavior,
-                    );
-                }
-
                 state_id = super_state;
+                strategy = next_hsm_state.strategy;
                 behavior = next_hsm_state.behavior;
             }
-
-            match behavior {
-                ExitTransitionBehavior::Rebirth => Ok(vec![Transition::Enter(state_id)]),
-                ExitTransitionBehavior::Resurrection => Ok(vec![Transition::Update(state_id)]),
-                ExitTransitionBehavior::Death => Ok(vec![Transition::End]),
-            }
         }
     }
 }
@@ -278,6 +328,20 @@ fn build_exit_transition_plan(
 #[derive(Resource, Debug, Default, Clone, PartialEq, Eq, Deref, DerefMut)]
 pub(crate) struct CheckOnTransitionStates(HashSet<Entity>);
 
+/// # 转换系统集\Transition System Set
+/// * 层级状态机的转换系统所在的系统集，位于 [`StateMachinePlugin`](crate::StateMachinePlugin) 指定的调度中，
+///   可用于将自定义系统排序在转换评估之前或之后。
+/// - The system set holding the transition systems of hierarchical state machines, in the schedule chosen on
+///   [`StateMachinePlugin`](crate::StateMachinePlugin); useful to order custom systems before or after transition evaluation.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HsmTransitionSystems;
+
+/// 转换系统所在的调度
+///
+/// The schedule the transition systems run in
+#[derive(Resource, Debug, Clone, Copy)]
+pub(crate) struct HsmTransitionSchedule(pub(crate) InternedScheduleLabel);
+
 /// 在指定的调度中安装状态转换系统。
 ///
 /// # Arguments
@@ -285,17 +349,43 @@ pub(crate) struct CheckOnTransitionStates(HashSet<Entity>);
 /// * `app` - Bevy 应用实例。
 /// * `schedule` - 要安装系统的调度标签。
 pub(crate) fn install_transition_systems<T: ScheduleLabel>(app: &mut App, schedule: T) {
+    let schedule = schedule.intern();
+    app.insert_resource(HsmTransitionSchedule(schedule));
     app.add_systems(
         schedule,
-        (handle_enter_transitions, handle_exit_transitions)
+        (
+            crate::hsm::validation::validate_new_state_machines,
+            HsmTransitionPayload::expire.run_if(any_with_component::<HsmTransitionPayload>),
+            crate::hsm::requester::TransitionRequests::resolve,
+            StateTree::refresh_sealed,
+            HsmFallthrough::track.run_if(any_with_component::<HsmFallthrough>),
+            (
+                crate::hsm::transitions::HsmTransitions::handle_automatic_transitions,
+                handle_enter_transitions,
+                handle_exit_transitions,
+            )
+                .chain()
+                .run_if(|check_on_transition_states: Res<CheckOnTransitionStates>| {
+                    !check_on_transition_states.is_empty()
+                }),
+            LifecycleQueue::process.run_if(|queue: Res<LifecycleQueue>| !queue.is_empty()),
+            TransitionPipeline::run_extra_passes
+                .run_if(|query: Query<(), With<TransitionPipeline>>| !query.is_empty()),
+        )
             .chain()
-            .run_if(|check_on_transition_states: Res<CheckOnTransitionStates>| {
-                !check_on_transition_states.is_empty()
-            }),
+            .in_set(HsmTransitionSystems),
+    );
+    app.add_systems(
+        schedule,
+        (
+            ScheduledGuardVerdicts::clear,
+            HsmSleep::sleep.run_if(any_with_component::<HsmSleep>),
+        )
+            .after(HsmTransitionSystems),
     );
 }
 
-fn handle_enter_transitions(
+pub(crate) fn handle_enter_transitions(
     mut commands: Commands,
     check_on_transition_states: Res<CheckOnTransitionStates>,
     query_state_machines: Query<(Entity, &HsmStateMachine), Without<Paused>>,
@@ -314,52 +404,123 @@ fn handle_enter_transitions(
         };
         commands.queue(move |world: &mut World| {
             let Some(state_tree) = world.get::<StateTree>(state_tree_id) else {
-                warn!("{}", StateMachineError::StateTreeNotFound(state_tree_id));
+                StateMachineError::StateTreeNotFound(state_tree_id).report(world);
                 return;
             };
-            let sub_state_iter = state_tree.traversal_iter_with(world, curr_state_id, |e| {
-                if !e.contains::<HsmState>() {
-                    warn!("{}", StateMachineError::HsmStateMissing(e.id()));
-                    return false;
-                }
-                e.contains::<GuardEnter>()
-            });
+            let missing = RefCell::new(Vec::new());
+            let mut sub_states =
+                state_tree.traversal_iter_for(world, state_machine_id, curr_state_id, |e| {
+                    if !e.contains::<HsmState>() {
+                        missing.borrow_mut().push(e.id());
+                        return false;
+                    }
+                    (e.contains::<GuardEnter>() || e.contains::<GuardEnterExpensive>())
+                        && !e.contains::<DisabledState>()
+                });
+            for state in missing.into_inner() {
+                StateMachineError::HsmStateMissing(state).report(world);
+            }
+            LastChildren::prefer(world, state_machine_id, curr_state_id, &mut sub_states);
+            let selection = EnterSelection::of(world, curr_state_id);
             let Some(enter_state_id) = world.resource_scope(
                 |world: &mut World, condition_buffer: Mut<GuardEnterCache>| {
-                    for sub_state_id in sub_state_iter {
-                        let Some(condition_id) = condition_buffer.get(&sub_state_id) else {
-                            continue;
-                        };
-
-                        let service_target = get_service_target(world, state_machine_id);
-                        match condition_id.run(
-                            world,
-                            GuardContext::new(
-                                service_target,
-                                state_machine_id,
-                                curr_state_id,
-                                sub_state_id,
-                            ),
-                        ) {
-                            Ok(true) => return Some(sub_state_id),
-                            Ok(false) => continue,
-                            Err(e) => {
-                                error!(
-                                    "{}",
-                                    StateMachineError::GuardRunFailed {
-                                        state_machine: state_machine_id,
-                                        from_state: curr_state_id,
-                                        to_state: Some(sub_state_id),
-                                        source: e.into(),
+                    world.resource_scope(
+                        |world: &mut World, expensive_buffer: Mut<GuardEnterExpensiveCache>| {
+                            let service_target = get_service_target(world, state_machine_id);
+                            let context_for = |sub_state_id| {
+                                GuardContext::new(
+                                    service_target,
+                                    state_machine_id,
+                                    curr_state_id,
+                                    sub_state_id,
+                                )
+                            };
+
+                            // 廉价层：通过的子状态按遍历顺序等待昂贵层
+                            // Cheap tier: passing sub-states wait for the expensive tier in traversal order
+                            let mut passed = Vec::new();
+                            for sub_state_id in sub_states {
+                                let context = context_for(sub_state_id);
+                                if !HsmEnterRequirements::check(world, context) {
+                                    continue;
+                                }
+                                let expensive = LocalRegistry::guard::<GuardEnterExpensive>(
+                                    world,
+                                    state_machine_id,
+                                    sub_state_id,
+                                    &expensive_buffer,
+                                );
+                                if let Some(condition_id) = LocalRegistry::guard::<GuardEnter>(
+                                    world,
+                                    state_machine_id,
+                                    sub_state_id,
+                                    &condition_buffer,
+                                ) {
+                                    let schedule =
+                                        world.get::<GuardEnterSchedule>(sub_state_id).map(|s| s.0);
+                                    if !check_enter_guard::<GuardEnter>(
+                                        world,
+                                        &condition_id,
+                                        context,
+                                        schedule,
+                                    ) {
+                                        continue;
                                     }
+                                } else if world.entity(sub_state_id).contains::<GuardEnter>()
+                                    || expensive.is_none()
+                                {
+                                    // 守卫未能编译
+                                    // The guard failed to compile
+                                    continue;
+                                }
+                                let settled = expensive.is_none();
+                                passed.push((sub_state_id, expensive));
+                                if settled && selection == EnterSelection::First {
+                                    break;
+                                }
+                            }
+
+                            // 昂贵层
+                            // Expensive tier
+                            let mut best: Option<Entity> = None;
+                            let mut outranked = Vec::new();
+                            for (sub_state_id, expensive) in passed {
+                                if let Some(condition_id) = expensive
+                                    && !check_enter_guard::<GuardEnterExpensive>(
+                                        world,
+                                        &condition_id,
+                                        context_for(sub_state_id),
+                                        None,
+                                    )
+                                {
+                                    continue;
+                                }
+                                if selection == EnterSelection::First {
+                                    return Some(sub_state_id);
+                                }
+                                if let Some(best) = best
+                                    && StatePriority::of(world, sub_state_id)
+                                        <= StatePriority::of(world, best)
+                                {
+                                    outranked.push(sub_state_id);
+                                    continue;
+                                }
+                                outranked.extend(best);
+                                best = Some(sub_state_id);
+                            }
+                            for sub_state_id in outranked {
+                                HsmExplain::record_transition(
+                                    world,
+                                    context_for(sub_state_id),
+                                    TransitionOutcome::Outranked,
                                 );
-                                continue;
                             }
-                        }
-                    }
-                    None
+                            best
+                        },
+                    )
                 },
             ) else {
+                let _ = HsmFallthrough::expire(world, state_machine_id, curr_state_id);
                 return;
             };
 
@@ -370,6 +531,41 @@ fn handle_enter_transitions(
     }
 }
 
+/// 运行一个进入守卫并记录其结果，运行失败时报告错误并视为不成立
+///
+/// Run an enter guard and record its result, reporting a failed run and treating it as not holding
+fn check_enter_guard<T: Component + std::ops::Deref<Target = GuardCondition>>(
+    world: &mut World,
+    condition_id: &CompiledGuard,
+    context: GuardContext,
+    schedule: Option<InternedScheduleLabel>,
+) -> bool {
+    let result = ScheduledGuardVerdicts::run(world, condition_id, context, schedule);
+    HsmExplain::record_condition(
+        world,
+        context,
+        |world| {
+            world
+                .get::<T>(context.to_state())
+                .map(|guard| guard.to_string())
+        },
+        &result,
+    );
+    match result {
+        Ok(result) => result,
+        Err(e) => {
+            StateMachineError::GuardRunFailed {
+                state_machine: context.state_machine,
+                from_state: context.from_state(),
+                to_state: Some(context.to_state()),
+                source: e.into(),
+            }
+            .report(world);
+            false
+        }
+    }
+}
+
 pub(super) fn handle_enter_transition(
     state_machine_id: Entity,
     curr_state_id: Entity,
@@ -377,16 +573,26 @@ pub(super) fn handle_enter_transition(
     strategy: StateTransitionStrategy,
 ) -> impl Command<Result<()>> {
     move |world: &mut World| {
+        let context = GuardContext::new(
+            get_service_target(world, state_machine_id),
+            state_machine_id,
+            curr_state_id,
+            enter_state_id,
+        );
+        if !HsmTransitionHooks::run_before(world, context) {
+            HsmExplain::record_transition(world, context, TransitionOutcome::RejectedByHook);
+            HsmEventLog::record_vetoed(world, context);
+            return Ok(());
+        }
+        HsmExplain::record_transition(world, context, TransitionOutcome::Selected);
+
         world
             .resource_mut::<CheckOnTransitionStates>()
             .remove(&state_machine_id);
 
         let mut service_target = world.entity_mut(state_machine_id);
         let Some(mut state_machine) = service_target.get_mut::<HsmStateMachine>() else {
-            warn!(
-                "{}",
-                StateMachineError::HsmStateMachineMissing(state_machine_id)
-            );
+            StateMachineError::HsmStateMachineMissing(state_machine_id).report(world);
             return Ok(());
         };
 
@@ -403,11 +609,15 @@ pub(super) fn handle_enter_transition(
         };
 
         service_target.insert(next_on_state);
+        HsmTransitionHooks::run_after(world, context);
+        LastChildren::record(world, state_machine_id, curr_state_id, enter_state_id);
+        TransitionCooldowns::record(world, context);
+        HsmEventLog::record_transition(world, context);
         Ok(())
     }
 }
 
-fn handle_exit_transitions(
+pub(crate) fn handle_exit_transitions(
     mut commands: Commands,
     check_on_transition_states: Res<CheckOnTransitionStates>,
     query_state_machines: Query<(Entity, &HsmStateMachine), Without<Paused>>,
@@ -424,51 +634,64 @@ fn handle_exit_transitions(
             continue;
         };
         let Ok(state_tree) = query_state_trees.get(state_tree_id) else {
-            warn!("{}", StateMachineError::StateTreeNotFound(state_tree_id));
+            StateMachineError::StateTreeNotFound(state_tree_id).report_with(&mut commands);
             continue;
         };
         let Some(super_state_id) = state_tree.get_super_state(curr_state_id) else {
-            warn!(
-                "{}",
-                StateMachineError::SuperStateNotFound {
-                    state_tree: state_tree_id,
-                    state: curr_state_id
-                }
-            );
+            StateMachineError::SuperStateNotFound {
+                state_tree: state_tree_id,
+                state: curr_state_id,
+            }
+            .report_with(&mut commands);
             continue;
         };
         commands.queue(move |world: &mut World| -> Result<()> {
             match world.resource_scope(
-                |world: &mut World, exit_guard_cache: Mut<GuardExitCache>| match exit_guard_cache
-                    .get(&curr_state_id)
-                {
-                    Some(guard) => {
-                        let service_target = get_service_target(world, state_machine_id);
-                        guard.run(
-                            world,
-                            GuardContext::new(
+                |world: &mut World, exit_guard_cache: Mut<GuardExitCache>| {
+                    match LocalRegistry::guard::<GuardExit>(
+                        world,
+                        state_machine_id,
+                        curr_state_id,
+                        &exit_guard_cache,
+                    ) {
+                        Some(guard) => {
+                            let service_target = get_service_target(world, state_machine_id);
+                            let schedule =
+                                world.get::<GuardExitSchedule>(curr_state_id).map(|s| s.0);
+                            let context = GuardContext::new(
                                 service_target,
                                 state_machine_id,
                                 curr_state_id,
                                 super_state_id,
-                            ),
-                        )
+                            );
+                            let result =
+                                ScheduledGuardVerdicts::run(world, &guard, context, schedule);
+                            HsmExplain::record_condition(
+                                world,
+                                context,
+                                |world| {
+                                    world
+                                        .get::<GuardExit>(curr_state_id)
+                                        .map(|guard| guard.0.to_string())
+                                },
+                                &result,
+                            );
+                            result
+                        }
+                        None => Ok(false),
                     }
-                    None => Ok(false),
                 },
             ) {
                 Ok(true) => {}
                 Ok(false) => return Ok(()),
                 Err(e) => {
-                    error!(
-                        "{}",
-                        StateMachineError::GuardRunFailed {
-                            state_machine: state_machine_id,
-                            from_state: curr_state_id,
-                            to_state: None,
-                            source: e.into(),
-                        }
-                    );
+                    StateMachineError::GuardRunFailed {
+                        state_machine: state_machine_id,
+                        from_state: curr_state_id,
+                        to_state: None,
+                        source: e.into(),
+                    }
+                    .report(world);
                     return Ok(());
                 }
             };
@@ -492,6 +715,19 @@ pub(super) fn handle_exit_transition(
     exit_state_id: Entity,
 ) -> impl Command<Result<()>> {
     move |world: &mut World| -> Result<()> {
+        let context = GuardContext::new(
+            get_service_target(world, state_machine_id),
+            state_machine_id,
+            curr_state_id,
+            exit_state_id,
+        );
+        if !HsmTransitionHooks::run_before(world, context) {
+            HsmExplain::record_transition(world, context, TransitionOutcome::RejectedByHook);
+            HsmEventLog::record_vetoed(world, context);
+            return Ok(());
+        }
+        HsmExplain::record_transition(world, context, TransitionOutcome::Selected);
+
         world
             .resource_mut::<CheckOnTransitionStates>()
             .remove(&state_machine_id);
@@ -500,7 +736,7 @@ pub(super) fn handle_exit_transition(
             .get::<HsmState>(exit_state_id)
             .map(|state| (state.strategy, state.behavior))
         else {
-            warn!("{}", StateMachineError::HsmStateMissing(exit_state_id));
+            StateMachineError::HsmStateMissing(exit_state_id).report(world);
             return Ok(());
         };
 
@@ -509,21 +745,21 @@ pub(super) fn handle_exit_transition(
 
         let mut service_target = world.entity_mut(state_machine_id);
         let Some(mut state_machine) = service_target.get_mut::<HsmStateMachine>() else {
-            warn!(
-                "{}",
-                StateMachineError::HsmStateMachineMissing(state_machine_id)
-            );
+            StateMachineError::HsmStateMachineMissing(state_machine_id).report(world);
             return Ok(());
         };
 
         state_machine.push_next_states(transition_queue);
         state_machine.set_curr_state(curr_state_id);
         service_target.insert(StateLifecycle::Exit);
+        HsmTransitionHooks::run_after(world, context);
+        TransitionCooldowns::record(world, context);
+        HsmEventLog::record_transition(world, context);
         Ok(())
     }
 }
 
-fn get_service_target(world: &World, state_machine_id: Entity) -> Entity {
+pub(super) fn get_service_target(world: &World, state_machine_id: Entity) -> Entity {
     world
         .get::<ServiceTarget>(state_machine_id)
         .map_or(state_machine_id, |st| st.0)
@@ -1063,4 +1299,130 @@ mod tests {
             ),
         ]);
     }
+
+    /// 递归实现的旧版退出计划，作为迭代实现的对照
+    ///
+    /// The former recursive exit plan, used as a reference for the iterative one
+    fn recursive_exit_plan(
+        world: &World,
+        state_tree_id: Entity,
+        mut state_id: Entity,
+        strategy: StateTransitionStrategy,
+        mut behavior: ExitTransitionBehavior,
+    ) -> Result<Vec<Transition>, StateMachineError> {
+        use ExitTransitionBehavior::*;
+        use StateTransitionStrategy::*;
+        match (strategy, behavior) {
+            (_, Resurrection) => Ok(vec![Transition::Update(state_id)]),
+            (_, Rebirth) => Ok(vec![Transition::Enter(state_id)]),
+            (Nested, Death) => {
+                let state_tree = get_state_tree(world, state_tree_id)?;
+                let mut transition_queue = vec![Transition::Exit(state_id)];
+                if state_tree.get_root() == state_id {
+                    return Ok(transition_queue);
+                }
+                while let Some(super_state) = state_tree.get_super_state(state_id) {
+                    let next = get_hsm_state(world, super_state)?;
+                    if state_tree.get_root() == super_state {
+                        transition_queue
+                            .push(Transition::with_behavior(super_state, next.behavior));
+                        return Ok(transition_queue);
+                    }
+                    if next.strategy == Nested && next.behavior == Death {
+                        transition_queue.push(Transition::Exit(super_state));
+                        state_id = super_state;
+                        continue;
+                    }
+                    transition_queue.extend(recursive_exit_plan(
+                        world,
+                        state_tree_id,
+                        super_state,
+                        next.strategy,
+                        next.behavior,
+                    )?);
+                    return Ok(transition_queue);
+                }
+                Ok(transition_queue)
+            }
+            (Parallel, Death) => {
+                let state_tree = get_state_tree(world, state_tree_id)?;
+                while let Some(super_state) = state_tree.get_super_state(state_id) {
+                    let next = get_hsm_state(world, super_state)?;
+                    if !(next.strategy == Parallel && next.behavior == Death) {
+                        return recursive_exit_plan(
+                            world,
+                            state_tree_id,
+                            super_state,
+                            next.strategy,
+                            next.behavior,
+                        );
+                    }
+                    state_id = super_state;
+                    behavior = next.behavior;
+                }
+                match behavior {
+                    Rebirth => Ok(vec![Transition::Enter(state_id)]),
+                    Resurrection => Ok(vec![Transition::Update(state_id)]),
+                    Death => Ok(vec![Transition::End]),
+                }
+            }
+        }
+    }
+
+    fn spawn_chain(
+        world: &mut World,
+        states: &[(StateTransitionStrategy, ExitTransitionBehavior)],
+    ) -> (Entity, Vec<Entity>) {
+        let ids = states
+            .iter()
+            .map(|&(strategy, behavior)| world.spawn(HsmState::with(strategy, behavior)).id())
+            .collect::<Vec<_>>();
+        let mut state_tree = StateTree::new(ids[0]);
+        for pair in ids.windows(2) {
+            state_tree.with_child(pair[0], pair[1]);
+        }
+        (world.spawn(state_tree).id(), ids)
+    }
+
+    #[test]
+    fn test_exit_plan_matches_recursive() {
+        let digits = ["00", "01", "02", "10", "11", "12"];
+        let mut world = World::new();
+        for depth in 1..=4u32 {
+            for n in 0..digits.len().pow(depth) {
+                let trinary = (0..depth)
+                    .map(|i| digits[n / digits.len().pow(i) % digits.len()])
+                    .collect::<Vec<_>>()
+                    .join("_");
+                let states = create_states_from_trinary(&trinary);
+                let (state_tree, ids) = spawn_chain(&mut world, &states);
+                for (&state, &(strategy, behavior)) in ids.iter().zip(&states) {
+                    assert_eq!(
+                        build_exit_transition_plan(&world, state_tree, state, strategy, behavior)
+                            .unwrap(),
+                        recursive_exit_plan(&world, state_tree, state, strategy, behavior).unwrap(),
+                        "exit plan of {:?} in {}",
+                        state,
+                        trinary
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_plan_deep_hierarchy() {
+        use ExitTransitionBehavior::Death;
+        use StateTransitionStrategy::*;
+
+        let mut world = World::new();
+        let mut states = vec![(Nested, ExitTransitionBehavior::Rebirth)];
+        states.extend((1..20_000).map(|i| (if i % 2 == 0 { Nested } else { Parallel }, Death)));
+        let (state_tree, ids) = spawn_chain(&mut world, &states);
+
+        let leaf = *ids.last().unwrap();
+        let plan = build_exit_transition_plan(&world, state_tree, leaf, Nested, Death).unwrap();
+        assert_eq!(plan.first(), Some(&Transition::Exit(leaf)));
+        assert_eq!(plan.last(), Some(&Transition::Enter(ids[0])));
+    }
 }
@@ -0,0 +1,280 @@
+use std::borrow::Cow;
+
+use bevy::{
+    ecs::entity::{EntityMapper, MapEntities},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    context::GuardContext,
+    error::StateMachineError,
+    guards::{GuardCondition, GuardRegistry},
+    hsm::{
+        disabled::DisabledState, event::HsmTrigger, explain::HsmExplain,
+        guard_retry::HsmGuardRetry, requirements::HsmEnterRequirements,
+        state_machine::HsmStateMachine, transition_strategy::CheckOnTransitionStates,
+    },
+    local_registry::LocalRegistry,
+    markers::Paused,
+    state_actions::ServiceTarget,
+};
+
+/// 命名转换的触发方式
+///
+/// How a named transition is triggered
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HsmTransitionKind {
+    /// 在起点状态的更新阶段检查条件，满足时自动转换
+    ///
+    /// The condition is checked during the update phase of the source state and the transition fires when it holds
+    #[default]
+    Automatic,
+    /// 只有通过 [`HsmFireTransition`] 按名称触发时才检查条件
+    ///
+    /// The condition is only checked when fired by name through [`HsmFireTransition`]
+    Event,
+}
+
+/// # 命名转换\Named Transition
+/// * 以数据形式描述的一条转换边，拥有名称、条件、触发方式与元数据，可在运行时按名称启用或禁用。
+///   转换以链式转换（[`HsmTrigger::chain`]）执行，起点必须是状态机的当前状态。
+/// - A transition edge described as data, with a name, a condition, a kind and metadata; it can be enabled or disabled by
+///   name at runtime. Transitions run as chain transitions ([`HsmTrigger::chain`]) and the source must be the machine's
+///   current state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HsmTransition {
+    pub name: Cow<'static, str>,
+    pub from: Entity,
+    pub to: Entity,
+    /// 为 `None` 时条件视为满足
+    ///
+    /// The condition is treated as satisfied when `None`
+    pub condition: Option<GuardCondition>,
+    pub kind: HsmTransitionKind,
+    pub metadata: HashMap<Cow<'static, str>, Cow<'static, str>>,
+    pub enabled: bool,
+}
+
+impl HsmTransition {
+    pub fn new(name: impl Into<Cow<'static, str>>, from: Entity, to: Entity) -> Self {
+        Self {
+            name: name.into(),
+            from,
+            to,
+            condition: None,
+            kind: HsmTransitionKind::default(),
+            metadata: HashMap::new(),
+            enabled: true,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: impl Into<GuardCondition>) -> Self {
+        self.condition = Some(condition.into());
+        self
+    }
+
+    pub fn with_kind(mut self, kind: HsmTransitionKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_metadata(
+        mut self,
+        key: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// # 命名转换表\Named Transition Table
+/// * 挂载在 [`HsmStateMachine`] 实体上的转换表，按插入顺序检查，第一条满足的转换生效，
+///   并优先于状态上的 [`GuardEnter`](crate::prelude::GuardEnter)/[`GuardExit`](crate::prelude::GuardExit)。
+/// - A transition table living on the [`HsmStateMachine`] entity, checked in insertion order; the first satisfied transition
+///   wins and takes precedence over [`GuardEnter`](crate::prelude::GuardEnter)/[`GuardExit`](crate::prelude::GuardExit) on states.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, state_machine: Entity, idle: Entity, jump: Entity) {
+/// commands.entity(state_machine).insert(HsmTransitions::default().with(
+///     HsmTransition::new("jump", idle, jump)
+///         .with_kind(HsmTransitionKind::Event)
+///         .with_metadata("animation", "jump_start"),
+/// ));
+/// commands.trigger(HsmFireTransition::new(state_machine, "jump"));
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmTransitions(Vec<HsmTransition>);
+
+impl HsmTransitions {
+    pub fn with(mut self, transition: HsmTransition) -> Self {
+        self.0.push(transition);
+        self
+    }
+
+    pub fn push(&mut self, transition: HsmTransition) {
+        self.0.push(transition);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HsmTransition> {
+        self.0.iter().find(|transition| transition.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut HsmTransition> {
+        self.0.iter_mut().find(|transition| transition.name == name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<HsmTransition> {
+        let index = self
+            .0
+            .iter()
+            .position(|transition| transition.name == name)?;
+        Some(self.0.remove(index))
+    }
+
+    /// 按名称启用或禁用转换，返回转换是否存在
+    ///
+    /// Enable or disable a transition by name, returning whether it exists
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.get_mut(name)
+            .map(|transition| transition.enabled = enabled)
+            .is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HsmTransition> {
+        self.0.iter()
+    }
+
+    /// 在状态机当前状态上选择第一条满足条件的转换
+    fn select(
+        world: &mut World,
+        state_machine_id: Entity,
+        filter: impl Fn(&HsmTransition) -> bool,
+    ) -> Option<Entity> {
+        let curr_state_id = world
+            .get::<HsmStateMachine>(state_machine_id)?
+            .curr_state_id();
+        let candidates = world
+            .get::<HsmTransitions>(state_machine_id)?
+            .iter()
+            .filter(|transition| transition.enabled && transition.from == curr_state_id)
+            .filter(|transition| world.get::<DisabledState>(transition.to).is_none())
+            .filter(|transition| filter(transition))
+            .map(|transition| (transition.to, transition.condition.clone()))
+            .collect::<Vec<_>>();
+        let service_target = world
+            .get::<ServiceTarget>(state_machine_id)
+            .map_or(state_machine_id, |target| target.0);
+
+        for (to, condition) in candidates {
+            let context = GuardContext::new(service_target, state_machine_id, curr_state_id, to);
+            if !HsmEnterRequirements::check(world, context) {
+                continue;
+            }
+            let Some(condition) = condition else {
+                return Some(to);
+            };
+            let compiled = LocalRegistry::compile(world, state_machine_id, &condition)
+                .unwrap_or_else(|| {
+                    world
+                        .resource::<GuardRegistry>()
+                        .to_combinator_condition_id(&condition)
+                });
+            let guard = match compiled {
+                Ok(guard) => guard,
+                Err(e) => {
+                    warn!("[HsmTransitions] {}: {}", condition, e);
+                    continue;
+                }
+            };
+            let result = HsmGuardRetry::run(world, context, |world| guard.run(world, context));
+            HsmExplain::record_condition(world, context, |_| Some(condition.to_string()), &result);
+            match result {
+                Ok(true) => return Some(to),
+                Ok(false) => {}
+                Err(e) => StateMachineError::GuardRunFailed {
+                    state_machine: state_machine_id,
+                    from_state: curr_state_id,
+                    to_state: Some(to),
+                    source: e.into(),
+                }
+                .report(world),
+            }
+        }
+        None
+    }
+
+    pub(crate) fn handle_automatic_transitions(
+        mut commands: Commands,
+        check_on_transition_states: Res<CheckOnTransitionStates>,
+        query: Query<Entity, (With<HsmTransitions>, Without<Paused>)>,
+    ) {
+        for state_machine_id in query.iter_many(check_on_transition_states.iter()) {
+            commands.queue(move |world: &mut World| {
+                let Some(to) = Self::select(world, state_machine_id, |transition| {
+                    transition.kind == HsmTransitionKind::Automatic
+                }) else {
+                    return;
+                };
+                world
+                    .resource_mut::<CheckOnTransitionStates>()
+                    .remove(&state_machine_id);
+                world.trigger(HsmTrigger::chain(state_machine_id, to));
+            });
+        }
+    }
+
+    pub(crate) fn handle_fire_transition(on: On<HsmFireTransition>, mut commands: Commands) {
+        let HsmFireTransition {
+            state_machine,
+            name,
+        } = on.event().clone();
+        commands.queue(move |world: &mut World| {
+            if world.get::<Paused>(state_machine).is_some() {
+                return;
+            }
+            let Some(to) = Self::select(world, state_machine, |transition| transition.name == name)
+            else {
+                return;
+            };
+            world
+                .resource_mut::<CheckOnTransitionStates>()
+                .remove(&state_machine);
+            world.trigger(HsmTrigger::chain(state_machine, to));
+        });
+    }
+}
+
+impl MapEntities for HsmTransitions {
+    fn map_entities<E: EntityMapper>(&mut self, entity_mapper: &mut E) {
+        for transition in self.0.iter_mut() {
+            transition.from = entity_mapper.get_mapped(transition.from);
+            transition.to = entity_mapper.get_mapped(transition.to);
+        }
+    }
+}
+
+/// # 触发命名转换\Fire Named Transition
+/// * 按名称触发 [`HsmTransitions`] 中的一条转换（任意触发方式），只有起点为当前状态且条件满足时才会转换。
+/// - Fires a transition of [`HsmTransitions`] by name (of any kind); it only runs when its source is the current state and
+///   its condition holds.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct HsmFireTransition {
+    #[event_target]
+    pub state_machine: Entity,
+    pub name: Cow<'static, str>,
+}
+
+impl HsmFireTransition {
+    pub fn new(state_machine: Entity, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            state_machine,
+            name: name.into(),
+        }
+    }
+}
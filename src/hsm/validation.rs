@@ -0,0 +1,497 @@
+//! # 配置校验\Configuration Validation
+//!
+//! 检测不完整的状态机配置（缺少组件、引用了未注册的系统等），并以 [`InvalidStateConfig`] 事件的形式报告，
+//! 而不是在运行时静默地跳过转换。
+//!
+//! Detects incomplete state machine configurations (missing components, references to unregistered systems, ...)
+//! and reports them as [`InvalidStateConfig`] events instead of silently skipping transitions at runtime.
+
+use std::fmt;
+
+use bevy::{prelude::*, utils::prelude::ShortName};
+
+use crate::{
+    action_dispatcher::ActionDispatch,
+    guards::GuardRegistry,
+    hsm::{
+        HsmState,
+        guards::{GuardEnter, GuardExit},
+        state_lifecycle::StateLifecycle,
+        state_machine::HsmStateMachine,
+        state_tree::StateTree,
+    },
+    labels::{ActionKey, SystemLabel},
+    local_registry::LocalRegistry,
+    state_actions::{
+        ActionRegistry, AfterEnterSystem, AfterExitSystem, BeforeEnterSystem, BeforeExitSystem,
+        OnUpdateSystem, OnUpdateSystems, RequiredServiceComponents, ServiceTarget,
+        TransitionRegistry,
+    },
+};
+
+/// 状态机配置问题
+///
+/// State machine configuration issue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateConfigIssue {
+    /// 状态机引用的实体上没有 [`StateTree`]
+    ///
+    /// The entity referenced by the state machine has no [`StateTree`]
+    MissingStateTree { state_tree: Entity },
+    /// 状态机上没有 [`StateLifecycle`]，状态机永远不会启动
+    ///
+    /// The state machine has no [`StateLifecycle`], so it never starts
+    MissingLifecycle,
+    /// 初始状态不在状态树中
+    ///
+    /// The initial state is not part of the state tree
+    InitStateNotInTree { state: Entity },
+    /// 状态树中的实体缺少某个必需组件
+    ///
+    /// An entity in the state tree is missing a required component
+    MissingComponent {
+        state: Entity,
+        component: &'static str,
+    },
+    /// 状态组件引用了一个未注册的系统
+    ///
+    /// A state component references a system that has not been registered
+    UnregisteredSystem {
+        state: Entity,
+        component: &'static str,
+        system_name: SystemLabel,
+    },
+    /// 更新动作引用的系统没有在其指定的调度中注册，只在其他调度中注册
+    ///
+    /// An update action references a system that is not registered in the schedule it names, only in other schedules
+    ScheduleMismatch {
+        state: Entity,
+        component: &'static str,
+        key: ActionKey,
+        registered_in: Vec<String>,
+    },
+    /// 旧格式的键按名称匹配到多个调度，只会解析为最先注册的一个
+    ///
+    /// A legacy key matches several schedules by name and only resolves to the first registered one
+    AmbiguousSchedule {
+        state: Entity,
+        component: &'static str,
+        key: ActionKey,
+        candidates: usize,
+    },
+    /// 服务目标缺少 [`RequiredServiceComponents`] 声明的组件
+    ///
+    /// The service target lacks components declared in [`RequiredServiceComponents`]
+    MissingServiceComponents {
+        service_target: Entity,
+        components: Vec<&'static str>,
+    },
+}
+
+impl fmt::Display for StateConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateConfigIssue::MissingStateTree { state_tree } => {
+                write!(
+                    f,
+                    "StateTree component not found on entity {:?}",
+                    state_tree
+                )
+            }
+            StateConfigIssue::MissingLifecycle => {
+                write!(
+                    f,
+                    "StateLifecycle component not found, the machine never starts"
+                )
+            }
+            StateConfigIssue::InitStateNotInTree { state } => {
+                write!(f, "initial state {:?} is not part of the state tree", state)
+            }
+            StateConfigIssue::MissingComponent { state, component } => {
+                write!(
+                    f,
+                    "state {:?} is missing the {} component",
+                    state, component
+                )
+            }
+            StateConfigIssue::UnregisteredSystem {
+                state,
+                component,
+                system_name,
+            } => write!(
+                f,
+                "{} on state {:?} references unregistered system <{}>",
+                component, state, system_name
+            ),
+            StateConfigIssue::ScheduleMismatch {
+                state,
+                component,
+                key,
+                registered_in,
+            } => write!(
+                f,
+                "{} on state {:?} references <{}>, but <{}> is only registered in {}",
+                component,
+                state,
+                key,
+                key.name(),
+                registered_in.join(", ")
+            ),
+            StateConfigIssue::AmbiguousSchedule {
+                state,
+                component,
+                key,
+                candidates,
+            } => write!(
+                f,
+                "{} on state {:?} references <{}>, but {} schedules are named {}; use a typed ActionKey instead",
+                component,
+                state,
+                key,
+                candidates,
+                key.schedule_name()
+            ),
+            StateConfigIssue::MissingServiceComponents {
+                service_target,
+                components,
+            } => {
+                write!(f, "service target {:?} is missing ", service_target)?;
+                for (i, component) in components.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", ShortName(component))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// # 无效配置事件\Invalid Configuration Event
+/// * 在新的 [`HsmStateMachine`] 首次进入转换调度时，对每个检测到的问题触发一次。
+/// - Triggered once per detected issue when a new [`HsmStateMachine`] first reaches the transition schedule.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct InvalidStateConfig {
+    #[event_target]
+    pub state_machine: Entity,
+    pub issue: StateConfigIssue,
+}
+
+/// 校验一个层级状态机的配置
+///
+/// Validate the configuration of a hierarchical state machine
+pub fn validate_hsm(world: &World, state_machine_id: Entity) -> Vec<StateConfigIssue> {
+    let mut issues = Vec::new();
+    let Some(state_machine) = world.get::<HsmStateMachine>(state_machine_id) else {
+        return issues;
+    };
+
+    if world.get::<StateLifecycle>(state_machine_id).is_none() {
+        issues.push(StateConfigIssue::MissingLifecycle);
+    }
+
+    if let Some(required) = world.get::<RequiredServiceComponents>(state_machine_id) {
+        let service_target = world
+            .get::<ServiceTarget>(state_machine_id)
+            .map_or(state_machine_id, |target| target.0);
+        let components = match world.get_entity(service_target) {
+            Ok(entity) => required.missing(entity),
+            Err(_) => required.names().collect(),
+        };
+        if !components.is_empty() {
+            issues.push(StateConfigIssue::MissingServiceComponents {
+                service_target,
+                components,
+            });
+        }
+    }
+
+    let state_tree_id = state_machine.state_tree();
+    let Some(state_tree) = world.get::<StateTree>(state_tree_id) else {
+        issues.push(StateConfigIssue::MissingStateTree {
+            state_tree: state_tree_id,
+        });
+        return issues;
+    };
+
+    let init_state = state_machine.init_state();
+    if !state_tree.contains(init_state) {
+        issues.push(StateConfigIssue::InitStateNotInTree { state: init_state });
+    }
+
+    let actions = world.resource::<ActionRegistry>();
+    let transitions = world.resource::<TransitionRegistry>();
+    let guards = world.resource::<GuardRegistry>();
+    let dispatch = world.resource::<ActionDispatch>();
+    let local = world.get::<LocalRegistry>(state_machine_id);
+    let has_action = |label: &SystemLabel| {
+        actions.get(label).is_some()
+            || local.is_some_and(|local| local.actions.get(label).is_some())
+    };
+    let has_transition = |label: &SystemLabel| {
+        transitions.get(label).is_some()
+            || local.is_some_and(|local| local.transitions.get(label).is_some())
+    };
+    let has_guard = |label: &SystemLabel| {
+        guards.contains(label)
+            || guards.get_param(label).is_some()
+            || local.is_some_and(|local| local.contains_guard(label))
+    };
+
+    for state in state_tree.iter() {
+        let Ok(entity_ref) = world.get_entity(state) else {
+            issues.push(StateConfigIssue::MissingComponent {
+                state,
+                component: "HsmState",
+            });
+            continue;
+        };
+        if !entity_ref.contains::<HsmState>() {
+            issues.push(StateConfigIssue::MissingComponent {
+                state,
+                component: "HsmState",
+            });
+        }
+
+        let mut mismatches = Vec::new();
+        let mut check = |component: &'static str, label: Option<&SystemLabel>, found: bool| {
+            if let Some(label) = label
+                && !found
+            {
+                issues.push(StateConfigIssue::UnregisteredSystem {
+                    state,
+                    component,
+                    system_name: label.clone(),
+                });
+            }
+        };
+
+        let before_enter = entity_ref.get::<BeforeEnterSystem>().map(|s| &**s);
+        check(
+            "BeforeEnterSystem",
+            before_enter,
+            before_enter.is_some_and(has_transition),
+        );
+        let after_enter = entity_ref.get::<AfterEnterSystem>().map(|s| &**s);
+        check(
+            "AfterEnterSystem",
+            after_enter,
+            after_enter.is_some_and(has_action),
+        );
+        let on_update = entity_ref
+            .get::<OnUpdateSystem>()
+            .map(|update| ("OnUpdateSystem", &**update));
+        let on_updates = entity_ref
+            .get::<OnUpdateSystems>()
+            .into_iter()
+            .flat_map(|updates| updates.iter().map(|key| ("OnUpdateSystems", key)));
+        for (component, key) in on_update.into_iter().chain(on_updates) {
+            let candidates = key
+                .legacy_schedule()
+                .map_or(0, |name| dispatch.schedules_named(name).len());
+            if candidates > 1 {
+                mismatches.push(StateConfigIssue::AmbiguousSchedule {
+                    state,
+                    component,
+                    key: key.clone(),
+                    candidates,
+                });
+            }
+            if dispatch.get(key).is_some() {
+                continue;
+            }
+            let registered_in = dispatch.schedules_of(key.name());
+            if registered_in.is_empty() {
+                check(component, Some(&SystemLabel::from(key.to_string())), false);
+            } else {
+                mismatches.push(StateConfigIssue::ScheduleMismatch {
+                    state,
+                    component,
+                    key: key.clone(),
+                    registered_in: registered_in
+                        .iter()
+                        .map(|schedule| format!("{:?}", schedule))
+                        .collect(),
+                });
+            }
+        }
+        let before_exit = entity_ref.get::<BeforeExitSystem>().map(|s| &**s);
+        check(
+            "BeforeExitSystem",
+            before_exit,
+            before_exit.is_some_and(has_action),
+        );
+        let after_exit = entity_ref.get::<AfterExitSystem>().map(|s| &**s);
+        check(
+            "AfterExitSystem",
+            after_exit,
+            after_exit.is_some_and(has_transition),
+        );
+
+        let guard_labels = entity_ref
+            .get::<GuardEnter>()
+            .map(|guard| ("GuardEnter", guard.labels()))
+            .into_iter()
+            .chain(
+                entity_ref
+                    .get::<GuardExit>()
+                    .map(|guard| ("GuardExit", guard.labels())),
+            );
+        for (component, labels) in guard_labels {
+            for label in labels {
+                check(component, Some(label), has_guard(label));
+            }
+        }
+        issues.append(&mut mismatches);
+    }
+
+    issues
+}
+
+/// 校验新添加的状态机，并为每个问题输出警告、触发 [`InvalidStateConfig`]；尚未封存的状态树在此时封存
+///
+/// Validate newly added state machines, logging a warning and triggering [`InvalidStateConfig`] for every issue; state
+/// trees not sealed yet are sealed at this point
+pub(crate) fn validate_new_state_machines(
+    mut commands: Commands,
+    query: Query<(Entity, &HsmStateMachine), Added<HsmStateMachine>>,
+) {
+    for (state_machine, hsm) in query.iter() {
+        let state_tree = hsm.state_tree();
+        commands.queue(move |world: &mut World| {
+            if world
+                .get::<StateTree>(state_tree)
+                .is_some_and(|tree| !tree.is_sealed())
+            {
+                StateTree::seal(world, state_tree);
+            }
+            for issue in validate_hsm(world, state_machine) {
+                warn!("[HsmStateMachine {:?}] {}", state_machine, issue);
+                world.trigger(InvalidStateConfig {
+                    state_machine,
+                    issue,
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StateMachinePlugin;
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Issues(Vec<StateConfigIssue>);
+
+    #[test]
+    fn test_validate_hsm() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .init_resource::<Issues>()
+            .add_observer(
+                |event: On<InvalidStateConfig>, mut issues: ResMut<Issues>| {
+                    issues.0.push(event.issue.clone());
+                },
+            );
+        let world = app.world_mut();
+        let guard = world.register_system(|_: In<crate::context::GuardContext>| true);
+        world.resource_mut::<GuardRegistry>().insert("ok", guard);
+
+        let root = world.spawn(HsmState::default()).id();
+        let child = world
+            .spawn((
+                GuardEnter(crate::guards::GuardCondition::parse("and(ok, missing)").unwrap()),
+                AfterEnterSystem::new("nope"),
+            ))
+            .id();
+        let orphan = world.spawn_empty().id();
+        let mut state_tree = StateTree::new(root);
+        state_tree.with_child(root, child).with_child(root, orphan);
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                state_machine,
+                root,
+                #[cfg(feature = "history")]
+                10,
+            ),
+        ));
+
+        app.update();
+
+        let issues = &app.world().resource::<Issues>().0;
+        assert_eq!(issues.len(), 4);
+        assert!(issues.contains(&StateConfigIssue::MissingLifecycle));
+        assert!(issues.contains(&StateConfigIssue::MissingComponent {
+            state: orphan,
+            component: "HsmState",
+        }));
+        assert!(issues.contains(&StateConfigIssue::UnregisteredSystem {
+            state: child,
+            component: "GuardEnter",
+            system_name: "missing".into(),
+        }));
+        assert!(issues.contains(&StateConfigIssue::UnregisteredSystem {
+            state: child,
+            component: "AfterEnterSystem",
+            system_name: "nope".into(),
+        }));
+
+        // 只校验一次
+        // Validated only once
+        app.update();
+        assert_eq!(app.world().resource::<Issues>().0.len(), 4);
+    }
+
+    #[test]
+    fn test_required_service_components() {
+        #[derive(Component)]
+        struct Health;
+
+        #[derive(Component)]
+        struct Stamina;
+
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .init_resource::<Issues>()
+            .add_observer(
+                |event: On<InvalidStateConfig>, mut issues: ResMut<Issues>| {
+                    issues.0.push(event.issue.clone());
+                },
+            );
+        let world = app.world_mut();
+        let root = world.spawn(HsmState::default()).id();
+        let target = world.spawn(Health).id();
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            StateTree::new(root),
+            HsmStateMachine::with(
+                state_machine,
+                root,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+            ServiceTarget(target),
+            RequiredServiceComponents::default()
+                .with::<Health>()
+                .with::<Stamina>(),
+        ));
+
+        app.update();
+
+        let issues = &app.world().resource::<Issues>().0;
+        assert_eq!(
+            issues,
+            &[StateConfigIssue::MissingServiceComponents {
+                service_target: target,
+                components: vec![std::any::type_name::<Stamina>()],
+            }]
+        );
+        assert!(issues[0].to_string().ends_with("is missing Stamina"));
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{
+    hsm::{
+        state_lifecycle::StateLifecycle,
+        state_machine::HsmStateMachine,
+        transition_strategy::{
+            CheckOnTransitionStates, HsmTransitionSchedule, HsmTransitionSystems,
+        },
+    },
+    markers::Paused,
+};
+
+/// # 状态机变量\Machine Variable
+/// * 挂载在状态机实体上的值。通过 [`HsmVarAppExt::add_hsm_var`] 注册后，每当该值被修改（或插入），
+///   状态机都会在本帧的转换调度中重新检查转换条件，无论修改发生在哪个调度、当前状态是否拥有更新系统。
+/// - A value living on the state machine entity. Once registered with [`HsmVarAppExt::add_hsm_var`], every mutation (or
+///   insertion) makes the machine re-check its transition conditions in this frame's transition schedule, no matter
+///   which schedule the change happened in or whether the current state has an update system.
+/// * 用 [`DetectChangesMut::set_if_neq`] 写入可以避免值未变化时的唤醒。
+/// - Write through [`DetectChangesMut::set_if_neq`] to avoid wake-ups when the value did not change.
+/// * 只会唤醒处于 [`StateLifecycle::Update`] 且未被 [`Paused`] 的状态机，进入或退出中的状态机在完成后照常检查。
+/// - Only machines in [`StateLifecycle::Update`] and not [`Paused`] are woken; machines mid-enter or mid-exit are
+///   checked as usual once they finish.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Alert {
+///     level: u32,
+/// }
+///
+/// fn alerted(context: GuardContext, query: Query<&HsmVar<Alert>>) -> bool {
+///     query
+///         .get(context.state_machine)
+///         .is_ok_and(|alert| alert.level > 2)
+/// }
+///
+/// fn raise(mut query: Query<&mut HsmVar<Alert>>) {
+///     for mut alert in query.iter_mut() {
+///         alert.level += 1;
+///     }
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .add_hsm_var::<Alert>()
+///     .register_read_only_guard("alerted", alerted)
+///     .add_systems(PostUpdate, raise);
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq, Deref, DerefMut)]
+pub struct HsmVar<T: Send + Sync + 'static>(pub T);
+
+impl<T: Send + Sync + 'static> HsmVar<T> {
+    fn wake(
+        mut check_on_transition_states: ResMut<CheckOnTransitionStates>,
+        query: Query<
+            (Entity, &StateLifecycle),
+            (Changed<Self>, With<HsmStateMachine>, Without<Paused>),
+        >,
+    ) {
+        for (state_machine, lifecycle) in query.iter() {
+            if *lifecycle == StateLifecycle::Update {
+                check_on_transition_states.insert(state_machine);
+            }
+        }
+    }
+}
+
+/// # 状态机变量扩展\Machine Variable Extension
+/// * 注册 [`HsmVar`] 的唤醒系统，位于转换系统（[`HsmTransitionSystems`]）之前。需要先添加
+///   [`StateMachinePlugin`](crate::StateMachinePlugin)，否则假定转换系统在 [`Last`] 中运行。
+/// - Registers the wake-up system of an [`HsmVar`], ordered before the transition systems ([`HsmTransitionSystems`]).
+///   [`StateMachinePlugin`](crate::StateMachinePlugin) should be added first, otherwise the transition systems are
+///   assumed to run in [`Last`].
+pub trait HsmVarAppExt {
+    fn add_hsm_var<T: Send + Sync + 'static>(&mut self) -> &mut Self;
+}
+
+impl HsmVarAppExt for App {
+    fn add_hsm_var<T: Send + Sync + 'static>(&mut self) -> &mut Self {
+        let schedule = self
+            .world()
+            .get_resource::<HsmTransitionSchedule>()
+            .map_or(Last.intern(), |schedule| schedule.0);
+        self.add_systems(schedule, HsmVar::<T>::wake.before(HsmTransitionSystems))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateMachinePlugin, prelude::*};
+
+    use super::*;
+
+    #[test]
+    fn test_hsm_var_wakes_machine() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default())
+            .add_hsm_var::<u32>();
+        let world = app.world_mut();
+        let root = world.spawn(HsmState::default()).id();
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            StateTree::new(root),
+            HsmStateMachine::with(
+                state_machine,
+                root,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+            HsmVar(0u32),
+        ));
+        app.update();
+        app.update();
+
+        // 模拟一个不在检查集合中的状态机
+        // Simulate a machine that dropped out of the check set
+        app.world_mut()
+            .resource_mut::<CheckOnTransitionStates>()
+            .clear();
+        app.update();
+        assert!(
+            !app.world()
+                .resource::<CheckOnTransitionStates>()
+                .contains(&state_machine)
+        );
+
+        app.world_mut()
+            .get_mut::<HsmVar<u32>>(state_machine)
+            .unwrap()
+            .0 = 1;
+        app.update();
+        assert!(
+            app.world()
+                .resource::<CheckOnTransitionStates>()
+                .contains(&state_machine)
+        );
+
+        // 值不变时不唤醒
+        // Unchanged values do not wake the machine
+        app.world_mut()
+            .resource_mut::<CheckOnTransitionStates>()
+            .clear();
+        let mut var = app
+            .world_mut()
+            .get_mut::<HsmVar<u32>>(state_machine)
+            .unwrap();
+        assert!(!var.set_if_neq(HsmVar(1)));
+        app.update();
+        assert!(
+            !app.world()
+                .resource::<CheckOnTransitionStates>()
+                .contains(&state_machine)
+        );
+    }
+}
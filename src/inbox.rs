@@ -0,0 +1,173 @@
+//! # 状态机信箱\State Machine Inbox
+//!
+//! 状态机之间按地址投递的消息。状态的动作通过 [`StateMachineCommandsExt::send_message`] 向另一个状态机发送命名消息，
+//! 消息进入接收方的 [`HsmInbox`]，接收方的条件可以用内置守卫 `has_message("name")` 检查它。与
+//! [`StickyGuards`](crate::prelude::StickyGuards) 相同，成立过的 `has_message` 会在其起点状态退出或目标状态进入时
+//! 消耗一条同名消息，因此一条命令只驱动一次转换。适合指挥官与单位之间的协调。
+//!
+//! Addressed messages between state machines. A state's actions send a named message to another machine through
+//! [`StateMachineCommandsExt::send_message`]; it lands in the receiver's [`HsmInbox`], where the receiver's conditions can
+//! check it with the built-in guard `has_message("name")`. As with [`StickyGuards`](crate::prelude::StickyGuards), a
+//! `has_message` that held consumes one message of that name when its from state exits or its to state is entered, so
+//! one order drives a single transition. Suited to commander-to-unit coordination.
+//!
+//! # 示例\Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_hsm::prelude::*;
+//! #[derive(Component)]
+//! struct Squad(Vec<Entity>);
+//!
+//! fn order_retreat(context: In<ActionContext>, query: Query<&Squad>, mut commands: Commands) {
+//!     let Ok(squad) = query.get(context.service_target) else {
+//!         return;
+//!     };
+//!     for &unit in &squad.0 {
+//!         commands.send_message(context.state_machine, unit, "retreat_order");
+//!     }
+//! }
+//!
+//! # fn foo(mut commands: Commands, retreat: Entity) {
+//! commands
+//!     .entity(retreat)
+//!     .insert(GuardEnter::parse(r#"has_message("retreat_order")"#).unwrap());
+//! # }
+//! ```
+//!
+//! [`StateMachineCommandsExt::send_message`]: crate::prelude::StateMachineCommandsExt::send_message
+
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+
+use crate::{
+    builtin_guards::{HAS_MESSAGE, invalid_args},
+    context::GuardContext,
+    guards::GuardArgs,
+};
+
+/// 一条投递给状态机的消息
+///
+/// A message delivered to a state machine
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HsmMessage {
+    pub name: Cow<'static, str>,
+    /// 发送消息的状态机
+    ///
+    /// The state machine that sent the message
+    pub sender: Entity,
+}
+
+/// # 信箱\Inbox
+/// * 挂载在状态机实体上，按到达顺序保存尚未消耗的消息，首次收到消息时自动插入。
+/// - Lives on the state machine entity and keeps the messages not consumed yet in arrival order; inserted automatically
+///   when the first message arrives.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct HsmInbox {
+    messages: Vec<HsmMessage>,
+    peeked: Vec<(Entity, Entity, String)>,
+}
+
+impl HsmInbox {
+    pub fn push(&mut self, message: HsmMessage) {
+        self.messages.push(message);
+    }
+
+    /// 是否有该名称的消息
+    ///
+    /// Whether there is a message with the name
+    pub fn contains(&self, name: &str) -> bool {
+        self.messages.iter().any(|message| message.name == name)
+    }
+
+    pub fn messages(&self) -> &[HsmMessage] {
+        &self.messages
+    }
+
+    /// 取出最早到达的该名称的消息
+    ///
+    /// Take the earliest message with the name
+    pub fn take(&mut self, name: &str) -> Option<HsmMessage> {
+        let index = self
+            .messages
+            .iter()
+            .position(|message| message.name == name)?;
+        Some(self.messages.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// 每个以该状态为起点或目标、且 `has_message` 成立过的转换消耗一条同名消息
+    ///
+    /// Every transition from or to the given state whose `has_message` held consumes one message of that name
+    pub fn consume(&mut self, state: Entity) {
+        let (consumed, peeked) = std::mem::take(&mut self.peeked)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(from_state, to_state, _)| {
+                *from_state == state || *to_state == state
+            });
+        self.peeked = peeked;
+        for (_, _, name) in consumed {
+            self.take(&name);
+        }
+    }
+
+    pub(crate) fn consume_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut inbox) = world.get_mut::<HsmInbox>(state_machine) {
+                inbox.consume(state);
+            }
+        }
+    }
+
+    /// 向状态机 `to` 投递一条消息，`to` 不存在时返回 `false`
+    ///
+    /// Deliver a message to the state machine `to`, returning `false` when `to` does not exist
+    pub fn send(
+        world: &mut World,
+        from: Entity,
+        to: Entity,
+        name: impl Into<Cow<'static, str>>,
+    ) -> bool {
+        let Ok(mut entity) = world.get_entity_mut(to) else {
+            return false;
+        };
+        entity
+            .entry::<HsmInbox>()
+            .or_default()
+            .get_mut()
+            .push(HsmMessage {
+                name: name.into(),
+                sender: from,
+            });
+        true
+    }
+}
+
+pub(crate) fn has_message(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    mut query: Query<&mut HsmInbox>,
+    mut commands: Commands,
+) -> bool {
+    let [name] = &args[..] else {
+        invalid_args(HAS_MESSAGE, &args, "expected a message name").report_with(&mut commands);
+        return false;
+    };
+    let Ok(mut inbox) = query.get_mut(context.state_machine) else {
+        return false;
+    };
+    if !inbox.contains(name) {
+        return false;
+    }
+    let key = (context.from_state(), context.to_state(), name.clone());
+    if !inbox.peeked.contains(&key) {
+        inbox.peeked.push(key);
+    }
+    true
+}
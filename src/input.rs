@@ -0,0 +1,198 @@
+//! # 输入条件\Input Conditions
+//!
+//! 内置的带参数输入守卫，原型开发时无需再手写 `is_up`/`is_down` 之类的输入条件系统。
+//! 按键参数使用 [`KeyCode`] 的变体名称，动作参数使用 [`InputActionMap`] 中绑定的名称，多个参数时任意一个满足即可。
+//!
+//! Built-in parameterized input guards, so prototypes don't need hand-written input condition systems such as `is_up`/`is_down`.
+//! Key arguments are [`KeyCode`] variant names and action arguments are names bound in [`InputActionMap`]; with several
+//! arguments any one of them is enough.
+//!
+//! | 守卫\Guard | 示例\Example |
+//! | --- | --- |
+//! | `pressed` | `pressed("KeyW", "ArrowUp")` |
+//! | `just_pressed` | `just_pressed("Space")` |
+//! | `just_released` | `just_released("Space")` |
+//! | `action_pressed` | `action_pressed("Jump")` |
+//! | `action_just_pressed` | `action_just_pressed("Jump")` |
+//! | `action_just_released` | `action_just_released("Jump")` |
+
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    reflect::{DynamicEnum, DynamicVariant, TypeInfo, Typed, VariantInfo},
+};
+
+use crate::{context::GuardContext, guards::GuardArgs, state_actions::RegisterStateSystem};
+
+/// # 输入动作映射\Input Action Map
+/// * 将动作名称绑定到一组按键，供 `action_*` 守卫使用。
+/// - Binds action names to a set of keys, used by the `action_*` guards.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut input_map: ResMut<InputActionMap>) {
+/// input_map.bind("Jump", KeyCode::Space).bind("Jump", KeyCode::KeyW);
+/// # }
+/// ```
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct InputActionMap(HashMap<String, Vec<KeyCode>>);
+
+impl InputActionMap {
+    /// 将按键绑定到动作
+    ///
+    /// Bind a key to an action
+    pub fn bind(&mut self, action: impl Into<String>, key: KeyCode) -> &mut Self {
+        self.0.entry(action.into()).or_default().push(key);
+        self
+    }
+
+    /// 获取动作绑定的按键
+    ///
+    /// Get the keys bound to an action
+    pub fn keys(&self, action: &str) -> &[KeyCode] {
+        self.0.get(action).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// 按变体名称解析 [`KeyCode`]，例如 `"Space"`、`"KeyW"`
+///
+/// Parse a [`KeyCode`] by its variant name, e.g. `"Space"`, `"KeyW"`
+pub fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let TypeInfo::Enum(info) = KeyCode::type_info() else {
+        return None;
+    };
+    if !matches!(info.variant(name), Some(VariantInfo::Unit(_))) {
+        return None;
+    }
+    KeyCode::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
+
+fn any_key(
+    args: &GuardArgs,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+    check: impl Fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+) -> bool {
+    let Some(input) = input else {
+        return false;
+    };
+    args.iter().any(|name| match parse_key_code(name) {
+        Some(key) => check(&input, key),
+        None => {
+            warn!("[Input] Unknown key code: {}", name);
+            false
+        }
+    })
+}
+
+fn any_action(
+    args: &GuardArgs,
+    input_map: Res<InputActionMap>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+    check: impl Fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+) -> bool {
+    let Some(input) = input else {
+        return false;
+    };
+    args.iter()
+        .flat_map(|action| input_map.keys(action))
+        .any(|key| check(&input, *key))
+}
+
+fn pressed(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_key(&args, input, ButtonInput::pressed)
+}
+
+fn just_pressed(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_key(&args, input, ButtonInput::just_pressed)
+}
+
+fn just_released(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_key(&args, input, ButtonInput::just_released)
+}
+
+fn action_pressed(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input_map: Res<InputActionMap>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_action(&args, input_map, input, ButtonInput::pressed)
+}
+
+fn action_just_pressed(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input_map: Res<InputActionMap>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_action(&args, input_map, input, ButtonInput::just_pressed)
+}
+
+fn action_just_released(
+    In((_, args)): In<(GuardContext, GuardArgs)>,
+    input_map: Res<InputActionMap>,
+    input: Option<Res<ButtonInput<KeyCode>>>,
+) -> bool {
+    any_action(&args, input_map, input, ButtonInput::just_released)
+}
+
+pub(crate) fn register_input_guards(app: &mut App) {
+    app.init_resource::<InputActionMap>()
+        .register_param_guard("pressed", pressed)
+        .register_param_guard("just_pressed", just_pressed)
+        .register_param_guard("just_released", just_released)
+        .register_param_guard("action_pressed", action_pressed)
+        .register_param_guard("action_just_pressed", action_just_pressed)
+        .register_param_guard("action_just_released", action_just_released);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateMachinePlugin, guards::GuardCondition, prelude::GuardRegistry};
+
+    use super::*;
+
+    #[test]
+    fn test_input_guards() {
+        assert_eq!(parse_key_code("Space"), Some(KeyCode::Space));
+        assert_eq!(parse_key_code("Unknown"), None);
+
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.world_mut()
+            .resource_mut::<InputActionMap>()
+            .bind("Jump", KeyCode::Space);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::Space);
+
+        let world = app.world_mut();
+        let mut run = |condition: &str| {
+            let condition = GuardCondition::parse(condition).unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            let entity = world.spawn_empty().id();
+            guard
+                .run(world, GuardContext::new(entity, entity, entity, entity))
+                .unwrap()
+        };
+
+        assert!(run(r#"just_pressed("Space")"#));
+        assert!(run(r#"pressed("KeyW", "Space")"#));
+        assert!(!run(r#"just_released("Space")"#));
+        assert!(run(r#"action_just_pressed("Jump")"#));
+        assert!(!run(r#"action_pressed("Crouch")"#));
+    }
+}
@@ -1,6 +1,12 @@
-use std::borrow::Cow;
+use std::{any::Any, borrow::Cow};
 
-use bevy::{ecs::entity::Entity, prelude::Deref};
+use bevy::{
+    ecs::{
+        entity::Entity,
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+    },
+    prelude::Deref,
+};
 
 use crate::error::StateMachineError;
 
@@ -54,3 +60,146 @@ impl std::fmt::Display for SystemLabel {
         self.0.fmt(f)
     }
 }
+
+/// # 动作键\Action Key
+/// * 由调度标签的值与动作名称组成，唯一标识一个通过
+///   [`SystemState::add_action_system`](crate::prelude::SystemState::add_action_system) 注册的动作系统，
+///   因此同名动作可以分别存在于不同的调度中（例如 `Update` 与 `FixedUpdate`），携带数据的标签的不同取值也互不冲突。
+/// - Made of a schedule label value and an action name, uniquely identifying an action system registered with
+///   [`SystemState::add_action_system`](crate::prelude::SystemState::add_action_system). The same action name can
+///   therefore live in several schedules (e.g. `Update` and `FixedUpdate`), and different values of a label carrying
+///   data never collide.
+///
+/// # 旧格式\Legacy Format
+/// * 字符串 `"Schedule:action"` 仍可转换为动作键（见 [`ActionKey::parse`]）。此时调度只以名称保存，
+///   查找时按名称解析为以该名称注册的调度。
+/// - Strings in the `"Schedule:action"` format still convert into action keys (see [`ActionKey::parse`]). The schedule
+///   is then only kept by name and is resolved to the schedule registered under that name on lookup.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// let fixed = ActionKey::new(FixedUpdate, "move");
+/// let legacy = ActionKey::parse("Update:move");
+/// assert_ne!(fixed, ActionKey::new(Update, "move"));
+/// assert_eq!(legacy.to_string(), "Update:move");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ActionKey {
+    schedule: InternedScheduleLabel,
+    name: SystemLabel,
+}
+
+/// 从旧格式字符串解析出的调度，只保存名称
+///
+/// A schedule parsed from a legacy string, kept by name only
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Hash)]
+struct LegacySchedule(Cow<'static, str>);
+
+impl std::fmt::Debug for LegacySchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ActionKey {
+    pub fn new(schedule: impl ScheduleLabel, name: impl Into<SystemLabel>) -> Self {
+        Self {
+            schedule: schedule.intern(),
+            name: name.into(),
+        }
+    }
+
+    /// 解析旧格式 `"Schedule:action"`，没有 `:` 时整个字符串视为调度名称
+    ///
+    /// Parse the legacy `"Schedule:action"` format; without `:` the whole string is the schedule name
+    pub fn parse(key: &str) -> Self {
+        let (schedule, name) = key.split_once(':').unwrap_or((key, ""));
+        Self {
+            schedule: LegacySchedule(Cow::Owned(schedule.to_owned())).intern(),
+            name: SystemLabel::from(name.to_owned()),
+        }
+    }
+
+    pub fn schedule(&self) -> InternedScheduleLabel {
+        self.schedule
+    }
+
+    pub fn name(&self) -> &SystemLabel {
+        &self.name
+    }
+
+    /// 调度名称（调度标签的 [`Debug`] 输出）
+    ///
+    /// The schedule name (the [`Debug`] output of the schedule label)
+    pub fn schedule_name(&self) -> String {
+        format!("{:?}", self.schedule)
+    }
+
+    /// 是否由旧格式解析而来，调度尚未解析
+    ///
+    /// Whether the key was parsed from the legacy format, with its schedule not resolved yet
+    pub fn is_legacy(&self) -> bool {
+        self.legacy_schedule().is_some()
+    }
+
+    /// 由旧格式解析而来时，尚未解析的调度名称；不会分配内存
+    ///
+    /// The unresolved schedule name when the key was parsed from the legacy format; never allocates
+    pub fn legacy_schedule(&self) -> Option<&str> {
+        let schedule: &dyn Any = &*self.schedule;
+        schedule
+            .downcast_ref::<LegacySchedule>()
+            .map(|legacy| legacy.0.as_ref())
+    }
+
+    /// 使用另一个调度替换调度部分
+    ///
+    /// Replace the schedule part with another schedule
+    pub fn with_schedule(&self, schedule: InternedScheduleLabel) -> Self {
+        Self {
+            schedule,
+            name: self.name.clone(),
+        }
+    }
+
+    /// 找不到该动作的错误
+    pub(crate) fn not_found_error(&self, state: Entity) -> StateMachineError {
+        SystemLabel::from(self.to_string()).not_found_error(state)
+    }
+}
+
+impl std::fmt::Display for ActionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "{:?}", self.schedule)
+        } else {
+            write!(f, "{:?}:{}", self.schedule, self.name)
+        }
+    }
+}
+
+impl std::fmt::Debug for ActionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        <Self as std::fmt::Display>::fmt(self, f)
+    }
+}
+
+impl From<&str> for ActionKey {
+    fn from(value: &str) -> Self {
+        Self::parse(value)
+    }
+}
+
+impl From<String> for ActionKey {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
+}
+
+impl From<SystemLabel> for ActionKey {
+    fn from(value: SystemLabel) -> Self {
+        Self::parse(&value)
+    }
+}
@@ -48,34 +48,55 @@
 //! - **Highly Customizable**: Easily configure which schedule the state machine systems run in.
 //!
 pub mod action_dispatcher;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod behavior;
+pub mod builtin_guards;
+pub mod clock;
+pub mod commands;
+#[cfg(feature = "console")]
+pub mod console;
 pub mod context;
-mod error;
+pub mod error;
+pub mod fault;
 #[cfg(feature = "fsm")]
 pub mod fsm;
 pub mod guards;
 #[cfg(feature = "hsm")]
 pub mod hsm;
+pub mod inbox;
+#[cfg(feature = "input")]
+pub mod input;
 pub mod labels;
+pub mod local_registry;
 pub mod markers;
+#[cfg(feature = "physics")]
+pub mod physics;
+pub mod plugins;
+pub mod read_only_guards;
+pub mod registry_usage;
+pub mod rng;
+pub mod staged_guards;
 pub mod state_actions;
 #[cfg(feature = "state_data")]
 pub mod state_data;
-
-#[cfg(feature = "hsm")]
-use std::sync::Arc;
+pub mod state_systems;
+pub mod tasks;
+pub mod topology;
+#[cfg(feature = "ui")]
+pub mod ui;
 
 #[cfg(feature = "hsm")]
 use bevy::ecs::schedule::ScheduleLabel;
 use bevy::prelude::*;
 
-use crate::action_dispatcher::ActionDispatch;
-use crate::guards::GuardRegistry;
-use crate::prelude::TransitionRegistry;
-use crate::state_actions::ActionRegistry;
+use crate::plugins::StateMachinePlugins;
 
 /// Bevy 插件，用于初始化状态机所需的所有资源和系统。
+/// 它添加 [`StateMachinePlugins`] 的全部成员；需要只启用部分子系统时改用该插件组。
 ///
 /// A Bevy plugin that initializes all the resources and systems required for the state machine.
+/// It adds every member of [`StateMachinePlugins`]; use that group instead to enable only some of the subsystems.
 ///
 /// ## Example
 ///
@@ -88,9 +109,9 @@ use crate::state_actions::ActionRegistry;
 ///     .add_plugins(StateMachinePlugin::default())
 ///     .run();
 /// ```
+#[derive(Default)]
 pub struct StateMachinePlugin {
-    #[cfg(feature = "hsm")]
-    transition_system: Arc<dyn for<'a> Fn(&'a mut App) + Send + Sync>,
+    plugins: StateMachinePlugins,
 }
 
 #[cfg(feature = "hsm")]
@@ -101,52 +122,15 @@ impl StateMachinePlugin {
     /// Creates a new [`StateMachinePlugin`] and specifies in which schedule the HSM's transition systems should run.
     /// By default, the systems run in the [`Last`] schedule.
     pub fn with_schedule<T: ScheduleLabel + Clone>(schedule: T) -> Self {
-        let f = move |app: &mut App| {
-            crate::hsm::transition_strategy::install_transition_systems(app, schedule.clone());
-        };
         StateMachinePlugin {
-            transition_system: Arc::new(f),
+            plugins: StateMachinePlugins::with_schedule(schedule),
         }
     }
 }
 
 impl Plugin for StateMachinePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ActionDispatch>();
-        app.init_resource::<ActionRegistry>();
-        app.init_resource::<GuardRegistry>();
-        app.init_resource::<TransitionRegistry>();
-
-        #[cfg(feature = "hsm")]
-        {
-            use crate::hsm::{
-                guards::{GuardEnterCache, GuardExitCache},
-                transition_strategy::CheckOnTransitionStates,
-            };
-
-            app.init_resource::<CheckOnTransitionStates>();
-            app.init_resource::<GuardEnterCache>();
-            app.init_resource::<GuardExitCache>();
-            app.init_resource::<prelude::ActionSystemRegistry>();
-
-            (self.transition_system)(app);
-
-            app.add_observer(hsm::state_machine::HsmStateMachine::handle_hsm_trigger);
-        }
-
-        #[cfg(feature = "fsm")]
-        app.add_observer(fsm::state_machine::FsmStateMachine::handle_fsm_trigger);
-    }
-}
-
-impl Default for StateMachinePlugin {
-    fn default() -> Self {
-        Self {
-            #[cfg(feature = "hsm")]
-            transition_system: Arc::new(|app: &mut App| {
-                crate::hsm::transition_strategy::install_transition_systems(app, Last);
-            }),
-        }
+        app.add_plugins(self.plugins.clone());
     }
 }
 
@@ -190,19 +174,46 @@ macro_rules! system_registry {
 
 pub mod prelude {
     pub use crate::{
-        StateMachinePlugin, action_dispatcher::*, context::*, guards::*, markers::*,
-        state_actions::*,
+        StateMachinePlugin, action_dispatcher::*, behavior::*, builtin_guards::*, clock::*,
+        commands::*, context::*, error::*, fault::*, guards::*, inbox::*, labels::ActionKey,
+        local_registry::*, markers::*, plugins::*, read_only_guards::*, registry_usage::*, rng::*,
+        staged_guards::*, state_actions::*, state_systems::*, tasks::*, topology::*,
     };
 
     #[cfg(feature = "state_data")]
     pub use crate::state_data::*;
 
+    #[cfg(feature = "audio")]
+    pub use crate::audio::*;
+
+    #[cfg(feature = "console")]
+    pub use crate::console::*;
+
+    #[cfg(feature = "input")]
+    pub use crate::input::*;
+
+    #[cfg(feature = "physics")]
+    pub use crate::physics::*;
+
+    #[cfg(feature = "ui")]
+    pub use crate::ui::*;
+
     #[cfg(feature = "hsm")]
     pub use crate::hsm::{
-        HsmState, event::*, guards::*, state_lifecycle::*, state_machine::*, state_tree::*,
-        transition_strategy::*,
+        HsmState, analysis::*, bundles::*, checkpoints::*, deferred_links::*, diff::*, disabled::*,
+        emit::*, event::*, event_log::*, explain::*, fallthrough::*, golden::*, guard_retry::*,
+        guards::*, hooks::*, latch::*, limits::*, loop_detection::*, name_index::*, payload::*,
+        phase_schedules::*, pipeline::*, priority::*, requester::*, requirements::*, sleep::*,
+        start_selector::*, state_lifecycle::*, state_machine::*, state_tree::*, supervisor::*,
+        transition_strategy::*, transitions::*, vars::*,
     };
 
+    #[cfg(feature = "stats")]
+    pub use crate::hsm::stats::HsmStats;
+
+    #[cfg(all(feature = "hsm", feature = "history"))]
+    pub use crate::hsm::history::{HistoricalNode, HistoryCompression, StateHistory};
+
     #[cfg(feature = "hsm")]
     pub use bevy_hsm_macros::{hsm, hsm_tree};
 
@@ -214,3 +225,86 @@ pub mod prelude {
 
     pub use bevy_hsm_macros::combination_condition;
 }
+
+/// # 稳定预导入\Stable Prelude
+/// * 精选的稳定接口：插件、状态机控制、状态组件、构建宏与事件。这里的每一项都按语义化版本维护，
+///   只会在主版本更新时移除或改名；[`prelude`] 继续导出全部内容，包括更贴近内部实现的类型。
+/// - A curated, stable surface: the plugin, machine control, state components, builder macros and events. Every item
+///   here follows semantic versioning and is only removed or renamed in a major release; [`prelude`] keeps exporting
+///   everything, including types closer to the implementation.
+///
+/// # 示例\Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_hsm::prelude_v2::*;
+///
+/// fn setup(mut commands: Commands) {
+///     commands.spawn(hsm!(
+///         #[state]:Idle(
+///             #[state]:Alert,
+///         )
+///         StateLifecycle::default(),
+///     ));
+/// }
+///
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .register_guard("alert", |_: In<GuardContext>| false)
+///     .add_systems(Startup, setup);
+/// # }
+/// ```
+pub mod prelude_v2 {
+    pub use crate::{
+        StateMachinePlugin,
+        action_dispatcher::{ActionVerdict, IntoActionSystem, SystemState},
+        commands::{StateMachineCommandsExt, StateMachineForestCommandsExt},
+        context::{ActionContext, GuardContext, TransitionContext},
+        guards::GuardCondition,
+        labels::{ActionKey, SystemLabel},
+        markers::{Paused, ServiceTargetLost, ServiceTargetLostPolicy, Terminated},
+        plugins::{
+            StateMachineAssetPlugin, StateMachineCorePlugin, StateMachineDebugPlugin,
+            StateMachineIntegrationsPlugin, StateMachinePlugins,
+        },
+        state_actions::{
+            AfterEnterSystem, AfterExitSystem, BeforeEnterSystem, BeforeExitSystem, OnUpdateSystem,
+            OnUpdateSystems, RegisterStateSystem, ServiceTarget,
+        },
+    };
+
+    #[cfg(feature = "hsm")]
+    pub use crate::{
+        commands::{clone_hsm, despawn_hsm},
+        hsm::{
+            HsmState,
+            event::{HsmTrigger, HsmTriggerType},
+            guards::{GuardEnter, GuardExit},
+            priority::StatePriority,
+            state_lifecycle::StateLifecycle,
+            state_machine::HsmStateMachine,
+            state_tree::StateTree,
+            transition_strategy::{StateTransitionStrategy, TraversalStrategy},
+            transitions::{HsmFireTransition, HsmTransition, HsmTransitions},
+        },
+    };
+
+    #[cfg(feature = "hsm")]
+    pub use bevy_hsm_macros::{hsm, hsm_tree};
+
+    #[cfg(feature = "fsm")]
+    pub use crate::{
+        commands::despawn_fsm,
+        fsm::{
+            FsmState,
+            event::{FsmTrigger, FsmTriggerType},
+            graph::FsmGraph,
+            state_machine::FsmStateMachine,
+        },
+    };
+
+    #[cfg(feature = "fsm")]
+    pub use bevy_hsm_macros::{fsm, fsm_graph};
+
+    pub use bevy_hsm_macros::combination_condition;
+}
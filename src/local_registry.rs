@@ -0,0 +1,326 @@
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{
+    context::{ActionContext, GuardContext, TransitionContext},
+    guards::{CompiledGuard, GuardCondition, GuardRegistry, GuardResolveError, TargetGuardMemo},
+    labels::SystemLabel,
+    state_actions::{ActionRegistry, TransitionRegistry},
+};
+
+/// # 局部注册表\Local Registry
+/// * 挂载在状态机实体上，存放只对该状态机生效的条件、动作与转换系统，按名称遮蔽全局的 [`GuardRegistry`]、
+///   [`ActionRegistry`] 与 [`TransitionRegistry`]。不同的插件或模组因此可以使用相同的名称而互不冲突。
+/// - Lives on a state machine entity and holds conditions, actions and transition systems that only apply to that
+///   machine, shadowing the global [`GuardRegistry`], [`ActionRegistry`] and [`TransitionRegistry`] by name. Different
+///   plugins or mods can therefore use the same names without clashing.
+/// * 组件被移除（包括状态机被销毁）或名称被重新注册时，注销不再被全局注册表或其他局部注册表引用的系统。
+///   克隆的状态机共享同一批系统，因此只有最后一个引用它们的状态机被销毁时才会注销。
+/// - When the component is removed (despawning the machine included) or a name is registered again, the systems no
+///   longer referenced by the global registries or another local registry are unregistered. Cloned machines share the
+///   same systems, so those are only unregistered once the last machine referencing them is despawned.
+/// * 局部条件在运行时按状态机编译，[`GuardOverrides`](crate::guards::GuardOverrides) 与
+///   [`TargetGuardMemo`](crate::guards::TargetGuardMemo) 只作用于全局注册的条件。
+/// - Local conditions are compiled per machine at runtime; [`GuardOverrides`](crate::guards::GuardOverrides) and
+///   [`TargetGuardMemo`](crate::guards::TargetGuardMemo) only apply to globally registered conditions.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands
+///     .entity(state_machine)
+///     .register_local_guard("is_hungry", |_: In<GuardContext>| true)
+///     .register_local_action("eat", |_: In<ActionContext>| {});
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+#[component(on_remove = Self::on_remove)]
+pub struct LocalRegistry {
+    pub guards: GuardRegistry,
+    pub actions: ActionRegistry,
+    pub transitions: TransitionRegistry,
+}
+
+impl LocalRegistry {
+    /// 该局部注册表是否注册了名为 `name` 的条件
+    ///
+    /// Whether this local registry holds a condition named `name`
+    pub fn contains_guard(&self, name: &SystemLabel) -> bool {
+        self.guards.contains(name)
+    }
+
+    /// 按状态机编译守卫：状态机带有非空的局部条件时，局部名称遮蔽全局名称；否则返回 `None`，应使用全局缓存
+    ///
+    /// Compile a guard for a machine: with non-empty local conditions, local names shadow global ones; otherwise
+    /// `None` is returned and the global cache should be used
+    pub fn compile(
+        world: &World,
+        state_machine: Entity,
+        condition: &GuardCondition,
+    ) -> Option<Result<CompiledGuard, GuardResolveError>> {
+        let local = world
+            .get::<Self>(state_machine)
+            .filter(|local| !local.guards.is_empty())?;
+        let global = world.resource::<GuardRegistry>();
+        Some(
+            local
+                .guards
+                .to_combinator_condition_id_with(global, condition),
+        )
+    }
+
+    /// 状态 `state` 上的守卫 `T` 对状态机生效的编译结果，没有局部条件时取自全局缓存 `cache`
+    ///
+    /// The compiled guard `T` of `state` as it applies to a machine, taken from the global `cache` without local
+    /// conditions
+    #[cfg(feature = "hsm")]
+    pub(crate) fn guard<'a, T>(
+        world: &World,
+        state_machine: Entity,
+        state: Entity,
+        cache: &'a bevy::platform::collections::HashMap<Entity, CompiledGuard>,
+    ) -> Option<std::borrow::Cow<'a, CompiledGuard>>
+    where
+        T: Component + std::ops::Deref<Target = GuardCondition>,
+    {
+        use std::borrow::Cow;
+
+        let Some(condition) = world.get::<T>(state) else {
+            return cache.get(&state).map(Cow::Borrowed);
+        };
+        match Self::compile(world, state_machine, condition) {
+            Some(compiled) => compiled.ok().map(Cow::Owned),
+            None => cache.get(&state).map(Cow::Borrowed),
+        }
+    }
+
+    /// 报告无法通过全局注册表解析的守卫，名称已在某个局部注册表中注册时不报告
+    ///
+    /// Report a guard the global registry cannot resolve, unless the name is registered in some local registry
+    #[cfg(feature = "hsm")]
+    pub(crate) fn report_unresolved(world: &mut World, state: Entity, source: GuardResolveError) {
+        let GuardResolveError::UnregisteredGuard(label) = &source;
+        let mut query = world.query::<&Self>();
+        if query.iter(world).any(|local| local.contains_guard(label)) {
+            return;
+        }
+        crate::error::StateMachineError::GuardUnresolved { state, source }.report(world);
+    }
+
+    /// 在钩子中延迟执行 [`LocalRegistry::report_unresolved`]
+    ///
+    /// Defer [`LocalRegistry::report_unresolved`] from a hook
+    #[cfg(feature = "hsm")]
+    pub(crate) fn report_unresolved_deferred(
+        world: &mut DeferredWorld,
+        state: Entity,
+        source: GuardResolveError,
+    ) {
+        world
+            .commands()
+            .queue(move |world: &mut World| Self::report_unresolved(world, state, source));
+    }
+
+    fn on_remove(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+        let Some(local) = world.get::<Self>(entity).cloned() else {
+            return;
+        };
+        world
+            .commands()
+            .queue(move |world: &mut World| Self::release(world, &local));
+    }
+
+    /// 注销 `released` 中不再被全局注册表或任何局部注册表引用的系统
+    ///
+    /// Unregister the systems in `released` that neither the global registries nor any local registry reference
+    pub(crate) fn release(world: &mut World, released: &Self) {
+        let mut query = world.query::<&Self>();
+        let locals = query.iter(world).collect::<Vec<_>>();
+        let guards = world.get_resource::<GuardRegistry>();
+        let guard_ids = released
+            .guards
+            .0
+            .values()
+            .copied()
+            .filter(|&id| {
+                !guards.is_some_and(|guards| guards.contains_id(id))
+                    && !locals.iter().any(|local| local.guards.contains_id(id))
+            })
+            .collect::<Vec<_>>();
+        let actions = world.get_resource::<ActionRegistry>();
+        let action_ids = released
+            .actions
+            .0
+            .values()
+            .copied()
+            .filter(|id| {
+                !actions.is_some_and(|actions| actions.0.values().any(|global| global == id))
+                    && !locals
+                        .iter()
+                        .any(|local| local.actions.0.values().any(|other| other == id))
+            })
+            .collect::<Vec<_>>();
+        let transitions = world.get_resource::<TransitionRegistry>();
+        let transition_ids = released
+            .transitions
+            .0
+            .values()
+            .copied()
+            .filter(|id| {
+                !transitions
+                    .is_some_and(|transitions| transitions.0.values().any(|global| global == id))
+                    && !locals
+                        .iter()
+                        .any(|local| local.transitions.0.values().any(|other| other == id))
+            })
+            .collect::<Vec<_>>();
+        for id in guard_ids {
+            if let Some(mut memo) = world.get_resource_mut::<TargetGuardMemo>() {
+                memo.remove(id);
+            }
+            let _ = world.unregister_system(id);
+        }
+        for id in action_ids {
+            let _ = world.unregister_system(id);
+        }
+        for id in transition_ids {
+            let _ = world.unregister_system(id);
+        }
+    }
+}
+
+/// # 局部注册扩展\Local Registration Extension
+/// * 在状态机实体上注册只对该状态机生效的系统，按需插入 [`LocalRegistry`]。
+/// - Registers systems that only apply to one state machine on its entity, inserting a [`LocalRegistry`] as needed.
+pub trait LocalRegistryExt {
+    /// 注册局部条件
+    ///
+    /// Register a local condition
+    fn register_local_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + Send + 'static,
+    ) -> &mut Self;
+
+    /// 注册局部动作系统
+    ///
+    /// Register a local action system
+    fn register_local_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + Send + 'static,
+    ) -> &mut Self;
+
+    /// 注册局部转换系统
+    ///
+    /// Register a local transition system
+    fn register_local_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + Send + 'static,
+    ) -> &mut Self;
+}
+
+impl LocalRegistryExt for EntityWorldMut<'_> {
+    fn register_local_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.world_scope(|world| world.register_system(system));
+        let replaced = self
+            .entry::<LocalRegistry>()
+            .or_default()
+            .get_mut()
+            .guards
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.guards.insert(name, replaced);
+            self.world_scope(|world| LocalRegistry::release(world, &released));
+        }
+        self
+    }
+
+    fn register_local_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.world_scope(|world| world.register_system(system));
+        let replaced = self
+            .entry::<LocalRegistry>()
+            .or_default()
+            .get_mut()
+            .actions
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.actions.insert(name, replaced);
+            self.world_scope(|world| LocalRegistry::release(world, &released));
+        }
+        self
+    }
+
+    fn register_local_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.world_scope(|world| world.register_system(system));
+        let replaced = self
+            .entry::<LocalRegistry>()
+            .or_default()
+            .get_mut()
+            .transitions
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.transitions.insert(name, replaced);
+            self.world_scope(|world| LocalRegistry::release(world, &released));
+        }
+        self
+    }
+}
+
+impl LocalRegistryExt for EntityCommands<'_> {
+    fn register_local_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.queue(move |mut entity: EntityWorldMut| {
+            entity.register_local_guard(name, system);
+        })
+    }
+
+    fn register_local_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.queue(move |mut entity: EntityWorldMut| {
+            entity.register_local_action(name, system);
+        })
+    }
+
+    fn register_local_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.queue(move |mut entity: EntityWorldMut| {
+            entity.register_local_transition(name, system);
+        })
+    }
+}
@@ -199,3 +199,108 @@ impl SpawnStateMachine {
         commands.entity(entity).remove::<Self>();
     }
 }
+
+/// # 服务目标丢失策略\Service Target Lost Policy
+/// * 决定当状态机的 [`ServiceTarget`] 所指向的实体被销毁后，状态机应如何处理。
+/// - Decides what a state machine does once the entity its [`ServiceTarget`] points to has been despawned.
+///
+/// 未添加该组件的状态机默认采用 [`ServiceTargetLostPolicy::Pause`]。
+///
+/// State machines without this component fall back to [`ServiceTargetLostPolicy::Pause`].
+#[derive(Component, Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ServiceTargetLostPolicy {
+    /// 插入 [`Paused`]，保留当前状态
+    ///
+    /// Insert [`Paused`], keeping the current state
+    #[default]
+    Pause,
+    /// 插入 [`Terminated`]
+    ///
+    /// Insert [`Terminated`]
+    Terminate,
+    /// 销毁状态机实体及其私有的状态实体
+    ///
+    /// Despawn the state machine entity and its private state entities
+    Despawn,
+}
+
+/// # 服务目标丢失事件\Service Target Lost Event
+/// * 当状态机的服务目标被销毁时触发，在 [`ServiceTargetLostPolicy`] 生效之前发送。
+/// - Triggered when the service target of a state machine has been despawned, before the [`ServiceTargetLostPolicy`] is applied.
+#[derive(EntityEvent, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServiceTargetLost {
+    #[event_target]
+    pub state_machine: Entity,
+    /// 已被销毁的服务目标
+    ///
+    /// The service target that has been despawned
+    pub service_target: Entity,
+}
+
+/// 等待销毁的状态机：在移除关系组件的过程中无法直接销毁实体，因此延后到下一次 [`First`] 调度中处理
+///
+/// State machines waiting to be despawned: the entity cannot be despawned while its relationship is being removed, so this is deferred to the next [`First`] schedule
+#[derive(Resource, Default, Debug)]
+pub(crate) struct PendingServiceTargetDespawns(Vec<Entity>);
+
+impl ServiceTargetLostPolicy {
+    /// 服务目标被销毁时，关系组件 [`ServiceTarget`] 会从状态机上移除，此时检查目标是否仍然存在
+    ///
+    /// When the service target is despawned, the [`ServiceTarget`] relationship is removed from the state machine; check whether the target still exists
+    pub(crate) fn on_service_target_removed(
+        remove: On<Remove, ServiceTarget>,
+        query: Query<(&ServiceTarget, Option<&ServiceTargetLostPolicy>)>,
+        entities: &bevy::ecs::entity::Entities,
+        mut pending: ResMut<PendingServiceTargetDespawns>,
+        mut commands: Commands,
+    ) {
+        let state_machine = remove.entity;
+        let Ok((service_target, policy)) = query.get(state_machine) else {
+            return;
+        };
+        let service_target = service_target.0;
+        if entities.contains(service_target) {
+            return;
+        }
+
+        commands.trigger(ServiceTargetLost {
+            state_machine,
+            service_target,
+        });
+
+        let Ok(mut entity_commands) = commands.get_entity(state_machine) else {
+            return;
+        };
+        match policy.copied().unwrap_or_default() {
+            ServiceTargetLostPolicy::Pause => {
+                entity_commands.insert(Paused);
+            }
+            ServiceTargetLostPolicy::Terminate => {
+                entity_commands.insert(Terminated);
+            }
+            ServiceTargetLostPolicy::Despawn => {
+                entity_commands.insert(Paused);
+                pending.0.push(state_machine);
+            }
+        }
+    }
+
+    pub(crate) fn despawn_pending(
+        mut pending: ResMut<PendingServiceTargetDespawns>,
+        mut commands: Commands,
+    ) {
+        for state_machine in pending.0.drain(..) {
+            commands.queue(move |world: &mut World| {
+                #[cfg(feature = "hsm")]
+                if world.get::<HsmStateMachine>(state_machine).is_some() {
+                    crate::commands::despawn_hsm(world, state_machine);
+                }
+                #[cfg(feature = "fsm")]
+                if world.get::<FsmStateMachine>(state_machine).is_some() {
+                    crate::commands::despawn_fsm(world, state_machine);
+                }
+                let _ = world.try_despawn(state_machine);
+            });
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! # 物理接触条件\Physics Contact Conditions
+//!
+//! 与具体物理引擎（avian/rapier）无关的接触适配层：把引擎的碰撞事件转换为 [`HsmContact`] 消息，
+//! 本模块会按帧将其锁存到 [`ContactLatch`] 中，并提供内置的带参数守卫，便于直接搭建“着地/空中”之类的移动状态机。
+//!
+//! A contact adapter layer that is independent of the physics engine (avian/rapier): convert the engine's collision events
+//! into [`HsmContact`] messages, this module latches them per frame into [`ContactLatch`] and provides built-in parameterized
+//! guards, so movement state machines such as grounded/airborne work out of the box.
+//!
+//! | 守卫\Guard | 示例\Example |
+//! | --- | --- |
+//! | `collided_with_tag` | `collided_with_tag("ground")` |
+//! | `sensor_overlap` | `sensor_overlap("player")` |
+//!
+//! 守卫检查的是上下文中的服务目标（[`GuardContext::service_target`]）。
+//!
+//! The guards check the service target of the context ([`GuardContext::service_target`]).
+//!
+//! # 示例\Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_hsm::prelude::*;
+//! # #[derive(EntityEvent)]
+//! # struct CollisionStart { #[event_target] collider1: Entity, collider2: Entity }
+//! // 将物理引擎的事件转发为 `HsmContact`
+//! // Forward the physics engine's events as `HsmContact`
+//! fn forward(collision: On<CollisionStart>, mut contacts: MessageWriter<HsmContact>) {
+//!     contacts.write(HsmContact::collision(collision.collider1, collision.collider2));
+//! }
+//!
+//! # fn foo(mut commands: Commands) {
+//! commands.spawn((Name::new("Ground"), CollisionTag::new("ground")));
+//! commands.spawn((
+//!     HsmState::default(),
+//!     GuardEnter(GuardCondition::call("collided_with_tag", ["ground"])),
+//! ));
+//! # }
+//! ```
+
+use std::borrow::Cow;
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{context::GuardContext, guards::GuardArgs, state_actions::RegisterStateSystem};
+
+/// # 碰撞标签\Collision Tag
+/// * 挂载在碰撞体上，接触条件按标签匹配对方。
+/// - Lives on a collider; contact conditions match the other side by tag.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash, Deref)]
+pub struct CollisionTag(pub Cow<'static, str>);
+
+impl CollisionTag {
+    pub fn new(tag: impl Into<Cow<'static, str>>) -> Self {
+        Self(tag.into())
+    }
+}
+
+/// 接触类型
+///
+/// Contact kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContactKind {
+    /// 实体碰撞
+    ///
+    /// Solid collision
+    Collision,
+    /// 传感器重叠
+    ///
+    /// Sensor overlap
+    Sensor,
+}
+
+/// # 接触消息\Contact Message
+/// * 由物理引擎的碰撞事件转换而来，两侧实体都会被锁存。
+/// - Converted from the physics engine's collision events; both sides are latched.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HsmContact {
+    pub entity: Entity,
+    pub other: Entity,
+    pub kind: ContactKind,
+}
+
+impl HsmContact {
+    pub const fn collision(entity: Entity, other: Entity) -> Self {
+        Self {
+            entity,
+            other,
+            kind: ContactKind::Collision,
+        }
+    }
+
+    pub const fn sensor(entity: Entity, other: Entity) -> Self {
+        Self {
+            entity,
+            other,
+            kind: ContactKind::Sensor,
+        }
+    }
+}
+
+/// # 接触锁存\Contact Latch
+/// * 记录实体在本帧接触到的碰撞标签，每帧开始时清空。
+/// - Records the collision tags an entity touched this frame, cleared at the start of every frame.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContactLatch(HashMap<ContactKind, HashSet<Cow<'static, str>>>);
+
+impl ContactLatch {
+    /// 本帧是否接触到带有该标签的实体
+    ///
+    /// Whether an entity with the tag was touched this frame
+    pub fn contains(&self, kind: ContactKind, tag: &str) -> bool {
+        self.0.get(&kind).is_some_and(|tags| tags.contains(tag))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(HashSet::is_empty)
+    }
+
+    fn latch_contacts(
+        mut commands: Commands,
+        mut contacts: MessageReader<HsmContact>,
+        mut query: Query<&mut ContactLatch>,
+        query_tag: Query<&CollisionTag>,
+    ) {
+        for mut latch in query.iter_mut() {
+            if !latch.is_empty() {
+                latch.0.clear();
+            }
+        }
+
+        let mut pending = HashMap::<Entity, ContactLatch>::new();
+        for contact in contacts.read() {
+            for (entity, other) in [
+                (contact.entity, contact.other),
+                (contact.other, contact.entity),
+            ] {
+                let Ok(tag) = query_tag.get(other) else {
+                    continue;
+                };
+                let tags = match query.get_mut(entity) {
+                    Ok(latch) => latch.into_inner().0.entry(contact.kind).or_default(),
+                    Err(_) => pending
+                        .entry(entity)
+                        .or_default()
+                        .0
+                        .entry(contact.kind)
+                        .or_default(),
+                };
+                tags.insert(tag.0.clone());
+            }
+        }
+
+        for (entity, latch) in pending {
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.try_insert(latch);
+            }
+        }
+    }
+
+    fn check(
+        context: &GuardContext,
+        args: &GuardArgs,
+        query: &Query<&ContactLatch>,
+        kind: ContactKind,
+    ) -> bool {
+        query
+            .get(context.service_target)
+            .is_ok_and(|latch| args.iter().any(|tag| latch.contains(kind, tag)))
+    }
+}
+
+fn collided_with_tag(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    query: Query<&ContactLatch>,
+) -> bool {
+    ContactLatch::check(&context, &args, &query, ContactKind::Collision)
+}
+
+fn sensor_overlap(
+    In((context, args)): In<(GuardContext, GuardArgs)>,
+    query: Query<&ContactLatch>,
+) -> bool {
+    ContactLatch::check(&context, &args, &query, ContactKind::Sensor)
+}
+
+pub(crate) fn register_physics_guards(app: &mut App) {
+    app.add_message::<HsmContact>()
+        .add_systems(PreUpdate, ContactLatch::latch_contacts)
+        .register_param_guard("collided_with_tag", collided_with_tag)
+        .register_param_guard("sensor_overlap", sensor_overlap);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateMachinePlugin, guards::GuardCondition, prelude::GuardRegistry};
+
+    use super::*;
+
+    #[test]
+    fn test_contact_latch() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let player = app.world_mut().spawn_empty().id();
+        let ground = app.world_mut().spawn(CollisionTag::new("ground")).id();
+
+        app.world_mut()
+            .write_message(HsmContact::collision(player, ground));
+        app.update();
+
+        let run = |world: &mut World, condition: &str| {
+            let condition = GuardCondition::parse(condition).unwrap();
+            let guard = world
+                .resource::<GuardRegistry>()
+                .to_combinator_condition_id(&condition)
+                .unwrap();
+            guard
+                .run(world, GuardContext::new(player, player, player, player))
+                .unwrap()
+        };
+
+        assert!(run(app.world_mut(), r#"collided_with_tag("ground")"#));
+        assert!(!run(app.world_mut(), r#"sensor_overlap("ground")"#));
+
+        // 只锁存一帧
+        // Latched for a single frame
+        app.update();
+        assert!(!run(app.world_mut(), r#"collided_with_tag("ground")"#));
+    }
+}
@@ -0,0 +1,289 @@
+#[cfg(feature = "hsm")]
+use std::sync::Arc;
+
+use bevy::app::PluginGroupBuilder;
+#[cfg(feature = "hsm")]
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+
+use crate::{
+    action_dispatcher::{self, ActionDispatch},
+    behavior, builtin_guards, clock, error,
+    guards::{self, GuardRegistry},
+    markers, read_only_guards, registry_usage, staged_guards,
+    state_actions::{self, ActionRegistry, TransitionRegistry},
+    tasks,
+};
+
+/// # 状态机插件组\State Machine Plugin Group
+/// * 按领域拆分的插件组，成员可以单独禁用，只保留需要的子系统：
+///   * [`StateMachineCorePlugin`]：注册表、转换管线、触发器、时钟、转换循环检测与延迟状态链接等必需部分，不应禁用；
+///   * [`StateMachineAssetPlugin`]：[`StateMachineLimits`](crate::hsm::limits::StateMachineLimits) 等数据驱动功能；
+///   * [`StateMachineDebugPlugin`]：解释记录、事件日志与控制台；
+///   * [`StateMachineIntegrationsPlugin`]：输入、物理与 UI 集成。
+/// - A plugin group split by domain whose members can be disabled one by one, keeping only the subsystems you need:
+///   * [`StateMachineCorePlugin`]: the required parts such as registries, the transition pipeline, triggers, clocks,
+///     transition loop detection and deferred state links; must not be disabled;
+///   * [`StateMachineAssetPlugin`]: data-driven features such as
+///     [`StateMachineLimits`](crate::hsm::limits::StateMachineLimits);
+///   * [`StateMachineDebugPlugin`]: explain recording, the event log and the console;
+///   * [`StateMachineIntegrationsPlugin`]: input, physics and UI integrations.
+/// * [`StateMachinePlugin`](crate::StateMachinePlugin) 添加全部成员，与该插件组的默认配置等价。
+/// - [`StateMachinePlugin`](crate::StateMachinePlugin) adds every member, equivalent to the default configuration of
+///   this group.
+///
+/// # 示例\Example
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_hsm::prelude::*;
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(StateMachinePlugins::default().disable::<StateMachineDebugPlugin>())
+///     .run();
+/// ```
+#[derive(Default, Clone)]
+pub struct StateMachinePlugins {
+    core: StateMachineCorePlugin,
+}
+
+impl StateMachinePlugins {
+    /// 指定 HSM 的转换系统在哪个调度阶段运行，见 [`StateMachineCorePlugin::with_schedule`]
+    ///
+    /// Specify in which schedule the HSM's transition systems run, see [`StateMachineCorePlugin::with_schedule`]
+    #[cfg(feature = "hsm")]
+    pub fn with_schedule<T: ScheduleLabel + Clone>(schedule: T) -> Self {
+        Self {
+            core: StateMachineCorePlugin::with_schedule(schedule),
+        }
+    }
+
+    /// 禁用插件组中的成员 `T`
+    ///
+    /// Disable the member `T` of the group
+    pub fn disable<T: Plugin>(self) -> PluginGroupBuilder {
+        self.build().disable::<T>()
+    }
+}
+
+impl PluginGroup for StateMachinePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(self.core)
+            .add(StateMachineAssetPlugin)
+            .add(StateMachineDebugPlugin)
+            .add(StateMachineIntegrationsPlugin)
+    }
+}
+
+/// # 核心插件\Core Plugin
+/// * 初始化状态机运行所必需的资源、系统与观察者，其余插件都依赖于它。
+/// - Initializes the resources, systems and observers a state machine needs to run; every other member depends on it.
+#[derive(Clone)]
+pub struct StateMachineCorePlugin {
+    #[cfg(feature = "hsm")]
+    transition_system: Arc<dyn for<'a> Fn(&'a mut App) + Send + Sync>,
+}
+
+#[cfg(feature = "hsm")]
+impl StateMachineCorePlugin {
+    /// 创建一个新的 [`StateMachineCorePlugin`]，并指定 HSM 的转换系统在哪个调度阶段运行。
+    /// 默认情况下，系统在 [`Last`] 调度中运行。
+    ///
+    /// Creates a new [`StateMachineCorePlugin`] and specifies in which schedule the HSM's transition systems should
+    /// run. By default, the systems run in the [`Last`] schedule.
+    pub fn with_schedule<T: ScheduleLabel + Clone>(schedule: T) -> Self {
+        let f = move |app: &mut App| {
+            crate::hsm::transition_strategy::install_transition_systems(app, schedule.clone());
+        };
+        Self {
+            transition_system: Arc::new(f),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "hsm"), allow(clippy::derivable_impls))]
+impl Default for StateMachineCorePlugin {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "hsm")]
+            transition_system: Arc::new(|app: &mut App| {
+                crate::hsm::transition_strategy::install_transition_systems(app, Last);
+            }),
+        }
+    }
+}
+
+impl Plugin for StateMachineCorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionDispatch>();
+        app.init_resource::<action_dispatcher::ActionSystemRegistry>();
+        app.init_resource::<ActionRegistry>();
+        app.init_resource::<GuardRegistry>();
+        app.init_resource::<read_only_guards::ReadOnlyGuards>();
+        app.init_resource::<staged_guards::StagedGuardChanges>();
+        app.add_systems(
+            First,
+            staged_guards::StagedGuardChanges::apply
+                .run_if(|staged: Res<staged_guards::StagedGuardChanges>| !staged.is_empty()),
+        );
+        app.init_resource::<guards::GuardOverrides>();
+        app.init_resource::<guards::TargetGuardMemo>();
+        app.add_systems(First, guards::TargetGuardMemo::tick);
+        app.init_resource::<TransitionRegistry>();
+        app.init_resource::<state_actions::LifecyclePhases>();
+        app.init_resource::<registry_usage::RegistryUsage>();
+        app.init_resource::<error::StateMachineErrorPolicy>();
+        app.add_message::<error::StateMachineErrorMessage>();
+
+        app.init_resource::<markers::PendingServiceTargetDespawns>();
+        app.add_observer(markers::ServiceTargetLostPolicy::on_service_target_removed);
+        app.add_systems(First, markers::ServiceTargetLostPolicy::despawn_pending);
+
+        app.add_systems(PreUpdate, tasks::StateTasks::poll);
+        state_actions::RegisterStateSystem::register_guard(
+            app,
+            tasks::TASK_DONE,
+            tasks::StateTasks::task_done,
+        );
+
+        builtin_guards::register_builtin_guards(app);
+        clock::install_state_clocks(app);
+        behavior::install_behavior_runner(app);
+
+        #[cfg(feature = "hsm")]
+        {
+            use crate::hsm::{
+                self,
+                guards::{GuardEnterCache, GuardEnterExpensiveCache, GuardExitCache},
+                transition_strategy::CheckOnTransitionStates,
+            };
+
+            app.init_resource::<CheckOnTransitionStates>();
+            app.init_resource::<hsm::requester::TransitionRequests>();
+            app.init_resource::<GuardEnterCache>();
+            app.init_resource::<GuardEnterExpensiveCache>();
+            app.init_resource::<GuardExitCache>();
+            app.init_resource::<hsm::guards::ScheduledGuardVerdicts>();
+            app.init_resource::<hsm::hooks::HsmTransitionHooks>();
+            app.init_resource::<hsm::state_lifecycle::LifecycleQueue>();
+
+            app.init_resource::<hsm::pipeline::PipelinePhaseCounts>();
+            app.add_systems(First, hsm::pipeline::PipelinePhaseCounts::reset);
+
+            app.init_resource::<hsm::loop_detection::TransitionLoopDetection>();
+            app.add_systems(
+                First,
+                hsm::loop_detection::TransitionLoopDetection::tick
+                    .run_if(resource_exists::<hsm::loop_detection::TransitionLoopDetection>),
+            );
+            app.init_resource::<hsm::deferred_links::PendingStateLinks>();
+            app.add_observer(hsm::deferred_links::PendingStateLinks::on_insert_state);
+
+            (self.transition_system)(app);
+
+            app.add_observer(hsm::state_machine::HsmStateMachine::handle_hsm_trigger);
+            app.add_observer(hsm::transitions::HsmTransitions::handle_fire_transition);
+            hsm::supervisor::register_supervisor_guards(app);
+
+            app.init_resource::<hsm::name_index::HsmNameIndex>();
+            app.add_observer(hsm::name_index::HsmNameIndex::on_insert_name);
+            app.add_observer(hsm::name_index::HsmNameIndex::on_insert_state);
+            app.add_observer(hsm::name_index::HsmNameIndex::on_remove);
+            app.init_resource::<hsm::phase_schedules::PhaseContextStack>();
+            app.add_observer(
+                action_dispatcher::ScheduleActionBuffers::on_remove_state_machine::<
+                    hsm::state_machine::HsmStateMachine,
+                >,
+            );
+        }
+
+        #[cfg(feature = "fsm")]
+        {
+            app.add_observer(crate::fsm::state_machine::FsmStateMachine::handle_fsm_trigger);
+            app.add_observer(
+                action_dispatcher::ScheduleActionBuffers::on_remove_state_machine::<
+                    crate::fsm::state_machine::FsmStateMachine,
+                >,
+            );
+        }
+    }
+}
+
+/// # 资源插件\Asset Plugin
+/// * 数据驱动构建所需的功能：状态机规模限制。禁用后 [`StateMachineLimits`](crate::hsm::limits::StateMachineLimits)
+///   不会被检查。
+/// - What data-driven construction needs: state machine size limits. Once disabled,
+///   [`StateMachineLimits`](crate::hsm::limits::StateMachineLimits) is not checked.
+#[derive(Default, Clone, Copy)]
+pub struct StateMachineAssetPlugin;
+
+impl Plugin for StateMachineAssetPlugin {
+    #[cfg_attr(not(feature = "hsm"), allow(unused_variables))]
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "hsm")]
+        {
+            use crate::hsm::limits::StateMachineLimits;
+
+            app.init_resource::<StateMachineLimits>();
+            app.add_observer(StateMachineLimits::on_insert_state_machine);
+        }
+    }
+}
+
+/// # 调试插件\Debug Plugin
+/// * 诊断工具：[`HsmExplain`](crate::hsm::explain::HsmExplain) 记录、事件日志与控制台命令。
+///   转换循环检测属于 [`StateMachineCorePlugin`]，禁用本插件后循环仍会被截断，只是不再记录到事件日志。
+/// - Diagnostics: [`HsmExplain`](crate::hsm::explain::HsmExplain) recording, the event log and console commands.
+///   Transition loop detection belongs to [`StateMachineCorePlugin`], so loops are still cut short with this plugin
+///   disabled, they are just no longer recorded in the event log.
+#[derive(Default, Clone, Copy)]
+pub struct StateMachineDebugPlugin;
+
+impl Plugin for StateMachineDebugPlugin {
+    #[cfg_attr(
+        not(any(feature = "hsm", feature = "console")),
+        allow(unused_variables)
+    )]
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "console")]
+        crate::console::install_console(app);
+
+        #[cfg(feature = "hsm")]
+        {
+            use crate::hsm::{event_log::HsmEventLog, explain::HsmExplain};
+
+            app.add_systems(
+                First,
+                HsmExplain::tick.run_if(resource_exists::<HsmExplain>),
+            );
+            app.add_observer(HsmEventLog::on_request_rejected);
+            app.add_observer(HsmEventLog::on_loop_detected);
+            app.add_observer(HsmEventLog::on_faulted);
+        }
+    }
+}
+
+/// # 集成插件\Integrations Plugin
+/// * 按特性启用的第三方集成：`input` 的输入守卫、`physics` 的物理守卫与 `ui` 的界面绑定。
+/// - Feature-gated integrations: the input guards of `input`, the physics guards of `physics` and the UI bindings
+///   of `ui`.
+#[derive(Default, Clone, Copy)]
+pub struct StateMachineIntegrationsPlugin;
+
+impl Plugin for StateMachineIntegrationsPlugin {
+    #[cfg_attr(
+        not(any(feature = "input", feature = "physics", feature = "ui")),
+        allow(unused_variables)
+    )]
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "input")]
+        crate::input::register_input_guards(app);
+
+        #[cfg(feature = "physics")]
+        crate::physics::register_physics_guards(app);
+
+        #[cfg(feature = "ui")]
+        crate::ui::install_ui_bindings(app);
+    }
+}
@@ -0,0 +1,195 @@
+//! # 只读守卫\Read-only Guards
+//!
+//! 以闭包形式注册、直接声明 [`SystemParam`](bevy::ecs::system::SystemParam) 参数的守卫。参数状态由 [`SystemState`] 缓存，
+//! 评估时只需要 `&World`：不会像一次性系统那样取出、运行再放回系统，也不会在每次检查前刷新命令队列。
+//! 参数必须是只读的（[`ReadOnlySystemParam`]），例如 `Query<&T>`、`Res<T>`，最多 8 个。
+//!
+//! Guards registered as closures declaring their [`SystemParam`](bevy::ecs::system::SystemParam)s directly. The parameter state is cached in a
+//! [`SystemState`] and evaluating the guard only needs `&World`: there is no taking out, running and putting back of a
+//! one-shot system, and no command flush before every check. Parameters have to be read-only
+//! ([`ReadOnlySystemParam`]), such as `Query<&T>` or `Res<T>`, with at most 8 of them.
+//!
+//! # 示例\Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_hsm::prelude::*;
+//! #[derive(Resource)]
+//! struct SightRange(f32);
+//!
+//! # fn my_fn() {
+//! let mut app = App::new();
+//! app.add_plugins(StateMachinePlugin::default())
+//!     .register_read_only_guard(
+//!         "can_see",
+//!         |context: GuardContext, query: Query<&Transform>, range: Res<SightRange>| {
+//!             query
+//!                 .get(context.service_target)
+//!                 .is_ok_and(|transform| transform.translation.length() < range.0)
+//!         },
+//!     );
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::system::{
+        ReadOnlySystemParam, RegisteredSystemError, SystemParamItem, SystemParamValidationError,
+        SystemState,
+    },
+    prelude::*,
+};
+
+use crate::{context::GuardContext, fault::catch_fault};
+
+/// 只读守卫的编号
+///
+/// Id of a read-only guard
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReadOnlyGuardId(usize);
+
+/// # 只读守卫函数\Read-only Guard Function
+/// * 为形如 `FnMut(GuardContext, P0, P1, ...) -> bool` 的闭包与函数实现，其中每个参数都是 [`ReadOnlySystemParam`]。
+/// - Implemented for closures and functions shaped like `FnMut(GuardContext, P0, P1, ...) -> bool` where every
+///   parameter is a [`ReadOnlySystemParam`].
+pub trait ReadOnlyGuardFunction<Marker>: Send + Sync + 'static {
+    type Param: ReadOnlySystemParam + 'static;
+
+    fn run(&mut self, context: GuardContext, param: SystemParamItem<Self::Param>) -> bool;
+}
+
+macro_rules! impl_read_only_guard_function {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<Func, $($param: ReadOnlySystemParam + 'static),*> ReadOnlyGuardFunction<fn($($param,)*) -> bool>
+            for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func: FnMut(GuardContext, $($param),*) -> bool
+                + FnMut(GuardContext, $(SystemParamItem<$param>),*) -> bool,
+        {
+            type Param = ($($param,)*);
+
+            fn run(&mut self, context: GuardContext, param: SystemParamItem<Self::Param>) -> bool {
+                // 帮助编译器推断闭包参数的生命周期
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<$($param,)*>(
+                    mut f: impl FnMut(GuardContext, $($param,)*) -> bool,
+                    context: GuardContext,
+                    $($param: $param,)*
+                ) -> bool {
+                    f(context, $($param,)*)
+                }
+                let ($($param,)*) = param;
+                call_inner(self, context, $($param),*)
+            }
+        }
+    };
+}
+
+impl_read_only_guard_function!();
+impl_read_only_guard_function!(P0);
+impl_read_only_guard_function!(P0, P1);
+impl_read_only_guard_function!(P0, P1, P2);
+impl_read_only_guard_function!(P0, P1, P2, P3);
+impl_read_only_guard_function!(P0, P1, P2, P3, P4);
+impl_read_only_guard_function!(P0, P1, P2, P3, P4, P5);
+impl_read_only_guard_function!(P0, P1, P2, P3, P4, P5, P6);
+impl_read_only_guard_function!(P0, P1, P2, P3, P4, P5, P6, P7);
+
+trait AnyReadOnlyGuard: Send + Sync {
+    fn run(
+        &mut self,
+        world: &World,
+        context: GuardContext,
+    ) -> Result<bool, SystemParamValidationError>;
+}
+
+struct ReadOnlyGuard<F: ReadOnlyGuardFunction<M>, M> {
+    function: F,
+    state: SystemState<F::Param>,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<F: ReadOnlyGuardFunction<M>, M: 'static> AnyReadOnlyGuard for ReadOnlyGuard<F, M> {
+    fn run(
+        &mut self,
+        world: &World,
+        context: GuardContext,
+    ) -> Result<bool, SystemParamValidationError> {
+        // SAFETY: 参数是只读的，且状态由同一个 `World` 创建
+        unsafe {
+            SystemState::validate_param(&mut self.state, world.as_unsafe_world_cell_readonly())?
+        };
+        let param = self.state.get(world);
+        Ok(self.function.run(context, param))
+    }
+}
+
+/// # 只读守卫存储\Read-only Guard Storage
+/// * 保存所有只读守卫及其缓存的参数状态，名称映射保存在 [`GuardRegistry`](crate::guards::GuardRegistry) 中。
+/// - Holds every read-only guard together with its cached parameter state; the name mapping lives in the
+///   [`GuardRegistry`](crate::guards::GuardRegistry).
+#[derive(Resource, Default)]
+pub struct ReadOnlyGuards(Vec<Option<Box<dyn AnyReadOnlyGuard>>>);
+
+impl ReadOnlyGuards {
+    /// 创建守卫的参数状态并保存守卫
+    ///
+    /// Create the guard's parameter state and store the guard
+    pub fn insert<M: 'static>(
+        world: &mut World,
+        function: impl ReadOnlyGuardFunction<M>,
+    ) -> ReadOnlyGuardId {
+        let guard = ReadOnlyGuard {
+            state: SystemState::new(world),
+            function,
+            marker: PhantomData,
+        };
+        let mut guards = world.get_resource_or_init::<Self>();
+        guards.0.push(Some(Box::new(guard)));
+        ReadOnlyGuardId(guards.0.len() - 1)
+    }
+
+    /// 移除一个守卫，守卫不存在时返回 `false`
+    ///
+    /// Remove a guard, returning `false` when it does not exist
+    pub fn remove(&mut self, id: ReadOnlyGuardId) -> bool {
+        self.0.get_mut(id.0).and_then(Option::take).is_some()
+    }
+
+    pub fn contains(&self, id: ReadOnlyGuardId) -> bool {
+        self.0.get(id.0).is_some_and(Option::is_some)
+    }
+
+    /// 评估一个守卫，守卫不存在或参数无效时返回 `false`
+    ///
+    /// Evaluate a guard, returning `false` when it does not exist or its parameters are invalid
+    pub(crate) fn run(world: &mut World, id: ReadOnlyGuardId, context: GuardContext) -> bool {
+        world
+            .try_resource_scope(|world, mut guards: Mut<Self>| {
+                let Some(guard) = guards.0.get_mut(id.0).and_then(Option::as_mut) else {
+                    warn!("[ReadOnlyGuards] {:?} does not exist", id);
+                    return false;
+                };
+                catch_fault(
+                    world,
+                    context.state_machine,
+                    context.from_state(),
+                    |world| {
+                        Ok::<_, RegisteredSystemError<In<GuardContext>, bool>>(
+                            match guard.run(world, context) {
+                                Ok(result) => result,
+                                Err(e) => {
+                                    warn!("[ReadOnlyGuards] Failed to run {:?}: {}", id, e);
+                                    false
+                                }
+                            },
+                        )
+                    },
+                )
+                .unwrap_or_default()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,183 @@
+//! # 注册表引用计数\Registry Usage Tracking
+//!
+//! 记录每个已注册系统被多少个状态组件引用。被标记为“作用域”的系统会在最后一个引用它的状态被移除后自动注销，
+//! 避免长时间运行的会话中不断生成/销毁状态机而泄漏系统存储。
+//!
+//! Tracks how many state components reference each registered system. Systems marked as "scoped" are
+//! unregistered automatically once the last state referencing them is removed, so long sessions that keep
+//! spawning and despawning state machines don't leak system storage.
+//!
+//! # 注意\Note
+//! 只统计状态实体上的 [`BeforeEnterSystem`]、[`AfterEnterSystem`]、[`BeforeExitSystem`]、[`AfterExitSystem`]
+//! 以及 HSM 的 `GuardEnter`/`GuardExit`；[`FsmGraph`](crate::prelude::FsmGraph) 中的守卫不计入。
+//!
+//! Only [`BeforeEnterSystem`], [`AfterEnterSystem`], [`BeforeExitSystem`], [`AfterExitSystem`] and the HSM
+//! `GuardEnter`/`GuardExit` on state entities are counted; guards inside an
+//! [`FsmGraph`](crate::prelude::FsmGraph) are not.
+//!
+//! [`BeforeEnterSystem`]: crate::state_actions::BeforeEnterSystem
+//! [`AfterEnterSystem`]: crate::state_actions::AfterEnterSystem
+//! [`BeforeExitSystem`]: crate::state_actions::BeforeExitSystem
+//! [`AfterExitSystem`]: crate::state_actions::AfterExitSystem
+
+use bevy::{
+    ecs::world::DeferredWorld,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use crate::{
+    guards::GuardRegistry,
+    labels::SystemLabel,
+    read_only_guards::ReadOnlyGuards,
+    state_actions::{ActionRegistry, TransitionRegistry},
+};
+
+/// 系统所在的注册表
+///
+/// The registry a system lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegistryKind {
+    /// [`ActionRegistry`]
+    Action,
+    /// [`TransitionRegistry`]
+    Transition,
+    /// [`GuardRegistry`]
+    Guard,
+}
+
+/// # 注册表引用计数\Registry Usage
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn is_ok(_: In<GuardContext>) -> bool { true }
+/// # fn foo(mut commands: Commands, mut guard_registry: ResMut<GuardRegistry>, mut usage: ResMut<RegistryUsage>) {
+/// let system_id = commands.register_system(is_ok);
+/// guard_registry.insert("is_ok", system_id);
+/// // 最后一个使用 "is_ok" 的状态被销毁后，该系统会被注销
+/// // The system is unregistered after the last state using "is_ok" is despawned
+/// usage.mark_scoped(RegistryKind::Guard, "is_ok");
+/// # }
+/// ```
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub struct RegistryUsage {
+    counts: HashMap<(RegistryKind, SystemLabel), usize>,
+    scoped: HashSet<(RegistryKind, SystemLabel)>,
+}
+
+impl RegistryUsage {
+    /// 标记一个系统，使其在不再被任何状态引用时自动注销
+    ///
+    /// Mark a system so that it is unregistered automatically once no state references it
+    pub fn mark_scoped(&mut self, kind: RegistryKind, name: impl Into<SystemLabel>) -> &mut Self {
+        self.scoped.insert((kind, name.into()));
+        self
+    }
+
+    /// 取消自动注销标记
+    ///
+    /// Remove the auto-unregister mark
+    pub fn unmark_scoped(&mut self, kind: RegistryKind, name: impl Into<SystemLabel>) -> bool {
+        self.scoped.remove(&(kind, name.into()))
+    }
+
+    /// 是否被标记为自动注销
+    ///
+    /// Whether the system is marked for automatic unregistration
+    pub fn is_scoped(&self, kind: RegistryKind, name: impl Into<SystemLabel>) -> bool {
+        self.scoped.contains(&(kind, name.into()))
+    }
+
+    /// 当前引用该系统的状态组件数量
+    ///
+    /// Number of state components currently referencing the system
+    pub fn count(&self, kind: RegistryKind, name: impl Into<SystemLabel>) -> usize {
+        self.counts
+            .get(&(kind, name.into()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn acquire(world: &mut DeferredWorld, kind: RegistryKind, label: SystemLabel) {
+        let Some(mut usage) = world.get_resource_mut::<Self>() else {
+            return;
+        };
+        *usage.counts.entry((kind, label)).or_default() += 1;
+    }
+
+    pub(crate) fn release(world: &mut DeferredWorld, kind: RegistryKind, label: SystemLabel) {
+        let Some(mut usage) = world.get_resource_mut::<Self>() else {
+            return;
+        };
+        let key = (kind, label);
+        let Some(count) = usage.counts.get_mut(&key) else {
+            return;
+        };
+        *count = count.saturating_sub(1);
+        if *count > 0 {
+            return;
+        }
+        usage.counts.remove(&key);
+        if !usage.scoped.contains(&key) {
+            return;
+        }
+
+        world.commands().queue(move |world: &mut World| {
+            let (kind, label) = key;
+            // 同一帧内可能又有新的状态引用了该系统
+            // A new state may have referenced the system within the same frame
+            let mut usage = world.resource_mut::<Self>();
+            if usage.count(kind, label.clone()) > 0 {
+                return;
+            }
+            usage.scoped.remove(&(kind, label.clone()));
+            unregister(world, kind, &label);
+        });
+    }
+}
+
+fn unregister(world: &mut World, kind: RegistryKind, label: &SystemLabel) {
+    let result = match kind {
+        RegistryKind::Action => match world.resource_mut::<ActionRegistry>().remove(label) {
+            Some(id) => world
+                .unregister_system(id)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            None => return,
+        },
+        RegistryKind::Transition => {
+            match world.resource_mut::<TransitionRegistry>().remove(label) {
+                Some(id) => world
+                    .unregister_system(id)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                None => return,
+            }
+        }
+        RegistryKind::Guard => {
+            let mut guards = world.resource_mut::<GuardRegistry>();
+            if let Some(id) = guards.remove_read_only(label) {
+                world.resource_mut::<ReadOnlyGuards>().remove(id);
+                return;
+            }
+            if let Some(id) = guards.remove(label) {
+                world
+                    .unregister_system(id)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            } else {
+                match guards.remove_param(label) {
+                    Some(id) => world
+                        .unregister_system(id)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string()),
+                    None => return,
+                }
+            }
+        }
+    };
+    if let Err(e) = result {
+        warn!("Failed to unregister system <{}>: {}", label, e);
+    }
+}
@@ -0,0 +1,118 @@
+//! # 暂存守卫修改\Staged Guard Changes
+//!
+//! 直接通过 `ResMut<GuardRegistry>` 增删守卫时，修改会立即对之后的检查生效，而本帧已排队的转换评估可能已经编译了旧的守卫，
+//! 被注销的系统也可能仍在其中等待运行。写入 [`StagedGuardChanges`] 的修改则会在 [`First`] 中统一应用：
+//! 同一帧内的所有评估看到的都是同一份注册表，应用后引用了这些条件的进入/退出守卫缓存会被重新编译，被移除的守卫系统也在此时注销。
+//! 在守卫或动作运行期间修改注册表时应使用这种方式。
+//!
+//! Adding or removing guards directly through `ResMut<GuardRegistry>` affects the following checks at once, while the
+//! transition evaluations already queued this frame may have compiled the old guard, and an unregistered system may still
+//! be waiting to run in them. Changes written to [`StagedGuardChanges`] are instead applied together in [`First`]: every
+//! evaluation within a frame sees the same registry, the cached enter/exit guards referencing the changed conditions are
+//! recompiled afterwards, and removed guard systems are unregistered at that point. Use this whenever the registry is
+//! modified while guards or actions are running.
+//!
+//! # 示例\Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_hsm::prelude::*;
+//! fn once(_: In<GuardContext>, mut staged: ResMut<StagedGuardChanges>) -> bool {
+//!     // 只放行一次，下一帧起不再可用\Passes once, unavailable from the next frame on
+//!     staged.remove("once");
+//!     true
+//! }
+//!
+//! # fn my_fn() {
+//! let mut app = App::new();
+//! app.add_plugins(StateMachinePlugin::default())
+//!     .register_guard("once", once);
+//! # }
+//! ```
+
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{
+    guards::{GuardId, GuardRegistry},
+    labels::SystemLabel,
+};
+
+/// 一条暂存的注册表修改
+///
+/// A staged registry change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StagedGuardChange {
+    /// 插入或替换一个守卫，被替换的系统会被注销
+    ///
+    /// Insert or replace a guard; the replaced system is unregistered
+    Insert(SystemLabel, GuardId),
+    /// 移除一个守卫并注销其系统
+    ///
+    /// Remove a guard and unregister its system
+    Remove(SystemLabel),
+}
+
+/// # 暂存守卫修改\Staged Guard Changes
+/// * 按写入顺序在 [`First`] 中应用到 [`GuardRegistry`]，详见[模块文档](self)。
+/// - Applied to the [`GuardRegistry`] in [`First`] in the order they were written, see the [module docs](self).
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct StagedGuardChanges(Vec<StagedGuardChange>);
+
+impl StagedGuardChanges {
+    /// 暂存插入一个守卫
+    ///
+    /// Stage the insertion of a guard
+    pub fn insert(&mut self, name: impl Into<SystemLabel>, guard: GuardId) -> &mut Self {
+        self.0.push(StagedGuardChange::Insert(name.into(), guard));
+        self
+    }
+
+    /// 暂存移除一个守卫
+    ///
+    /// Stage the removal of a guard
+    pub fn remove(&mut self, name: impl Into<SystemLabel>) -> &mut Self {
+        self.0.push(StagedGuardChange::Remove(name.into()));
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StagedGuardChange> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn apply(world: &mut World) {
+        let changes = std::mem::take(&mut world.resource_mut::<Self>().0);
+        let mut labels = HashSet::new();
+        let mut unregister = Vec::new();
+        let mut registry = world.resource_mut::<GuardRegistry>();
+        for change in changes {
+            let (label, replaced) = match change {
+                StagedGuardChange::Insert(label, guard) => {
+                    let replaced = registry.insert(label.clone(), guard);
+                    (label, replaced.filter(|replaced| *replaced != guard))
+                }
+                StagedGuardChange::Remove(label) => {
+                    let removed = registry.remove(&label);
+                    (label, removed)
+                }
+            };
+            unregister.extend(replaced);
+            labels.insert(label);
+        }
+
+        #[cfg(feature = "hsm")]
+        crate::hsm::guards::refresh_guard_caches(world, &labels);
+
+        for guard in unregister {
+            if !world.resource::<GuardRegistry>().contains_id(guard) {
+                let _ = world.unregister_system(guard);
+            }
+        }
+    }
+}
@@ -1,15 +1,24 @@
-use std::{borrow::Borrow, hash::Hash};
+use std::{any::TypeId, borrow::Borrow, hash::Hash};
 
 use bevy::{
-    ecs::schedule::ScheduleLabel,
+    ecs::{
+        lifecycle::HookContext, schedule::ScheduleLabel, system::SystemParam, world::DeferredWorld,
+    },
     platform::collections::{Equivalent, HashMap},
     prelude::*,
 };
+use smallvec::SmallVec;
 
 use crate::{
-    context::{ActionId, TransitionId},
+    action_dispatcher::ScheduleActionBuffers,
+    builtin_guards::GuardValues,
+    context::{ActionContext, ActionId, GuardContext, TransitionContext, TransitionId},
     error::StateMachineError,
-    labels::SystemLabel,
+    guards::{GuardArgs, GuardRegistry, TargetGuardMemo},
+    labels::{ActionKey, SystemLabel},
+    local_registry::LocalRegistry,
+    read_only_guards::{ReadOnlyGuardFunction, ReadOnlyGuards},
+    registry_usage::{RegistryKind, RegistryUsage},
 };
 
 /// 注册一次性用于运行[`AfterEnterSystem`] [`BeforeExitSystem`]的系统
@@ -82,14 +91,20 @@ impl ActionRegistry {
         self.0.get(name).copied()
     }
 
+    /// 获取状态上的系统，状态机的 [`LocalRegistry`] 优先于全局注册表
+    ///
+    /// Get the system of a state, the machine's [`LocalRegistry`] taking precedence over the global registry
     pub(crate) fn get_action_id<T: Component + std::ops::Deref<Target = SystemLabel>>(
         world: &bevy::ecs::world::DeferredWorld,
+        state_machine_id: Entity,
         state_id: Entity,
     ) -> Option<ActionId> {
         let on_system = world.get::<T>(state_id)?;
-        let system = world.resource::<ActionRegistry>();
         let system_name: &SystemLabel = <T as std::ops::Deref>::deref(on_system);
-        let id = system.get(system_name);
+        let id = world
+            .get::<LocalRegistry>(state_machine_id)
+            .and_then(|local| local.actions.get(system_name))
+            .or_else(|| world.resource::<ActionRegistry>().get(system_name));
         if id.is_none() {
             warn!(
                 "{}",
@@ -148,7 +163,7 @@ impl TransitionRegistry {
     /// Remove a registered transition system
     pub fn remove<Q>(&mut self, name: &Q) -> Option<TransitionId>
     where
-        Q: Hash + Equivalent<SystemLabel>,
+        Q: Hash + Equivalent<SystemLabel> + ?Sized,
         SystemLabel: Borrow<Q>,
     {
         self.0.remove(name)
@@ -170,14 +185,20 @@ impl TransitionRegistry {
         self.0.is_empty()
     }
 
+    /// 获取状态上的系统，状态机的 [`LocalRegistry`] 优先于全局注册表
+    ///
+    /// Get the system of a state, the machine's [`LocalRegistry`] taking precedence over the global registry
     pub(crate) fn get_transition_id<T: Component + std::ops::Deref<Target = SystemLabel>>(
         world: &bevy::ecs::world::DeferredWorld,
+        state_machine_id: Entity,
         state_id: Entity,
     ) -> Option<TransitionId> {
         let on_system = world.get::<T>(state_id)?;
-        let system = world.resource::<TransitionRegistry>();
         let system_name: &SystemLabel = <T as std::ops::Deref>::deref(on_system);
-        let id = system.get(system_name);
+        let id = world
+            .get::<LocalRegistry>(state_machine_id)
+            .and_then(|local| local.transitions.get(system_name))
+            .or_else(|| world.resource::<TransitionRegistry>().get(system_name));
         if id.is_none() {
             warn!(
                 "{}",
@@ -203,16 +224,746 @@ impl<S: Into<SystemLabel>, const N: usize> From<[(S, TransitionId); N]> for Tran
     }
 }
 
+/// # 注册状态系统\Register State Systems
+/// * 直接在 [`App`] 或 [`World`] 上注册守卫、动作与转换系统，无需额外的 `Startup` 系统。
+/// - Register guard, action and transition systems directly on an [`App`] or [`World`], without an extra `Startup` system.
+/// * 以已注册的名称再次注册时，被替换的系统会被注销，除非它仍以其他名称注册。
+/// - Registering under a name that is already taken unregisters the replaced system, unless it is still registered
+///   under another name.
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn is_open(_: In<GuardContext>) -> bool { true }
+/// # fn debug(_: In<ActionContext>) {}
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .register_guard("is_open", is_open)
+///     .register_action("debug", debug);
+/// # }
+/// ```
+pub trait RegisterStateSystem {
+    /// 注册一个守卫系统至 [`GuardRegistry`](crate::guards::GuardRegistry)
+    ///
+    /// Register a guard system into [`GuardRegistry`](crate::guards::GuardRegistry)
+    fn register_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个只依赖服务目标的守卫系统，同一帧内共享服务目标的状态机复用其结果，参见 [`TargetGuardMemo`](crate::guards::TargetGuardMemo)
+    ///
+    /// Register a guard system depending on the service target only, whose result is reused by the machines sharing a
+    /// service target within a frame, see [`TargetGuardMemo`](crate::guards::TargetGuardMemo)
+    fn register_target_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个带参数的守卫系统至 [`GuardRegistry`](crate::guards::GuardRegistry)，在条件中以 `name("arg", 1.0)` 的形式调用
+    ///
+    /// Register a parameterized guard system into [`GuardRegistry`](crate::guards::GuardRegistry), called as `name("arg", 1.0)` in conditions
+    fn register_param_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<(GuardContext, GuardArgs)>, bool, M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个只读守卫至 [`GuardRegistry`](crate::guards::GuardRegistry)，参数状态会被缓存，参见 [`crate::read_only_guards`]
+    ///
+    /// Register a read-only guard into [`GuardRegistry`](crate::guards::GuardRegistry) with its parameter state cached,
+    /// see [`crate::read_only_guards`]
+    fn register_read_only_guard<M: 'static>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        guard: impl ReadOnlyGuardFunction<M>,
+    ) -> &mut Self;
+
+    /// 注册一个数值系统至 [`GuardValues`]，在 `above`/`below` 等内置条件中按名称引用
+    ///
+    /// Register a value system into [`GuardValues`], referenced by name in built-in conditions such as `above`/`below`
+    fn register_guard_value<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, f64, M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个动作系统至 [`ActionRegistry`]，用于 [`AfterEnterSystem`] 与 [`BeforeExitSystem`]
+    ///
+    /// Register an action system into [`ActionRegistry`], used by [`AfterEnterSystem`] and [`BeforeExitSystem`]
+    fn register_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个转换系统至 [`TransitionRegistry`]，用于 [`BeforeEnterSystem`] 与 [`AfterExitSystem`]
+    ///
+    /// Register a transition system into [`TransitionRegistry`], used by [`BeforeEnterSystem`] and [`AfterExitSystem`]
+    fn register_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + 'static,
+    ) -> &mut Self;
+
+    /// 注册一个按顺序运行多个已注册动作的动作管道，每次运行时按名称查找各步骤
+    ///
+    /// Register an action pipeline running several registered actions in order; steps are looked up by name on every run
+    /// # 示例\Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_hsm::prelude::*;
+    /// # fn play_sound(_: In<ActionContext>) {}
+    /// # fn set_animation(_: In<ActionContext>) {}
+    /// # fn my_fn() {
+    /// let mut app = App::new();
+    /// app.add_plugins(StateMachinePlugin::default())
+    ///     .register_action("play_sound", play_sound)
+    ///     .register_action("set_animation", set_animation)
+    ///     .register_action_pipeline("jump_fx", ["play_sound", "set_animation"]);
+    /// # }
+    /// # fn foo(mut commands: Commands) {
+    /// commands.spawn(AfterEnterSystem::new("jump_fx"));
+    /// // 等价的管道写法\Equivalent pipeline syntax
+    /// commands.spawn(AfterEnterSystem::new("play_sound|set_animation"));
+    /// # }
+    /// ```
+    fn register_action_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self;
+
+    /// 注册一个按顺序运行多个已注册转换系统的转换管道
+    ///
+    /// Register a transition pipeline running several registered transition systems in order
+    fn register_transition_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self;
+}
+
+/// 管道写法中分隔各步骤的字符，例如 `"play_sound|set_animation|spawn_vfx"`
+///
+/// Separator between steps in the pipeline syntax, e.g. `"play_sound|set_animation|spawn_vfx"`
+pub const PIPELINE_SEPARATOR: char = '|';
+
+fn pipeline_steps(label: &SystemLabel) -> Option<Vec<SystemLabel>> {
+    label.contains(PIPELINE_SEPARATOR).then(|| {
+        label
+            .split(PIPELINE_SEPARATOR)
+            .map(|step| SystemLabel::from(step.trim().to_string()))
+            .collect()
+    })
+}
+
+/// 为使用管道写法的标签按需注册管道
+///
+/// Register a pipeline on demand for labels using the pipeline syntax
+fn ensure_pipeline(world: &mut DeferredWorld, kind: RegistryKind, label: SystemLabel) {
+    let Some(steps) = pipeline_steps(&label) else {
+        return;
+    };
+    world.commands().queue(move |world: &mut World| match kind {
+        RegistryKind::Action => {
+            if world.resource::<ActionRegistry>().get(&label).is_none() {
+                world.register_action_pipeline(label, steps);
+            }
+        }
+        RegistryKind::Transition => {
+            if world.resource::<TransitionRegistry>().get(&label).is_none() {
+                world.register_transition_pipeline(label, steps);
+            }
+        }
+        RegistryKind::Guard => {}
+    });
+}
+
+impl RegisterStateSystem for World {
+    fn register_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.register_system(system);
+        let replaced = self
+            .get_resource_or_init::<GuardRegistry>()
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.guards.insert(name, replaced);
+            LocalRegistry::release(self, &released);
+        }
+        self
+    }
+
+    fn register_target_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.register_guard(name.clone(), system);
+        if let Some(id) = self.resource::<GuardRegistry>().get(&name) {
+            self.get_resource_or_init::<TargetGuardMemo>().insert(id);
+        }
+        self
+    }
+
+    fn register_param_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<(GuardContext, GuardArgs)>, bool, M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(system);
+        let mut guards = self.get_resource_or_init::<GuardRegistry>();
+        if let Some(replaced) = guards.insert_param(name, id)
+            && !guards.1.values().any(|&other| other == replaced)
+        {
+            let _ = self.unregister_system(replaced);
+        }
+        self
+    }
+
+    fn register_read_only_guard<M: 'static>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        guard: impl ReadOnlyGuardFunction<M>,
+    ) -> &mut Self {
+        let id = ReadOnlyGuards::insert(self, guard);
+        let mut guards = self.get_resource_or_init::<GuardRegistry>();
+        if let Some(replaced) = guards.insert_read_only(name, id)
+            && !guards.2.values().any(|&other| other == replaced)
+        {
+            self.resource_mut::<ReadOnlyGuards>().remove(replaced);
+        }
+        self
+    }
+
+    fn register_guard_value<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, f64, M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(system);
+        if let Some(replaced) = self.get_resource_or_init::<GuardValues>().insert(name, id) {
+            let _ = self.unregister_system(replaced);
+        }
+        self
+    }
+
+    fn register_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.register_system(system);
+        let replaced = self
+            .get_resource_or_init::<ActionRegistry>()
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.actions.insert(name, replaced);
+            LocalRegistry::release(self, &released);
+        }
+        self
+    }
+
+    fn register_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        let id = self.register_system(system);
+        let replaced = self
+            .get_resource_or_init::<TransitionRegistry>()
+            .insert(name.clone(), id);
+        if let Some(replaced) = replaced {
+            let mut released = LocalRegistry::default();
+            released.transitions.insert(name, replaced);
+            LocalRegistry::release(self, &released);
+        }
+        self
+    }
+
+    fn register_action_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        let steps = steps.into_iter().map(Into::into).collect::<Vec<_>>();
+        self.register_action(
+            name,
+            move |context: In<ActionContext>, world: &mut World| {
+                for step in &steps {
+                    let Some(id) = world.resource::<ActionRegistry>().get(step) else {
+                        StateMachineError::ActionNotFound(step.clone()).report(world);
+                        continue;
+                    };
+                    if let Err(e) = world.run_system_with(id, *context) {
+                        warn!("[ActionPipeline] {}: {}", step.as_ref(), e);
+                    }
+                }
+            },
+        )
+    }
+
+    fn register_transition_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        let steps = steps.into_iter().map(Into::into).collect::<Vec<_>>();
+        self.register_transition(
+            name,
+            move |context: In<TransitionContext>, world: &mut World| {
+                for step in &steps {
+                    let Some(id) = world.resource::<TransitionRegistry>().get(step) else {
+                        StateMachineError::ActionNotFound(step.clone()).report(world);
+                        continue;
+                    };
+                    if let Err(e) = world.run_system_with(id, *context) {
+                        warn!("[TransitionPipeline] {}: {}", step.as_ref(), e);
+                    }
+                }
+            },
+        )
+    }
+}
+
+impl RegisterStateSystem for App {
+    fn register_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_guard(name, system);
+        self
+    }
+
+    fn register_target_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, bool, M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_target_guard(name, system);
+        self
+    }
+
+    fn register_param_guard<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<(GuardContext, GuardArgs)>, bool, M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_param_guard(name, system);
+        self
+    }
+
+    fn register_read_only_guard<M: 'static>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        guard: impl ReadOnlyGuardFunction<M>,
+    ) -> &mut Self {
+        self.world_mut().register_read_only_guard(name, guard);
+        self
+    }
+
+    fn register_guard_value<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<GuardContext>, f64, M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_guard_value(name, system);
+        self
+    }
+
+    fn register_action<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<ActionContext>, (), M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_action(name, system);
+        self
+    }
+
+    fn register_transition<M>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        system: impl IntoSystem<In<TransitionContext>, (), M> + 'static,
+    ) -> &mut Self {
+        self.world_mut().register_transition(name, system);
+        self
+    }
+
+    fn register_action_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.world_mut().register_action_pipeline(name, steps);
+        self
+    }
+
+    fn register_transition_pipeline<S: Into<SystemLabel>>(
+        &mut self,
+        name: impl Into<SystemLabel>,
+        steps: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.world_mut().register_transition_pipeline(name, steps);
+        self
+    }
+}
+
+/// 生命周期动作所处的阶段
+///
+/// The phase a lifecycle action runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionPhase {
+    /// [`BeforeEnterSystem`]，注册在 [`TransitionRegistry`] 中
+    ///
+    /// [`BeforeEnterSystem`], registered in [`TransitionRegistry`]
+    BeforeEnter,
+    /// [`AfterEnterSystem`]，注册在 [`ActionRegistry`] 中
+    ///
+    /// [`AfterEnterSystem`], registered in [`ActionRegistry`]
+    AfterEnter,
+    /// [`BeforeExitSystem`]，注册在 [`ActionRegistry`] 中
+    ///
+    /// [`BeforeExitSystem`], registered in [`ActionRegistry`]
+    BeforeExit,
+    /// [`AfterExitSystem`]，注册在 [`TransitionRegistry`] 中
+    ///
+    /// [`AfterExitSystem`], registered in [`TransitionRegistry`]
+    AfterExit,
+}
+
+impl ActionPhase {
+    /// 该阶段是否使用 [`TransitionContext`](crate::context::TransitionContext) 作为输入
+    ///
+    /// Whether this phase takes a [`TransitionContext`](crate::context::TransitionContext) as input
+    pub const fn is_transition(self) -> bool {
+        matches!(self, Self::BeforeEnter | Self::AfterExit)
+    }
+}
+
+/// 生命周期系统的ID，对应 [`ActionId`] 或 [`TransitionId`]
+///
+/// Lifecycle system ID, either an [`ActionId`] or a [`TransitionId`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LifecycleSystemId {
+    Action(ActionId),
+    Transition(TransitionId),
+}
+
+impl From<ActionId> for LifecycleSystemId {
+    fn from(value: ActionId) -> Self {
+        Self::Action(value)
+    }
+}
+
+impl From<TransitionId> for LifecycleSystemId {
+    fn from(value: TransitionId) -> Self {
+        Self::Transition(value)
+    }
+}
+
+/// 系统的输入类型与阶段不匹配
+///
+/// The system's input type does not match the phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionPhaseMismatch {
+    pub phase: ActionPhase,
+    pub name: SystemLabel,
+}
+
+impl std::fmt::Display for ActionPhaseMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let expected = match self.phase.is_transition() {
+            true => "TransitionContext",
+            false => "ActionContext",
+        };
+        write!(
+            f,
+            "system <{}> registered for {:?} must take In<{}>",
+            self.name, self.phase, expected
+        )
+    }
+}
+
+impl std::error::Error for ActionPhaseMismatch {}
+
+/// 名称已注册在共享同一注册表的另一个阶段中
+///
+/// The name is already registered for another phase sharing the same registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionPhaseConflict {
+    pub phase: ActionPhase,
+    pub registered: ActionPhase,
+    pub name: SystemLabel,
+}
+
+impl std::fmt::Display for ActionPhaseConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "system <{}> cannot be registered for {:?}, it is already registered for {:?}",
+            self.name, self.phase, self.registered
+        )
+    }
+}
+
+impl std::error::Error for ActionPhaseConflict {}
+
+/// [`LifecycleRegistry`] 的注册错误
+///
+/// Registration error of [`LifecycleRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleRegistryError {
+    Mismatch(ActionPhaseMismatch),
+    Conflict(ActionPhaseConflict),
+}
+
+impl std::fmt::Display for LifecycleRegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LifecycleRegistryError::Mismatch(e) => e.fmt(f),
+            LifecycleRegistryError::Conflict(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleRegistryError {}
+
+impl From<ActionPhaseMismatch> for LifecycleRegistryError {
+    fn from(value: ActionPhaseMismatch) -> Self {
+        Self::Mismatch(value)
+    }
+}
+
+impl From<ActionPhaseConflict> for LifecycleRegistryError {
+    fn from(value: ActionPhaseConflict) -> Self {
+        Self::Conflict(value)
+    }
+}
+
+/// 通过 [`LifecycleRegistry`] 注册的名称所属的阶段
+///
+/// The phase each name registered through [`LifecycleRegistry`] belongs to
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LifecyclePhases {
+    actions: HashMap<SystemLabel, ActionPhase>,
+    transitions: HashMap<SystemLabel, ActionPhase>,
+}
+
+impl LifecyclePhases {
+    fn of(&self, phase: ActionPhase) -> &HashMap<SystemLabel, ActionPhase> {
+        match phase.is_transition() {
+            false => &self.actions,
+            true => &self.transitions,
+        }
+    }
+
+    fn of_mut(&mut self, phase: ActionPhase) -> &mut HashMap<SystemLabel, ActionPhase> {
+        match phase.is_transition() {
+            false => &mut self.actions,
+            true => &mut self.transitions,
+        }
+    }
+
+    /// 名称 `name` 在阶段 `phase` 所用的注册表中登记的阶段
+    ///
+    /// The phase `name` is recorded for in the registry `phase` uses
+    pub fn get(&self, phase: ActionPhase, name: &str) -> Option<ActionPhase> {
+        self.of(phase).get(name).copied()
+    }
+}
+
+/// # 生命周期注册表\Lifecycle Registry
+/// * 以 `(阶段, 名称)` 为键统一访问 [`ActionRegistry`] 与 [`TransitionRegistry`]。
+///   [`AfterEnterSystem`] 与 [`BeforeExitSystem`] 共用 [`ActionRegistry`]，[`BeforeEnterSystem`] 与
+///   [`AfterExitSystem`] 共用 [`TransitionRegistry`]，因此共用同一注册表的两个阶段不能注册同名系统：
+///   这样的注册返回 [`ActionPhaseConflict`]，`get` 与 `remove` 也不会返回或删除另一个阶段的系统。
+/// - Unified access to [`ActionRegistry`] and [`TransitionRegistry`] keyed by `(phase, name)`. [`AfterEnterSystem`]
+///   and [`BeforeExitSystem`] share [`ActionRegistry`] while [`BeforeEnterSystem`] and [`AfterExitSystem`] share
+///   [`TransitionRegistry`], so two phases sharing a registry cannot register the same name: such a registration
+///   returns [`ActionPhaseConflict`], and `get` and `remove` never return or delete the other phase's system.
+/// * 直接写入注册表、未经该参数登记阶段的名称可用于共用该注册表的任意阶段。
+/// - Names written to the registries directly, without a phase recorded through this parameter, serve any phase
+///   sharing that registry.
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn after_enter(_: In<ActionContext>) {}
+/// # fn before_enter(_: In<TransitionContext>) {}
+/// fn foo(mut commands: Commands, mut registry: LifecycleRegistry) {
+///     let after_enter = commands.register_system(after_enter);
+///     let before_enter = commands.register_system(before_enter);
+///     registry
+///         .extend([
+///             (ActionPhase::AfterEnter, "debug", after_enter.into()),
+///             (ActionPhase::BeforeEnter, "debug", before_enter.into()),
+///         ])
+///         .unwrap();
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct LifecycleRegistry<'w> {
+    actions: ResMut<'w, ActionRegistry>,
+    transitions: ResMut<'w, TransitionRegistry>,
+    phases: ResMut<'w, LifecyclePhases>,
+}
+
+impl LifecycleRegistry<'_> {
+    fn contains(&self, phase: ActionPhase, name: &str) -> bool {
+        match phase.is_transition() {
+            false => self.actions.get(name).is_some(),
+            true => self.transitions.get(name).is_some(),
+        }
+    }
+
+    /// 名称 `name` 是否可用于阶段 `phase`：未登记阶段或登记的正是该阶段
+    ///
+    /// Whether `name` serves `phase`: no phase is recorded for it, or exactly this one is
+    fn serves(&self, phase: ActionPhase, name: &str) -> bool {
+        self.phases
+            .get(phase, name)
+            .is_none_or(|registered| registered == phase)
+    }
+
+    /// 注册系统，系统的输入类型必须与阶段匹配，且名称不能已被共用同一注册表的另一个阶段占用
+    ///
+    /// Register a system; its input type must match the phase, and the name must not be taken by another phase
+    /// sharing the same registry
+    pub fn insert(
+        &mut self,
+        phase: ActionPhase,
+        name: impl Into<SystemLabel>,
+        system_id: impl Into<LifecycleSystemId>,
+    ) -> Result<Option<LifecycleSystemId>, LifecycleRegistryError> {
+        let name = name.into();
+        let system_id = system_id.into();
+        if !matches!(
+            (phase.is_transition(), system_id),
+            (false, LifecycleSystemId::Action(_)) | (true, LifecycleSystemId::Transition(_))
+        ) {
+            return Err(ActionPhaseMismatch { phase, name }.into());
+        }
+        if let Some(registered) = self.phases.get(phase, &name)
+            && registered != phase
+            && self.contains(phase, &name)
+        {
+            return Err(ActionPhaseConflict {
+                phase,
+                registered,
+                name,
+            }
+            .into());
+        }
+
+        self.phases.of_mut(phase).insert(name.clone(), phase);
+        Ok(match system_id {
+            LifecycleSystemId::Action(id) => {
+                self.actions.insert(name, id).map(LifecycleSystemId::from)
+            }
+            LifecycleSystemId::Transition(id) => self
+                .transitions
+                .insert(name, id)
+                .map(LifecycleSystemId::from),
+        })
+    }
+
+    /// 移除系统，名称登记在另一个阶段时不做任何修改
+    ///
+    /// Remove a system; nothing changes when the name is recorded for another phase
+    pub fn remove(&mut self, phase: ActionPhase, name: &str) -> Option<LifecycleSystemId> {
+        if !self.serves(phase, name) {
+            return None;
+        }
+        self.phases.of_mut(phase).remove(name);
+        match phase.is_transition() {
+            false => self.actions.remove(name).map(LifecycleSystemId::from),
+            true => self.transitions.remove(name).map(LifecycleSystemId::from),
+        }
+    }
+
+    /// 获取系统，名称登记在另一个阶段时返回 `None`
+    ///
+    /// Get a system; `None` when the name is recorded for another phase
+    pub fn get(&self, phase: ActionPhase, name: &str) -> Option<LifecycleSystemId> {
+        if !self.serves(phase, name) {
+            return None;
+        }
+        match phase.is_transition() {
+            false => self.actions.get(name).map(LifecycleSystemId::from),
+            true => self.transitions.get(name).map(LifecycleSystemId::from),
+        }
+    }
+
+    /// 批量注册系统，遇到第一个无法注册的系统时停止
+    ///
+    /// Register systems in bulk, stopping at the first system that cannot be registered
+    pub fn extend<S: Into<SystemLabel>>(
+        &mut self,
+        iter: impl IntoIterator<Item = (ActionPhase, S, LifecycleSystemId)>,
+    ) -> Result<(), LifecycleRegistryError> {
+        for (phase, name, system_id) in iter {
+            self.insert(phase, name, system_id)?;
+        }
+        Ok(())
+    }
+}
+
 macro_rules! define_state_action_component {
     ($(#[$outer:meta])* $name:ident) => {
         $(#[$outer])*
-        #[derive(Component, PartialEq, Eq, Hash, Default, Debug, Deref, DerefMut)]
+        #[derive(Component, Clone, PartialEq, Eq, Hash, Default, Debug, Deref, DerefMut)]
+        pub struct $name(SystemLabel);
+
+        impl $name {
+            pub fn new(name: impl Into<SystemLabel>) -> Self {
+                Self(name.into())
+            }
+        }
+
+        impl Equivalent<SystemLabel> for $name {
+            fn equivalent(&self, other: &SystemLabel) -> bool {
+                self.0.eq(other)
+            }
+        }
+    };
+    ($(#[$outer:meta])* $name:ident => $kind:ident) => {
+        $(#[$outer])*
+        #[derive(Component, Clone, PartialEq, Eq, Hash, Default, Debug, Deref)]
+        #[component(immutable, on_insert = Self::on_insert, on_replace = Self::on_replace)]
         pub struct $name(SystemLabel);
 
         impl $name {
             pub fn new(name: impl Into<SystemLabel>) -> Self {
                 Self(name.into())
             }
+
+            fn on_insert(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+                let Some(label) = world.get::<Self>(entity).map(|s| s.0.clone()) else {
+                    return;
+                };
+                RegistryUsage::acquire(&mut world, RegistryKind::$kind, label.clone());
+                ensure_pipeline(&mut world, RegistryKind::$kind, label);
+            }
+
+            fn on_replace(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+                let Some(label) = world.get::<Self>(entity).map(|s| s.0.clone()) else {
+                    return;
+                };
+                RegistryUsage::release(&mut world, RegistryKind::$kind, label);
+            }
         }
 
         impl Equivalent<SystemLabel> for $name {
@@ -235,7 +986,7 @@ define_state_action_component! {
     /// commands.spawn(BeforeEnterSystem::new("before_enter"));
     /// # }
     /// ```
-    BeforeEnterSystem
+    BeforeEnterSystem => Transition
 }
 
 define_state_action_component! {
@@ -250,48 +1001,135 @@ define_state_action_component! {
     /// commands.spawn(AfterEnterSystem::new("enter"));
     /// # }
     /// ```
-    AfterEnterSystem
+    AfterEnterSystem => Action
 }
 
-define_state_action_component! {
-    /// 更新状态时调用
+/// 更新状态时调用
+///
+/// Update state when calling
+/// # 使用方法\Usage
+///  由于注册动作系统时，通过[`ScheduleLabel`]来确定系统调用时间，
+///  所以组件保存的是调度标签的值与动作名称组成的 [`ActionKey`]。
+///
+///  When registering an action system, the system call time is determined through [`ScheduleLabel`],
+///  Therefore the component stores an [`ActionKey`] made of the schedule label value and the action name.
+/// * 推荐写法: [`OnUpdateSystem::in_schedule`] 或 [`OnUpdateSystem::with_schedule`]
+/// - Preferred: [`OnUpdateSystem::in_schedule`] or [`OnUpdateSystem::with_schedule`]
+/// * 兼容格式: [`ScheduleLabel`] + `:` + `方法名称`，按调度名称解析
+/// - Legacy format: [`ScheduleLabel`] + `:` + `method name`, resolved by schedule name
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn add(contexts:In<Vec<ActionContext>>)->Option<Vec<ActionContext>>{None}
+/// # fn my_fn(){
+/// # let mut app = App::new();
+///
+/// app.add_action_system(Update, "add", add);
+/// app.add_action_system(FixedUpdate, "add", add);
+///
+/// # }
+/// # fn foo(mut commands: Commands) {
+/// commands.spawn(OnUpdateSystem::in_schedule(FixedUpdate, "add"));
+/// commands.spawn(OnUpdateSystem::new("Update:add"));
+/// # }
+/// ```
+/// * 在状态运行时替换该组件（例如从 [`FixedUpdate`] 切换到 [`Update`]）时，等待运行的上下文会转移到新的动作缓存。
+/// - Replacing the component while the state runs (e.g. switching from [`FixedUpdate`] to [`Update`]) moves the pending
+///   contexts to the new action buffer.
+#[derive(Component, PartialEq, Eq, Hash, Debug, Clone, Deref, DerefMut)]
+#[component(on_replace = rebind_update_actions)]
+pub struct OnUpdateSystem(pub ActionKey);
+
+impl OnUpdateSystem {
+    pub fn new(key: impl Into<ActionKey>) -> Self {
+        Self(key.into())
+    }
+
+    pub fn with_schedule<T: ScheduleLabel + Default>(action_name: impl Into<SystemLabel>) -> Self {
+        Self(ActionKey::new(T::default(), action_name))
+    }
+
+    /// 使用调度标签的值创建，适用于携带数据的标签
     ///
-    /// Update state when calling
-    /// # 使用方法\Usage
-    ///  由于注册动作系统时，通过[`ScheduleLabel`]来确定系统调用时间，
-    ///  所以在使用对应[`ScheduleLabel`]的系统时，需要特定格式。
+    /// Create from a schedule label value, for labels carrying data
+    pub fn in_schedule(schedule: impl ScheduleLabel, action_name: impl Into<SystemLabel>) -> Self {
+        Self(ActionKey::new(schedule, action_name))
+    }
+}
+
+/// 更新状态时调用的多个动作系统
+///
+/// Several action systems called while the state updates
+/// # 使用方法\Usage
+/// * 每一项与 [`OnUpdateSystem`] 相同，都是一个 [`ActionKey`]，可以来自不同的 [`ScheduleLabel`]。
+///   与 [`OnUpdateSystem`] 同时存在时，先处理 [`OnUpdateSystem`]，再按列表顺序处理每一项。
+///   同一帧中不同动作系统之间的运行顺序由各自的调度决定。
+/// - Every entry is an [`ActionKey`] just like [`OnUpdateSystem`] and may come from a different [`ScheduleLabel`].
+///   When both are present, [`OnUpdateSystem`] is handled first, then every entry in list order.
+///   The run order between different action systems within a frame is decided by their schedules.
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands) {
+/// commands.spawn(
+///     OnUpdateSystems::default()
+///         .with::<Update>("move_along_path")
+///         .with::<PostUpdate>("scan_for_targets"),
+/// );
+/// # }
+/// ```
+#[derive(Component, PartialEq, Eq, Hash, Default, Debug, Clone, Deref, DerefMut)]
+#[component(on_replace = rebind_update_actions)]
+pub struct OnUpdateSystems(pub SmallVec<[ActionKey; 2]>);
+
+impl OnUpdateSystems {
+    pub fn new<S: Into<ActionKey>>(keys: impl IntoIterator<Item = S>) -> Self {
+        Self(keys.into_iter().map(Into::into).collect())
+    }
+
+    /// 追加一个指定调度的动作系统
     ///
-    ///  When registering an action system, the system call time is determined through [`ScheduleLabel`],
-    ///  Therefore, when using the system corresponding to [`ScheduleLabel`], a specific format is required.
-    /// * 正常格式: [`ScheduleLabel`] + `:` + `方法名称`
-    /// - Normal format: [`ScheduleLabel`] + `:` + `method name`
-    /// ```
-    /// # use bevy::prelude::*;
-    /// # use bevy_hsm::prelude::*;
-    /// # fn add(contexts:In<Vec<ActionContext>>)->Option<Vec<ActionContext>>{None}
-    /// # fn my_fn(){
-    /// # let mut app = App::new();
+    /// Append an action system of the given schedule
+    pub fn with<T: ScheduleLabel + Default>(mut self, action_name: impl Into<SystemLabel>) -> Self {
+        self.0.push(ActionKey::new(T::default(), action_name));
+        self
+    }
+
+    /// 追加一个指定调度标签值的动作系统，适用于携带数据的标签
     ///
-    /// app.add_action_system(Update, "add", add);
+    /// Append an action system of the given schedule label value, for labels carrying data
+    pub fn with_label(
+        mut self,
+        schedule: impl ScheduleLabel,
+        action_name: impl Into<SystemLabel>,
+    ) -> Self {
+        self.0.push(ActionKey::new(schedule, action_name));
+        self
+    }
+
+    /// 状态上所有更新动作的键，[`OnUpdateSystem`] 在前
     ///
-    /// # }
-    /// # fn foo(mut commands: Commands) {
-    /// commands.spawn(OnUpdateSystem::new("Update:add"));
-    /// # }
-    /// ```
-    OnUpdateSystem
+    /// Keys of every update action on the state, [`OnUpdateSystem`] first
+    pub fn labels_of(entity: &EntityRef) -> SmallVec<[ActionKey; 2]> {
+        entity
+            .get::<OnUpdateSystem>()
+            .map(|update| update.0.clone())
+            .into_iter()
+            .chain(
+                entity
+                    .get::<OnUpdateSystems>()
+                    .into_iter()
+                    .flat_map(|updates| updates.0.iter().cloned()),
+            )
+            .collect()
+    }
 }
 
-impl OnUpdateSystem {
-    pub fn with_schedule<T: ScheduleLabel>(action_name: impl Into<String>) -> Self {
-        let label = ShortName::of::<T>();
-        let action_name = action_name.into();
-        let name = match action_name.is_empty() {
-            false => format!("{}:{}", label, action_name),
-            true => label.to_string(),
-        };
-        Self(name.into())
-    }
+fn rebind_update_actions(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let old_keys = OnUpdateSystems::labels_of(&world.entity(entity));
+    world.commands().queue(move |world: &mut World| {
+        ScheduleActionBuffers::rebind_state(world, entity, &old_keys);
+    });
 }
 
 define_state_action_component! {
@@ -306,7 +1144,7 @@ define_state_action_component! {
     /// commands.spawn(BeforeExitSystem::new("exit"));
     /// # }
     /// ```
-    BeforeExitSystem
+    BeforeExitSystem => Action
 }
 
 define_state_action_component! {
@@ -322,7 +1160,7 @@ define_state_action_component! {
     /// # }
     /// ```
     ///
-    AfterExitSystem
+    AfterExitSystem => Transition
 }
 
 /// # 状态机服务目标
@@ -354,3 +1192,226 @@ pub struct ServiceTarget(pub Entity);
 #[derive(Component, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deref)]
 #[relationship_target(relationship = ServiceTarget)]
 pub struct StateMachineForest(Vec<Entity>);
+
+impl StateMachineForest {
+    /// 服务于该实体的所有状态机
+    ///
+    /// All state machines serving this entity
+    pub fn machines(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+/// # 服务目标必需组件\Required Service Target Components
+/// * 挂载在状态机实体上，声明其 [`ServiceTarget`] 必须拥有的组件。层级状态机首次进入转换调度时会校验这些组件，
+///   缺少时以 `InvalidStateConfig` 报告并列出所缺的组件，而不是等到条件或动作中的 `query.get(...).unwrap()` 时 panic。
+/// - Lives on the state machine entity and declares the components its [`ServiceTarget`] must have. A hierarchical
+///   state machine checks them the first time it reaches the transition schedule and reports an `InvalidStateConfig`
+///   listing the missing components, instead of panicking later on a `query.get(...).unwrap()` inside a condition or
+///   action.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// # fn foo(mut commands: Commands, state_machine: Entity) {
+/// commands.entity(state_machine).insert(
+///     RequiredServiceComponents::default()
+///         .with::<Transform>()
+///         .with::<Health>(),
+/// );
+/// # }
+/// ```
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RequiredServiceComponents(Vec<(TypeId, &'static str)>);
+
+impl RequiredServiceComponents {
+    pub fn with<T: Component>(mut self) -> Self {
+        let type_id = TypeId::of::<T>();
+        if !self.0.iter().any(|(id, _)| *id == type_id) {
+            self.0.push((type_id, std::any::type_name::<T>()));
+        }
+        self
+    }
+
+    /// 声明的组件名称
+    ///
+    /// Names of the declared components
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.iter().map(|(_, name)| *name)
+    }
+
+    /// 实体缺少的组件名称
+    ///
+    /// Names of the declared components the entity lacks
+    pub fn missing(&self, entity: EntityRef) -> Vec<&'static str> {
+        self.0
+            .iter()
+            .filter(|(type_id, _)| !entity.contains_type_id(*type_id))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// # 状态机森林查询\State Machine Forest Query
+/// * 按服务目标查询状态机的系统参数。批量暂停、终止或广播转换参见 [`StateMachineForestCommandsExt`](crate::prelude::StateMachineForestCommandsExt)。
+/// - A system parameter looking up state machines by service target. See
+///   [`StateMachineForestCommandsExt`](crate::prelude::StateMachineForestCommandsExt) for pausing, terminating or
+///   broadcasting transitions in bulk.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn count_machines(forests: StateMachineForests, player: Single<Entity, With<Name>>) {
+///     info!("{} machines serve the player", forests.machines_of(*player).len());
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct StateMachineForests<'w, 's> {
+    query: Query<'w, 's, &'static StateMachineForest>,
+}
+
+impl StateMachineForests<'_, '_> {
+    /// 服务于 `target` 的所有状态机
+    ///
+    /// All state machines serving `target`
+    pub fn machines_of(&self, target: Entity) -> &[Entity] {
+        self.query
+            .get(target)
+            .map_or(&[], StateMachineForest::machines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    fn setup() -> (World, ActionId, ActionId, TransitionId) {
+        let mut world = World::new();
+        world.init_resource::<ActionRegistry>();
+        world.init_resource::<TransitionRegistry>();
+        world.init_resource::<LifecyclePhases>();
+        let after_enter = world.register_system(|_: In<ActionContext>| {});
+        let before_exit = world.register_system(|_: In<ActionContext>| {});
+        let before_enter = world.register_system(|_: In<TransitionContext>| {});
+        (world, after_enter, before_exit, before_enter)
+    }
+
+    #[test]
+    fn test_lifecycle_registry_phase_conflict() {
+        let (mut world, after_enter, before_exit, _) = setup();
+        let mut state = SystemState::<LifecycleRegistry>::new(&mut world);
+        let mut registry = state.get_mut(&mut world);
+
+        registry
+            .insert(ActionPhase::AfterEnter, "debug", after_enter)
+            .unwrap();
+        assert_eq!(
+            registry.insert(ActionPhase::BeforeExit, "debug", before_exit),
+            Err(LifecycleRegistryError::Conflict(ActionPhaseConflict {
+                phase: ActionPhase::BeforeExit,
+                registered: ActionPhase::AfterEnter,
+                name: "debug".into(),
+            }))
+        );
+        assert_eq!(
+            registry.get(ActionPhase::AfterEnter, "debug"),
+            Some(after_enter.into())
+        );
+        assert_eq!(registry.get(ActionPhase::BeforeExit, "debug"), None);
+        assert_eq!(registry.remove(ActionPhase::BeforeExit, "debug"), None);
+        assert_eq!(
+            registry.remove(ActionPhase::AfterEnter, "debug"),
+            Some(after_enter.into())
+        );
+
+        // 移除后名称可以被另一个阶段使用
+        // Once removed, the name is free for another phase
+        registry
+            .insert(ActionPhase::BeforeExit, "debug", before_exit)
+            .unwrap();
+        assert_eq!(
+            registry.get(ActionPhase::BeforeExit, "debug"),
+            Some(before_exit.into())
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_registry_mismatch() {
+        let (mut world, after_enter, _, before_enter) = setup();
+        let mut state = SystemState::<LifecycleRegistry>::new(&mut world);
+        let mut registry = state.get_mut(&mut world);
+
+        assert_eq!(
+            registry.insert(ActionPhase::BeforeEnter, "debug", after_enter),
+            Err(LifecycleRegistryError::Mismatch(ActionPhaseMismatch {
+                phase: ActionPhase::BeforeEnter,
+                name: "debug".into(),
+            }))
+        );
+        registry
+            .insert(ActionPhase::BeforeEnter, "debug", before_enter)
+            .unwrap();
+        registry
+            .insert(ActionPhase::AfterEnter, "debug", after_enter)
+            .unwrap();
+        assert_eq!(registry.get(ActionPhase::AfterExit, "debug"), None);
+        assert_eq!(
+            registry.get(ActionPhase::BeforeEnter, "debug"),
+            Some(before_enter.into())
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_registry_unrecorded_name() {
+        let (mut world, after_enter, _, _) = setup();
+        world
+            .resource_mut::<ActionRegistry>()
+            .insert("shared", after_enter);
+        let mut state = SystemState::<LifecycleRegistry>::new(&mut world);
+        let registry = state.get_mut(&mut world);
+
+        // 直接写入注册表的名称可用于共用该注册表的任意阶段
+        // A name written to the registry directly serves any phase sharing it
+        assert_eq!(
+            registry.get(ActionPhase::AfterEnter, "shared"),
+            Some(after_enter.into())
+        );
+        assert_eq!(
+            registry.get(ActionPhase::BeforeExit, "shared"),
+            Some(after_enter.into())
+        );
+    }
+
+    #[test]
+    fn test_reregister_releases_replaced_system() {
+        let mut world = World::new();
+        world
+            .register_guard("ready", |_: In<GuardContext>| true)
+            .register_action("on_ready", |_: In<ActionContext>| {});
+        let guard = world.resource::<GuardRegistry>().get("ready").unwrap();
+        let action = world.resource::<ActionRegistry>().get("on_ready").unwrap();
+
+        world
+            .register_guard("ready", |_: In<GuardContext>| false)
+            .register_action("on_ready", |_: In<ActionContext>| {});
+        assert_ne!(world.resource::<GuardRegistry>().get("ready"), Some(guard));
+        assert!(world.unregister_system(guard).is_err());
+        assert!(world.unregister_system(action).is_err());
+
+        // 仍以其他名称注册的系统不会被注销
+        // A system still registered under another name is kept
+        let shared = world.resource::<GuardRegistry>().get("ready").unwrap();
+        world
+            .resource_mut::<GuardRegistry>()
+            .insert("alias", shared);
+        world.register_guard("ready", |_: In<GuardContext>| true);
+        assert!(world.unregister_system(shared).is_ok());
+    }
+}
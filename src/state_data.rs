@@ -182,8 +182,6 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::StateMachinePlugin;
-
     use super::*;
 
     #[derive(Component, Debug, PartialEq, Eq, Clone)]
@@ -222,6 +220,7 @@ mod tests {
     #[cfg(feature = "hsm")]
     fn test_hsm_state_machine_state_data() {
         use crate::{
+            StateMachinePlugin,
             hsm::{HsmState, event::*, state_machine::*, state_tree::*},
             prelude::StateLifecycle,
         };
@@ -0,0 +1,153 @@
+use std::borrow::Cow;
+
+use bevy::{
+    ecs::{
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::ScheduleSystem,
+    },
+    platform::collections::HashSet,
+    prelude::*,
+};
+
+use crate::builtin_guards::active_states;
+
+/// # 状态系统集\State System Set
+/// * 只在指定名称（[`Name`]）的状态处于激活时运行的系统集：可以是任意状态机，也可以限定为某个状态机。
+///   对层级状态机而言，当前状态的所有祖先同样视为激活。运行条件由 [`StateSystemsAppExt::add_state_systems`] 自动配置。
+/// - A system set that only runs while the state with the given [`Name`] is active, on any state machine or on a specific
+///   one. For hierarchical state machines, every ancestor of the current state counts as active as well. The run condition
+///   is configured automatically by [`StateSystemsAppExt::add_state_systems`].
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StateSystemSet {
+    state: Cow<'static, str>,
+    state_machine: Option<Entity>,
+}
+
+impl StateSystemSet {
+    /// 任意状态机处于该状态时运行
+    ///
+    /// Runs while any state machine is in the state
+    pub fn new(state: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            state: state.into(),
+            state_machine: None,
+        }
+    }
+
+    /// 指定的状态机处于该状态时运行
+    ///
+    /// Runs while the given state machine is in the state
+    pub fn on(state_machine: Entity, state: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            state: state.into(),
+            state_machine: Some(state_machine),
+        }
+    }
+
+    /// 该系统集的运行条件
+    ///
+    /// The run condition of this set
+    pub fn is_active(&self, world: &World) -> bool {
+        let in_state = |state_machine: Entity| {
+            active_states(world, state_machine)
+                .into_iter()
+                .any(|state| {
+                    world
+                        .get::<Name>(state)
+                        .is_some_and(|name| name.as_str() == self.state)
+                })
+        };
+        if let Some(state_machine) = self.state_machine {
+            return in_state(state_machine);
+        }
+
+        #[cfg(feature = "hsm")]
+        if let Some(mut query) =
+            world.try_query_filtered::<Entity, With<crate::hsm::state_machine::HsmStateMachine>>()
+            && query.iter(world).any(in_state)
+        {
+            return true;
+        }
+        #[cfg(feature = "fsm")]
+        if let Some(mut query) =
+            world.try_query_filtered::<Entity, With<crate::fsm::state_machine::FsmStateMachine>>()
+            && query.iter(world).any(in_state)
+        {
+            return true;
+        }
+        false
+    }
+}
+
+impl From<&'static str> for StateSystemSet {
+    fn from(value: &'static str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for StateSystemSet {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+/// 已配置运行条件的状态系统集
+///
+/// State system sets whose run condition has been configured
+#[derive(Resource, Default, Debug)]
+struct ConfiguredStateSystemSets(HashSet<(InternedScheduleLabel, StateSystemSet)>);
+
+/// # 状态系统\State Systems
+/// * 为简单的按状态逻辑提供的高层接口：添加的系统只在状态激活时运行，无需经过动作缓冲。
+/// - A higher-level interface for simple per-state logic: the added systems only run while the state is active, without
+///   going through the action buffers.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn chase() {}
+/// # fn growl() {}
+/// # fn my_fn() {
+/// let mut app = App::new();
+/// app.add_plugins(StateMachinePlugin::default())
+///     .add_state_systems(Update, "Chase", (chase, growl));
+/// # }
+/// ```
+pub trait StateSystemsAppExt {
+    /// 在调度中添加只在状态激活时运行的系统
+    ///
+    /// Add systems to a schedule that only run while the state is active
+    fn add_state_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        set: impl Into<StateSystemSet>,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self;
+}
+
+impl StateSystemsAppExt for App {
+    fn add_state_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel,
+        set: impl Into<StateSystemSet>,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        let set = set.into();
+        let schedule = schedule.intern();
+        let newly_configured = self
+            .world_mut()
+            .get_resource_or_init::<ConfiguredStateSystemSets>()
+            .0
+            .insert((schedule, set.clone()));
+        if newly_configured {
+            let condition = set.clone();
+            self.configure_sets(
+                schedule,
+                set.clone()
+                    .run_if(move |world: &World| condition.is_active(world)),
+            );
+        }
+        self.add_systems(schedule, systems.in_set(set))
+    }
+}
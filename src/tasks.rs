@@ -0,0 +1,192 @@
+//! # 状态任务\State Tasks
+//!
+//! 让进入/更新动作启动长时间运行的异步任务（例如寻路、加载），并通过内置守卫 `task_done` 将完成状态反馈给状态机。
+//! 任务句柄由状态机按 `(状态机, 状态)` 持有，退出该状态时自动取消。
+//!
+//! Lets enter/update actions start long-running async work (pathfinding, loading, ...) and feeds its completion back
+//! into the state machine through the built-in `task_done` guard. Task handles are owned per `(machine, state)` and
+//! cancelled automatically when the state is exited.
+
+use std::future::Future;
+
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, TaskPool, futures::check_ready},
+};
+
+use crate::context::{ActionContext, GuardContext};
+
+/// 内置守卫的名称：当前状态（[`GuardContext::from_state`]）的任务已完成时返回 `true`
+///
+/// Name of the built-in guard: returns `true` once the task of the current state ([`GuardContext::from_state`]) has finished
+pub const TASK_DONE: &str = "task_done";
+
+enum StateTask {
+    Running(Task<()>),
+    Done,
+}
+
+/// # 状态任务组件\State Tasks Component
+/// * 挂载在状态机实体上，保存每个状态的任务。
+/// - Lives on the state machine entity and holds the task of every state.
+#[derive(Component, Default)]
+pub struct StateTasks(HashMap<Entity, StateTask>);
+
+impl StateTasks {
+    /// 该状态的任务是否已完成
+    ///
+    /// Whether the task of the state has finished
+    pub fn is_done(&self, state: Entity) -> bool {
+        matches!(self.0.get(&state), Some(StateTask::Done))
+    }
+
+    /// 该状态的任务是否仍在运行
+    ///
+    /// Whether the task of the state is still running
+    pub fn is_running(&self, state: Entity) -> bool {
+        matches!(self.0.get(&state), Some(StateTask::Running(_)))
+    }
+
+    /// 取消（丢弃）该状态的任务
+    ///
+    /// Cancel (drop) the task of the state
+    pub fn cancel(&mut self, state: Entity) -> bool {
+        self.0.remove(&state).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn cancel_command(state_machine: Entity, state: Entity) -> impl Command {
+        move |world: &mut World| {
+            if let Some(mut tasks) = world.get_mut::<StateTasks>(state_machine) {
+                tasks.cancel(state);
+            }
+        }
+    }
+
+    pub(crate) fn poll(mut query: Query<&mut StateTasks>) {
+        for mut tasks in query.iter_mut() {
+            for task in tasks.0.values_mut() {
+                if let StateTask::Running(running) = task
+                    && check_ready(running).is_some()
+                {
+                    *task = StateTask::Done;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn task_done(context: In<GuardContext>, query: Query<&StateTasks>) -> bool {
+        query
+            .get(context.state_machine)
+            .is_ok_and(|tasks| tasks.is_done(context.from_state()))
+    }
+}
+
+/// # 状态任务命令扩展\State Task Commands Extension
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn find_path(context: In<ActionContext>, mut commands: Commands) {
+///     commands.spawn_state_task(*context, async move {
+///         // 耗时的工作
+///         // Expensive work
+///     });
+/// }
+/// # fn foo(mut commands: Commands) {
+/// // 任务完成后离开当前状态
+/// // Leave the current state once the task has finished
+/// commands.spawn((HsmState::default(), AfterEnterSystem::new("find_path"), GuardExit::new(TASK_DONE)));
+/// # }
+/// ```
+pub trait StateTaskCommandsExt {
+    /// 为上下文中的 `(状态机, 状态)` 启动一个异步任务，会替换该状态已有的任务
+    ///
+    /// Start an async task for the `(machine, state)` in the context, replacing any existing task of that state
+    fn spawn_state_task(
+        &mut self,
+        context: ActionContext,
+        future: impl Future<Output = ()> + Send + 'static,
+    );
+}
+
+impl StateTaskCommandsExt for Commands<'_, '_> {
+    fn spawn_state_task(
+        &mut self,
+        context: ActionContext,
+        future: impl Future<Output = ()> + Send + 'static,
+    ) {
+        self.queue(move |world: &mut World| {
+            let Ok(mut entity) = world.get_entity_mut(context.state_machine) else {
+                return;
+            };
+            let task = AsyncComputeTaskPool::get_or_init(TaskPool::default).spawn(future);
+            entity
+                .entry::<StateTasks>()
+                .or_default()
+                .get_mut()
+                .0
+                .insert(context.state(), StateTask::Running(task));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StateMachinePlugin;
+
+    use super::*;
+
+    #[test]
+    fn test_state_task_completes_and_cancels() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let state_machine = app.world_mut().spawn_empty().id();
+        let state = app.world_mut().spawn_empty().id();
+        let context = ActionContext::new(state_machine, state_machine, state);
+
+        app.world_mut()
+            .commands()
+            .spawn_state_task(context, async {});
+        app.world_mut().flush();
+        assert!(app.world().get::<StateTasks>(state_machine).is_some());
+
+        for _ in 0..100 {
+            app.update();
+            if app
+                .world()
+                .get::<StateTasks>(state_machine)
+                .unwrap()
+                .is_done(state)
+            {
+                break;
+            }
+            std::thread::yield_now();
+        }
+        let guard = GuardContext::new(state_machine, state_machine, state, state);
+        assert!(
+            app.world_mut()
+                .run_system_cached_with(StateTasks::task_done, guard)
+                .unwrap()
+        );
+
+        app.world_mut()
+            .commands()
+            .queue(StateTasks::cancel_command(state_machine, state));
+        app.world_mut().flush();
+        assert!(
+            app.world()
+                .get::<StateTasks>(state_machine)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
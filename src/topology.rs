@@ -0,0 +1,108 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+#[cfg(feature = "fsm")]
+use crate::fsm::{graph::FsmGraph, state_machine::FsmStateMachine};
+#[cfg(feature = "hsm")]
+use crate::hsm::{state_machine::HsmStateMachine, state_tree::StateTree};
+
+/// # 状态机拓扑\State Machine Topology
+/// * 统一查询状态机所属状态的系统参数，无需关心状态机使用的是层级状态机的 [`StateTree`]
+///   还是有限状态机的 [`FsmGraph`]。有限状态机没有层级，其所有状态都视为没有父状态的叶状态。
+/// - A system parameter enumerating the states belonging to a state machine, whether the machine uses the [`StateTree`]
+///   of a hierarchical state machine or the [`FsmGraph`] of a finite state machine. Finite state machines have no
+///   hierarchy, so all of their states count as leaves without a parent.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// fn count_states(topology: HsmTopology, query: Query<Entity, With<HsmStateMachine>>) {
+///     for state_machine in query.iter() {
+///         let states = topology.states_of(state_machine).count();
+///         let leaves = topology.leaves_of(state_machine).count();
+///         info!("{:?}: {} states, {} leaves", state_machine, states, leaves);
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct HsmTopology<'w, 's> {
+    #[cfg(feature = "hsm")]
+    hsms: Query<'w, 's, &'static HsmStateMachine>,
+    #[cfg(feature = "hsm")]
+    state_trees: Query<'w, 's, &'static StateTree>,
+    #[cfg(feature = "fsm")]
+    fsms: Query<'w, 's, &'static FsmStateMachine>,
+    #[cfg(feature = "fsm")]
+    graphs: Query<'w, 's, &'static FsmGraph>,
+}
+
+impl HsmTopology<'_, '_> {
+    #[cfg(feature = "hsm")]
+    fn state_tree(&self, state_machine: Entity) -> Option<&StateTree> {
+        let hsm = self.hsms.get(state_machine).ok()?;
+        self.state_trees.get(hsm.state_tree()).ok()
+    }
+
+    #[cfg(feature = "fsm")]
+    fn graph(&self, state_machine: Entity) -> Option<&FsmGraph> {
+        let fsm = self.fsms.get(state_machine).ok()?;
+        self.graphs.get(fsm.graph_id()).ok()
+    }
+
+    /// 状态机的所有状态
+    ///
+    /// Every state of the state machine
+    pub fn states_of(&self, state_machine: Entity) -> impl Iterator<Item = Entity> + '_ {
+        let mut states = Vec::new();
+        #[cfg(feature = "hsm")]
+        if let Some(state_tree) = self.state_tree(state_machine) {
+            states.extend(state_tree.iter());
+        }
+        #[cfg(feature = "fsm")]
+        if let Some(graph) = self.graph(state_machine) {
+            states.extend(graph.states());
+        }
+        states.into_iter()
+    }
+
+    /// 状态机中没有子状态的状态
+    ///
+    /// The states of the state machine without sub-states
+    pub fn leaves_of(&self, state_machine: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.states_of(state_machine)
+            .filter(move |&state| self.children_of(state_machine, state).next().is_none())
+    }
+
+    /// 状态在状态机中的直接子状态
+    ///
+    /// The direct sub-states of a state in the state machine
+    pub fn children_of(
+        &self,
+        state_machine: Entity,
+        state: Entity,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        #[cfg(feature = "hsm")]
+        let children = self
+            .state_tree(state_machine)
+            .and_then(|state_tree| state_tree.get_sub_states(state))
+            .unwrap_or_default();
+        #[cfg(not(feature = "hsm"))]
+        let children: &[Entity] = {
+            let _ = (state_machine, state);
+            &[]
+        };
+        children.iter().copied()
+    }
+
+    /// 状态在状态机中的父状态
+    ///
+    /// The super state of a state in the state machine
+    pub fn parent_of(&self, state_machine: Entity, state: Entity) -> Option<Entity> {
+        #[cfg(feature = "hsm")]
+        if let Some(state_tree) = self.state_tree(state_machine) {
+            return state_tree.get_super_state(state);
+        }
+        let _ = (state_machine, state);
+        None
+    }
+}
@@ -0,0 +1,217 @@
+//! # UI 绑定\UI Bindings
+//!
+//! 根据状态是否处于活动中来驱动 bevy_ui 节点的显示，菜单是层级状态机最常见的用途之一。
+//!
+//! Drives bevy_ui node visibility from whether a state is active; menus are one of the primary uses of a hierarchical state machine.
+
+use bevy::{platform::collections::HashSet, prelude::*, ui::UiSystems};
+
+use crate::markers::Terminated;
+
+/// 绑定的显示方式
+///
+/// How the binding shows and hides the node
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VisibilityBindingMode {
+    /// 设置 [`Visibility`]，隐藏的节点仍参与布局
+    ///
+    /// Sets [`Visibility`]; hidden nodes still take part in layout
+    #[default]
+    Visibility,
+    /// 设置 [`Node::display`]，隐藏的节点不参与布局；显示时恢复隐藏前的取值
+    ///
+    /// Sets [`Node::display`]; hidden nodes are removed from layout, and showing restores the value from before hiding
+    Display,
+}
+
+/// # 显示绑定\Visibility Binding
+/// * 挂载在状态实体上，当任意状态机处于该状态（HSM 中包括其子状态）时显示 `node`，否则隐藏。
+/// - Lives on a state entity and shows `node` while any state machine is in that state (including its sub-states for HSMs),
+///   hiding it otherwise.
+///
+/// # 示例\Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_hsm::prelude::*;
+/// # fn foo(mut commands: Commands) {
+/// let menu = commands.spawn(Node::default()).id();
+/// commands.spawn((
+///     Name::new("MainMenu"),
+///     HsmState::default(),
+///     HsmVisibilityBinding::display(menu),
+/// ));
+/// # }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HsmVisibilityBinding {
+    pub node: Entity,
+    pub mode: VisibilityBindingMode,
+}
+
+impl HsmVisibilityBinding {
+    pub const fn new(node: Entity) -> Self {
+        Self {
+            node,
+            mode: VisibilityBindingMode::Visibility,
+        }
+    }
+
+    pub const fn display(node: Entity) -> Self {
+        Self {
+            node,
+            mode: VisibilityBindingMode::Display,
+        }
+    }
+
+    fn sync(
+        #[cfg(feature = "hsm")] query_hsm: Query<
+            &crate::hsm::state_machine::HsmStateMachine,
+            Without<Terminated>,
+        >,
+        #[cfg(feature = "hsm")] query_state_tree: Query<&crate::hsm::state_tree::StateTree>,
+        #[cfg(feature = "fsm")] query_fsm: Query<
+            &crate::fsm::state_machine::FsmStateMachine,
+            Without<Terminated>,
+        >,
+        mut commands: Commands,
+        query_binding: Query<(Entity, &HsmVisibilityBinding)>,
+        mut query_node: Query<(
+            Option<&mut Visibility>,
+            Option<&mut Node>,
+            Option<&HiddenDisplay>,
+        )>,
+    ) {
+        let mut active = HashSet::new();
+        #[cfg(feature = "hsm")]
+        for state_machine in query_hsm.iter() {
+            let curr_state_id = state_machine.curr_state_id();
+            active.insert(curr_state_id);
+            if let Ok(state_tree) = query_state_tree.get(state_machine.state_tree()) {
+                active.extend(state_tree.path_iter(curr_state_id));
+            }
+        }
+        #[cfg(feature = "fsm")]
+        active.extend(
+            query_fsm
+                .iter()
+                .map(|state_machine| state_machine.curr_state_id()),
+        );
+
+        for (state, binding) in query_binding.iter() {
+            let Ok((visibility, node, hidden)) = query_node.get_mut(binding.node) else {
+                continue;
+            };
+            let is_active = active.contains(&state);
+            match binding.mode {
+                VisibilityBindingMode::Visibility => {
+                    let value = match is_active {
+                        true => Visibility::Inherited,
+                        false => Visibility::Hidden,
+                    };
+                    if let Some(mut visibility) = visibility {
+                        visibility.set_if_neq(value);
+                    }
+                }
+                VisibilityBindingMode::Display => {
+                    let Some(mut node) = node else {
+                        continue;
+                    };
+                    match (is_active, node.display) {
+                        (true, Display::None) => {
+                            node.display = hidden.map_or(Display::DEFAULT, |hidden| hidden.0);
+                            commands.entity(binding.node).remove::<HiddenDisplay>();
+                        }
+                        (false, display) if display != Display::None => {
+                            commands.entity(binding.node).insert(HiddenDisplay(display));
+                            node.display = Display::None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 节点被隐藏前的 [`Node::display`]，再次显示时恢复
+///
+/// The [`Node::display`] of a node before it was hidden, restored when it is shown again
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct HiddenDisplay(Display);
+
+pub(crate) fn install_ui_bindings(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        HsmVisibilityBinding::sync
+            .run_if(any_with_component::<HsmVisibilityBinding>)
+            .before(UiSystems::Prepare),
+    );
+}
+
+#[cfg(all(test, feature = "hsm"))]
+mod tests {
+    use crate::{StateMachinePlugin, prelude::*};
+
+    use super::*;
+
+    #[test]
+    fn test_visibility_binding() {
+        let mut app = App::new();
+        app.add_plugins(StateMachinePlugin::default());
+        let world = app.world_mut();
+        let root_node = world.spawn(Visibility::Visible).id();
+        let child_node = world
+            .spawn(Node {
+                display: Display::Grid,
+                ..default()
+            })
+            .id();
+        let root = world
+            .spawn((HsmState::default(), HsmVisibilityBinding::new(root_node)))
+            .id();
+        let child = world
+            .spawn((
+                HsmState::default(),
+                HsmVisibilityBinding::display(child_node),
+            ))
+            .id();
+        let mut state_tree = StateTree::new(root);
+        state_tree.with_child(root, child);
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                state_machine,
+                root,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+        ));
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.get::<Visibility>(root_node),
+            Some(&Visibility::Inherited)
+        );
+        assert_eq!(
+            world.get::<Node>(child_node).unwrap().display,
+            Display::None
+        );
+
+        app.world_mut()
+            .trigger(HsmTrigger::to_sub(state_machine, child));
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.get::<Visibility>(root_node),
+            Some(&Visibility::Inherited)
+        );
+        assert_eq!(
+            world.get::<Node>(child_node).unwrap().display,
+            Display::Grid
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy_hsm::hsm::validation::{InvalidStateConfig, StateConfigIssue};
 use bevy_hsm::prelude::*;
 
 #[derive(Component, Debug, Clone, Copy)]
@@ -130,3 +131,628 @@ fn remove_action_system() {
     app.update();
     app.update();
 }
+
+#[test]
+fn inspect_action_buffers() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default());
+
+    app.add_action_system(Update, "debug_hello_world", debug_hello_world);
+    app.add_systems(Startup, (register_condition, setup).chain());
+
+    app.update();
+
+    let world = app.world_mut();
+    let snapshots = ActionDispatch::snapshots(world);
+    let (_, listed) = snapshots
+        .iter()
+        .find(|(key, _)| *key == ActionKey::new(Update, "debug_hello_world"))
+        .unwrap();
+
+    let snapshot = ActionDispatch::snapshot(world, "Update:debug_hello_world").unwrap();
+    assert_eq!(snapshot.scheduled.len(), 1);
+    assert_eq!(&snapshot, listed);
+    assert!(ActionDispatch::snapshot(world, "Update:missing").is_none());
+}
+
+#[test]
+fn despawned_machine_leaves_action_buffers() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default());
+
+    app.add_action_system(Update, "debug_hello_world", debug_hello_world);
+    app.add_systems(Startup, (register_condition, setup).chain());
+
+    app.update();
+    app.update();
+
+    let world = app.world_mut();
+    let state_machine = world
+        .query_filtered::<Entity, With<HsmStateMachine>>()
+        .single(world)
+        .unwrap();
+    world.despawn(state_machine);
+
+    let snapshot = ActionDispatch::snapshot(world, "Update:debug_hello_world").unwrap();
+    assert!(snapshot.current.is_empty());
+    assert!(snapshot.scheduled.is_empty());
+    assert_eq!(snapshot.filters, 0);
+    assert_eq!(snapshot.interceptors, 0);
+
+    app.update();
+}
+
+#[derive(Resource, Default)]
+struct UpdateLog(Vec<&'static str>);
+
+fn log_update(
+    name: &'static str,
+) -> impl Fn(In<Vec<ActionContext>>, ResMut<UpdateLog>) -> Option<Vec<ActionContext>> {
+    move |contexts: In<Vec<ActionContext>>, mut log: ResMut<UpdateLog>| {
+        log.0.push(name);
+        Some(contexts.0)
+    }
+}
+
+#[test]
+fn multiple_update_actions() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>();
+
+    app.add_action_system(Update, "move_along_path", log_update("move"));
+    app.add_action_system(PostUpdate, "scan_for_targets", log_update("scan"));
+
+    let world = app.world_mut();
+    let patrol = world
+        .spawn(
+            OnUpdateSystems::default()
+                .with::<Update>("move_along_path")
+                .with::<PostUpdate>("scan_for_targets"),
+        )
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(patrol),
+        HsmStateMachine::with(
+            state_machine,
+            patrol,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    app.update();
+    app.update();
+
+    let log = &app.world().resource::<UpdateLog>().0;
+    assert_eq!(log.iter().filter(|name| **name == "move").count(), 2);
+    assert_eq!(log.iter().filter(|name| **name == "scan").count(), 2);
+    assert_eq!(&log[..2], ["move", "scan"]);
+}
+
+#[test]
+fn same_action_name_in_several_schedules() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>();
+
+    app.add_action_system(Update, "tick", log_update("update"))
+        .add_action_system(PostUpdate, "tick", log_update("post_update"));
+
+    let world = app.world_mut();
+    let ticking = world
+        .spawn((
+            OnUpdateSystem::with_schedule::<PostUpdate>("tick"),
+            // 旧格式的键按调度名称解析
+            // Legacy keys resolve by schedule name
+            OnUpdateSystems::new(["Update:tick"]),
+        ))
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(ticking),
+        HsmStateMachine::with(
+            state_machine,
+            ticking,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    app.update();
+    app.update();
+
+    let log = &app.world().resource::<UpdateLog>().0;
+    assert_eq!(log.iter().filter(|name| **name == "update").count(), 2);
+    assert_eq!(log.iter().filter(|name| **name == "post_update").count(), 2);
+
+    let dispatch = app.world().resource::<ActionDispatch>();
+    assert!(ActionKey::parse("Update:tick").is_legacy());
+    assert_eq!(
+        dispatch.resolve(&ActionKey::parse("Update:tick")),
+        ActionKey::new(Update, "tick")
+    );
+    let keys = dispatch.keys().collect::<Vec<_>>();
+    assert!(keys.contains(&&ActionKey::new(Update, "tick")));
+    assert!(keys.contains(&&ActionKey::new(PostUpdate, "tick")));
+}
+
+#[test]
+fn rebind_update_schedule() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>();
+
+    app.add_action_system(Update, "tick", log_update("update"))
+        .add_action_system(PostUpdate, "tick", log_update("post_update"));
+
+    let world = app.world_mut();
+    let moving = world
+        .spawn(OnUpdateSystem::with_schedule::<Update>("tick"))
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(moving),
+        HsmStateMachine::with(
+            state_machine,
+            moving,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    app.update();
+    app.update();
+
+    // 状态运行时切换到另一个调度，等待运行的上下文随之转移
+    // Switch to another schedule while the state runs, the pending context moves along
+    app.world_mut()
+        .entity_mut(moving)
+        .insert(OnUpdateSystem::with_schedule::<PostUpdate>("tick"));
+    let old = ActionDispatch::snapshot(app.world_mut(), ActionKey::new(Update, "tick")).unwrap();
+    assert!(old.scheduled.is_empty());
+
+    app.update();
+    app.update();
+
+    let log = &app.world().resource::<UpdateLog>().0;
+    assert_eq!(log.iter().filter(|name| **name == "update").count(), 2);
+    assert_eq!(log.iter().filter(|name| **name == "post_update").count(), 2);
+}
+
+#[test]
+fn update_action_in_wrong_schedule() {
+    #[derive(Resource, Default)]
+    struct Issues(Vec<StateConfigIssue>);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>()
+        .init_resource::<Issues>()
+        .add_observer(
+            |event: On<InvalidStateConfig>, mut issues: ResMut<Issues>| {
+                issues.0.push(event.issue.clone());
+            },
+        );
+
+    app.add_action_system(FixedUpdate, "dash", log_update("dash"));
+
+    let world = app.world_mut();
+    let dashing = world
+        .spawn((
+            HsmState::default(),
+            OnUpdateSystem::with_schedule::<Update>("dash"),
+        ))
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(dashing),
+        HsmStateMachine::with(
+            state_machine,
+            dashing,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    app.update();
+
+    let issues = &app.world().resource::<Issues>().0;
+    assert_eq!(
+        issues,
+        &[StateConfigIssue::ScheduleMismatch {
+            state: dashing,
+            component: "OnUpdateSystem",
+            key: ActionKey::new(Update, "dash"),
+            registered_in: vec!["FixedUpdate".to_owned()],
+        }]
+    );
+    assert!(
+        issues[0]
+            .to_string()
+            .ends_with("only registered in FixedUpdate")
+    );
+}
+
+#[derive(bevy::ecs::schedule::ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum Lane {
+    Fast,
+    Slow,
+}
+
+#[derive(bevy::ecs::schedule::ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum Speed {
+    Fast,
+}
+
+#[test]
+fn ambiguous_legacy_schedule() {
+    #[derive(Resource, Default)]
+    struct Issues(Vec<StateConfigIssue>);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>()
+        .init_resource::<Issues>()
+        .add_observer(
+            |event: On<InvalidStateConfig>, mut issues: ResMut<Issues>| {
+                issues.0.push(event.issue.clone());
+            },
+        );
+
+    app.add_action_system(Lane::Fast, "tick", log_update("lane"))
+        .add_action_system(Speed::Fast, "tick", log_update("speed"));
+
+    let legacy = ActionKey::parse("Fast:tick");
+    assert_eq!(legacy.legacy_schedule(), Some("Fast"));
+    assert_eq!(ActionKey::new(Lane::Fast, "tick").legacy_schedule(), None);
+    let dispatch = app.world().resource::<ActionDispatch>();
+    assert_eq!(dispatch.schedules_named("Fast").len(), 2);
+    assert_eq!(
+        dispatch.resolve(&legacy),
+        ActionKey::new(Lane::Fast, "tick")
+    );
+
+    let world = app.world_mut();
+    let ticking = world
+        .spawn((HsmState::default(), OnUpdateSystem::new("Fast:tick")))
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(ticking),
+        HsmStateMachine::with(
+            state_machine,
+            ticking,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Issues>().0,
+        [StateConfigIssue::AmbiguousSchedule {
+            state: ticking,
+            component: "OnUpdateSystem",
+            key: legacy,
+            candidates: 2,
+        }]
+    );
+}
+
+#[test]
+fn action_systems_in_label_values() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>();
+
+    app.add_action_system(Lane::Fast, "tick", log_update("fast"))
+        .add_action_system(Lane::Slow, "tick", log_update("slow"))
+        .add_systems(Update, |world: &mut World, mut frame: Local<u32>| {
+            world.run_schedule(Lane::Fast);
+            if frame.is_multiple_of(2) {
+                world.run_schedule(Lane::Slow);
+            }
+            *frame += 1;
+        });
+
+    let world = app.world_mut();
+    let patrol = world
+        .spawn(
+            OnUpdateSystems::default()
+                .with_label(Lane::Fast, "tick")
+                .with_label(Lane::Slow, "tick"),
+        )
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(patrol),
+        HsmStateMachine::with(
+            state_machine,
+            patrol,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+
+    for _ in 0..4 {
+        app.update();
+    }
+
+    let log = &app.world().resource::<UpdateLog>().0;
+    assert_eq!(log.iter().filter(|name| **name == "fast").count(), 4);
+    assert_eq!(log.iter().filter(|name| **name == "slow").count(), 2);
+    assert!(ActionDispatch::snapshot(app.world_mut(), "Fast:tick").is_some());
+}
+
+fn log_action(name: &'static str) -> impl Fn(In<ActionContext>, ResMut<UpdateLog>) {
+    move |_: In<ActionContext>, mut log: ResMut<UpdateLog>| log.0.push(name)
+}
+
+#[test]
+fn action_pipelines() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<UpdateLog>()
+        .register_action("play_sound", log_action("sound"))
+        .register_action("set_animation", log_action("animation"))
+        .register_action("spawn_vfx", log_action("vfx"))
+        .register_action_pipeline("jump_fx", ["set_animation", "play_sound"]);
+
+    let world = app.world_mut();
+    let root = world
+        .spawn((
+            HsmState::default(),
+            AfterEnterSystem::new("play_sound | set_animation|spawn_vfx"),
+            BeforeExitSystem::new("jump_fx"),
+        ))
+        .id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(root),
+        HsmStateMachine::with(
+            state_machine,
+            root,
+            #[cfg(feature = "history")]
+            10,
+        ),
+    ));
+    world.flush();
+    world
+        .entity_mut(state_machine)
+        .insert(StateLifecycle::Enter);
+    world.flush();
+    world.entity_mut(state_machine).insert(StateLifecycle::Exit);
+    world.flush();
+
+    assert_eq!(
+        app.world().resource::<UpdateLog>().0,
+        ["sound", "animation", "vfx", "animation", "sound"]
+    );
+}
+
+#[test]
+fn lifecycle_queue_driver() {
+    let run = |driver: LifecycleDriver| {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StateMachinePlugin::default())
+            .insert_resource(driver)
+            .init_resource::<UpdateLog>()
+            .register_guard("always", |_: In<GuardContext>| true)
+            .register_action("enter_a", log_action("enter_a"))
+            .register_action("enter_b", log_action("enter_b"));
+
+        let world = app.world_mut();
+        let a = world
+            .spawn((HsmState::default(), AfterEnterSystem::new("enter_a")))
+            .id();
+        let b = world
+            .spawn((
+                HsmState::default(),
+                AfterEnterSystem::new("enter_b"),
+                GuardEnter::new("always"),
+            ))
+            .id();
+        let mut state_tree = StateTree::new(a);
+        state_tree.with_child(a, b);
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                state_machine,
+                a,
+                #[cfg(feature = "history")]
+                10,
+            ),
+        ));
+        world.flush();
+        world
+            .entity_mut(state_machine)
+            .insert(StateLifecycle::Enter);
+        world.flush();
+
+        // 队列模式下阶段在转换调度中才被处理，但状态组件立即可读
+        // In queue mode the phase waits for the transition schedule, but the status component is readable at once
+        if driver == LifecycleDriver::Queue {
+            assert_eq!(
+                world.get::<StateLifecycle>(state_machine),
+                Some(&StateLifecycle::Enter)
+            );
+            assert!(world.resource::<UpdateLog>().0.is_empty());
+        }
+
+        app.update();
+        app.update();
+        std::mem::take(&mut app.world_mut().resource_mut::<UpdateLog>().0)
+    };
+
+    let hooks = run(LifecycleDriver::Hooks);
+    assert_eq!(hooks, ["enter_a", "enter_b"]);
+    assert_eq!(run(LifecycleDriver::Queue), hooks);
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+struct Plan(ActionVerdict, usize);
+
+fn follow_plan(
+    contexts: In<Vec<ActionContext>>,
+    mut query: Query<&mut Plan>,
+) -> Vec<(ActionContext, ActionVerdict)> {
+    contexts
+        .iter()
+        .map(|&context| {
+            let mut plan = query.get_mut(context.service_target).unwrap();
+            plan.1 += 1;
+            (context, plan.0)
+        })
+        .collect()
+}
+
+#[test]
+fn action_verdicts() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .register_guard(
+            "first_time",
+            |context: In<GuardContext>, query: Query<&Plan>| {
+                query.get(context.service_target).unwrap().1 == 0
+            },
+        )
+        .add_action_system(Update, "follow_plan", follow_plan);
+
+    let world = app.world_mut();
+    let mut spawn = |verdict: ActionVerdict| {
+        let a = world.spawn(HsmState::default()).id();
+        let b = world
+            .spawn((
+                HsmState::default(),
+                GuardEnter::new("first_time"),
+                OnUpdateSystem::new("Update:follow_plan"),
+            ))
+            .id();
+        let mut state_tree = StateTree::new(a);
+        state_tree.with_child(a, b);
+        let state_machine = world.spawn_empty().id();
+        world.entity_mut(state_machine).insert((
+            state_tree,
+            HsmStateMachine::with(
+                state_machine,
+                a,
+                #[cfg(feature = "history")]
+                10,
+            ),
+            StateLifecycle::default(),
+            Plan(verdict, 0),
+        ));
+        (state_machine, a, b)
+    };
+    let keep = spawn(ActionVerdict::Keep);
+    let drop = spawn(ActionVerdict::Drop);
+    let exit = spawn(ActionVerdict::Exit);
+
+    for _ in 0..5 {
+        app.update();
+    }
+
+    let world = app.world();
+    let runs =
+        |(state_machine, ..): (Entity, Entity, Entity)| world.get::<Plan>(state_machine).unwrap().1;
+    let curr_state = |(state_machine, ..): (Entity, Entity, Entity)| {
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    assert!(runs(keep) > 1);
+    assert_eq!(runs(drop), 1);
+    assert_eq!(runs(exit), 1);
+    assert_eq!(curr_state(keep), keep.2);
+    assert_eq!(curr_state(drop), drop.2);
+    assert_eq!(curr_state(exit), exit.1);
+}
+
+#[test]
+fn exit_requests() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .register_guard(
+            "first_time",
+            |context: In<GuardContext>, query: Query<&Plan>| {
+                query.get(context.service_target).unwrap().1 == 0
+            },
+        )
+        .add_action_system(
+            Update,
+            "tire_out",
+            |contexts: In<Vec<ActionContext>>,
+             mut query: Query<&mut Plan>,
+             mut exits: HsmExitRequests| {
+                for context in contexts.iter() {
+                    let mut plan = query.get_mut(context.service_target).unwrap();
+                    plan.1 += 1;
+                    if plan.1 == 2 {
+                        exits.request(*context);
+                    }
+                }
+                Some(contexts.0)
+            },
+        );
+
+    let world = app.world_mut();
+    let a = world.spawn(HsmState::default()).id();
+    let b = world
+        .spawn((
+            HsmState::default(),
+            GuardEnter::new("first_time"),
+            OnUpdateSystem::new("Update:tire_out"),
+        ))
+        .id();
+    let mut state_tree = StateTree::new(a);
+    state_tree.with_child(a, b);
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        state_tree,
+        HsmStateMachine::with(
+            state_machine,
+            a,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+        Plan(ActionVerdict::Keep, 0),
+    ));
+
+    for _ in 0..6 {
+        app.update();
+    }
+
+    let world = app.world();
+    assert_eq!(world.get::<Plan>(state_machine).unwrap().1, 2);
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        a
+    );
+}
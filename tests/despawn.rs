@@ -0,0 +1,399 @@
+use bevy::prelude::*;
+use bevy_hsm::prelude::*;
+
+#[derive(Resource, Default)]
+struct LostTargets(Vec<(Entity, Entity)>);
+
+fn setup() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<LostTargets>()
+        .add_observer(
+            |lost: On<ServiceTargetLost>, mut lost_targets: ResMut<LostTargets>| {
+                lost_targets
+                    .0
+                    .push((lost.state_machine, lost.service_target));
+            },
+        );
+    app
+}
+
+fn spawn_hsm(world: &mut World, service_target: Entity) -> Entity {
+    let root = world.spawn(HsmState::default()).id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(root),
+        HsmStateMachine::with(
+            state_machine,
+            root,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+        ServiceTarget(service_target),
+    ));
+    state_machine
+}
+
+#[test]
+fn service_target_lost_policy() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let paused = spawn_hsm(world, target);
+    let terminated = spawn_hsm(world, target);
+    world
+        .entity_mut(terminated)
+        .insert(ServiceTargetLostPolicy::Terminate);
+    let despawned = spawn_hsm(world, target);
+    world
+        .entity_mut(despawned)
+        .insert(ServiceTargetLostPolicy::Despawn);
+    app.update();
+
+    app.world_mut().despawn(target);
+    app.update();
+
+    let world = app.world();
+    assert!(world.entity(paused).contains::<Paused>());
+    assert!(!world.entity(paused).contains::<Terminated>());
+    assert!(world.entity(terminated).contains::<Terminated>());
+    assert!(world.get_entity(despawned).is_err());
+
+    let lost_targets = &world.resource::<LostTargets>().0;
+    assert_eq!(lost_targets.len(), 3);
+    assert!(lost_targets.iter().all(|(_, t)| *t == target));
+}
+
+#[test]
+fn removing_service_target_is_not_a_loss() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let state_machine = spawn_hsm(world, target);
+    app.update();
+
+    app.world_mut()
+        .entity_mut(state_machine)
+        .remove::<ServiceTarget>();
+    app.update();
+
+    let world = app.world();
+    assert!(!world.entity(state_machine).contains::<Paused>());
+    assert!(world.resource::<LostTargets>().0.is_empty());
+}
+
+#[test]
+fn despawn_hsm_removes_private_states() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let state_machine = spawn_hsm(world, target);
+    let root = world
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .init_state();
+    let child = world.spawn(HsmState::default()).id();
+    world
+        .get_mut::<StateTree>(state_machine)
+        .unwrap()
+        .with_child(root, child);
+    app.update();
+
+    app.world_mut().commands().despawn_hsm(state_machine);
+    app.update();
+
+    let world = app.world();
+    assert!(world.get_entity(state_machine).is_err());
+    assert!(world.get_entity(root).is_err());
+    assert!(world.get_entity(child).is_err());
+    assert!(world.get_entity(target).is_ok());
+}
+
+#[test]
+fn despawn_hsm_keeps_shared_tree() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let root = world.spawn(HsmState::default()).id();
+    let state_tree = world.spawn(StateTree::new(root)).id();
+    let machines = [0, 1].map(|_| {
+        world
+            .spawn((
+                HsmStateMachine::with(
+                    state_tree,
+                    root,
+                    #[cfg(feature = "history")]
+                    10,
+                ),
+                StateLifecycle::default(),
+            ))
+            .id()
+    });
+    app.update();
+
+    app.world_mut().commands().despawn_hsm(machines[0]);
+    app.update();
+
+    let world = app.world();
+    assert!(world.get_entity(machines[0]).is_err());
+    assert!(world.get_entity(machines[1]).is_ok());
+    assert!(world.get_entity(state_tree).is_ok());
+    assert!(world.get_entity(root).is_ok());
+}
+
+#[test]
+fn clone_hsm_copies_private_states() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let prototype = spawn_hsm(world, target);
+    let root = world
+        .get::<HsmStateMachine>(prototype)
+        .unwrap()
+        .init_state();
+    let child = world
+        .spawn((HsmState::default(), GuardEnter::new("never")))
+        .id();
+    world
+        .get_mut::<StateTree>(prototype)
+        .unwrap()
+        .with_child(root, child);
+    world
+        .entity_mut(prototype)
+        .insert(HsmTransitions::default().with(HsmTransition::new("enter", root, child)));
+    app.update();
+
+    let clone = app.world_mut().commands().clone_hsm(prototype);
+    app.update();
+
+    let world = app.world();
+    let hsm = world.get::<HsmStateMachine>(clone).unwrap();
+    assert_eq!(hsm.state_tree(), clone);
+    let new_root = hsm.init_state();
+    assert_ne!(new_root, root);
+    assert_eq!(world.get::<ServiceTarget>(clone).unwrap().0, target);
+
+    let state_tree = world.get::<StateTree>(clone).unwrap();
+    let new_child = state_tree.get_sub_states(new_root).unwrap()[0];
+    assert_ne!(new_child, child);
+    assert!(!state_tree.contains(root) && !state_tree.contains(child));
+    assert!(state_tree.contains(hsm.curr_state_id()));
+    assert!(world.entity(new_child).contains::<GuardEnter>());
+
+    let transition = world
+        .get::<HsmTransitions>(clone)
+        .unwrap()
+        .get("enter")
+        .unwrap();
+    assert_eq!((transition.from, transition.to), (new_root, new_child));
+
+    // 原型保持不变
+    // The prototype is left untouched
+    let state_tree = world.get::<StateTree>(prototype).unwrap();
+    assert_eq!(state_tree.get_sub_states(root), Some(&[child][..]));
+}
+
+#[test]
+fn scoped_systems_are_unregistered_with_last_user() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let guard = world.register_system(|_: In<GuardContext>| true);
+    let action = world.register_system(|_: In<ActionContext>| {});
+    world
+        .resource_mut::<GuardRegistry>()
+        .insert("scoped", guard);
+    world
+        .resource_mut::<ActionRegistry>()
+        .insert("scoped", action);
+    let global_action = world.register_system(|_: In<ActionContext>| {});
+    world
+        .resource_mut::<ActionRegistry>()
+        .insert("global", global_action);
+    world.register_guard("global", |_: In<GuardContext>| false);
+    world
+        .resource_mut::<RegistryUsage>()
+        .mark_scoped(RegistryKind::Guard, "scoped")
+        .mark_scoped(RegistryKind::Action, "scoped");
+
+    let target = world.spawn_empty().id();
+    let machines = [0, 1].map(|_| {
+        let state_machine = spawn_hsm(world, target);
+        let root = world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .init_state();
+        let child = world
+            .spawn((
+                HsmState::default(),
+                GuardEnter::new("scoped"),
+                AfterEnterSystem::new("scoped"),
+                BeforeExitSystem::new("global"),
+                GuardExit::new("global"),
+            ))
+            .id();
+        world
+            .get_mut::<StateTree>(state_machine)
+            .unwrap()
+            .with_child(root, child);
+        state_machine
+    });
+    app.update();
+
+    let usage = app.world().resource::<RegistryUsage>();
+    assert_eq!(usage.count(RegistryKind::Guard, "scoped"), 2);
+    assert_eq!(usage.count(RegistryKind::Action, "global"), 2);
+
+    app.world_mut().commands().despawn_hsm(machines[0]);
+    app.update();
+    assert!(
+        app.world()
+            .resource::<GuardRegistry>()
+            .get("scoped")
+            .is_some()
+    );
+
+    app.world_mut().commands().despawn_hsm(machines[1]);
+    app.update();
+
+    let world = app.world();
+    assert!(world.resource::<GuardRegistry>().get("scoped").is_none());
+    assert!(world.resource::<ActionRegistry>().get("scoped").is_none());
+    // 未标记为作用域的系统即使只被这两个状态机引用也会保留，之后生成的状态机仍可使用
+    // Systems not marked as scoped are kept even when only these machines referenced them, so machines spawned later
+    // can still use them
+    assert!(world.resource::<ActionRegistry>().get("global").is_some());
+    assert!(world.resource::<GuardRegistry>().get("global").is_some());
+}
+
+#[test]
+fn reparent_state_evicts_or_follows() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let state_machine = spawn_hsm(world, target);
+    let root = world
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .init_state();
+    let [b, c, d] = [(); 3].map(|_| world.spawn(HsmState::default()).id());
+    world
+        .get_mut::<StateTree>(state_machine)
+        .unwrap()
+        .with_child(root, b)
+        .with_child(b, c)
+        .with_child(root, d);
+    app.update();
+
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    let enter_c = |app: &mut App| {
+        app.world_mut().trigger(HsmTrigger::chain(state_machine, c));
+        app.update();
+        app.update();
+        assert_eq!(curr_state(app), c);
+    };
+
+    // 被移动的子树包含当前状态时，按策略驱逐到原父状态
+    // When the moved subtree holds the current state, the machine is evicted to the old super state
+    enter_c(&mut app);
+    app.world_mut()
+        .commands()
+        .reparent_state(state_machine, b, d, StateEviction::ToSuper);
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), root);
+    let state_tree = app.world().get::<StateTree>(state_machine).unwrap();
+    assert_eq!(state_tree.get_super_state(b), Some(d));
+    assert_eq!(state_tree.get_sub_states(root), Some(&[d][..]));
+
+    // 跟随时状态机留在原状态，路径随子树改变
+    // When following, the machine stays put and its path changes with the subtree
+    enter_c(&mut app);
+    assert!(reparent_state(
+        app.world_mut(),
+        state_machine,
+        b,
+        root,
+        StateEviction::Stay
+    ));
+    app.update();
+    assert_eq!(curr_state(&app), c);
+    let state_tree = app.world().get::<StateTree>(state_machine).unwrap();
+    assert_eq!(state_tree.path_iter(c).collect::<Vec<_>>(), [b, root]);
+
+    // 不能移动到自身的子树中
+    // A state cannot move into its own subtree
+    assert!(!reparent_state(
+        app.world_mut(),
+        state_machine,
+        b,
+        c,
+        StateEviction::Stay
+    ));
+}
+
+#[test]
+fn clone_hsm_shares_local_registry() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let target = world.spawn_empty().id();
+    let prototype = spawn_hsm(world, target);
+    world
+        .entity_mut(prototype)
+        .register_local_guard("ready", |_: In<GuardContext>| true);
+    let replaced = world
+        .get::<LocalRegistry>(prototype)
+        .unwrap()
+        .guards
+        .get("ready")
+        .unwrap();
+
+    // 重新注册同名局部条件时注销被替换的系统
+    // Registering a local condition under the same name unregisters the replaced system
+    world
+        .entity_mut(prototype)
+        .register_local_guard("ready", |_: In<GuardContext>| false);
+    assert!(world.unregister_system(replaced).is_err());
+    let guard = world
+        .get::<LocalRegistry>(prototype)
+        .unwrap()
+        .guards
+        .get("ready")
+        .unwrap();
+    app.update();
+
+    let clone = app.world_mut().commands().clone_hsm(prototype);
+    app.update();
+
+    // 销毁原型不会注销克隆体仍在使用的系统
+    // Despawning the prototype keeps the systems the clone still uses
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .get::<LocalRegistry>(clone)
+            .unwrap()
+            .guards
+            .get("ready"),
+        Some(guard)
+    );
+    world.entity_mut(prototype).despawn();
+    world.flush();
+    assert!(world.get_entity(guard.entity()).is_ok());
+
+    world.entity_mut(clone).despawn();
+    world.flush();
+    assert!(world.unregister_system(guard).is_err());
+}
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{ecs::message::Messages, prelude::*};
 use bevy_hsm::{prelude::*, system_registry};
 
 #[derive(Resource, Deref)]
@@ -95,6 +95,336 @@ fn test_fsm_event() {
     assert_eq!(get_curr_state(world, state_machine), ids[3]);
 }
 
+#[test]
+fn test_fsm_guard_transition_cancels_tasks() {
+    let mut app = setup();
+    app.register_action(
+        "start_task",
+        |context: In<ActionContext>, mut commands: Commands| {
+            commands.spawn_state_task(*context, std::future::pending());
+        },
+    );
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state(after_enter = "start_task")]: A,
+                #[state]: B,
+            },
+            transitions:{
+                A => B : guard("tautology"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    app.update();
+
+    let world = app.world_mut();
+    assert!(
+        world
+            .get::<StateTasks>(state_machine)
+            .unwrap()
+            .is_running(ids[0])
+    );
+
+    // 通过守卫边离开 A 时取消其任务
+    // Leaving A through a guard edge cancels its task
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(
+        world
+            .get::<FsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+    assert!(
+        !world
+            .get::<StateTasks>(state_machine)
+            .unwrap()
+            .is_running(ids[0])
+    );
+}
+
+#[test]
+fn test_fsm_guard_transition_cooldown() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                A => B : guard("tautology"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let graph = world
+        .get::<FsmStateMachine>(state_machine)
+        .unwrap()
+        .graph_id();
+    world.get_mut::<FsmGraph>(graph).unwrap().with_condition(
+        ids[1],
+        GuardCondition::parse("cooldown(10)").unwrap(),
+        ids[0],
+    );
+
+    let curr_state = |world: &World| {
+        world
+            .get::<FsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(
+        world
+            .get::<TransitionCooldowns>(state_machine)
+            .unwrap()
+            .since(ids[0], ids[1]),
+        Some(std::time::Duration::ZERO)
+    );
+
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[0]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[0]);
+
+    // 守卫边刚发生过，冷却期间不再触发
+    // The guard edge just fired, so it is suppressed during the cooldown
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[0]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[1]);
+}
+
+#[test]
+fn test_fsm_guard_transition_clears_counters() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                B => A : guard("tautology"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let graph = world
+        .get::<FsmStateMachine>(state_machine)
+        .unwrap()
+        .graph_id();
+    world.get_mut::<FsmGraph>(graph).unwrap().with_condition(
+        ids[0],
+        GuardCondition::parse("once()").unwrap(),
+        ids[1],
+    );
+
+    // 每次重新进入 A 后 `once()` 都会再次放行
+    // `once()` lets the edge through again after every re-entry into A
+    for _ in 0..2 {
+        world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+        world.flush();
+        assert_eq!(
+            world
+                .get::<FsmStateMachine>(state_machine)
+                .unwrap()
+                .curr_state_id(),
+            ids[1]
+        );
+        world.trigger(FsmTrigger::with_guard(state_machine, ids[0]));
+        world.flush();
+    }
+}
+
+#[test]
+fn test_fsm_guard_transition_resets_behavior() {
+    #[derive(Clone)]
+    struct CountDown(usize);
+
+    impl BehaviorRunner for CountDown {
+        fn tick(&mut self, _: &mut World, _: ActionContext) -> BehaviorStatus {
+            self.0 = self.0.saturating_sub(1);
+            match self.0 {
+                0 => BehaviorStatus::Success,
+                _ => BehaviorStatus::Running,
+            }
+        }
+
+        fn reset(&mut self, _: &mut World, _: ActionContext) {
+            self.0 = 2;
+        }
+    }
+
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let a = world.spawn((FsmState, HsmBehavior::new(CountDown(2)))).id();
+    let b = world.spawn(FsmState).id();
+    let mut graph = FsmGraph::new(a);
+    graph
+        .with_condition(a, GuardCondition::new(BT_SUCCEEDED), b)
+        .with_condition(b, GuardCondition::new("tautology"), a);
+    let graph = world.spawn(graph).id();
+    world.flush();
+    let state_machine = world
+        .spawn(FsmStateMachine::with(
+            graph,
+            a,
+            #[cfg(feature = "history")]
+            10,
+        ))
+        .id();
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let world = app.world_mut();
+    let curr_state = |world: &World| {
+        world
+            .get::<FsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    world.trigger(FsmTrigger::with_guard(state_machine, b));
+    world.flush();
+    assert_eq!(curr_state(world), b);
+    world.trigger(FsmTrigger::with_guard(state_machine, a));
+    world.flush();
+    assert_eq!(curr_state(world), a);
+
+    // 重新进入 A 后不会沿用上一次的成功结果
+    // Re-entering A does not reuse the previous success
+    world.trigger(FsmTrigger::with_guard(state_machine, b));
+    world.flush();
+    assert_eq!(curr_state(world), a);
+}
+
+#[test]
+fn test_fsm_guard_transition_consumes_sticky() {
+    #[derive(Resource, Default)]
+    struct Pressed(bool);
+
+    let mut app = setup();
+    app.init_resource::<Pressed>()
+        .register_guard("pressed", |_: In<GuardContext>, pressed: Res<Pressed>| {
+            pressed.0
+        });
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                B => A : guard("tautology"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let graph = world
+        .get::<FsmStateMachine>(state_machine)
+        .unwrap()
+        .graph_id();
+    world.get_mut::<FsmGraph>(graph).unwrap().with_condition(
+        ids[0],
+        GuardCondition::parse("sticky(pressed)").unwrap(),
+        ids[1],
+    );
+    let curr_state = |world: &World| {
+        world
+            .get::<FsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    world.resource_mut::<Pressed>().0 = true;
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[1]);
+
+    world.resource_mut::<Pressed>().0 = false;
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[0]));
+    world.flush();
+
+    // 守卫边已消耗粘滞条件
+    // The guard edge consumed the sticky condition
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[0]);
+}
+
+#[test]
+fn test_fsm_guard_transition_consumes_inbox() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                B => A : guard("tautology"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let graph = world
+        .get::<FsmStateMachine>(state_machine)
+        .unwrap()
+        .graph_id();
+    world.get_mut::<FsmGraph>(graph).unwrap().with_condition(
+        ids[0],
+        GuardCondition::parse(r#"has_message("go")"#).unwrap(),
+        ids[1],
+    );
+    let mut inbox = HsmInbox::default();
+    inbox.push(HsmMessage {
+        name: "go".into(),
+        sender: state_machine,
+    });
+    world.entity_mut(state_machine).insert(inbox);
+    let curr_state = |world: &World| {
+        world
+            .get::<FsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[1]);
+    assert!(world.get::<HsmInbox>(state_machine).unwrap().is_empty());
+
+    // 消息只驱动一次转换
+    // The message drives a single transition
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[0]));
+    world.flush();
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+    assert_eq!(curr_state(world), ids[0]);
+}
+
 #[test]
 fn test_hsm_event() {
     let mut app = setup();
@@ -183,3 +513,2929 @@ fn test_hsm_event() {
     world.flush();
     assert_eq!(get_curr_state(world, state_machine), ids[2]);
 }
+
+#[test]
+fn test_hsm_observer_transition() {
+    #[derive(EntityEvent)]
+    struct Clicked(Entity);
+
+    let mut app = setup();
+
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+
+    let ids = world.remove_resource::<StateIds>().unwrap();
+
+    fn get_curr_state(world: &World, state_machine: Entity) -> Entity {
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    }
+
+    world
+        .entity_mut(state_machine)
+        .insert(HsmOnObserverTransition::<Clicked>::to(ids[2]));
+    world.flush();
+
+    // A -> C true
+    world.trigger(Clicked(state_machine));
+    world.flush();
+    assert_eq!(get_curr_state(world, state_machine), ids[2]);
+
+    // 移除后不再触发
+    // No transition once removed
+    world
+        .entity_mut(state_machine)
+        .remove::<HsmOnObserverTransition<Clicked>>();
+    world.trigger(HsmTrigger::to_super(state_machine));
+    world.flush();
+    world.trigger(Clicked(state_machine));
+    world.flush();
+    assert_eq!(get_curr_state(world, state_machine), ids[0]);
+}
+
+#[test]
+fn test_hsm_transition_requester() {
+    use bevy::ecs::system::SystemState;
+
+    #[derive(Resource, Default)]
+    struct Rejections(Vec<(TransitionRequestId, RequestRejection)>);
+
+    let mut app = setup();
+    app.init_resource::<Rejections>().add_observer(
+        |rejected: On<TransitionRequestRejected>, mut rejections: ResMut<Rejections>| {
+            rejections.0.push((rejected.id, rejected.rejection));
+        },
+    );
+
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let outsider = world.spawn(HsmState::default()).id();
+
+    let mut requester = SystemState::<HsmTransitionRequester>::new(world);
+    let mut param = requester.get_mut(world);
+    let low = param.submit(state_machine, ids[1], 0, "wander");
+    let high = param.submit(state_machine, ids[2], 10, "flee");
+    let stray = param.submit(Entity::PLACEHOLDER, ids[2], 0, "missing");
+    assert_eq!(param.outcome(high), Some(RequestOutcome::Pending));
+    requester.apply(world);
+
+    app.update();
+
+    let world = app.world_mut();
+    let param = requester.get_mut(world);
+    assert!(param.is_accepted(high));
+    assert_eq!(
+        param.outcome(low),
+        Some(RequestOutcome::Rejected(RequestRejection::Superseded))
+    );
+    assert_eq!(
+        param.outcome(stray),
+        Some(RequestOutcome::Rejected(RequestRejection::Unavailable))
+    );
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[2]
+    );
+
+    let mut param = requester.get_mut(world);
+    let foreign = param.submit(state_machine, outsider, 0, "foreign");
+    requester.apply(world);
+    app.update();
+
+    let rejections = &app.world().resource::<Rejections>().0;
+    assert_eq!(rejections.len(), 3);
+    assert!(rejections.contains(&(foreign, RequestRejection::NotInTree)));
+
+    // 被拒绝的高优先级请求不压制低优先级请求
+    // A rejected higher-priority request does not supersede a lower-priority one
+    let world = app.world_mut();
+    let mut param = requester.get_mut(world);
+    let stay = param.submit(state_machine, ids[2], 10, "stay");
+    let wander = param.submit(state_machine, ids[1], 0, "wander");
+    requester.apply(world);
+    app.update();
+
+    let world = app.world_mut();
+    let param = requester.get_mut(world);
+    assert_eq!(
+        param.outcome(stay),
+        Some(RequestOutcome::Rejected(RequestRejection::AlreadyInState))
+    );
+    assert!(param.is_accepted(wander));
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+}
+
+#[test]
+fn test_hsm_named_transitions() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(state_machine).insert(
+        HsmTransitions::default()
+            .with(HsmTransition::new("blocked", ids[0], ids[1]).with_condition("contradiction"))
+            .with(
+                HsmTransition::new("open", ids[0], ids[2])
+                    .with_kind(HsmTransitionKind::Event)
+                    .with_metadata("reason", "door"),
+            )
+            .with(HsmTransition::new("back", ids[2], ids[1]).with_condition("tautology")),
+    );
+
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+
+    let world = app.world_mut();
+    let mut transitions = world.get_mut::<HsmTransitions>(state_machine).unwrap();
+    assert_eq!(
+        transitions.get("open").unwrap().metadata.get("reason"),
+        Some(&"door".into())
+    );
+    assert!(transitions.set_enabled("open", false));
+    assert!(transitions.set_enabled("back", false));
+    world.trigger(HsmFireTransition::new(state_machine, "open"));
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+
+    let world = app.world_mut();
+    let mut transitions = world.get_mut::<HsmTransitions>(state_machine).unwrap();
+    transitions.set_enabled("open", true);
+    world.trigger(HsmFireTransition::new(state_machine, "open"));
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+
+    let world = app.world_mut();
+    let mut transitions = world.get_mut::<HsmTransitions>(state_machine).unwrap();
+    transitions.set_enabled("back", true);
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+}
+
+#[test]
+fn test_hsm_disabled_state() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert((GuardEnter::new("tautology"), DisabledState::default()));
+
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+
+    app.world_mut().entity_mut(ids[1]).remove::<DisabledState>();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+
+    app.world_mut()
+        .entity_mut(ids[1])
+        .insert(DisabledState::new(StateEviction::To(ids[2])));
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+}
+
+#[test]
+fn test_machine_in_condition() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let squad = world.spawn_empty().id();
+    let leader = world
+        .spawn((
+            hsm!(
+                #[state]:Patrol(
+                    #[state]:Advance,
+                    #[state]:Retreat,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ),
+            Name::new("Leader"),
+            ChildOf(squad),
+        ))
+        .id();
+    let leader_ids = world.remove_resource::<StateIds>().unwrap();
+
+    let follower = world
+        .spawn((
+            hsm!(
+                #[state]:Follow(
+                    #[state]:Flee,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ),
+            ChildOf(squad),
+        ))
+        .id();
+    let follower_ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(follower_ids[1]).insert(GuardEnter(
+        GuardCondition::parse(
+            r#"and(machine_in("Leader", "Retreat"), machine_in("self", "Follow"))"#,
+        )
+        .unwrap(),
+    ));
+
+    let curr_state = |app: &App, state_machine: Entity| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    assert_eq!(curr_state(&app, follower), follower_ids[0]);
+
+    app.world_mut()
+        .trigger(HsmTrigger::chain(leader, leader_ids[2]));
+    app.update();
+    assert_eq!(curr_state(&app, leader), leader_ids[2]);
+    app.update();
+    assert_eq!(curr_state(&app, follower), follower_ids[1]);
+}
+
+#[test]
+fn test_state_machine_forest_broadcast() {
+    use bevy::ecs::system::SystemState;
+
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let player = world.spawn_empty().id();
+    let machines = (0..2)
+        .map(|_| {
+            world
+                .spawn((
+                    hsm!(
+                        #[state]:Idle(
+                            #[state]:Stunned,
+                        )
+                        StateLifecycle::default(),
+                    ),
+                    ServiceTarget(player),
+                ))
+                .id()
+        })
+        .collect::<Vec<_>>();
+
+    let mut forests = SystemState::<StateMachineForests>::new(world);
+    assert_eq!(forests.get(world).machines_of(player), machines.as_slice());
+
+    world
+        .commands()
+        .broadcast_transition(player, "Stunned", 0, "hit");
+    world.flush();
+    app.update();
+
+    let world = app.world_mut();
+    for &state_machine in &machines {
+        let curr_state_id = world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id();
+        assert_eq!(
+            world.get::<Name>(curr_state_id).map(Name::as_str),
+            Some("Stunned")
+        );
+    }
+
+    world.commands().broadcast_pause(player);
+    world.flush();
+    assert!(machines.iter().all(|&e| world.get::<Paused>(e).is_some()));
+
+    world.commands().broadcast_resume(player);
+    world.flush();
+    assert!(machines.iter().all(|&e| world.get::<Paused>(e).is_none()));
+
+    world.commands().broadcast_terminate(player);
+    world.commands().broadcast_resume(player);
+    world.flush();
+    assert!(machines.iter().all(|&e| world.get::<Paused>(e).is_some()));
+}
+
+#[test]
+fn test_hsm_state_priority() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+                #[state]:D,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    for &id in &ids[1..] {
+        world.entity_mut(id).insert(GuardEnter::new("tautology"));
+    }
+    world.entity_mut(ids[3]).insert(StatePriority(5));
+
+    let state_tree = world
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .state_tree();
+    let order = |world: &World| {
+        world
+            .get::<StateTree>(state_tree)
+            .unwrap()
+            .traversal_iter(world, ids[0])
+    };
+    // 高优先级在前，相同优先级保持声明顺序
+    // Higher priorities first, ties keep declaration order
+    assert_eq!(order(world), [ids[3], ids[1], ids[2]]);
+
+    world.get_mut::<StatePriority>(ids[3]).unwrap().0 = -1;
+    world.entity_mut(ids[2]).insert(StatePriority(1));
+    assert_eq!(order(world), [ids[2], ids[1], ids[3]]);
+
+    app.update();
+    assert_eq!(
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[2]
+    );
+}
+
+#[test]
+fn test_state_machine_error_message() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state = world.spawn(GuardEnter::new("missing")).id();
+    world.flush();
+
+    let messages = world.resource::<Messages<StateMachineErrorMessage>>();
+    let mut cursor = messages.get_cursor();
+    let errors = cursor
+        .read(messages)
+        .map(|message| &message.0)
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        StateMachineError::GuardUnresolved { state: s, .. } if *s == state
+    ));
+
+    // 守卫触发的 FSM 转换同样报告未注册的守卫
+    // Guard-triggered FSM transitions report unregistered guards as well
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                A => B : guard("missing"),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+    world.flush();
+
+    let messages = world.resource::<Messages<StateMachineErrorMessage>>();
+    assert!(cursor.read(messages).any(|message| matches!(
+        &message.0,
+        StateMachineError::GuardUnresolved { state: s, .. } if *s == ids[1]
+    )));
+}
+
+#[test]
+#[should_panic(expected = "cannot be resolved")]
+fn test_state_machine_error_policy_panic() {
+    let mut app = setup();
+    let world = app.world_mut();
+    world.insert_resource(StateMachineErrorPolicy::Panic);
+
+    world.spawn(GuardEnter::new("missing"));
+    world.flush();
+}
+
+#[test]
+fn test_hsm_transition_hooks() {
+    #[derive(Component)]
+    struct Locked;
+
+    #[derive(Resource, Default)]
+    struct HookLog(Vec<(&'static str, Entity, Entity)>);
+
+    fn unlocked(
+        context: In<GuardContext>,
+        query: Query<(), With<Locked>>,
+        mut log: ResMut<HookLog>,
+    ) -> bool {
+        log.0
+            .push(("before", context.from_state(), context.to_state()));
+        !query.contains(context.to_state())
+    }
+
+    fn after(context: In<GuardContext>, mut log: ResMut<HookLog>) {
+        log.0
+            .push(("after", context.from_state(), context.to_state()));
+    }
+
+    let mut app = setup();
+    app.init_resource::<HookLog>()
+        .add_before_transition_hook("unlocked", unlocked)
+        .add_after_transition_hook("after", after);
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert((GuardEnter::new("tautology"), Locked));
+
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    // 被转换前钩子取消
+    // Cancelled by the before-transition hook
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+    assert_eq!(
+        app.world().resource::<HookLog>().0,
+        [("before", ids[0], ids[1])]
+    );
+
+    app.world_mut().entity_mut(ids[1]).remove::<Locked>();
+    app.world_mut().resource_mut::<HookLog>().0.clear();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+    assert_eq!(
+        app.world().resource::<HookLog>().0,
+        [("before", ids[0], ids[1]), ("after", ids[0], ids[1])]
+    );
+
+    // 链式跳转同样经过钩子
+    // Chained jumps go through the hooks as well
+    app.world_mut().resource_mut::<HookLog>().0.clear();
+    app.world_mut()
+        .trigger(HsmTrigger::chain(state_machine, ids[2]));
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+    assert_eq!(
+        app.world().resource::<HookLog>().0[..2],
+        [("before", ids[1], ids[2]), ("after", ids[1], ids[2])]
+    );
+
+    app.world_mut().remove_transition_hook("unlocked");
+    assert_eq!(
+        app.world()
+            .resource::<HsmTransitionHooks>()
+            .before_labels()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_hsm_current_lifecycle() {
+    #[derive(Resource, Default)]
+    struct Changes(usize);
+
+    fn count_changes(query: Query<(), Changed<CurrentLifecycle>>, mut changes: ResMut<Changes>) {
+        changes.0 += query.iter().count();
+    }
+
+    let mut app = setup();
+    app.init_resource::<Changes>()
+        .add_systems(Update, count_changes);
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::new("tautology"));
+    world.flush();
+
+    let current = |app: &App| *app.world().get::<CurrentLifecycle>(state_machine).unwrap();
+    assert_eq!(
+        current(&app),
+        CurrentLifecycle {
+            state: ids[0],
+            lifecycle: StateLifecycle::Update
+        }
+    );
+
+    app.update();
+    assert_eq!(
+        current(&app),
+        CurrentLifecycle {
+            state: ids[1],
+            lifecycle: StateLifecycle::Update
+        }
+    );
+
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Changes>().0, 2);
+}
+
+#[test]
+fn test_hsm_loop_detection() {
+    #[derive(Resource, Default)]
+    struct Detected(Vec<Vec<Entity>>);
+
+    let mut app = setup();
+    app.init_resource::<Detected>()
+        .insert_resource(TransitionLoopDetection::new(3, 10))
+        .add_observer(|on: On<HsmLoopDetected>, mut detected: ResMut<Detected>| {
+            detected.0.push(on.states.clone());
+        });
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    // 进入与退出条件同时成立，状态机在 A 与 B 之间来回转换
+    // Enter and exit conditions both hold, so the machine ping-pongs between A and B
+    world
+        .entity_mut(ids[1])
+        .insert((GuardEnter::new("tautology"), GuardExit::new("tautology")));
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    assert!(app.world().entity(state_machine).contains::<Paused>());
+    let detected = &app.world().resource::<Detected>().0;
+    assert_eq!(detected.len(), 1);
+    assert!(detected[0].contains(&ids[1]));
+}
+
+#[test]
+fn test_guard_overrides() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let spawn = |world: &mut World| {
+        let state_machine = world
+            .spawn(hsm!(
+                #[state]:A(
+                    #[state]:B,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ))
+            .id();
+        let ids = world.remove_resource::<StateIds>().unwrap();
+        world
+            .entity_mut(ids[1])
+            .insert(GuardEnter::new("contradiction"));
+        (state_machine, ids[1])
+    };
+    let (forced, forced_b) = spawn(world);
+    let (other, other_b) = spawn(world);
+
+    world
+        .resource_mut::<GuardOverrides>()
+        .set_for(forced, "contradiction", true);
+    app.update();
+
+    let curr_state = |app: &App, state_machine| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    assert_eq!(curr_state(&app, forced), forced_b);
+    assert_ne!(curr_state(&app, other), other_b);
+
+    app.world_mut()
+        .resource_mut::<GuardOverrides>()
+        .set("contradiction", true)
+        .set_for(other, "contradiction", false);
+    app.update();
+    assert_ne!(curr_state(&app, other), other_b);
+
+    app.world_mut()
+        .resource_mut::<GuardOverrides>()
+        .clear_for(other, "contradiction");
+    app.update();
+    assert_eq!(curr_state(&app, other), other_b);
+}
+
+#[test]
+fn test_guard_overrides_local_registry() {
+    let mut app = setup();
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:Idle(
+                #[state(guard_enter = "ready")]:Ready,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .register_local_guard("ready", |_: In<GuardContext>| false)
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+
+    // 覆盖按编译时的名称生效，局部注册的守卫同样可以被覆盖
+    // Overrides apply by the compiled name, so locally registered guards can be overridden too
+    world
+        .resource_mut::<GuardOverrides>()
+        .set_for(state_machine, "ready", true);
+    app.update();
+    let curr_state = app
+        .world()
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .curr_state_id();
+    assert_eq!(curr_state, ids[1]);
+}
+
+#[test]
+fn test_state_systems() {
+    #[derive(Resource, Default)]
+    struct Runs(Vec<&'static str>);
+
+    let mut app = setup();
+    app.init_resource::<Runs>()
+        .add_state_systems(Update, "A", |mut runs: ResMut<Runs>| runs.0.push("A"))
+        .add_state_systems(Update, "B", |mut runs: ResMut<Runs>| runs.0.push("B"));
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::new("tautology"));
+    app.add_state_systems(
+        Update,
+        StateSystemSet::on(state_machine, "B"),
+        |mut runs: ResMut<Runs>| runs.0.push("on"),
+    );
+
+    app.update();
+    assert_eq!(app.world().resource::<Runs>().0, ["A"]);
+
+    // 祖先状态同样视为激活
+    // Ancestor states count as active too
+    app.world_mut().resource_mut::<Runs>().0.clear();
+    app.update();
+    let mut runs = std::mem::take(&mut app.world_mut().resource_mut::<Runs>().0);
+    runs.sort();
+    assert_eq!(runs, ["A", "B", "on"]);
+}
+
+#[test]
+fn test_hsm_name_index() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let spawn = |world: &mut World| {
+        let state_machine = world
+            .spawn(hsm!(
+                #[state]:A(
+                    #[state]:B,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ))
+            .id();
+        (state_machine, world.remove_resource::<StateIds>().unwrap())
+    };
+    let (first, first_ids) = spawn(world);
+    let (second, second_ids) = spawn(world);
+
+    // 同名状态按状态机区分
+    // States sharing a name are told apart by state machine
+    assert_eq!(HsmNameIndex::find(world, first, "B"), Some(first_ids[1]));
+    assert_eq!(HsmNameIndex::find(world, second, "B"), Some(second_ids[1]));
+    assert_eq!(world.resource::<HsmNameIndex>().states_named("B").len(), 2);
+    assert_eq!(
+        world.resource::<HsmNameIndex>().name_of(first_ids[0]),
+        Some("A")
+    );
+
+    world.entity_mut(first_ids[1]).insert(Name::new("C"));
+    assert_eq!(HsmNameIndex::find(world, first, "B"), None);
+    assert_eq!(HsmNameIndex::find(world, first, "C"), Some(first_ids[1]));
+
+    world.despawn(second_ids[1]);
+    assert_eq!(HsmNameIndex::find(world, second, "B"), None);
+    assert!(
+        world
+            .resource::<HsmNameIndex>()
+            .states_named("B")
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_hsm_enter_selection() {
+    #[derive(Resource, Default)]
+    struct Checked(Vec<Entity>);
+
+    let mut app = setup();
+    let world = app.world_mut();
+    world.init_resource::<Checked>();
+    world.resource_scope(
+        |world: &mut World, mut guard_registry: Mut<GuardRegistry>| {
+            system_registry!(<world,guard_registry>[
+                "checked"=>|context: In<GuardContext>, mut checked: ResMut<Checked>| {
+                    checked.0.push(context.to_state());
+                    true
+                }
+            ]);
+        },
+    );
+
+    let spawn = |world: &mut World| {
+        let state_machine = world
+            .spawn(hsm!(
+                #[state]:A(
+                    #[state]:B,
+                    #[state]:C,
+                    #[state]:D,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ))
+            .id();
+        let ids = world.remove_resource::<StateIds>().unwrap();
+        for &id in &ids[1..] {
+            world.entity_mut(id).insert(GuardEnter::new("checked"));
+        }
+        world.entity_mut(ids[2]).insert(StatePriority(5));
+        let state_tree = world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .state_tree();
+        world
+            .get_mut::<StateTree>(state_tree)
+            .unwrap()
+            .with_traversal(ids[0], TraversalStrategy::new(ReverseTraversal));
+        (state_machine, ids)
+    };
+    let curr_state = |app: &App, state_machine| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    // 默认在遍历顺序中第一个成立的子状态处停止
+    // By default, stop at the first passing sub-state in traversal order
+    let (first, ids) = spawn(app.world_mut());
+    app.update();
+    assert_eq!(curr_state(&app, first), ids[3]);
+    assert_eq!(app.world().resource::<Checked>().0, [ids[3]]);
+
+    app.world_mut().despawn(first);
+    app.world_mut().resource_mut::<Checked>().0.clear();
+
+    // 检查所有子状态并选择优先级最高者
+    // Check every sub-state and pick the highest priority
+    let (best, ids) = spawn(app.world_mut());
+    app.world_mut()
+        .entity_mut(ids[0])
+        .insert(EnterSelection::Best);
+    app.update();
+    assert_eq!(curr_state(&app, best), ids[2]);
+    assert_eq!(
+        app.world().resource::<Checked>().0,
+        [ids[3], ids[2], ids[1]]
+    );
+}
+
+#[test]
+fn test_state_machine_limits() {
+    let mut app = setup();
+    let world = app.world_mut();
+    world.insert_resource(StateMachineLimits {
+        max_depth: Some(1),
+        max_children: Some(2),
+        max_machines: Some(2),
+    });
+
+    let shallow = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+        ))
+        .id();
+    let deep = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B(
+                    #[state]:C,
+                ),
+            )
+            StateLifecycle::default(),
+        ))
+        .id();
+    let crowded = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+                #[state]:D,
+            )
+            StateLifecycle::default(),
+        ))
+        .id();
+    world.flush();
+
+    assert!(!world.entity(shallow).contains::<Paused>());
+    assert!(world.entity(deep).contains::<Paused>());
+    assert!(world.entity(crowded).contains::<Paused>());
+
+    let messages = world.resource::<Messages<StateMachineErrorMessage>>();
+    let mut cursor = messages.get_cursor();
+    let limits = cursor
+        .read(messages)
+        .filter_map(|message| match &message.0 {
+            StateMachineError::LimitExceeded {
+                state_machine,
+                limit,
+            } => Some((*state_machine, *limit)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(limits.len(), 2);
+    assert_eq!(limits[0].0, deep);
+    assert!(matches!(
+        limits[0].1,
+        StateMachineLimit::Depth { max: 1, .. }
+    ));
+    // 第三个状态机同时超出了数量限制，先检查数量
+    // The third machine also exceeds the machine count, which is checked first
+    assert_eq!(limits[1].0, crowded);
+    assert_eq!(
+        limits[1].1,
+        StateMachineLimit::Machines { count: 3, max: 2 }
+    );
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn test_recently_in_guard() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:Dodge,
+                #[state]:Counter,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert((GuardEnter::new("tautology"), GuardExit::new("tautology")));
+    world.entity_mut(ids[2]).insert((
+        GuardEnter(GuardCondition::parse("recently_in(\"Dodge\", 3)").unwrap()),
+        StatePriority(1),
+    ));
+
+    for _ in 0..6 {
+        app.update();
+    }
+
+    let hsm = app.world().get::<HsmStateMachine>(state_machine).unwrap();
+    assert!(hsm.was_in_state_within(ids[1], 3));
+    assert!(hsm.last_time_in(ids[1]).is_some());
+    assert_eq!(hsm.curr_state_id(), ids[2]);
+}
+
+#[test]
+fn test_hsm_latch() {
+    struct Jump;
+
+    #[derive(Resource, Default)]
+    struct JumpPressed(bool);
+
+    let mut app = setup();
+    app.init_resource::<JumpPressed>()
+        .add_latch::<Jump>("jump")
+        .latch_when::<Jump, _>(Update, |pressed: Res<JumpPressed>| pressed.0);
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert(GuardEnter::new("jump"));
+
+    for _ in 0..3 {
+        app.update();
+    }
+    let hsm = app.world().get::<HsmStateMachine>(state_machine).unwrap();
+    assert_eq!(hsm.curr_state_id(), ids[0]);
+
+    app.world_mut().resource_mut::<JumpPressed>().0 = true;
+    app.update();
+    app.world_mut().resource_mut::<JumpPressed>().0 = false;
+    for _ in 0..2 {
+        app.update();
+    }
+
+    let hsm = app.world().get::<HsmStateMachine>(state_machine).unwrap();
+    assert_eq!(hsm.curr_state_id(), ids[1]);
+    assert!(!app.world().resource::<HsmLatch<Jump>>().is_set());
+}
+
+#[test]
+fn test_macro_guard_grammar() {
+    let pressed = GuardCondition::new("pressed");
+    assert_eq!(
+        combination_condition!(and(
+            sticky(#pressed, 0.2),
+            chance(0.3),
+            field(Door) == "Open",
+            above("speed", -1, 8),
+            not(once())
+        )),
+        GuardCondition::parse(
+            r#"and(sticky(pressed, 0.2), chance(0.3), field(Door) == "Open", above("speed", -1, 8), not(once()))"#
+        )
+        .unwrap()
+    );
+
+    // 字符串形式的表达式在运行时解析
+    // Expressions written as strings are parsed at runtime
+    assert_eq!(
+        GuardCondition::from("every(3)"),
+        GuardCondition::call("every", ["3"])
+    );
+    assert_eq!(
+        GuardCondition::from("tautology"),
+        GuardCondition::new("tautology")
+    );
+
+    let mut app = setup();
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(fsm!(
+            states:{
+                #[state]: A,
+                #[state]: B,
+            },
+            transitions:{
+                A => B : guard(every(2)),
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    for expected in [ids[0], ids[1]] {
+        world.trigger(FsmTrigger::with_guard(state_machine, ids[1]));
+        world.flush();
+        assert_eq!(
+            world
+                .get::<FsmStateMachine>(state_machine)
+                .unwrap()
+                .curr_state_id(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_guard_in_schedule() {
+    #[derive(Resource, Default)]
+    struct IsUp(bool);
+
+    let mut app = setup();
+    app.init_resource::<IsUp>()
+        .register_guard("is_up", |_: In<GuardContext>, is_up: Res<IsUp>| is_up.0)
+        .evaluate_guards_in(Update)
+        .add_systems(
+            Update,
+            (|mut is_up: ResMut<IsUp>| is_up.0 = true).before(GuardScheduleSystems),
+        )
+        .add_systems(PostUpdate, |mut is_up: ResMut<IsUp>| is_up.0 = false);
+    let world = app.world_mut();
+
+    let scheduled = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let scheduled_ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(scheduled_ids[1])
+        .insert(GuardEnter::new("is_up").in_schedule(Update));
+
+    let direct = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let direct_ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(direct_ids[1])
+        .insert(GuardEnter::new("is_up"));
+
+    for _ in 0..4 {
+        app.update();
+    }
+
+    let world = app.world();
+    let hsm = world.get::<HsmStateMachine>(scheduled).unwrap();
+    assert_eq!(hsm.curr_state_id(), scheduled_ids[1]);
+    let hsm = world.get::<HsmStateMachine>(direct).unwrap();
+    assert_eq!(hsm.curr_state_id(), direct_ids[0]);
+}
+
+#[test]
+fn test_transition_pipeline() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let spawn = |world: &mut World, pipeline: Option<TransitionPipeline>| {
+        let state_machine = world
+            .spawn(hsm!(
+                #[state]:A(
+                    #[state]:B(
+                        #[state]:C,
+                    ),
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ))
+            .id();
+        let ids = world.remove_resource::<StateIds>().unwrap();
+        world
+            .entity_mut(ids[1])
+            .insert(GuardEnter::new("tautology"));
+        world
+            .entity_mut(ids[2])
+            .insert(GuardEnter::new("tautology"));
+        if let Some(pipeline) = pipeline {
+            world.entity_mut(state_machine).insert(pipeline);
+        }
+        (state_machine, ids)
+    };
+    let (default, default_ids) = spawn(world, None);
+    let (collapsed, collapsed_ids) = spawn(world, Some(TransitionPipeline::collapsed(8)));
+    let (throttled, throttled_ids) = spawn(world, Some(TransitionPipeline::throttled(1)));
+
+    let curr = |app: &App, state_machine: Entity| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    assert_eq!(curr(&app, default), default_ids[1]);
+    assert_eq!(curr(&app, collapsed), collapsed_ids[2]);
+    assert_eq!(curr(&app, throttled), throttled_ids[1]);
+
+    app.update();
+    assert_eq!(curr(&app, default), default_ids[2]);
+    assert_eq!(curr(&app, throttled), throttled_ids[1]);
+
+    for _ in 0..2 {
+        app.update();
+    }
+    assert_eq!(curr(&app, throttled), throttled_ids[2]);
+}
+
+#[test]
+fn test_topology() {
+    use bevy::{ecs::system::RunSystemOnce, platform::collections::HashSet};
+
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let hsm = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B(
+                    #[state]:C,
+                ),
+                #[state]:D,
+            )
+            :spawn_state_ids,
+        ))
+        .id();
+    let hsm_ids = world.remove_resource::<StateIds>().unwrap();
+    let fsm = world
+        .spawn(fsm!(
+            states:{
+                #[state]: E,
+                #[state]: F,
+            },
+            transitions:{
+                E => F,
+            }
+            :spawn_state_ids,
+        ))
+        .id();
+    let fsm_ids = world.remove_resource::<StateIds>().unwrap();
+
+    world
+        .run_system_once(move |topology: HsmTopology| {
+            let set = |states: &[Entity]| states.iter().copied().collect::<HashSet<_>>();
+
+            assert_eq!(
+                topology.states_of(hsm).collect::<HashSet<_>>(),
+                set(&hsm_ids)
+            );
+            assert_eq!(
+                topology.leaves_of(hsm).collect::<HashSet<_>>(),
+                set(&[hsm_ids[2], hsm_ids[3]])
+            );
+            assert_eq!(
+                topology
+                    .children_of(hsm, hsm_ids[0])
+                    .collect::<HashSet<_>>(),
+                set(&[hsm_ids[1], hsm_ids[2]])
+            );
+            assert_eq!(topology.parent_of(hsm, hsm_ids[3]), Some(hsm_ids[1]));
+            assert_eq!(topology.parent_of(hsm, hsm_ids[0]), None);
+
+            assert_eq!(
+                topology.states_of(fsm).collect::<HashSet<_>>(),
+                set(&fsm_ids)
+            );
+            assert_eq!(
+                topology.leaves_of(fsm).collect::<HashSet<_>>(),
+                set(&fsm_ids)
+            );
+            assert_eq!(topology.children_of(fsm, fsm_ids[0]).count(), 0);
+            assert_eq!(topology.parent_of(fsm, fsm_ids[1]), None);
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_deferred_state_links() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let root = world.spawn(HsmState::default()).id();
+    let state_tree = world.spawn(StateTree::new(root)).id();
+    let super_state = world.spawn_empty().id();
+    let sub_state = world.spawn(HsmState::default()).id();
+
+    let mut commands = world.commands();
+    commands.link_state(state_tree, super_state, sub_state);
+    commands.link_state(state_tree, root, super_state);
+    world.flush();
+
+    assert_eq!(world.resource::<PendingStateLinks>().links().len(), 2);
+    assert!(
+        !world
+            .get::<StateTree>(state_tree)
+            .unwrap()
+            .contains(sub_state)
+    );
+
+    world.entity_mut(super_state).insert(HsmState::default());
+    world.flush();
+
+    assert!(world.resource::<PendingStateLinks>().is_empty());
+    let tree = world.get::<StateTree>(state_tree).unwrap();
+    assert_eq!(tree.get_super_state(super_state), Some(root));
+    assert_eq!(tree.get_super_state(sub_state), Some(super_state));
+}
+
+#[test]
+fn test_phase_schedules() {
+    #[derive(Resource, Default)]
+    struct Phases(Vec<(&'static str, Entity)>);
+
+    let mut app = setup();
+    app.init_resource::<Phases>()
+        .add_systems(
+            HsmEnterOf::new("B"),
+            |context: HsmPhaseContext, mut phases: ResMut<Phases>| {
+                phases.0.push(("enter", context.state()));
+            },
+        )
+        .add_systems(
+            HsmExitOf::new("B"),
+            |context: HsmPhaseContext, mut phases: ResMut<Phases>| {
+                phases.0.push(("exit", context.state()));
+            },
+        );
+    let world = app.world_mut();
+
+    world.spawn(hsm!(
+        #[state]:A(
+            #[state]:B,
+        )
+        StateLifecycle::default(),
+        :spawn_state_ids,
+    ));
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert((GuardEnter::new("tautology"), GuardExit::new("tautology")));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let phases = &app.world().resource::<Phases>().0;
+    assert!(phases.len() >= 2);
+    assert_eq!(phases[0], ("enter", ids[1]));
+    assert_eq!(phases[1], ("exit", ids[1]));
+}
+
+#[test]
+fn test_sticky_condition() {
+    #[derive(Resource, Default)]
+    struct Input {
+        pressed: bool,
+        ready: bool,
+    }
+
+    let mut app = setup();
+    app.init_resource::<Input>()
+        .register_guard("pressed", |_: In<GuardContext>, input: Res<Input>| {
+            input.pressed
+        })
+        .register_guard("ready", |_: In<GuardContext>, input: Res<Input>| {
+            input.ready
+        });
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert(GuardEnter(
+        GuardCondition::parse("and(sticky(pressed), ready)").unwrap(),
+    ));
+
+    app.world_mut().resource_mut::<Input>().pressed = true;
+    app.update();
+    app.world_mut().resource_mut::<Input>().pressed = false;
+    for _ in 0..2 {
+        app.update();
+    }
+    let world = app.world();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[0]
+    );
+    assert!(world.get::<StickyGuards>(state_machine).unwrap().contains(
+        ids[0],
+        ids[1],
+        "sticky(pressed)"
+    ));
+
+    app.world_mut().resource_mut::<Input>().ready = true;
+    for _ in 0..2 {
+        app.update();
+    }
+    let world = app.world();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+    assert!(!world.get::<StickyGuards>(state_machine).unwrap().contains(
+        ids[0],
+        ids[1],
+        "sticky(pressed)"
+    ));
+    assert_eq!(
+        GuardCondition::parse("sticky(pressed, 0.5)")
+            .unwrap()
+            .to_string(),
+        "sticky(pressed, 0.5)"
+    );
+}
+
+#[test]
+fn test_transition_cooldown() {
+    #[derive(Resource, Default)]
+    struct Entered(u32);
+
+    let mut app = setup();
+    app.init_resource::<Entered>()
+        .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_millis(100),
+        ))
+        .register_action(
+            "count_enter",
+            |_: In<ActionContext>, mut entered: ResMut<Entered>| entered.0 += 1,
+        );
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:Root(
+                #[state(guard_exit="tautology", after_enter="count_enter")]:Toggle,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::parse("cooldown(0.35)").unwrap());
+
+    for _ in 0..12 {
+        app.update();
+    }
+    let since = |app: &App| {
+        app.world()
+            .get::<TransitionCooldowns>(state_machine)
+            .unwrap()
+            .since(ids[0], ids[1])
+            .unwrap()
+    };
+    assert_eq!(app.world().resource::<Entered>().0, 3);
+    let before_pause = since(&app);
+
+    app.world_mut().entity_mut(state_machine).insert(Paused);
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(since(&app), before_pause);
+
+    app.world_mut().entity_mut(state_machine).remove::<Paused>();
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Entered>().0, 4);
+}
+
+#[test]
+fn test_hsm_explain() {
+    let mut app = setup();
+    app.init_resource::<HsmExplain>();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+                #[state]:D,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[0]).insert(EnterSelection::Best);
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::new("contradiction"));
+    world
+        .entity_mut(ids[2])
+        .insert((GuardEnter::new("tautology"), StatePriority(5)));
+    world
+        .entity_mut(ids[3])
+        .insert(GuardEnter::new("tautology"));
+
+    app.update();
+    let report = app
+        .world()
+        .resource::<HsmExplain>()
+        .last_report(state_machine)
+        .unwrap()
+        .clone();
+    let mut conditions = report
+        .conditions
+        .iter()
+        .map(|condition| {
+            (
+                condition.to,
+                condition.condition.as_str(),
+                condition.result.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    // 条件按遍历顺序记录，这里只比较内容
+    // Conditions are recorded in traversal order, only compare their contents here
+    conditions.sort_by_key(|condition| ids.iter().position(|&id| id == condition.0));
+    assert_eq!(
+        conditions,
+        [
+            (ids[1], "contradiction", Ok(false)),
+            (ids[2], "tautology", Ok(true)),
+            (ids[3], "tautology", Ok(true)),
+        ]
+    );
+    assert_eq!(
+        report
+            .transitions
+            .iter()
+            .map(|transition| (transition.from, transition.to, transition.outcome))
+            .collect::<Vec<_>>(),
+        [
+            (ids[0], ids[3], TransitionOutcome::Outranked),
+            (ids[0], ids[2], TransitionOutcome::Selected),
+        ]
+    );
+
+    // 之后的帧没有再评估该状态机，报告保持不变
+    // Later frames did not evaluate the machine again, so the report is kept
+    app.update();
+    assert_eq!(
+        app.world()
+            .resource::<HsmExplain>()
+            .last_report(state_machine),
+        Some(&report)
+    );
+}
+
+#[test]
+fn test_hsm_enter_requirements() {
+    #[derive(Component)]
+    struct Weapon;
+
+    let mut app = setup();
+    app.init_resource::<HsmExplain>();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:Attack,
+                #[state]:Idle,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert((
+        GuardEnter::new("tautology"),
+        StatePriority(5),
+        HsmEnterRequirements::default().with_component::<Weapon>(),
+    ));
+    world
+        .entity_mut(ids[2])
+        .insert(GuardEnter::new("tautology"));
+
+    // 没有武器时跳过攻击状态，且不运行其守卫
+    // Without a weapon the attack state is skipped and its guard is not run
+    app.update();
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[2]
+    );
+    let report = world
+        .resource::<HsmExplain>()
+        .last_report(state_machine)
+        .unwrap();
+    assert!(
+        report
+            .conditions
+            .iter()
+            .all(|condition| condition.to != ids[1])
+    );
+    assert!(report.transitions.iter().any(|transition| {
+        transition.to == ids[1] && transition.outcome == TransitionOutcome::MissingRequirements
+    }));
+    let requirements = world.get::<HsmEnterRequirements>(ids[1]).unwrap();
+    assert_eq!(requirements.missing(world, state_machine).len(), 1);
+
+    world.entity_mut(state_machine).insert(Weapon);
+    world.trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+}
+
+#[test]
+fn test_hsm_start_selector() {
+    #[derive(Resource, Default)]
+    struct Entered(Vec<&'static str>);
+
+    let mut app = setup();
+    app.init_resource::<Entered>()
+        .register_action(
+            "enter_boot",
+            |_: In<ActionContext>, mut entered: ResMut<Entered>| entered.0.push("boot"),
+        )
+        .register_action(
+            "enter_resume",
+            |_: In<ActionContext>, mut entered: ResMut<Entered>| entered.0.push("resume"),
+        );
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            init(start = ["contradiction" => Intro, "tautology" => Resume])
+            #[state(after_enter = "enter_boot")]:Boot(
+                #[state]:Intro,
+                #[state(after_enter = "enter_resume")]:Resume,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    app.update();
+
+    let world = app.world();
+    let hsm = world.get::<HsmStateMachine>(state_machine).unwrap();
+    assert_eq!((hsm.init_state(), hsm.curr_state_id()), (ids[2], ids[2]));
+    assert!(!world.entity(state_machine).contains::<HsmStartSelector>());
+    assert_eq!(world.resource::<Entered>().0, ["resume"]);
+
+    // 没有候选成立时保持原本的初始状态
+    // The original initial state is kept when no candidate holds
+    let world = app.world_mut();
+    world.resource_mut::<Entered>().0.clear();
+    let state_machine = world
+        .spawn(hsm!(
+            init(start = ["contradiction" => Resume])
+            #[state(after_enter = "enter_boot")]:Boot(
+                #[state(after_enter = "enter_resume")]:Resume,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    app.update();
+
+    let world = app.world();
+    let hsm = world.get::<HsmStateMachine>(state_machine).unwrap();
+    assert_eq!(hsm.curr_state_id(), ids[0]);
+    assert_eq!(world.resource::<Entered>().0, ["boot"]);
+}
+
+#[test]
+fn test_staged_guard_changes() {
+    fn once(_: In<GuardContext>, mut staged: ResMut<StagedGuardChanges>) -> bool {
+        staged.remove("once");
+        true
+    }
+
+    let mut app = setup();
+    let world = app.world_mut();
+    let once_id = world.register_system(once);
+    world
+        .resource_mut::<GuardRegistry>()
+        .insert("once", once_id);
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state(guard_enter = "once")]:B,
+                #[state(guard_enter = "late")]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .resource_mut::<Messages<StateMachineErrorMessage>>()
+        .clear();
+
+    // 守卫在评估中暂存移除自身，本次评估仍使用它且不报错
+    // A guard staging its own removal while evaluated is still used by that pass without errors
+    app.update();
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+    assert!(world.resource::<GuardRegistry>().contains("once"));
+    assert!(
+        world
+            .resource::<Messages<StateMachineErrorMessage>>()
+            .is_empty()
+    );
+
+    // 下一帧开始时修改被应用，系统被注销
+    // The change is applied at the start of the next frame and the system is unregistered
+    app.update();
+    let world = app.world_mut();
+    assert!(!world.resource::<GuardRegistry>().contains("once"));
+    assert!(world.unregister_system(once_id).is_err());
+
+    // 暂存插入之前未注册的条件后，引用它的进入守卫开始生效
+    // Once a previously unregistered condition is staged in, the enter guard referencing it takes effect
+    let late = world.register_system(tautology);
+    world
+        .resource_mut::<StagedGuardChanges>()
+        .insert("late", late);
+    world.trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[2]
+    );
+}
+
+#[test]
+fn test_hsm_emit() {
+    #[derive(Event, Clone)]
+    struct Alarm;
+
+    #[derive(EntityEvent, Clone)]
+    struct Stunned(Entity);
+
+    #[derive(Resource, Default)]
+    struct Emitted(Vec<&'static str>, Vec<Entity>);
+
+    let mut app = setup();
+    app.init_resource::<Emitted>()
+        .add_observer(|_: On<Alarm>, mut emitted: ResMut<Emitted>| {
+            emitted.0.push("alarm");
+        })
+        .add_observer(|stunned: On<Stunned>, mut emitted: ResMut<Emitted>| {
+            emitted.0.push("stunned");
+            emitted.1.push(stunned.0);
+        });
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert((
+        GuardEnter::new("tautology"),
+        HsmEmit::new(Alarm),
+        HsmEmit::to_service_target(Stunned).on_exit(),
+    ));
+
+    app.update();
+    assert_eq!(app.world().resource::<Emitted>().0, ["alarm"]);
+
+    // 退出时发送到服务目标，随后守卫再次进入
+    // Sent to the service target on exit, then the guard enters again
+    app.world_mut().trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    let emitted = app.world().resource::<Emitted>();
+    assert_eq!(emitted.0, ["alarm", "stunned", "alarm"]);
+    assert_eq!(emitted.1, [state_machine]);
+
+    // 移除后不再发送
+    // Nothing is sent once removed
+    let world = app.world_mut();
+    world.entity_mut(ids[1]).remove::<HsmEmit<Alarm>>();
+    world.resource_mut::<Emitted>().0.clear();
+    world.trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    assert_eq!(app.world().resource::<Emitted>().0, ["stunned"]);
+}
+
+#[test]
+fn test_hsm_inbox() {
+    let mut app = setup();
+    app.register_action(
+        "order_retreat",
+        |context: In<ActionContext>,
+         units: Query<Entity, With<HsmInbox>>,
+         mut commands: Commands| {
+            for unit in &units {
+                commands.send_message(context.state_machine, unit, "retreat_order");
+            }
+        },
+    );
+    let world = app.world_mut();
+
+    let unit = world
+        .spawn((
+            hsm!(
+                #[state]:Unit(
+                    #[state]:Retreat,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ),
+            HsmInbox::default(),
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert(GuardEnter(
+        GuardCondition::parse(r#"has_message("retreat_order")"#).unwrap(),
+    ));
+    app.update();
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(unit)
+            .unwrap()
+            .curr_state_id()
+    };
+    assert_eq!(curr_state(&app), ids[0]);
+
+    // 指挥官进入状态时向单位发送命令，单位转换后消耗该消息
+    // The commander sends the order on entering its state, and the unit consumes it by transitioning
+    let commander = app
+        .world_mut()
+        .spawn(hsm!(
+            #[state(after_enter = "order_retreat")]:Command,
+            StateLifecycle::default(),
+        ))
+        .id();
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+    let inbox = app.world().get::<HsmInbox>(unit).unwrap();
+    assert!(inbox.is_empty());
+
+    // 消息已消耗，返回后不会再次进入
+    // Once consumed, returning does not enter again
+    app.world_mut().trigger(HsmTrigger::to_super(unit));
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+
+    let world = app.world_mut();
+    world
+        .commands()
+        .send_message(commander, unit, "retreat_order");
+    world.commands().send_message(commander, unit, "hold");
+    world.flush();
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+    let inbox = app.world().get::<HsmInbox>(unit).unwrap();
+    assert_eq!(
+        inbox.messages(),
+        [HsmMessage {
+            name: "hold".into(),
+            sender: commander,
+        }]
+    );
+}
+
+#[test]
+fn test_hsm_event_log() {
+    use bevy::ecs::system::SystemState;
+
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            HsmEventLog::new(2),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let outsider = world.spawn(HsmState::default()).id();
+
+    let mut requester = SystemState::<HsmTransitionRequester>::new(world);
+    let mut param = requester.get_mut(world);
+    param.submit(state_machine, ids[1], 0, "wander");
+    param.submit(state_machine, ids[2], 10, "flee");
+    requester.apply(world);
+    app.update();
+
+    let events = |app: &App| {
+        app.world()
+            .get::<HsmEventLog>(state_machine)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.event.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        events(&app),
+        [
+            HsmLogEvent::Transition {
+                from: ids[0],
+                to: ids[2],
+            },
+            HsmLogEvent::RequestRejected {
+                target: ids[1],
+                rejection: RequestRejection::Superseded,
+            },
+        ]
+    );
+
+    // 超出容量时丢弃最旧的一条
+    // The oldest entry is dropped once over capacity
+    let world = app.world_mut();
+    let mut param = requester.get_mut(world);
+    param.submit(state_machine, outsider, 0, "foreign");
+    requester.apply(world);
+    app.update();
+    let log = app.world().get::<HsmEventLog>(state_machine).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(
+        log.iter().last().unwrap().event,
+        HsmLogEvent::RequestRejected {
+            target: outsider,
+            rejection: RequestRejection::NotInTree,
+        }
+    );
+    let frames = log.iter().map(|entry| entry.frame).collect::<Vec<_>>();
+    assert!(frames[0] < frames[1]);
+
+    // 低于详细程度的事件不会被记录
+    // Events below the verbosity are not recorded
+    let world = app.world_mut();
+    world
+        .get_mut::<HsmEventLog>(state_machine)
+        .unwrap()
+        .set_verbosity(HsmEventLogVerbosity::Errors);
+    let mut param = requester.get_mut(world);
+    param.submit(state_machine, ids[2], 0, "again");
+    requester.apply(world);
+    app.update();
+    assert_eq!(
+        events(&app).last(),
+        Some(&HsmLogEvent::RequestRejected {
+            target: outsider,
+            rejection: RequestRejection::NotInTree,
+        })
+    );
+}
+
+#[test]
+fn test_hsm_checkpoints() {
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            Health(10),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(state_machine)
+        .insert(HsmCheckpoints::default().with("start", ids[0]));
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.world_mut()
+        .trigger(HsmTrigger::chain(state_machine, ids[1]));
+    app.update();
+    app.world_mut()
+        .commands()
+        .entity(state_machine)
+        .track_checkpoint_data::<Health>()
+        .checkpoint("phase2");
+    app.update();
+    let snapshot = app
+        .world()
+        .get::<HsmCheckpoints>(state_machine)
+        .unwrap()
+        .get("phase2")
+        .and_then(HsmCheckpoint::snapshot)
+        .unwrap();
+
+    app.world_mut()
+        .trigger(HsmTrigger::chain(state_machine, ids[2]));
+    app.world_mut().get_mut::<Health>(state_machine).unwrap().0 = 3;
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+
+    app.world_mut()
+        .commands()
+        .entity(state_machine)
+        .restore_checkpoint("phase2");
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+    assert_eq!(app.world().get::<Health>(state_machine), Some(&Health(10)));
+
+    // 声明的检查点只包含状态
+    // Declared checkpoints only hold a state
+    app.world_mut().get_mut::<Health>(state_machine).unwrap().0 = 1;
+    app.world_mut()
+        .commands()
+        .entity(state_machine)
+        .restore_checkpoint("start");
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+    assert_eq!(app.world().get::<Health>(state_machine), Some(&Health(1)));
+
+    app.world_mut()
+        .entity_mut(state_machine)
+        .remove::<HsmCheckpoints>();
+    app.world_mut().flush();
+    assert!(app.world().get_entity(snapshot).is_err());
+}
+
+#[test]
+fn test_hsm_supervisor() {
+    let mut app = setup();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    let worker = |mut child: EntityCommands, _: ActionContext, index: usize| {
+        child.insert(Name::new(format!("worker{}", index)));
+    };
+    world.entity_mut(ids[1]).insert((
+        HsmSupervisor::new(2, worker),
+        GuardExit::new(ALL_CHILDREN_TERMINATED),
+    ));
+    world.entity_mut(ids[2]).insert((
+        HsmSupervisor::new(1, worker),
+        GuardExit::new(ANY_CHILD_FAULTED),
+    ));
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    let children = |app: &App, state| {
+        app.world()
+            .get::<SupervisedChildren>(state_machine)
+            .unwrap()
+            .get(state)
+            .to_vec()
+    };
+
+    app.world_mut()
+        .trigger(HsmTrigger::chain(state_machine, ids[1]));
+    app.update();
+    let workers = children(&app, ids[1]);
+    assert_eq!(workers.len(), 2);
+    assert_eq!(
+        app.world().get::<SupervisedBy>(workers[1]),
+        Some(&SupervisedBy {
+            state_machine,
+            state: ids[1],
+        })
+    );
+    assert_eq!(
+        app.world().get::<Name>(workers[1]).map(Name::as_str),
+        Some("worker1")
+    );
+
+    // 只有部分子状态机终止时保持在监督状态
+    // The supervisor stays while only some children have terminated
+    app.world_mut().entity_mut(workers[0]).insert(Terminated);
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+
+    app.world_mut().entity_mut(workers[1]).insert(Terminated);
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+    assert!(children(&app, ids[1]).is_empty());
+    assert!(
+        workers
+            .iter()
+            .all(|&worker| app.world().get_entity(worker).is_err())
+    );
+
+    app.world_mut()
+        .trigger(HsmTrigger::chain(state_machine, ids[2]));
+    app.update();
+    let workers = children(&app, ids[2]);
+    assert_eq!(workers.len(), 1);
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+
+    app.world_mut().entity_mut(workers[0]).insert(HsmFaulted {
+        state: workers[0],
+        message: "boom".to_string(),
+        failures: 1,
+        recoveries: 0,
+    });
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+    assert!(app.world().get_entity(workers[0]).is_err());
+}
+
+#[test]
+fn test_read_only_guard() {
+    #[derive(Component)]
+    struct Key;
+
+    #[derive(Resource)]
+    struct DoorOpen(bool);
+
+    #[derive(Resource)]
+    struct Missing;
+
+    let mut app = setup();
+    app.register_read_only_guard(
+        "can_open",
+        |context: GuardContext, keys: Query<(), With<Key>>, door: Res<DoorOpen>| {
+            door.0 && keys.contains(context.service_target)
+        },
+    )
+    .register_read_only_guard("missing", |_: GuardContext, _: Res<Missing>| true)
+    .insert_resource(DoorOpen(true));
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+                #[state]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert(GuardEnter::new("can_open"));
+    world.entity_mut(ids[2]).insert(GuardEnter::new("missing"));
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[0]);
+
+    app.world_mut().entity_mut(state_machine).insert(Key);
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+}
+
+#[test]
+fn test_hsm_sleep() {
+    #[derive(Component)]
+    struct Door(bool);
+
+    #[derive(Resource, Default)]
+    struct Checks(u32);
+
+    fn door_open(
+        context: In<GuardContext>,
+        query: Query<&Door>,
+        mut checks: ResMut<Checks>,
+    ) -> bool {
+        checks.0 += 1;
+        query.get(context.service_target).is_ok_and(|door| door.0)
+    }
+
+    let mut app = setup();
+    app.init_resource::<Checks>()
+        .register_guard("door_open", door_open)
+        .wake_on_change::<Door>();
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            HsmSleep::default(),
+            Door(false),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::new("door_open"));
+    let checks = |app: &App| app.world().resource::<Checks>().0;
+    let asleep = |app: &App| {
+        app.world()
+            .get::<HsmSleep>(state_machine)
+            .unwrap()
+            .is_asleep()
+    };
+
+    for _ in 0..4 {
+        app.update();
+    }
+    assert!(asleep(&app));
+    let before = checks(&app);
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(checks(&app), before);
+
+    // 显式唤醒后检查一轮再重新休眠
+    // An explicit wake-up checks another round before falling asleep again
+    app.world_mut().commands().entity(state_machine).wake_hsm();
+    app.update();
+    app.update();
+    assert!(checks(&app) > before);
+    assert!(asleep(&app));
+
+    app.world_mut().get_mut::<Door>(state_machine).unwrap().0 = true;
+    app.update();
+    assert_eq!(
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+}
+
+#[test]
+fn test_hsm_golden_trace() {
+    let mut app = setup();
+    app.register_guard("alert", contradiction)
+        .register_guard("calm", contradiction);
+    let state_machine = app
+        .world_mut()
+        .spawn((
+            hsm!(
+                #[state]:Idle(
+                    #[state(guard_enter = "alert", guard_exit = "calm")]:Alert,
+                )
+                StateLifecycle::default(),
+            ),
+            HsmTrace::default(),
+        ))
+        .id();
+
+    let trace = HsmTraceScript::new()
+        .step([("alert", false)])
+        .step([("alert", true)])
+        .step([("calm", true)])
+        .step([("alert", false)])
+        .run(&mut app, state_machine);
+    assert_golden_trace(
+        &trace,
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/alert.trace"),
+    );
+
+    // 脚本结束后移除覆盖
+    // Overrides are removed once the script ends
+    let overrides = app.world().resource::<GuardOverrides>();
+    assert_eq!(overrides.get(state_machine, "alert"), None);
+}
+
+#[test]
+fn test_prefer_last_child() {
+    let mut app = setup();
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state(strategy = Parallel)]:A(
+                #[state(guard_enter = "tautology", behavior = Rebirth)]:B,
+                #[state(guard_enter = "tautology", behavior = Rebirth)]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[0]).insert(PreferLastChild);
+    world.entity_mut(ids[1]).insert(DisabledState::default());
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+    let last_children = app.world().get::<LastChildren>(state_machine).unwrap();
+    assert_eq!(last_children.get(ids[0]), Some(ids[2]));
+
+    // B 重新可用后，A 重生时仍优先选择上次的 C
+    // Once B is available again, A still prefers the last chosen C when reborn
+    app.world_mut().entity_mut(ids[1]).remove::<DisabledState>();
+    app.world_mut().trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[2]);
+
+    // 忘记记录后按常规顺序选择 B
+    // After forgetting, B is selected in the usual order
+    app.world_mut()
+        .get_mut::<LastChildren>(state_machine)
+        .unwrap()
+        .forget(ids[0]);
+    app.world_mut().trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+}
+
+#[test]
+fn test_guard_enter_expensive() {
+    #[derive(Resource, Default)]
+    struct ExpensiveRuns(Vec<Entity>, bool);
+
+    let mut app = setup();
+    app.init_resource::<ExpensiveRuns>().register_guard(
+        "line_of_sight",
+        |context: In<GuardContext>, mut runs: ResMut<ExpensiveRuns>| {
+            runs.0.push(context.to_state());
+            runs.1
+        },
+    );
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state(guard_enter = "tautology")]:B,
+                #[state(guard_enter = "contradiction")]:C,
+                #[state(guard_enter = "tautology")]:D,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    for &state in &ids[1..3] {
+        world
+            .entity_mut(state)
+            .insert(GuardEnterExpensive::new("line_of_sight"));
+    }
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+
+    // 只有通过廉价层的 B 会运行昂贵守卫，随后选择 D
+    // Only B passes the cheap tier and runs the expensive guard, then D is picked
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[3]);
+    assert_eq!(app.world().resource::<ExpensiveRuns>().0, [ids[1]]);
+
+    // 昂贵守卫成立时，仍按遍历顺序选择 B
+    // Once the expensive guard holds, B still wins in traversal order
+    app.world_mut().resource_mut::<ExpensiveRuns>().1 = true;
+    app.world_mut().trigger(HsmTrigger::to_super(state_machine));
+    app.update();
+    app.update();
+    assert_eq!(curr_state(&app), ids[1]);
+}
+
+#[test]
+fn test_target_guard_memo() {
+    #[derive(Resource, Default)]
+    struct TargetChecks(usize);
+
+    let mut app = setup();
+    app.init_resource::<TargetChecks>().register_target_guard(
+        "target_alert",
+        |_: In<GuardContext>, mut checks: ResMut<TargetChecks>| {
+            checks.0 += 1;
+            false
+        },
+    );
+    let world = app.world_mut();
+    let player = world.spawn_empty().id();
+    let other = world.spawn_empty().id();
+    for target in [player, player, player, other] {
+        world.spawn((
+            hsm!(
+                #[state]:Idle(
+                    #[state(guard_enter = "target_alert")]:Alert,
+                )
+                StateLifecycle::default(),
+            ),
+            ServiceTarget(target),
+        ));
+    }
+
+    // 同一帧内每个服务目标只检查一次
+    // Checked once per service target within a frame
+    app.update();
+    assert_eq!(app.world().resource::<TargetChecks>().0, 2);
+    assert_eq!(app.world().resource::<TargetGuardMemo>().cached(), 2);
+
+    // 缓存在下一帧开始时清空
+    // The cache is cleared at the start of the next frame
+    app.update();
+    assert_eq!(app.world().resource::<TargetChecks>().0, 4);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_hsm_stats() {
+    let mut app = setup();
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:Idle(
+                #[state(guard_enter = "tautology")]:Alert,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+
+    app.update();
+    app.update();
+    let stats = app.world().get::<HsmStats>(state_machine).unwrap();
+    assert_eq!(stats.transitions, 1);
+    assert_eq!(stats.active().map(|(state, _)| state), Some(ids[1]));
+    assert!(stats.total_time_by_state.contains_key(&ids[0]));
+}
+
+#[test]
+fn test_required_companions() {
+    let mut app = setup();
+    let world = app.world_mut();
+    let state = world.spawn(HsmState::default()).id();
+    let named = world.spawn((HsmState::default(), Name::new("Idle"))).id();
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        StateTree::new(state),
+        HsmStateMachine::with(
+            state_machine,
+            state,
+            #[cfg(feature = "history")]
+            10,
+        ),
+    ));
+
+    let world = app.world();
+    assert_eq!(
+        world.get::<Name>(state).unwrap().as_str(),
+        state.to_string()
+    );
+    assert_eq!(world.get::<Name>(named).unwrap().as_str(), "Idle");
+    assert_eq!(world.get::<StatePriority>(state), Some(&StatePriority(0)));
+    assert_eq!(
+        world.get::<StateClock>(state_machine).map(StateClock::mode),
+        Some(StateClockMode::Update)
+    );
+}
+
+#[test]
+fn test_fallthrough() {
+    #[derive(Resource, Default)]
+    struct Timeouts(Vec<HsmFallthroughTimeout>);
+
+    let mut app = setup();
+    app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+        std::time::Duration::from_millis(100),
+    ))
+    .init_resource::<Timeouts>()
+    .add_observer(
+        |on: On<HsmFallthroughTimeout>, mut timeouts: ResMut<Timeouts>| {
+            timeouts.0.push(*on.event());
+        },
+    );
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:Idle(
+                #[state(guard_enter = "contradiction")]:Alert,
+                #[state(guard_enter = "contradiction")]:Calm,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[0]).insert(HsmFallthrough::new(
+        std::time::Duration::from_millis(350),
+        FallthroughPolicy::Enter(ids[2]),
+    ));
+
+    let curr_state = |app: &App| {
+        app.world()
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(curr_state(&app), ids[0]);
+    assert!(app.world().resource::<Timeouts>().0.is_empty());
+
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(curr_state(&app), ids[2]);
+    let timeouts = &app.world().resource::<Timeouts>().0;
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!(timeouts[0].state, ids[0]);
+    assert_eq!(timeouts[0].policy, FallthroughPolicy::Enter(ids[2]));
+    assert!(timeouts[0].waited >= std::time::Duration::from_millis(350));
+}
+
+#[test]
+fn test_guard_retry() {
+    #[derive(Resource)]
+    struct Flag;
+
+    #[derive(Resource, Default)]
+    struct Failures(usize);
+
+    #[derive(Resource, Default)]
+    struct Exhausted(Vec<HsmGuardRetriesExhausted>);
+
+    let mut app = setup();
+    app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+        std::time::Duration::from_millis(100),
+    ))
+    .init_resource::<Failures>()
+    .init_resource::<Exhausted>()
+    .register_guard("flag", |_: In<GuardContext>, _: Res<Flag>| true)
+    .add_systems(
+        First,
+        |mut errors: MessageReader<StateMachineErrorMessage>, mut failures: ResMut<Failures>| {
+            failures.0 += errors.read().count();
+        },
+    )
+    .add_observer(
+        |on: On<HsmGuardRetriesExhausted>, mut exhausted: ResMut<Exhausted>| {
+            exhausted.0.push(*on.event());
+        },
+    );
+    let world = app.world_mut();
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:Idle(
+                #[state(guard_enter = "flag")]:Ready,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(state_machine)
+        .insert(HsmGuardRetry::new(std::time::Duration::from_millis(150)).with_max_attempts(3));
+
+    // 失败后按退避间隔重试，而不是每帧报告
+    // Failures are retried after the backoff instead of being reported every frame
+    for _ in 0..4 {
+        app.update();
+    }
+    let world = app.world();
+    assert_eq!(world.resource::<Failures>().0, 2);
+    assert_eq!(
+        world
+            .get::<HsmGuardRetry>(state_machine)
+            .unwrap()
+            .attempts(ids[0], ids[1]),
+        2
+    );
+    assert!(!world.entity(state_machine).contains::<Paused>());
+
+    // 连续失败达到上限后暂停状态机
+    // The machine is paused once the failures reach the limit
+    for _ in 0..6 {
+        app.update();
+    }
+    let world = app.world();
+    assert_eq!(world.resource::<Failures>().0, 3);
+    assert!(world.entity(state_machine).contains::<Paused>());
+    assert_eq!(
+        world.resource::<Exhausted>().0,
+        [HsmGuardRetriesExhausted {
+            state_machine,
+            from_state: ids[0],
+            to_state: ids[1],
+            attempts: 3,
+        }]
+    );
+
+    // 恢复后成功运行一次即清除失败记录
+    // Once recovered, a single successful run clears the failures
+    app.world_mut().insert_resource(Flag);
+    app.world_mut().entity_mut(state_machine).remove::<Paused>();
+    app.update();
+    app.update();
+    let world = app.world();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+    assert!(
+        !world
+            .get::<HsmGuardRetry>(state_machine)
+            .unwrap()
+            .is_retrying(ids[0])
+    );
+}
+
+#[test]
+fn test_local_registry() {
+    #[derive(Resource, Default)]
+    struct Entered(Vec<&'static str>);
+
+    let mut app = setup();
+    app.init_resource::<Entered>();
+    let world = app.world_mut();
+    let spawn = |world: &mut World, mod_name: &'static str, ready: bool| {
+        let state_machine = world
+            .spawn(hsm!(
+                #[state]:Idle(
+                    #[state(guard_enter = "ready", after_enter = "on_ready")]:Ready,
+                )
+                StateLifecycle::default(),
+                :spawn_state_ids,
+            ))
+            .register_local_guard("ready", move |_: In<GuardContext>| ready)
+            .register_local_action(
+                "on_ready",
+                move |_: In<ActionContext>, mut entered: ResMut<Entered>| {
+                    entered.0.push(mod_name);
+                },
+            )
+            .id();
+        let ids = world.remove_resource::<StateIds>().unwrap();
+        (state_machine, ids)
+    };
+    let (a, a_ids) = spawn(world, "a", true);
+    let (b, b_ids) = spawn(world, "b", false);
+    world
+        .resource_mut::<Messages<StateMachineErrorMessage>>()
+        .clear();
+
+    app.update();
+    app.update();
+    let world = app.world_mut();
+    let curr_state = |world: &World, state_machine| {
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id()
+    };
+    assert_eq!(curr_state(world, a), a_ids[1]);
+    assert_eq!(curr_state(world, b), b_ids[0]);
+    assert_eq!(world.resource::<Entered>().0, ["a"]);
+    assert!(!world.resource::<GuardRegistry>().contains("ready"));
+    assert!(
+        world
+            .resource::<Messages<StateMachineErrorMessage>>()
+            .is_empty()
+    );
+
+    // 状态机被销毁时注销其局部系统
+    // Despawning the machine unregisters its local systems
+    let guard = world
+        .get::<LocalRegistry>(a)
+        .unwrap()
+        .guards
+        .get("ready")
+        .unwrap();
+    world.entity_mut(a).despawn();
+    world.flush();
+    assert!(world.unregister_system(guard).is_err());
+}
+
+#[test]
+fn test_plugin_group_disable() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugins::default().disable::<StateMachineDebugPlugin>())
+        .register_guard("tautology", tautology);
+    let world = app.world_mut();
+    // 循环检测与延迟状态链接属于核心插件
+    // Loop detection and deferred state links belong to the core plugin
+    assert!(world.contains_resource::<TransitionLoopDetection>());
+    assert!(world.contains_resource::<PendingStateLinks>());
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state]:B,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter::new("tautology"));
+    world.flush();
+
+    // 禁用调试插件后，核心转换仍然可用
+    // The core transitions still work with the debug plugin disabled
+    app.update();
+    let curr_state = app
+        .world()
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .curr_state_id();
+    assert_eq!(curr_state, ids[1]);
+}
+
+#[test]
+fn test_hsm_transition_payload() {
+    use bevy::ecs::system::SystemState;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct DashTo(u32);
+
+    #[derive(Resource, Default)]
+    struct Received(Vec<Option<DashTo>>);
+
+    let mut app = setup();
+    app.init_resource::<Received>()
+        .register_guard(
+            "dash",
+            |context: In<GuardContext>, mut commands: Commands| {
+                commands
+                    .entity(context.state_machine)
+                    .attach_payload(context.to_state(), DashTo(7));
+                true
+            },
+        )
+        .register_action(
+            "record",
+            |context: In<ActionContext>, payload: HsmPayload, mut received: ResMut<Received>| {
+                received.0.push(payload.get::<DashTo>(&context).copied());
+            },
+        );
+    let world = app.world_mut();
+
+    let state_machine = world
+        .spawn(hsm!(
+            #[state]:A(
+                #[state(after_enter = "record")]:B,
+                #[state(after_enter = "record")]:C,
+            )
+            StateLifecycle::default(),
+            :spawn_state_ids,
+        ))
+        .id();
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world.entity_mut(ids[1]).insert(GuardEnter::new("dash"));
+    world.flush();
+
+    // 条件附加的载荷交给进入的状态
+    // A payload attached by a condition is delivered to the entered state
+    app.update();
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[1]
+    );
+    assert_eq!(world.resource::<Received>().0, [Some(DashTo(7))]);
+
+    // 外部请求携带的载荷在请求被接受后交给目标状态
+    // A payload carried by an external request is delivered once the request is accepted
+    let mut requester = SystemState::<HsmTransitionRequester>::new(world);
+    requester
+        .get_mut(world)
+        .submit_with_payload(state_machine, ids[2], 0, "dash", DashTo(9));
+    requester.apply(world);
+    app.update();
+    app.update();
+
+    let world = app.world();
+    assert_eq!(
+        world
+            .get::<HsmStateMachine>(state_machine)
+            .unwrap()
+            .curr_state_id(),
+        ids[2]
+    );
+    assert_eq!(
+        world.resource::<Received>().0,
+        [Some(DashTo(7)), Some(DashTo(9))]
+    );
+    let payload = world.get::<HsmTransitionPayload>(state_machine).unwrap();
+    assert!(!payload.contains(ids[1]));
+    assert!(payload.contains(ids[2]));
+}
+
+#[test]
+fn test_param_guard_run_failed() {
+    #[derive(Resource)]
+    struct Flag;
+
+    let mut app = setup();
+    app.register_param_guard(
+        "flagged",
+        |_: In<(GuardContext, GuardArgs)>, _: Res<Flag>| true,
+    );
+    let world = app.world_mut();
+    world.spawn(hsm!(
+        #[state]:Idle(
+            #[state]:Ready,
+        )
+        StateLifecycle::default(),
+        :spawn_state_ids,
+    ));
+    let ids = world.remove_resource::<StateIds>().unwrap();
+    world
+        .entity_mut(ids[1])
+        .insert(GuardEnter(GuardCondition::call("flagged", ["1"])));
+    world
+        .resource_mut::<Messages<StateMachineErrorMessage>>()
+        .clear();
+
+    // 带参数守卫运行失败时与普通守卫一样报告
+    // A parameterized guard failing to run is reported like a plain guard
+    app.update();
+    let world = app.world_mut();
+    let failed = world
+        .resource_mut::<Messages<StateMachineErrorMessage>>()
+        .drain()
+        .any(|message| {
+            matches!(
+                message.0,
+                StateMachineError::GuardRunFailed { to_state, .. } if to_state == Some(ids[1])
+            )
+        });
+    assert!(failed);
+}
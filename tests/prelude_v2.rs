@@ -0,0 +1,73 @@
+//! 稳定预导入的语义化版本守卫：此处逐项导入 [`bevy_hsm::prelude_v2`] 的每一项，
+//! 移除或改名任一项都会使本文件编译失败。
+//!
+//! Semver guard for the stable prelude: every item of [`bevy_hsm::prelude_v2`] is imported by name here, so removing
+//! or renaming any of them fails to compile this file.
+
+#[cfg(feature = "hsm")]
+use bevy::prelude::*;
+#[allow(unused_imports)]
+use bevy_hsm::prelude_v2::{
+    ActionContext, ActionKey, ActionVerdict, AfterEnterSystem, AfterExitSystem, BeforeEnterSystem,
+    BeforeExitSystem, GuardCondition, GuardContext, IntoActionSystem, OnUpdateSystem,
+    OnUpdateSystems, Paused, RegisterStateSystem, ServiceTarget, ServiceTargetLost,
+    ServiceTargetLostPolicy, StateMachineAssetPlugin, StateMachineCommandsExt,
+    StateMachineCorePlugin, StateMachineDebugPlugin, StateMachineForestCommandsExt,
+    StateMachineIntegrationsPlugin, StateMachinePlugin, StateMachinePlugins, SystemLabel,
+    SystemState, Terminated, TransitionContext, combination_condition,
+};
+
+#[cfg(feature = "hsm")]
+#[allow(unused_imports)]
+use bevy_hsm::prelude_v2::{
+    GuardEnter, GuardExit, HsmFireTransition, HsmState, HsmStateMachine, HsmTransition,
+    HsmTransitions, HsmTrigger, HsmTriggerType, StateLifecycle, StatePriority,
+    StateTransitionStrategy, StateTree, TraversalStrategy, clone_hsm, despawn_hsm, hsm, hsm_tree,
+};
+
+#[cfg(feature = "fsm")]
+#[allow(unused_imports)]
+use bevy_hsm::prelude_v2::{
+    FsmGraph, FsmState, FsmStateMachine, FsmTrigger, FsmTriggerType, despawn_fsm, fsm, fsm_graph,
+};
+
+#[cfg(feature = "hsm")]
+#[derive(Resource, Default)]
+struct Entered(u32);
+
+#[cfg(feature = "hsm")]
+#[test]
+fn hsm_with_stable_prelude() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StateMachinePlugin::default())
+        .init_resource::<Entered>()
+        .register_guard("always", |_: In<GuardContext>| true)
+        .register_action(
+            "count_enter",
+            |_: In<ActionContext>, mut entered: ResMut<Entered>| entered.0 += 1,
+        );
+
+    let state_machine = app
+        .world_mut()
+        .spawn(hsm!(
+            #[state]:Root(
+                #[state(guard_enter="always", after_enter="count_enter")]:Child,
+            )
+            StateLifecycle::default(),
+        ))
+        .id();
+    app.update();
+    app.update();
+
+    let hsm = app.world().get::<HsmStateMachine>(state_machine).unwrap();
+    let tree = app.world().get::<StateTree>(state_machine).unwrap();
+    assert_ne!(hsm.curr_state_id(), tree.get_root());
+    assert_eq!(app.world().resource::<Entered>().0, 1);
+
+    app.world_mut().commands().queue(move |world: &mut World| {
+        despawn_hsm(world, state_machine);
+    });
+    app.update();
+    assert!(app.world().get_entity(state_machine).is_err());
+}
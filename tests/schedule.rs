@@ -0,0 +1,77 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy_hsm::prelude::*;
+
+fn tautology(_: In<GuardContext>) -> bool {
+    true
+}
+
+fn setup(plugin: StateMachinePlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(plugin)
+        .register_guard("tautology", tautology);
+    app
+}
+
+fn spawn_machine(world: &mut World) -> (Entity, Entity) {
+    let root = world.spawn(HsmState::default()).id();
+    let child = world.spawn(GuardEnter::new("tautology")).id();
+    let mut state_tree = StateTree::new(root);
+    state_tree.with_child(root, child);
+
+    let state_machine = world.spawn_empty().id();
+    world.entity_mut(state_machine).insert((
+        state_tree,
+        HsmStateMachine::with(
+            state_machine,
+            root,
+            #[cfg(feature = "history")]
+            10,
+        ),
+        StateLifecycle::default(),
+    ));
+    (state_machine, child)
+}
+
+fn curr_state(world: &World, state_machine: Entity) -> Entity {
+    world
+        .get::<HsmStateMachine>(state_machine)
+        .unwrap()
+        .curr_state_id()
+}
+
+fn assert_transitions_only_in(schedule: impl ScheduleLabel + Clone, other: impl ScheduleLabel) {
+    let mut app = setup(StateMachinePlugin::with_schedule(schedule.clone()));
+    let world = app.world_mut();
+    let (state_machine, child) = spawn_machine(world);
+    let root = curr_state(world, state_machine);
+
+    world.run_schedule(other);
+    assert_eq!(curr_state(world, state_machine), root);
+
+    world.run_schedule(schedule);
+    assert_eq!(curr_state(world, state_machine), child);
+}
+
+#[test]
+fn test_transitions_in_post_update() {
+    assert_transitions_only_in(PostUpdate, Last);
+}
+
+#[test]
+fn test_transitions_in_fixed_update() {
+    assert_transitions_only_in(FixedUpdate, Last);
+}
+
+#[test]
+fn test_transitions_in_last_by_default() {
+    let mut app = setup(StateMachinePlugin::default());
+    let world = app.world_mut();
+    let (state_machine, child) = spawn_machine(world);
+
+    world.run_schedule(PreUpdate);
+    assert_ne!(curr_state(world, state_machine), child);
+
+    world.run_schedule(Last);
+    assert_eq!(curr_state(world, state_machine), child);
+}